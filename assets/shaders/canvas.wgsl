@@ -0,0 +1,68 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct CanvasSettings {
+    // In canvas pixels, so there's one scanline per row of pixels.
+    canvas_size: vec2<f32>,
+    curvature: f32,
+    scanline_strength: f32,
+    vignette_strength: f32,
+    // 0 leaves the colors as they are.
+    palette_size: u32,
+    // Nonzero to apply curvature, scanlines and vignette.
+    crt: u32,
+}
+
+@group(2) @binding(0) var<uniform> settings: CanvasSettings;
+@group(2) @binding(1) var canvas_texture: texture_2d<f32>;
+@group(2) @binding(2) var canvas_sampler: sampler;
+// One row of colors, one texel each.
+@group(2) @binding(3) var palette_texture: texture_2d<f32>;
+
+// Bulges the picture out from the center like the glass of a CRT.
+fn curve(uv: vec2<f32>) -> vec2<f32> {
+    let centered = uv * 2.0 - 1.0;
+    let bulge = centered.yx * centered.yx * settings.curvature;
+    return (centered + centered * bulge) * 0.5 + 0.5;
+}
+
+fn nearest_palette_color(color: vec3<f32>) -> vec3<f32> {
+    var nearest = color;
+    var nearest_distance = 1e9;
+    for (var i = 0u; i < settings.palette_size; i++) {
+        let candidate = textureLoad(palette_texture, vec2(i32(i), 0), 0).rgb;
+        let offset = candidate - color;
+        let distance = dot(offset, offset);
+        if distance < nearest_distance {
+            nearest = candidate;
+            nearest_distance = distance;
+        }
+    }
+    return nearest;
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    var uv = mesh.uv;
+    if settings.crt != 0u {
+        uv = curve(uv);
+        if any(uv < vec2(0.0)) || any(uv > vec2(1.0)) {
+            return vec4(0.0, 0.0, 0.0, 1.0);
+        }
+    }
+
+    // The explicit level is allowed after the early return, unlike textureSample.
+    var color = textureSampleLevel(canvas_texture, canvas_sampler, uv, 0.0).rgb;
+    color = nearest_palette_color(color);
+
+    if settings.crt != 0u {
+        // Darkest between rows of canvas pixels, full brightness through their middle.
+        let row = fract(uv.y * settings.canvas_size.y);
+        let edge = abs(row - 0.5) * 2.0;
+        color *= 1.0 - settings.scanline_strength * edge * edge;
+
+        let centered = uv * 2.0 - 1.0;
+        color *= clamp(1.0 - settings.vignette_strength * dot(centered, centered) * 0.5, 0.0, 1.0);
+    }
+
+    return vec4(color, 1.0);
+}
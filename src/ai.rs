@@ -0,0 +1,463 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    audio::{PlaySfx, Sfx},
+    collider::GameLayer,
+    culling::Culled,
+    enemy::Emerging,
+    lighting::{AmbientLight2d, Light2d},
+    pathfinding::{NavGrid, PathFollower},
+    player::Player,
+    state::GameplaySet,
+    status::StatusEffects,
+};
+
+/// How much farther than its sight range the player has to get before a chasing enemy gives up.
+const LOSE_SIGHT_FACTOR: f32 = 1.5;
+const WAYPOINT_REACHED_DISTANCE: f32 = 2.;
+/// How long an enemy spends going to and looking around where it last saw the player.
+const INVESTIGATE_SECS: f32 = 5.;
+/// How close a distracted enemy gathers around a lure, so it doesn't shove it around.
+const LURE_GATHER_DISTANCE: f32 = 16.;
+/// How much of its loudness a noise keeps when there's a wall in the way.
+const WALL_MUFFLING: f32 = 0.5;
+/// How long the player has to stay in view, in plain daylight, to be detected.
+const DETECTION_SECS: f32 = 1.;
+/// How long a full detection gauge takes to empty once the player is out of view.
+const DETECTION_FADE_SECS: f32 = 4.;
+/// How much faster the gauge fills while the player stands in a flare's light.
+const LIT_EXPOSURE: f32 = 2.5;
+/// How fast the gauge fills in pitch darkness, relative to daylight.
+const DARK_EXPOSURE: f32 = 0.3;
+/// Ambient light at which the player is as easy to spot as it gets without a flare.
+const DAYLIGHT_AMBIENT: f32 = 0.4;
+/// Enemies this close to one that detects the player are alerted too.
+const ALERT_RADIUS: f32 = 120.;
+const STEERING: f32 = 4.;
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AggroChanged>();
+        app.add_event::<NoiseEvent>();
+        app.init_resource::<PlayerExposure>();
+        app.configure_sets(Update, AiSet::Transition.in_set(GameplaySet));
+        app.configure_sets(FixedUpdate, AiSet::Act.in_set(GameplaySet));
+        app.add_systems(
+            Update,
+            (measure_player_exposure, update_ai_state)
+                .chain()
+                .in_set(AiSet::Transition),
+        );
+        app.add_systems(FixedUpdate, act_on_ai_state.in_set(AiSet::Act));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AiSet {
+    Transition,
+    Act,
+}
+
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AiState {
+    #[default]
+    Idle,
+    Patrol,
+    Chase,
+    Attack,
+    /// Heading to where the player was last seen, after losing sight of them.
+    Investigate,
+    /// Drawn to a `Lure` while the player is out of sight.
+    Distracted,
+}
+
+impl AiState {
+    /// Whether the enemy is after the player.
+    pub fn is_aggro(self) -> bool {
+        matches!(self, AiState::Chase | AiState::Attack)
+    }
+}
+
+/// Sent when an enemy starts or stops going after the player.
+#[derive(Event, Debug)]
+pub struct AggroChanged {
+    pub aggro: bool,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+#[require(Perception)]
+pub struct AiSenses {
+    pub sight_range: f32,
+    pub attack_range: f32,
+}
+
+/// A sound enemies can hear, such as a gunshot. Calm enemies that hear it go to
+/// investigate where it came from.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct NoiseEvent {
+    pub position: Vec2,
+    /// How far the noise carries in the open, in pixels. Walls cut it down.
+    pub loudness: f32,
+}
+
+/// Draws in enemies within `radius` that can't see the player, like a burning flare.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Lure {
+    pub radius: f32,
+}
+
+/// What an enemy remembers about the player.
+#[derive(Component, Debug)]
+pub struct Perception {
+    /// Where the player was last seen, or heard.
+    pub last_seen: Option<Vec2>,
+    /// The nearest `Lure` in range this frame.
+    pub lure: Option<Vec2>,
+    /// From 0 to 1, filling while the player is in view. The enemy goes after them once
+    /// it's full.
+    pub detection: f32,
+    /// Runs while investigating; the enemy gives up once it finishes.
+    pub investigation: Timer,
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            last_seen: None,
+            lure: None,
+            detection: 0.,
+            investigation: Timer::from_seconds(INVESTIGATE_SECS, TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+#[require(PathFollower)]
+pub struct AiMovement {
+    pub patrol_speed: f32,
+    pub chase_speed: f32,
+    pub lunge_speed: f32,
+}
+
+#[derive(Component, Debug)]
+pub struct PatrolRoute {
+    pub waypoints: Vec<Vec2>,
+    pub next: usize,
+}
+
+impl PatrolRoute {
+    pub fn new(waypoints: Vec<Vec2>) -> Self {
+        Self { waypoints, next: 0 }
+    }
+}
+
+/// Time between lunges while in `AiState::Attack`.
+#[derive(Component, Debug)]
+pub struct AttackCycle(pub Timer);
+
+/// How easy the player is to spot right now, scaling how fast detection fills.
+#[derive(Resource, Default)]
+struct PlayerExposure(f32);
+
+/// What an enemy perceives of the player this frame.
+struct Sighting {
+    distance: f32,
+    /// No terrain between the enemy and the player.
+    line_of_sight: bool,
+    /// The detection gauge is full.
+    detected: bool,
+    /// Whether a lure is in range.
+    lured: bool,
+    /// Where the nearest noise heard this frame came from.
+    heard: Option<Vec2>,
+}
+
+fn next_state(
+    state: AiState,
+    senses: &AiSenses,
+    sighting: &Sighting,
+    investigation_over: bool,
+    can_patrol: bool,
+) -> AiState {
+    let calm_state = if can_patrol {
+        AiState::Patrol
+    } else {
+        AiState::Idle
+    };
+    let distance = sighting.distance;
+    let lost_sight = !sighting.line_of_sight || distance > senses.sight_range * LOSE_SIGHT_FACTOR;
+
+    match state {
+        AiState::Idle | AiState::Patrol | AiState::Investigate | AiState::Distracted
+            if sighting.detected =>
+        {
+            AiState::Chase
+        }
+        AiState::Idle | AiState::Patrol | AiState::Investigate if sighting.lured => {
+            AiState::Distracted
+        }
+        AiState::Idle | AiState::Patrol if sighting.heard.is_some() => AiState::Investigate,
+        AiState::Idle | AiState::Patrol => calm_state,
+        AiState::Investigate if investigation_over => calm_state,
+        AiState::Investigate => AiState::Investigate,
+        AiState::Distracted if sighting.lured => AiState::Distracted,
+        AiState::Distracted => calm_state,
+        AiState::Chase if lost_sight && sighting.lured => AiState::Distracted,
+        AiState::Chase if lost_sight => AiState::Investigate,
+        AiState::Chase if distance <= senses.attack_range => AiState::Attack,
+        AiState::Chase => AiState::Chase,
+        AiState::Attack if distance > senses.attack_range * LOSE_SIGHT_FACTOR => AiState::Chase,
+        AiState::Attack => AiState::Attack,
+    }
+}
+
+/// Whether the straight line between two points is clear of terrain.
+fn line_of_sight(spatial_query: &SpatialQuery, from: Vec2, to: Vec2) -> bool {
+    let Ok((direction, distance)) = Dir2::new_and_length(to - from) else {
+        return true;
+    };
+    let filter = SpatialQueryFilter::from_mask(GameLayer::Terrain);
+    spatial_query
+        .cast_ray(from, direction, distance, true, &filter)
+        .is_none()
+}
+
+/// Whether a noise carries as far as `listener`.
+fn hears(spatial_query: &SpatialQuery, noise: &NoiseEvent, listener: Vec2) -> bool {
+    let distance = noise.position.distance(listener);
+    distance <= noise.loudness * WALL_MUFFLING
+        || (distance <= noise.loudness && line_of_sight(spatial_query, listener, noise.position))
+}
+
+fn measure_player_exposure(
+    ambient: Res<AmbientLight2d>,
+    player_transform: Single<&Transform, With<Player>>,
+    light_q: Query<(&Light2d, &Transform), Without<Player>>,
+    mut exposure: ResMut<PlayerExposure>,
+) {
+    let player_pos = player_transform.translation.truncate();
+    let lit = light_q.iter().any(|(light, transform)| {
+        transform.translation.truncate().distance(player_pos) <= light.radius
+    });
+
+    exposure.0 = if lit {
+        LIT_EXPOSURE
+    } else {
+        DARK_EXPOSURE.lerp(1., (ambient.0 / DAYLIGHT_AMBIENT).min(1.))
+    };
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_ai_state(
+    mut aggro_events: EventWriter<AggroChanged>,
+    mut noise_events: EventReader<NoiseEvent>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    time: Res<Time>,
+    exposure: Res<PlayerExposure>,
+    spatial_query: SpatialQuery,
+    player_transform: Single<&Transform, With<Player>>,
+    lure_q: Query<(&Lure, &Transform)>,
+    mut ai_q: Query<
+        (
+            &Transform,
+            &AiSenses,
+            &mut AiState,
+            &mut Perception,
+            Option<&PatrolRoute>,
+            Option<&mut AttackCycle>,
+        ),
+        Without<Culled>,
+    >,
+) {
+    let player_pos = player_transform.translation.truncate();
+    let noises: Vec<NoiseEvent> = noise_events.read().copied().collect();
+    let mut alerts = Vec::new();
+
+    for (transform, senses, mut state, mut perception, route, attack_cycle) in ai_q.iter_mut() {
+        let position = transform.translation.truncate();
+        let distance = position.distance(player_pos);
+        perception.lure = lure_q
+            .iter()
+            .map(|(lure, lure_transform)| (lure.radius, lure_transform.translation.truncate()))
+            .filter(|(radius, lure_pos)| position.distance(*lure_pos) <= *radius)
+            .map(|(_, lure_pos)| lure_pos)
+            .min_by(|a, b| position.distance(*a).total_cmp(&position.distance(*b)));
+        // Rays are only cast when the player is close enough to matter.
+        let has_line_of_sight = distance <= senses.sight_range * LOSE_SIGHT_FACTOR
+            && line_of_sight(&spatial_query, position, player_pos);
+        let in_view = has_line_of_sight && distance <= senses.sight_range;
+        perception.detection = if state.is_aggro() {
+            1.
+        } else if in_view {
+            (perception.detection + exposure.0 * time.delta_secs() / DETECTION_SECS).min(1.)
+        } else {
+            (perception.detection - time.delta_secs() / DETECTION_FADE_SECS).max(0.)
+        };
+        let sighting = Sighting {
+            distance,
+            line_of_sight: has_line_of_sight,
+            detected: perception.detection >= 1.,
+            lured: perception.lure.is_some(),
+            heard: noises
+                .iter()
+                .filter(|noise| hears(&spatial_query, noise, position))
+                .map(|noise| noise.position)
+                .min_by(|a, b| position.distance(*a).total_cmp(&position.distance(*b))),
+        };
+        if sighting.line_of_sight {
+            perception.last_seen = Some(player_pos);
+        } else if let Some(heard) = sighting.heard
+            && matches!(
+                *state,
+                AiState::Idle | AiState::Patrol | AiState::Investigate
+            )
+        {
+            // A fresh noise restarts the search from there.
+            perception.last_seen = Some(heard);
+            perception.investigation.reset();
+        }
+
+        let investigation_over = *state == AiState::Investigate
+            && perception.investigation.tick(time.delta()).finished();
+        let can_patrol = route.is_some_and(|route| !route.waypoints.is_empty());
+        let new_state = next_state(*state, senses, &sighting, investigation_over, can_patrol);
+
+        if new_state != *state {
+            if new_state == AiState::Investigate {
+                perception.investigation.reset();
+            }
+            if new_state == AiState::Attack
+                && let Some(mut attack_cycle) = attack_cycle
+            {
+                attack_cycle.0.reset();
+            }
+            if new_state.is_aggro() != state.is_aggro() {
+                aggro_events.write(AggroChanged {
+                    aggro: new_state.is_aggro(),
+                });
+                if new_state.is_aggro() {
+                    sfx_events.write(PlaySfx::at(
+                        Sfx::EnemyAlert,
+                        transform.translation.truncate(),
+                    ));
+                    // Only enemies that saw the player for themselves pass it on, so
+                    // alerts don't ripple across the whole level.
+                    if in_view {
+                        alerts.push(position);
+                    }
+                }
+            }
+            *state = new_state;
+        }
+    }
+
+    for (transform, _, state, mut perception, _, _) in ai_q.iter_mut() {
+        let position = transform.translation.truncate();
+        if !state.is_aggro()
+            && alerts
+                .iter()
+                .any(|alert| alert.distance(position) <= ALERT_RADIUS)
+        {
+            perception.detection = 1.;
+            perception.last_seen = Some(player_pos);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn act_on_ai_state(
+    time: Res<Time>,
+    nav_grid: Res<NavGrid>,
+    player_transform: Single<&Transform, With<Player>>,
+    mut ai_q: Query<
+        (
+            &Transform,
+            &AiState,
+            &AiMovement,
+            &MaxLinearSpeed,
+            &mut LinearVelocity,
+            Option<&Perception>,
+            Option<&mut PatrolRoute>,
+            Option<&mut AttackCycle>,
+            Option<&mut PathFollower>,
+            Option<&StatusEffects>,
+        ),
+        (Without<Culled>, Without<Emerging>),
+    >,
+) {
+    let player_pos = player_transform.translation.truncate();
+    let steering = (STEERING * time.delta_secs()).min(1.);
+
+    for (
+        transform,
+        state,
+        movement,
+        max_speed,
+        mut velocity,
+        perception,
+        route,
+        attack_cycle,
+        mut path,
+        statuses,
+    ) in ai_q.iter_mut()
+    {
+        if statuses.is_some_and(StatusEffects::is_stunned) {
+            velocity.0 = velocity.0.lerp(Vec2::ZERO, steering);
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let to_player = (player_pos - position).normalize_or_zero();
+        // Around walls when following a path, straight there otherwise.
+        let mut head_to = |goal: Vec2| match path.as_deref_mut() {
+            Some(path) => path.direction(&nav_grid, position, goal, time.delta()),
+            None => (goal - position).normalize_or_zero(),
+        };
+
+        let desired = match state {
+            AiState::Idle => Vec2::ZERO,
+            AiState::Patrol => {
+                let Some(mut route) = route else {
+                    continue;
+                };
+                let Some(&waypoint) = route.waypoints.get(route.next) else {
+                    continue;
+                };
+                if position.distance(waypoint) <= WAYPOINT_REACHED_DISTANCE {
+                    route.next = (route.next + 1) % route.waypoints.len();
+                }
+                (waypoint - position).normalize_or_zero() * movement.patrol_speed
+            }
+            AiState::Chase => head_to(player_pos) * movement.chase_speed,
+            // Waits at the spot once there, until the investigation runs out.
+            AiState::Investigate => match perception.and_then(|perception| perception.last_seen) {
+                Some(last_seen) if position.distance(last_seen) > WAYPOINT_REACHED_DISTANCE => {
+                    head_to(last_seen) * movement.chase_speed
+                }
+                _ => Vec2::ZERO,
+            },
+            AiState::Distracted => match perception.and_then(|perception| perception.lure) {
+                Some(lure) if position.distance(lure) > LURE_GATHER_DISTANCE => {
+                    head_to(lure) * movement.chase_speed
+                }
+                _ => Vec2::ZERO,
+            },
+            AiState::Attack => {
+                if let Some(mut attack_cycle) = attack_cycle
+                    && attack_cycle.0.tick(time.delta()).just_finished()
+                {
+                    velocity.0 = to_player * movement.lunge_speed.min(max_speed.0);
+                    continue;
+                }
+                Vec2::ZERO
+            }
+        };
+
+        velocity.0 = velocity
+            .0
+            .lerp(desired.clamp_length_max(max_speed.0), steering);
+    }
+}
@@ -0,0 +1,79 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    collider::GameLayer,
+    player::{Aim, Player},
+    settings::GameSettings,
+    state::GameplaySet,
+};
+
+/// Far enough to cross the widest zoomed-out view.
+const AIM_LINE_RANGE: f32 = 200.;
+/// Starts clear of the player's own sprite.
+const AIM_LINE_OFFSET: f32 = 6.;
+const AIM_LINE_COLOR: Color = Color::srgba(1., 0.2, 0.2, 0.6);
+/// Radius of the ring marking where the aim line hits a wall.
+const AIM_LINE_MARK_RADIUS: f32 = 2.;
+
+/// An optional sight line from the player along their aim, cut off at the first wall,
+/// for aiming at the canvas's low resolution.
+pub struct AimLinePlugin;
+
+impl Plugin for AimLinePlugin {
+    fn build(&self, app: &mut App) {
+        // One canvas pixel wide; gizmos draw on the pixel-perfect layer by default.
+        app.insert_gizmo_config(
+            AimLineGizmos,
+            GizmoConfig {
+                line: GizmoLineConfig {
+                    width: 1.,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        app.add_systems(Update, draw_aim_line.in_set(GameplaySet));
+    }
+}
+
+/// One-pixel gizmos for previews over the game: the aim line, and blast radii.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct AimLineGizmos;
+
+/// Draws a ring of `radius` around `center`, snapped to whole canvas pixels so it
+/// doesn't shimmer as the center moves.
+pub fn draw_radius(gizmos: &mut Gizmos<AimLineGizmos>, center: Vec2, radius: f32, color: Color) {
+    // About one segment per 3 pixels of circumference.
+    let resolution = (radius * 2.).clamp(8., 64.) as u32;
+    gizmos
+        .circle_2d(center.round(), radius, color)
+        .resolution(resolution);
+}
+
+fn draw_aim_line(
+    settings: Res<GameSettings>,
+    spatial_query: SpatialQuery,
+    mut gizmos: Gizmos<AimLineGizmos>,
+    player: Single<(&GlobalTransform, &Aim), With<Player>>,
+) {
+    if !settings.aim_line {
+        return;
+    }
+
+    let (transform, aim) = *player;
+    let Ok(direction) = Dir2::new(aim.0) else {
+        return;
+    };
+
+    let start = transform.translation().truncate() + aim.0 * AIM_LINE_OFFSET;
+    let filter = SpatialQueryFilter::from_mask(GameLayer::Terrain);
+    let hit = spatial_query.cast_ray(start, direction, AIM_LINE_RANGE, true, &filter);
+    let length = hit.map_or(AIM_LINE_RANGE, |hit| hit.distance);
+    let end = start + aim.0 * length;
+
+    gizmos.line_2d(start, end, AIM_LINE_COLOR);
+    if hit.is_some() {
+        draw_radius(&mut gizmos, end, AIM_LINE_MARK_RADIUS, AIM_LINE_COLOR);
+    }
+}
@@ -0,0 +1,104 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{culling::Culled, state::GameplaySet};
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, animate_sprites.in_set(GameplaySet));
+    }
+}
+
+/// Steps a sprite through a range of its texture atlas frames. The sprite needs a
+/// texture atlas; the animation does nothing without one.
+#[derive(Component, Clone, Debug)]
+pub struct SpriteAnimation {
+    pub first: usize,
+    pub last: usize,
+    pub looping: bool,
+    frame: usize,
+    frame_timer: Timer,
+}
+
+impl SpriteAnimation {
+    pub fn new(first: usize, last: usize, fps: f32, looping: bool) -> Self {
+        Self {
+            first,
+            last,
+            looping,
+            frame: first,
+            frame_timer: Timer::from_seconds(1. / fps, TimerMode::Repeating),
+        }
+    }
+
+    /// Whether this plays the same frames as `other`, regardless of how far along
+    /// either is. Used to avoid restarting an animation that's already playing.
+    pub fn plays_same_frames(&self, other: &SpriteAnimation) -> bool {
+        self.first == other.first && self.last == other.last
+    }
+}
+
+/// A sprite sheet with one row of frames per facing direction, starting with facing
+/// right and going counterclockwise. Animation frames index into the current row, so
+/// the same animation plays whichever way the sprite faces.
+#[derive(Component, Clone, Debug)]
+pub struct DirectionalSprite {
+    /// Usually 4 or 8.
+    pub directions: usize,
+    pub frames_per_direction: usize,
+    direction: usize,
+}
+
+impl DirectionalSprite {
+    pub fn new(directions: usize, frames_per_direction: usize) -> Self {
+        Self {
+            directions,
+            frames_per_direction,
+            direction: 0,
+        }
+    }
+
+    /// Picks the row whose direction is closest to `aim`.
+    pub fn face(&mut self, aim: Vec2) {
+        let sector = TAU / self.directions as f32;
+        let angle = aim.to_angle().rem_euclid(TAU);
+        self.direction = (angle / sector).round() as usize % self.directions;
+    }
+
+    fn first_frame(&self) -> usize {
+        self.direction * self.frames_per_direction
+    }
+}
+
+fn animate_sprites(
+    time: Res<Time>,
+    mut sprite_q: Query<
+        (
+            &mut SpriteAnimation,
+            &mut Sprite,
+            Option<&DirectionalSprite>,
+        ),
+        Without<Culled>,
+    >,
+) {
+    for (mut animation, mut sprite, directional) in sprite_q.iter_mut() {
+        let Some(atlas) = &mut sprite.texture_atlas else {
+            continue;
+        };
+
+        animation.frame_timer.tick(time.delta());
+        for _ in 0..animation.frame_timer.times_finished_this_tick() {
+            if animation.frame < animation.last {
+                animation.frame += 1;
+            } else if animation.looping {
+                animation.frame = animation.first;
+            }
+        }
+
+        let row_start = directional.map_or(0, DirectionalSprite::first_frame);
+        atlas.index = row_start + animation.frame;
+    }
+}
@@ -0,0 +1,194 @@
+use avian2d::prelude::*;
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{
+    audio::SoundEffect,
+    camera::MouseWorldPos,
+    input::{DeviceInput, PlayerInput, buffer_presses},
+    level::LevelSource,
+    replay::{Replay, apply_frames, playing_back},
+    rng::RunSeed,
+    settings::GameSettings,
+    state::{GameScoped, GameState, GameplaySet, NewGame},
+};
+
+const ATTRACT_REPLAY: &str = "replays/attract.replay.ron";
+/// Real seconds the main menu has to sit untouched before the attract mode starts.
+const IDLE_SECS: f32 = 20.;
+
+/// Plays a bundled replay behind the main menu once it's been left alone for a while,
+/// over and over, until the player touches anything. The game runs underneath the menu
+/// as it would while playing, with the replay in place of the player's input.
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<MenuMode>();
+        app.insert_resource(AttractIdle(Timer::from_seconds(IDLE_SECS, TimerMode::Once)));
+        app.init_resource::<AttractRun>();
+        app.add_systems(Startup, load_attract_replay);
+        app.add_systems(OnEnter(MenuMode::Interactive), reset_idle_timer);
+        app.add_systems(OnEnter(MenuMode::Attract), start_attract_run);
+        app.add_systems(OnExit(MenuMode::Attract), end_attract_run);
+        app.add_systems(
+            PreUpdate,
+            play_attract_input
+                .after(InputSystem)
+                .before(buffer_presses)
+                .run_if(in_state(MenuMode::Attract)),
+        );
+        app.add_systems(
+            Update,
+            (
+                start_attract_when_idle
+                    .run_if(in_state(MenuMode::Interactive).and(not(playing_back))),
+                (hold_in_menu.after(GameplaySet), loop_attract_run)
+                    .chain()
+                    .run_if(in_state(MenuMode::Attract)),
+            ),
+        );
+    }
+}
+
+/// What the main menu is doing. The menu only takes input while `Interactive`.
+#[derive(SubStates, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::MainMenu)]
+pub enum MenuMode {
+    #[default]
+    Interactive,
+    /// The bundled replay plays behind the menu.
+    Attract,
+}
+
+#[derive(Resource)]
+struct AttractReplay(Handle<Replay>);
+
+/// Counts the real time the main menu has gone untouched.
+#[derive(Resource)]
+struct AttractIdle(Timer);
+
+/// Where the attract mode is in the replay.
+#[derive(Resource, Default, Debug)]
+struct AttractRun {
+    frame: u32,
+    /// Index of the next entry in the replay's frames to apply.
+    next: usize,
+    /// Set once the replay ran out or the game ended, to start it over.
+    finished: bool,
+}
+
+fn load_attract_replay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AttractReplay(asset_server.load(ATTRACT_REPLAY)));
+}
+
+fn reset_idle_timer(mut idle: ResMut<AttractIdle>) {
+    idle.0.reset();
+}
+
+fn start_attract_when_idle(
+    time: Res<Time<Real>>,
+    settings: Res<GameSettings>,
+    replays: Res<Assets<Replay>>,
+    attract_replay: Res<AttractReplay>,
+    mut device_input: DeviceInput,
+    mut idle: ResMut<AttractIdle>,
+    mut next_mode: ResMut<NextState<MenuMode>>,
+) {
+    if device_input.any_activity() || !device_input.held_actions().is_empty() {
+        idle.0.reset();
+        return;
+    }
+    // A replay that failed to load just never starts.
+    if !idle.0.tick(time.delta()).finished()
+        || !settings.attract_mode
+        || !replays.contains(&attract_replay.0)
+    {
+        return;
+    }
+
+    next_mode.set(MenuMode::Attract);
+}
+
+/// Sets up a fresh game, seeded as the replay was recorded, the way a rematch is. It
+/// plays in whichever level is current, which is the one the bundled replay was
+/// recorded in unless a generated level was played since.
+fn start_attract_run(world: &mut World) {
+    let handle = world.resource::<AttractReplay>().0.clone();
+    let Some((seed, level)) = world
+        .resource::<Assets<Replay>>()
+        .get(&handle)
+        .map(|replay| (replay.seed, replay.level.clone()))
+    else {
+        return;
+    };
+    let level_source = world.resource::<LevelSource>();
+    if level.is_some_and(|level| level != *level_source) {
+        debug!("the attract replay was recorded in another level than {level_source:?}");
+    }
+
+    world.resource_mut::<RunSeed>().next = Some(seed);
+    world.run_schedule(NewGame);
+    world.resource_mut::<Time<Physics>>().unpause();
+    *world.resource_mut::<AttractRun>() = AttractRun::default();
+    info!("attract mode started");
+}
+
+/// Leaves nothing of the game behind, sounds included.
+fn end_attract_run(world: &mut World) {
+    let mut entity_q = world.query_filtered::<Entity, Or<(With<GameScoped>, With<SoundEffect>)>>();
+    let entities: Vec<Entity> = entity_q.iter(world).collect();
+    for entity in entities {
+        // Children are gone with their parents already.
+        if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn();
+        }
+    }
+    world.resource_mut::<Time<Physics>>().pause();
+}
+
+/// Feeds the replay in where the devices' input would go, and hands control back to the
+/// menu as soon as the player does anything. That input is used up on leaving the
+/// attract mode rather than also acting on the menu.
+fn play_attract_input(
+    replays: Res<Assets<Replay>>,
+    attract_replay: Res<AttractReplay>,
+    mut device_input: DeviceInput,
+    mut run: ResMut<AttractRun>,
+    mut player_input: ResMut<PlayerInput>,
+    mut mouse_world_pos: ResMut<MouseWorldPos>,
+    mut next_mode: ResMut<NextState<MenuMode>>,
+) {
+    let held = device_input.held_actions();
+    if device_input.any_activity() || !held.is_empty() {
+        player_input.swallow(held);
+        next_mode.set(MenuMode::Interactive);
+        info!("attract mode cancelled");
+        return;
+    }
+
+    let Some(replay) = replays.get(&attract_replay.0) else {
+        return;
+    };
+    let AttractRun { frame, next, .. } = &mut *run;
+    apply_frames(replay, frame, next, &mut player_input, &mut mouse_world_pos);
+    if *next >= replay.frames.len() {
+        run.finished = true;
+    }
+}
+
+/// Dying, winning or opening a screen would take the game out of the menu, so the run
+/// is started over instead.
+fn hold_in_menu(mut next_state: ResMut<NextState<GameState>>, mut run: ResMut<AttractRun>) {
+    if let NextState::Pending(state) = &*next_state {
+        debug!("the attract run went to {state:?}, starting it over");
+        next_state.reset();
+        run.finished = true;
+    }
+}
+
+fn loop_attract_run(world: &mut World) {
+    if world.resource::<AttractRun>().finished {
+        end_attract_run(world);
+        start_attract_run(world);
+    }
+}
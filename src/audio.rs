@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use bevy::{
+    audio::{DefaultSpatialScale, SpatialScale, Volume},
+    prelude::*,
+};
+
+use crate::{
+    health::DamageEvent, player::Player, projectile::ProjectileHitEvent, settings::GameSettings,
+    state::GameplaySet,
+};
+
+/// World pixels per audio unit. Positional sounds fall off with the inverse square of
+/// the distance in audio units, so one this far away plays at full volume and one four
+/// times as far, well off screen, at a sixteenth.
+const PIXELS_PER_AUDIO_UNIT: f32 = 40.;
+/// Distance between the listener's ears in world pixels. Wider pans harder.
+const EAR_GAP: f32 = 32.;
+
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>();
+        app.insert_resource(DefaultSpatialScale(SpatialScale::new_2d(
+            1. / PIXELS_PER_AUDIO_UNIT,
+        )));
+        app.add_systems(Startup, (load_sfx, spawn_listener));
+        app.add_systems(
+            Update,
+            (
+                follow_player_with_listener,
+                play_hit_sounds.in_set(GameplaySet),
+                play_sfx,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    FlareIgnite,
+    Footstep,
+    /// An enemy noticing the player.
+    EnemyAlert,
+    /// Something with health taking damage.
+    Hit,
+    /// A projectile striking anything.
+    Impact,
+    Explosion,
+}
+
+impl Sfx {
+    const ALL: [Sfx; 6] = [
+        Sfx::FlareIgnite,
+        Sfx::Footstep,
+        Sfx::EnemyAlert,
+        Sfx::Hit,
+        Sfx::Impact,
+        Sfx::Explosion,
+    ];
+
+    fn path(self) -> &'static str {
+        match self {
+            Sfx::FlareIgnite => "sfx/flare_ignite.ogg",
+            Sfx::Footstep => "sfx/footstep.ogg",
+            Sfx::EnemyAlert => "sfx/enemy_alert.ogg",
+            Sfx::Hit => "sfx/hit.ogg",
+            Sfx::Impact => "sfx/impact.ogg",
+            Sfx::Explosion => "sfx/explosion.ogg",
+        }
+    }
+
+    /// Mix level relative to the other effects, before the player's volume settings.
+    fn volume(self) -> f32 {
+        match self {
+            Sfx::Footstep => 0.4,
+            Sfx::FlareIgnite | Sfx::Impact => 0.7,
+            Sfx::EnemyAlert | Sfx::Hit | Sfx::Explosion => 1.,
+        }
+    }
+}
+
+/// Plays a sound effect once. Sounds with a position pan and fade with their distance
+/// from the player; the rest, like the player's own footsteps, play as they are.
+#[derive(Event, Debug)]
+pub struct PlaySfx {
+    pub sfx: Sfx,
+    pub position: Option<Vec2>,
+}
+
+impl PlaySfx {
+    pub fn new(sfx: Sfx) -> Self {
+        Self {
+            sfx,
+            position: None,
+        }
+    }
+
+    pub fn at(sfx: Sfx, position: Vec2) -> Self {
+        Self {
+            sfx,
+            position: Some(position),
+        }
+    }
+}
+
+/// A playing sound effect, despawned once it's done.
+#[derive(Component)]
+pub struct SoundEffect;
+
+/// Hears positional sounds from the player's position. It's kept apart from the
+/// player so the ears don't turn with the player's aim.
+#[derive(Component)]
+struct Listener;
+
+/// Loaded up front so the first time a sound plays isn't delayed by loading it.
+#[derive(Resource)]
+struct SfxHandles(HashMap<Sfx, Handle<AudioSource>>);
+
+fn load_sfx(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = Sfx::ALL
+        .into_iter()
+        .map(|sfx| (sfx, asset_server.load(sfx.path())))
+        .collect();
+    commands.insert_resource(SfxHandles(handles));
+}
+
+fn spawn_listener(mut commands: Commands) {
+    commands.spawn((
+        Listener,
+        Name::new("Audio listener"),
+        SpatialListener::new(EAR_GAP),
+        Transform::default(),
+    ));
+}
+
+fn follow_player_with_listener(
+    player_transform: Single<&Transform, With<Player>>,
+    mut listener_transform: Single<&mut Transform, (With<Listener>, Without<Player>)>,
+) {
+    listener_transform.translation = player_transform.translation;
+}
+
+fn play_hit_sounds(
+    mut damage_events: EventReader<DamageEvent>,
+    mut projectile_hit_events: EventReader<ProjectileHitEvent>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    transform_q: Query<&GlobalTransform>,
+) {
+    for event in damage_events.read() {
+        let position = transform_q
+            .get(event.target)
+            .map(|transform| transform.translation().truncate());
+        sfx_events.write(match position {
+            Ok(position) => PlaySfx::at(Sfx::Hit, position),
+            Err(_) => PlaySfx::new(Sfx::Hit),
+        });
+    }
+    for event in projectile_hit_events.read() {
+        sfx_events.write(PlaySfx::at(Sfx::Impact, event.position));
+    }
+}
+
+fn play_sfx(
+    mut commands: Commands,
+    mut sfx_events: EventReader<PlaySfx>,
+    settings: Res<GameSettings>,
+    handles: Res<SfxHandles>,
+) {
+    for PlaySfx { sfx, position } in sfx_events.read() {
+        let volume = sfx.volume() * settings.sfx_volume * settings.master_volume;
+        let playback = PlaybackSettings::DESPAWN.with_volume(Volume::Linear(volume));
+        let mut sound = commands.spawn((
+            SoundEffect,
+            Name::new(format!("{sfx:?} sound")),
+            AudioPlayer(handles.0[sfx].clone()),
+        ));
+        match position {
+            Some(position) => sound.insert((
+                playback.with_spatial(true),
+                Transform::from_translation(position.extend(0.)),
+            )),
+            None => sound.insert(playback),
+        };
+    }
+}
@@ -0,0 +1,472 @@
+use std::f32::consts::TAU;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    collider::{ColliderShape, GameLayer, collider_shape},
+    cutscene::{ActiveCutscene, Cutscene, PlayCutscene},
+    enemy::{Emerging, GRUNT, spawn_enemy},
+    health::{Damage, DespawnOnDeath, Health},
+    level::{LevelExits, MarkerKind, MarkerSpawnSet, SpawnMarker},
+    outline::Outlined,
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    projectile::{EnemyProjectile, Homing, Projectiles},
+    screen_shake::AddTrauma,
+    state::GameplaySet,
+    status::{Inflicts, StatusEffect, StatusEffects},
+    transition::RoomScoped,
+    vision::HiddenWhenUnseen,
+};
+
+const BOSS_HEALTH: f32 = 600.;
+const BOSS_MAX_SPEED: f32 = 200.;
+/// The fight starts once the player comes this close.
+const ENGAGE_RADIUS: f32 = 64.;
+/// Projectiles start this far from the boss's center, outside its collider.
+const MUZZLE_OFFSET: f32 = 20.;
+/// Minions appear on a circle this far from the boss.
+const SUMMON_RADIUS: f32 = 28.;
+/// Summoning stops while this many minions are still alive.
+const MAX_MINIONS: usize = 4;
+/// Seals overhang the exits they cover, so the player can't reach the exit sensor.
+const SEAL_MARGIN: f32 = 4.;
+const PHASE_CHANGE_TRAUMA: f32 = 0.5;
+/// Spread projectiles slow the player down to this speed for a moment.
+const SPREAD_SLOW: Inflicts = Inflicts {
+    effect: StatusEffect::Slow { max_speed: 50. },
+    secs: 1.5,
+};
+/// Each phase takes over once health drops to its `health` fraction.
+const BOSS_PHASES: &[BossPhase] = &[
+    BossPhase {
+        health: 1.,
+        speed: 25.,
+        attack_interval: 2.,
+        outline: Color::srgb(0.55, 0.2, 0.7),
+        attacks: &[
+            BossAttack::Spread {
+                count: 5,
+                arc: 0.8,
+                speed: 90.,
+                damage: 8.,
+            },
+            BossAttack::Charge {
+                speed: 160.,
+                secs: 0.6,
+            },
+        ],
+    },
+    BossPhase {
+        health: 0.66,
+        speed: 30.,
+        attack_interval: 1.6,
+        outline: Color::srgb(0.9, 0.45, 0.1),
+        attacks: &[
+            BossAttack::Spread {
+                count: 7,
+                arc: 1.1,
+                speed: 100.,
+                damage: 8.,
+            },
+            BossAttack::Summon { count: 2 },
+            BossAttack::Charge {
+                speed: 180.,
+                secs: 0.6,
+            },
+        ],
+    },
+    BossPhase {
+        health: 0.33,
+        speed: 40.,
+        attack_interval: 1.2,
+        outline: Color::srgb(1., 0.15, 0.15),
+        attacks: &[
+            BossAttack::Spread {
+                count: 9,
+                arc: 1.4,
+                speed: 110.,
+                damage: 10.,
+            },
+            BossAttack::Charge {
+                speed: 200.,
+                secs: 0.5,
+            },
+            BossAttack::Seeker {
+                speed: 70.,
+                damage: 14.,
+                turn_rate: 1.5,
+            },
+            BossAttack::Summon { count: 3 },
+            BossAttack::Charge {
+                speed: 200.,
+                secs: 0.5,
+            },
+        ],
+    },
+];
+
+/// A boss fight: the boss waits until the player comes close, seals the room's exits,
+/// then goes through its attack patterns, switching to harder ones as it loses
+/// health. The exits open again once it's dead.
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_level_bosses.in_set(MarkerSpawnSet));
+        app.add_systems(
+            Update,
+            (engage_bosses, unseal_arena).chain().in_set(GameplaySet),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (update_boss_phases, boss_attacks, update_boss_charges)
+                .chain()
+                .run_if(not(resource_exists::<ActiveCutscene>))
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Boss {
+    /// Index into `BOSS_PHASES`.
+    pub phase: usize,
+    /// Whether the fight has started. Exits stay sealed while an engaged boss lives.
+    pub engaged: bool,
+    /// Plays as the fight starts.
+    entrance: Option<Handle<Cutscene>>,
+    /// Index of the next attack in the phase's list, wrapping around.
+    next_attack: usize,
+    cooldown: Timer,
+}
+
+#[derive(Debug)]
+struct BossPhase {
+    health: f32,
+    /// Speed in px/s the boss closes in on the player with between attacks.
+    speed: f32,
+    attack_interval: f32,
+    outline: Color,
+    attacks: &'static [BossAttack],
+}
+
+#[derive(Clone, Copy, Debug)]
+enum BossAttack {
+    /// Rushes at where the player is, hurting them on contact.
+    Charge { speed: f32, secs: f32 },
+    /// Fires `count` projectiles fanned over `arc` radians, centered on the player.
+    Spread {
+        count: u32,
+        arc: f32,
+        speed: f32,
+        damage: f32,
+    },
+    /// Calls in regular enemies around the boss.
+    Summon { count: u32 },
+    /// Fires a slow projectile that follows the player, turning at up to `turn_rate`
+    /// radians per second.
+    Seeker {
+        speed: f32,
+        damage: f32,
+        turn_rate: f32,
+    },
+}
+
+/// A charge in progress. The boss keeps its heading until the timer runs out.
+#[derive(Component, Debug)]
+struct BossCharge {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+/// An enemy the boss summoned.
+#[derive(Component)]
+struct Minion;
+
+/// Blocks a level exit during a boss fight.
+#[derive(Component)]
+struct ArenaSeal;
+
+fn spawn_level_bosses(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        if let MarkerKind::Boss { cutscene } = &marker.kind {
+            let entrance = cutscene.as_ref().map(|path| asset_server.load(path));
+            spawn_boss(
+                &mut commands,
+                &asset_server,
+                marker.position,
+                entrance,
+                false,
+            );
+        }
+    }
+}
+
+/// A boss that's already `engaged` goes straight for the player, without sealing the
+/// exits or playing its `entrance`.
+pub fn spawn_boss(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    position: Vec2,
+    entrance: Option<Handle<Cutscene>>,
+    engaged: bool,
+) -> Entity {
+    commands
+        .spawn((
+            Boss {
+                phase: 0,
+                engaged,
+                entrance,
+                next_attack: 0,
+                cooldown: Timer::from_seconds(BOSS_PHASES[0].attack_interval, TimerMode::Once),
+            },
+            Name::new("Boss"),
+            Outlined {
+                color: BOSS_PHASES[0].outline,
+            },
+            RoomScoped,
+            HiddenWhenUnseen,
+            Transform::from_translation(position.extend(0.)),
+            Sprite::from_image(asset_server.load("boss.png")),
+            PIXEL_PERFECT_LAYER,
+            (
+                RigidBody::Dynamic,
+                collider_shape("boss").bundle(),
+                GameLayer::Enemy.collision_layers(),
+                LinearVelocity::ZERO,
+                LockedAxes::ROTATION_LOCKED,
+                MaxLinearSpeed(BOSS_MAX_SPEED),
+            ),
+            (
+                Health::new(BOSS_HEALTH),
+                Damage {
+                    amount: 15.,
+                    knockback: 200.,
+                },
+                DespawnOnDeath,
+                // Burns and slows take, but stuns are ignored; only regular enemies
+                // check for them.
+                StatusEffects::default(),
+            ),
+        ))
+        .id()
+}
+
+fn engage_bosses(
+    mut commands: Commands,
+    exits: Res<LevelExits>,
+    asset_server: Res<AssetServer>,
+    mut cutscene_events: EventWriter<PlayCutscene>,
+    player_transform: Single<&Transform, With<Player>>,
+    mut boss_q: Query<(&mut Boss, &Transform, &mut LinearVelocity), Without<Player>>,
+    seal_q: Query<(), With<ArenaSeal>>,
+) {
+    let player_pos = player_transform.translation.truncate();
+    let mut sealed = !seal_q.is_empty();
+
+    for (mut boss, transform, mut velocity) in boss_q.iter_mut() {
+        if boss.engaged || transform.translation.truncate().distance(player_pos) > ENGAGE_RADIUS {
+            continue;
+        }
+
+        boss.engaged = true;
+        velocity.0 = Vec2::ZERO;
+        if let Some(entrance) = &boss.entrance {
+            cutscene_events.write(PlayCutscene(entrance.clone()));
+        }
+
+        if sealed {
+            continue;
+        }
+        sealed = true;
+        for exit in &exits.0 {
+            let size = exit.area.size() + SEAL_MARGIN * 2.;
+            commands.spawn((
+                ArenaSeal,
+                Name::new("Arena seal"),
+                RoomScoped,
+                Transform::from_translation(exit.area.center().extend(0.)),
+                Sprite {
+                    image: asset_server.load("arena_seal.png"),
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                RigidBody::Static,
+                ColliderShape::Rectangle {
+                    width: size.x,
+                    height: size.y,
+                }
+                .bundle(),
+                GameLayer::Terrain.collision_layers(),
+                PIXEL_PERFECT_LAYER,
+            ));
+        }
+    }
+}
+
+/// Phases only ever advance, even if the boss were to heal.
+fn update_boss_phases(
+    mut trauma_events: EventWriter<AddTrauma>,
+    mut boss_q: Query<(&mut Boss, &Health, &mut Outlined)>,
+) {
+    for (mut boss, health, mut outlined) in boss_q.iter_mut() {
+        let fraction = health.current / health.max;
+        let phase = BOSS_PHASES
+            .iter()
+            .rposition(|phase| fraction <= phase.health)
+            .unwrap_or_default();
+        if phase <= boss.phase {
+            continue;
+        }
+
+        info!("boss enters phase {}", phase + 1);
+        boss.phase = phase;
+        boss.next_attack = 0;
+        boss.cooldown = Timer::from_seconds(BOSS_PHASES[phase].attack_interval, TimerMode::Once);
+        outlined.color = BOSS_PHASES[phase].outline;
+        trauma_events.write(AddTrauma(PHASE_CHANGE_TRAUMA));
+    }
+}
+
+/// Between attacks, engaged bosses walk toward the player.
+#[allow(clippy::type_complexity)]
+fn boss_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut projectiles: Projectiles,
+    player: Single<(Entity, &Transform), With<Player>>,
+    mut boss_q: Query<
+        (Entity, &mut Boss, &Transform, &mut LinearVelocity),
+        (Without<BossCharge>, Without<Player>, Without<Emerging>),
+    >,
+    minion_q: Query<(), With<Minion>>,
+) {
+    let (player_entity, player_transform) = *player;
+    let player_pos = player_transform.translation.truncate();
+
+    for (entity, mut boss, transform, mut velocity) in boss_q.iter_mut() {
+        if !boss.engaged {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let aim = (player_pos - position).normalize_or_zero();
+        let phase = &BOSS_PHASES[boss.phase];
+        velocity.0 = aim * phase.speed;
+
+        if !boss.cooldown.tick(time.delta()).finished() {
+            continue;
+        }
+        boss.cooldown = Timer::from_seconds(phase.attack_interval, TimerMode::Once);
+        let attack = phase.attacks[boss.next_attack % phase.attacks.len()];
+        boss.next_attack += 1;
+
+        match attack {
+            BossAttack::Charge { speed, secs } => {
+                commands.entity(entity).insert(BossCharge {
+                    velocity: aim * speed,
+                    timer: Timer::from_seconds(secs, TimerMode::Once),
+                });
+            }
+            BossAttack::Spread {
+                count,
+                arc,
+                speed,
+                damage,
+            } => {
+                let step = if count > 1 {
+                    arc / (count - 1) as f32
+                } else {
+                    0.
+                };
+                for index in 0..count {
+                    let angle = index as f32 * step - arc / 2.;
+                    let direction = Vec2::from_angle(angle).rotate(aim);
+                    projectiles
+                        .spawn(
+                            &mut commands,
+                            entity,
+                            position + direction * MUZZLE_OFFSET,
+                            direction * speed,
+                            damage,
+                        )
+                        .insert((
+                            EnemyProjectile,
+                            GameLayer::EnemyProjectile.collision_layers(),
+                            SPREAD_SLOW,
+                        ));
+                }
+            }
+            BossAttack::Seeker {
+                speed,
+                damage,
+                turn_rate,
+            } => {
+                projectiles
+                    .spawn(
+                        &mut commands,
+                        entity,
+                        position + aim * MUZZLE_OFFSET,
+                        aim * speed,
+                        damage,
+                    )
+                    .insert((
+                        EnemyProjectile,
+                        GameLayer::EnemyProjectile.collision_layers(),
+                        Homing {
+                            target: player_entity,
+                            turn_rate,
+                        },
+                    ));
+            }
+            BossAttack::Summon { count } => {
+                let room = MAX_MINIONS.saturating_sub(minion_q.iter().count());
+                for index in 0..(count as usize).min(room) {
+                    let angle = index as f32 * TAU / count as f32;
+                    let minion = spawn_enemy(
+                        &mut commands,
+                        &asset_server,
+                        &GRUNT,
+                        position + Vec2::from_angle(angle) * SUMMON_RADIUS,
+                    );
+                    commands.entity(minion).insert(Minion);
+                }
+            }
+        }
+    }
+}
+
+fn update_boss_charges(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut charge_q: Query<(Entity, &mut BossCharge, &mut LinearVelocity)>,
+) {
+    for (entity, mut charge, mut velocity) in charge_q.iter_mut() {
+        if charge.timer.tick(time.delta()).finished() {
+            velocity.0 = Vec2::ZERO;
+            commands.entity(entity).remove::<BossCharge>();
+        } else {
+            velocity.0 = charge.velocity;
+        }
+    }
+}
+
+fn unseal_arena(
+    mut commands: Commands,
+    boss_q: Query<&Boss>,
+    seal_q: Query<Entity, With<ArenaSeal>>,
+) {
+    if boss_q.iter().any(|boss| boss.engaged) {
+        return;
+    }
+    for entity in seal_q.iter() {
+        commands.entity(entity).despawn();
+    }
+}
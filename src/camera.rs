@@ -0,0 +1,138 @@
+use bevy::{prelude::*, transform::TransformSystem, window::PrimaryWindow};
+
+use crate::{
+    attract::MenuMode,
+    input::{Action, PlayerInput},
+    pixel_perfect::{Canvas, CanvasCoords, PixelCamera, PixelCanvasConfig, ZOOM_LEVELS},
+    player::Player,
+    replay::playing_back,
+    settings::GameSettings,
+};
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MouseWorldPos(Vec2::new(0., 0.)));
+        app.insert_resource(CameraFollow {
+            position: Vec2::ZERO,
+            smoothing: 8.,
+        });
+        app.init_resource::<CameraBounds>();
+        app.add_systems(
+            Update,
+            (
+                update_mouse_world_pos
+                    .run_if(not(playing_back).and(not(in_state(MenuMode::Attract)))),
+                zoom_camera,
+            ),
+        );
+        app.add_systems(
+            PostUpdate,
+            follow_player.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct MouseWorldPos(pub Vec2);
+
+/// Where the pixel camera would be if it weren't snapped to whole pixels.
+#[derive(Resource, Debug)]
+pub struct CameraFollow {
+    pub position: Vec2,
+    /// Higher values catch up to the target faster.
+    pub smoothing: f32,
+}
+
+/// World-space area the pixel camera may show, usually the current level's extents.
+/// `None` lets the camera go anywhere.
+#[derive(Resource, Debug, Default)]
+pub struct CameraBounds(pub Option<Rect>);
+
+impl CameraBounds {
+    /// Clamps a camera center so the whole view stays inside the bounds. Axes where the
+    /// bounds are smaller than the view are centered instead.
+    pub fn clamp(&self, center: Vec2, view_size: Vec2) -> Vec2 {
+        let Some(bounds) = self.0 else {
+            return center;
+        };
+
+        let half_view = view_size / 2.;
+        let min = bounds.min + half_view;
+        let max = bounds.max - half_view;
+        let bounds_center = bounds.center();
+
+        Vec2::new(
+            if min.x <= max.x {
+                center.x.clamp(min.x, max.x)
+            } else {
+                bounds_center.x
+            },
+            if min.y <= max.y {
+                center.y.clamp(min.y, max.y)
+            } else {
+                bounds_center.y
+            },
+        )
+    }
+}
+
+pub fn update_mouse_world_pos(
+    mut mouse_world_pos: ResMut<MouseWorldPos>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    coords: CanvasCoords,
+) {
+    let Some(cursor_pos) = window.cursor_position() else {
+        return;
+    };
+
+    mouse_world_pos.0 = coords.canvas_to_world(coords.screen_to_canvas(cursor_pos));
+}
+
+pub fn follow_player(
+    time: Res<Time>,
+    config: Res<PixelCanvasConfig>,
+    bounds: Res<CameraBounds>,
+    mut follow: ResMut<CameraFollow>,
+    player_transform: Single<&GlobalTransform, With<Player>>,
+    mut pixel_camera_transform: Single<&mut Transform, (With<PixelCamera>, Without<Canvas>)>,
+    mut canvas_transform: Single<&mut Transform, With<Canvas>>,
+) {
+    let target = player_transform.translation().truncate();
+    let blend = 1. - (-follow.smoothing * time.delta_secs()).exp();
+    follow.position = bounds.clamp(follow.position.lerp(target, blend), config.size_f32());
+
+    // The low-res camera only moves in whole pixels; the upscaled canvas makes up the
+    // sub-pixel remainder so motion still looks smooth.
+    let snapped = follow.position.round();
+    let remainder = follow.position - snapped;
+
+    pixel_camera_transform.translation = snapped.extend(pixel_camera_transform.translation.z);
+    canvas_transform.translation =
+        (-remainder * canvas_transform.scale.truncate()).extend(canvas_transform.translation.z);
+}
+
+/// Zooming steps the resolution setting, which the canvas follows.
+fn zoom_camera(input: Res<PlayerInput>, mut settings: ResMut<GameSettings>) {
+    let zoom_in = input.just_pressed(Action::ZoomIn);
+    let zoom_out = input.just_pressed(Action::ZoomOut);
+    if zoom_in == zoom_out {
+        return;
+    }
+
+    let current = ZOOM_LEVELS
+        .iter()
+        .position(|level| *level == settings.resolution)
+        .unwrap_or(0);
+    let next = if zoom_in {
+        current.saturating_sub(1)
+    } else {
+        (current + 1).min(ZOOM_LEVELS.len() - 1)
+    };
+
+    if next != current {
+        settings.resolution = ZOOM_LEVELS[next];
+        settings.save();
+    }
+}
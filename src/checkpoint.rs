@@ -0,0 +1,216 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    camera::CameraFollow,
+    collider::{GameLayer, collider_shape},
+    flare::{Flare, FlareInventory},
+    health::{DeathEvent, Health},
+    input::PlayerInput,
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    pool::EntityPool,
+    state::{GameplaySet, NewGame, game_over_on_player_death},
+    transition::{RoomEntered, RoomScoped},
+};
+
+const CHECKPOINT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const CHECKPOINT_ACTIVE_COLOR: Color = Color::srgb(0.4, 1.0, 0.5);
+/// Grace period after respawning, so whatever killed the player can't do it again
+/// straight away.
+const RESPAWN_INVULNERABILITY_SECS: f32 = 2.;
+
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RespawnPoint>();
+        app.add_event::<CheckpointReached>();
+        app.add_systems(NewGame, clear_respawn_point);
+        app.add_systems(
+            Update,
+            (
+                spawn_checkpoints.in_set(MarkerSpawnSet),
+                respawn_point_on_room_entry.run_if(on_event::<RoomEntered>),
+            ),
+        );
+        app.add_systems(
+            Update,
+            (
+                reach_checkpoints,
+                highlight_active_checkpoint,
+                respawn_at_checkpoint.before(game_over_on_player_death),
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Checkpoint;
+
+/// Sent when the player reaches a checkpoint that wasn't already the active one.
+#[derive(Event, Debug)]
+pub struct CheckpointReached {
+    pub position: Vec2,
+}
+
+/// What the player gets back when respawning.
+#[derive(Clone, Copy, Debug)]
+pub struct RespawnSnapshot {
+    pub position: Vec2,
+    pub flares: u32,
+}
+
+/// Where the player respawns after dying. Until a checkpoint is reached, or a room
+/// entered, dying ends the game.
+#[derive(Resource, Default, Debug)]
+pub struct RespawnPoint(pub Option<RespawnSnapshot>);
+
+type RespawningPlayer<'a> = (
+    &'a mut Transform,
+    &'a mut LinearVelocity,
+    &'a mut Health,
+    &'a mut FlareInventory,
+);
+
+fn clear_respawn_point(mut respawn_point: ResMut<RespawnPoint>) {
+    respawn_point.0 = None;
+}
+
+fn spawn_checkpoints(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        if marker.kind != MarkerKind::Checkpoint {
+            continue;
+        }
+
+        let position = marker.position;
+        commands.spawn((
+            Checkpoint,
+            Name::new("Checkpoint"),
+            RoomScoped,
+            Transform::from_translation(position.extend(-1.)),
+            Sprite {
+                image: asset_server.load("checkpoint.png"),
+                color: CHECKPOINT_COLOR,
+                ..Default::default()
+            },
+            RigidBody::Static,
+            collider_shape("checkpoint").bundle(),
+            GameLayer::Trigger.collision_layers(),
+            Sensor,
+            CollisionEventsEnabled,
+            PIXEL_PERFECT_LAYER,
+        ));
+    }
+}
+
+/// Arriving in a room counts as reaching a checkpoint at the entrance.
+fn respawn_point_on_room_entry(
+    mut respawn_point: ResMut<RespawnPoint>,
+    player: Single<(&Transform, &FlareInventory), With<Player>>,
+) {
+    let (transform, flares) = *player;
+    respawn_point.0 = Some(RespawnSnapshot {
+        position: transform.translation.truncate(),
+        flares: flares.count,
+    });
+}
+
+fn reach_checkpoints(
+    mut collision_events: EventReader<CollisionStarted>,
+    mut reached_events: EventWriter<CheckpointReached>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    checkpoint_q: Query<&Transform, With<Checkpoint>>,
+    player_q: Query<&FlareInventory, With<Player>>,
+) {
+    for CollisionStarted(a, b) in collision_events.read() {
+        let (checkpoint_entity, player_entity) = if checkpoint_q.contains(*a) {
+            (*a, *b)
+        } else {
+            (*b, *a)
+        };
+        let (Ok(checkpoint_transform), Ok(flares)) = (
+            checkpoint_q.get(checkpoint_entity),
+            player_q.get(player_entity),
+        ) else {
+            continue;
+        };
+
+        let position = checkpoint_transform.translation.truncate();
+        if respawn_point
+            .0
+            .is_none_or(|snapshot| snapshot.position != position)
+        {
+            reached_events.write(CheckpointReached { position });
+        }
+        respawn_point.0 = Some(RespawnSnapshot {
+            position,
+            flares: flares.count,
+        });
+        info!("checkpoint reached");
+    }
+}
+
+fn highlight_active_checkpoint(
+    respawn_point: Res<RespawnPoint>,
+    mut checkpoint_q: Query<(&Transform, &mut Sprite), With<Checkpoint>>,
+) {
+    if !respawn_point.is_changed() {
+        return;
+    }
+
+    let active_position = respawn_point.0.map(|snapshot| snapshot.position);
+    for (transform, mut sprite) in checkpoint_q.iter_mut() {
+        sprite.color = if Some(transform.translation.truncate()) == active_position {
+            CHECKPOINT_ACTIVE_COLOR
+        } else {
+            CHECKPOINT_COLOR
+        };
+    }
+}
+
+/// Brings a dead player back at the respawn point with full health. Runs before the
+/// game over check, which only fires if the player is still dead.
+#[allow(clippy::too_many_arguments)]
+pub fn respawn_at_checkpoint(
+    mut commands: Commands,
+    mut player_input: ResMut<PlayerInput>,
+    mut death_events: EventReader<DeathEvent>,
+    respawn_point: Res<RespawnPoint>,
+    mut camera_follow: ResMut<CameraFollow>,
+    mut flare_pool: ResMut<EntityPool<Flare>>,
+    flare_q: Query<Entity, With<Flare>>,
+    mut player_q: Query<RespawningPlayer, With<Player>>,
+) {
+    let Some(snapshot) = respawn_point.0 else {
+        return;
+    };
+
+    for event in death_events.read() {
+        let Ok((mut transform, mut velocity, mut health, mut flares)) =
+            player_q.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        transform.translation = snapshot.position.extend(transform.translation.z);
+        velocity.0 = Vec2::ZERO;
+        health.current = health.max;
+        health.grant_invulnerability(RESPAWN_INVULNERABILITY_SECS);
+        flares.count = snapshot.flares;
+        camera_follow.position = snapshot.position;
+        player_input.clear_buffer();
+
+        for flare in flare_q.iter() {
+            flare_pool.release(&mut commands, flare);
+        }
+        info!("respawned at checkpoint");
+    }
+}
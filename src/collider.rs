@@ -0,0 +1,239 @@
+use std::{collections::HashMap, fmt, sync::LazyLock};
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Every entity's collider shape, from `assets/colliders.ron`. The file is built into
+/// the game, so a mistake in it stops the game as it starts rather than when the entity
+/// first spawns.
+static COLLIDER_SHAPES: LazyLock<HashMap<String, ColliderShape>> = LazyLock::new(|| {
+    parse_collider_shapes(include_str!("../assets/colliders.ron"))
+        .unwrap_or_else(|error| panic!("assets/colliders.ron: {error}"))
+});
+
+pub struct ColliderPlugin;
+
+impl Plugin for ColliderPlugin {
+    fn build(&self, app: &mut App) {
+        LazyLock::force(&COLLIDER_SHAPES);
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, check_collider_sizes);
+    }
+}
+
+/// Capsules stand upright, `length` being the straight part between the round ends.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum ColliderShape {
+    Circle { radius: f32 },
+    Capsule { radius: f32, length: f32 },
+    Rectangle { width: f32, height: f32 },
+}
+
+impl ColliderShape {
+    pub fn collider(self) -> Collider {
+        match self {
+            ColliderShape::Circle { radius } => Collider::circle(radius),
+            ColliderShape::Capsule { radius, length } => Collider::capsule(radius, length),
+            ColliderShape::Rectangle { width, height } => Collider::rectangle(width, height),
+        }
+    }
+
+    pub fn size(self) -> Vec2 {
+        match self {
+            ColliderShape::Circle { radius } => Vec2::splat(radius * 2.),
+            ColliderShape::Capsule { radius, length } => {
+                Vec2::new(radius * 2., length + radius * 2.)
+            }
+            ColliderShape::Rectangle { width, height } => Vec2::new(width, height),
+        }
+    }
+
+    pub fn bundle(self) -> impl Bundle {
+        (self.collider(), self, ColliderSizeUnchecked)
+    }
+}
+
+#[derive(Component)]
+struct ColliderSizeUnchecked;
+
+/// The collider shape for an entity, by its name in `assets/colliders.ron`.
+pub fn collider_shape(name: &str) -> ColliderShape {
+    match COLLIDER_SHAPES.get(name) {
+        Some(shape) => *shape,
+        None => panic!("assets/colliders.ron has no collider for `{name}`"),
+    }
+}
+
+#[derive(Debug)]
+pub enum ColliderShapesError {
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for ColliderShapesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for ColliderShapesError {}
+
+fn parse_collider_shapes(
+    text: &str,
+) -> Result<HashMap<String, ColliderShape>, ColliderShapesError> {
+    let shapes: HashMap<String, ColliderShape> =
+        ron::from_str(text).map_err(ColliderShapesError::Parse)?;
+    for (name, shape) in &shapes {
+        validate(name, *shape)?;
+    }
+    Ok(shapes)
+}
+
+/// Avian builds degenerate colliders from zero or negative sizes without complaint.
+fn validate(name: &str, shape: ColliderShape) -> Result<(), ColliderShapesError> {
+    let dimensions: &[(&str, f32)] = match shape {
+        ColliderShape::Circle { radius } => &[("radius", radius)],
+        ColliderShape::Capsule { radius, length } => &[("radius", radius), ("length", length)],
+        ColliderShape::Rectangle { width, height } => &[("width", width), ("height", height)],
+    };
+    for &(dimension, value) in dimensions {
+        // A capsule with no straight part is just a circle, but still a valid one.
+        let valid = if dimension == "length" {
+            value >= 0.
+        } else {
+            value > 0.
+        };
+        if !valid || !value.is_finite() {
+            return Err(ColliderShapesError::Invalid(format!(
+                "`{name}` has a {dimension} of {value}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[derive(PhysicsLayer, Default, Clone, Copy, Debug)]
+pub enum GameLayer {
+    #[default]
+    Default,
+    Player,
+    Enemy,
+    Projectile,
+    /// Projectiles fired by enemies, which hit the player instead.
+    EnemyProjectile,
+    /// Melee swings, which hit enemies and catch enemy projectiles.
+    MeleeSwing,
+    /// Thrown things that bounce off walls, like flares and grenades.
+    Flare,
+    Terrain,
+    Pickup,
+    /// Sensors that react to the player, like level exits.
+    Trigger,
+    /// Sensors that hurt or swallow whatever walks into them, like spikes and pits.
+    Hazard,
+}
+
+impl GameLayer {
+    /// Membership in this layer plus the layers it is allowed to touch.
+    pub fn collision_layers(self) -> CollisionLayers {
+        let filters = match self {
+            GameLayer::Default | GameLayer::Terrain => LayerMask::ALL,
+            GameLayer::Player => [
+                GameLayer::Default,
+                GameLayer::Enemy,
+                GameLayer::Terrain,
+                GameLayer::Pickup,
+                GameLayer::Trigger,
+                GameLayer::EnemyProjectile,
+                GameLayer::Hazard,
+            ]
+            .into(),
+            GameLayer::Enemy => [
+                GameLayer::Default,
+                GameLayer::Player,
+                GameLayer::Enemy,
+                GameLayer::Projectile,
+                GameLayer::MeleeSwing,
+                GameLayer::Terrain,
+                GameLayer::Hazard,
+            ]
+            .into(),
+            GameLayer::Projectile => {
+                [GameLayer::Default, GameLayer::Enemy, GameLayer::Terrain].into()
+            }
+            GameLayer::EnemyProjectile => [
+                GameLayer::Default,
+                GameLayer::Player,
+                GameLayer::MeleeSwing,
+                GameLayer::Terrain,
+            ]
+            .into(),
+            GameLayer::MeleeSwing => [
+                GameLayer::Default,
+                GameLayer::Enemy,
+                GameLayer::EnemyProjectile,
+                GameLayer::Terrain,
+            ]
+            .into(),
+            GameLayer::Flare => [GameLayer::Default, GameLayer::Terrain].into(),
+            GameLayer::Pickup | GameLayer::Trigger => GameLayer::Player.into(),
+            GameLayer::Hazard => [GameLayer::Default, GameLayer::Player, GameLayer::Enemy].into(),
+        };
+
+        CollisionLayers::new(self, filters)
+    }
+}
+
+#[cfg(debug_assertions)]
+fn check_collider_sizes(
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    unchecked_q: Query<
+        (Entity, &ColliderShape, &Sprite, Option<&Name>),
+        With<ColliderSizeUnchecked>,
+    >,
+) {
+    for (entity, shape, sprite, name) in unchecked_q.iter() {
+        let Some(image) = images.get(&sprite.image) else {
+            continue;
+        };
+
+        let image_size = image.size_f32();
+        let collider_size = shape.size();
+        if collider_size.x > image_size.x * 2. || collider_size.y > image_size.y * 2. {
+            warn!(
+                "collider {:?} on {} is much larger than its {}x{} sprite",
+                shape,
+                name.map_or_else(|| entity.to_string(), |name| name.to_string()),
+                image_size.x,
+                image_size.y,
+            );
+        }
+
+        commands.entity(entity).remove::<ColliderSizeUnchecked>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_shapes_are_valid() {
+        let shapes = parse_collider_shapes(include_str!("../assets/colliders.ron")).unwrap();
+        assert_eq!(shapes["player"], ColliderShape::Circle { radius: 9. });
+    }
+
+    #[test]
+    fn invalid_shape_names_its_entity() {
+        let error = parse_collider_shapes(
+            r#"{ "player": Circle(radius: 9.0), "charger": Capsule(radius: -2.0, length: 6.0) }"#,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "`charger` has a radius of -2");
+    }
+}
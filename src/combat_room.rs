@@ -0,0 +1,264 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint::respawn_at_checkpoint,
+    door::{Door, Sealed, seal_door, unseal_door},
+    enemy::{Emerging, GRUNT, spawn_enemy},
+    health::{DeathEvent, Health},
+    level::{LevelMarker, LevelMarkers, LevelSource, MarkerKind, MarkerSpawnSet, SpawnMarker},
+    pickup::{Pickup, spawn_pickup},
+    player::Player,
+    rng::GameRng,
+    state::{GameplaySet, NewGame},
+    transition::RoomScoped,
+};
+
+/// How far in the player has to be before the doors shut, so they don't shut on the
+/// player in the doorway.
+const ENTRY_DEPTH: f32 = 12.;
+/// Doors this far outside a room's edge still belong to it, for doorways drawn along
+/// the edge rather than inside it.
+const DOORWAY_MARGIN: f32 = 8.;
+/// One of these is left in the middle of a cleared room.
+const ROOM_REWARDS: [Pickup; 3] = [Pickup::Health(25.), Pickup::Ammo(30), Pickup::Flares(2)];
+
+/// Rooms that lock the player in with their enemies. Stepping in shuts the doors
+/// around it and brings out the enemies placed inside, and killing the last of them
+/// opens the doors again and leaves a reward in the middle.
+pub struct CombatRoomPlugin;
+
+impl Plugin for CombatRoomPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClearedRooms>();
+        app.add_systems(NewGame, reset_cleared_rooms);
+        app.add_systems(Update, spawn_level_combat_rooms.in_set(MarkerSpawnSet));
+        app.add_systems(
+            Update,
+            (
+                clear_combat_rooms,
+                start_combat_rooms,
+                reset_combat_rooms_on_respawn.after(respawn_at_checkpoint),
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct CombatRoom {
+    pub area: Rect,
+    pub state: CombatRoomState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CombatRoomState {
+    /// For the player to step in.
+    Waiting,
+    Fighting,
+    Cleared,
+}
+
+/// An enemy brought out by the combat room entity it holds.
+#[derive(Component, Debug)]
+pub struct CombatRoomMember(pub Entity);
+
+/// The combat rooms won this game, so going back into one doesn't start its fight
+/// over. Saved games keep them.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ClearedRooms(pub Vec<ClearedRoom>);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClearedRoom {
+    pub level: LevelSource,
+    /// The room's center, in whole pixels.
+    pub center: [i32; 2],
+}
+
+impl ClearedRoom {
+    fn new(level: &LevelSource, area: Rect) -> Self {
+        Self {
+            level: level.clone(),
+            center: area.center().round().as_ivec2().to_array(),
+        }
+    }
+}
+
+fn combat_room_area(marker: &LevelMarker) -> Option<Rect> {
+    match marker.kind {
+        MarkerKind::CombatRoom { size } => {
+            Some(Rect::from_center_size(marker.position, size.as_vec2()))
+        }
+        _ => None,
+    }
+}
+
+/// The areas of the level's combat rooms.
+pub fn combat_room_areas(markers: &LevelMarkers) -> impl Iterator<Item = Rect> + '_ {
+    markers.0.iter().filter_map(combat_room_area)
+}
+
+fn reset_cleared_rooms(mut cleared: ResMut<ClearedRooms>) {
+    cleared.0.clear();
+}
+
+/// Rooms already in `ClearedRooms` start out cleared.
+fn spawn_level_combat_rooms(
+    mut commands: Commands,
+    level_source: Res<LevelSource>,
+    cleared: Res<ClearedRooms>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for area in marker_events
+        .read()
+        .filter_map(|SpawnMarker(marker)| combat_room_area(marker))
+    {
+        let state = if cleared.0.contains(&ClearedRoom::new(&level_source, area)) {
+            CombatRoomState::Cleared
+        } else {
+            CombatRoomState::Waiting
+        };
+        commands.spawn((
+            CombatRoom { area, state },
+            Name::new("Combat room"),
+            RoomScoped,
+            Transform::from_translation(area.center().extend(0.)),
+        ));
+    }
+}
+
+fn is_doorway(area: Rect, door_transform: &Transform) -> bool {
+    area.inflate(DOORWAY_MARGIN)
+        .contains(door_transform.translation.truncate())
+}
+
+fn start_combat_rooms(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    markers: Res<LevelMarkers>,
+    player_transform: Single<&Transform, With<Player>>,
+    mut room_q: Query<(Entity, &mut CombatRoom)>,
+    mut door_q: Query<(Entity, &Transform, &mut Door, &mut Sprite), Without<Sealed>>,
+) {
+    let player_pos = player_transform.translation.truncate();
+
+    for (room_entity, mut room) in room_q.iter_mut() {
+        if room.state != CombatRoomState::Waiting
+            || !room.area.inflate(-ENTRY_DEPTH).contains(player_pos)
+        {
+            continue;
+        }
+
+        room.state = CombatRoomState::Fighting;
+        for (door_entity, transform, mut door, mut sprite) in door_q.iter_mut() {
+            if is_doorway(room.area, transform) {
+                seal_door(
+                    &mut commands,
+                    &asset_server,
+                    door_entity,
+                    &mut door,
+                    &mut sprite,
+                );
+            }
+        }
+        for position in markers
+            .positions(MarkerKind::EnemySpawn)
+            .filter(|position| room.area.contains(*position))
+        {
+            let enemy = spawn_enemy(&mut commands, &asset_server, &GRUNT, position);
+            commands
+                .entity(enemy)
+                .insert((CombatRoomMember(room_entity), Emerging::default()));
+        }
+        info!("combat room started");
+    }
+}
+
+/// Members only exist from the frame after the fight starts, so this runs before
+/// `start_combat_rooms` to not find the room empty in between.
+#[allow(clippy::too_many_arguments)]
+fn clear_combat_rooms(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_source: Res<LevelSource>,
+    mut rng: ResMut<GameRng>,
+    mut cleared: ResMut<ClearedRooms>,
+    mut room_q: Query<(Entity, &mut CombatRoom)>,
+    member_q: Query<&CombatRoomMember>,
+    mut door_q: Query<(Entity, &Transform, &mut Door, &mut Sprite, &Sealed)>,
+) {
+    for (room_entity, mut room) in room_q.iter_mut() {
+        if room.state != CombatRoomState::Fighting
+            || member_q.iter().any(|member| member.0 == room_entity)
+        {
+            continue;
+        }
+
+        room.state = CombatRoomState::Cleared;
+        for (door_entity, transform, mut door, mut sprite, sealed) in door_q.iter_mut() {
+            if is_doorway(room.area, transform) {
+                unseal_door(
+                    &mut commands,
+                    &asset_server,
+                    door_entity,
+                    &mut door,
+                    &mut sprite,
+                    sealed,
+                );
+            }
+        }
+        let reward = ROOM_REWARDS[rng.gen_range(0..ROOM_REWARDS.len())];
+        spawn_pickup(&mut commands, &asset_server, reward, room.area.center());
+        cleared.0.push(ClearedRoom::new(&level_source, room.area));
+        info!("combat room cleared");
+    }
+}
+
+/// Coming back at a checkpoint after dying mid-fight starts the fight over: the room's
+/// enemies go, and its doors open until the player steps back in.
+fn reset_combat_rooms_on_respawn(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut death_events: EventReader<DeathEvent>,
+    player_q: Query<&Health, With<Player>>,
+    mut room_q: Query<(Entity, &mut CombatRoom)>,
+    member_q: Query<(Entity, &CombatRoomMember)>,
+    mut door_q: Query<(Entity, &Transform, &mut Door, &mut Sprite, &Sealed)>,
+) {
+    let respawned = death_events.read().any(|event| {
+        player_q
+            .get(event.entity)
+            .is_ok_and(|health| !health.is_dead())
+    });
+    if !respawned {
+        return;
+    }
+
+    for (room_entity, mut room) in room_q.iter_mut() {
+        if room.state != CombatRoomState::Fighting {
+            continue;
+        }
+
+        room.state = CombatRoomState::Waiting;
+        for (member, _) in member_q
+            .iter()
+            .filter(|(_, member)| member.0 == room_entity)
+        {
+            commands.entity(member).despawn();
+        }
+        for (door_entity, transform, mut door, mut sprite, sealed) in door_q.iter_mut() {
+            if is_doorway(room.area, transform) {
+                unseal_door(
+                    &mut commands,
+                    &asset_server,
+                    door_entity,
+                    &mut door,
+                    &mut sprite,
+                    sealed,
+                );
+            }
+        }
+    }
+}
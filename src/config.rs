@@ -0,0 +1,68 @@
+use std::{env, fmt, fs, io, path::PathBuf};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+const APP_DIR_NAME: &str = "untitled-game";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::Serialize(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+/// Per-user directory the game keeps its config files in, following each platform's
+/// convention. Falls back to the working directory when no home is known.
+pub fn config_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    base.unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_DIR_NAME)
+}
+
+/// Reads a RON config file. A missing file is `Ok(None)` so callers can fall back to
+/// their defaults without treating a first launch as an error.
+pub fn load_ron<T: DeserializeOwned>(file_name: &str) -> Result<Option<T>, ConfigError> {
+    let contents = match fs::read_to_string(config_dir().join(file_name)) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(ConfigError::Io(error)),
+    };
+
+    ron::from_str(&contents)
+        .map(Some)
+        .map_err(ConfigError::Parse)
+}
+
+pub fn save_ron<T: Serialize>(file_name: &str, value: &T) -> Result<(), ConfigError> {
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(ConfigError::Serialize)?;
+
+    let dir = config_dir();
+    fs::create_dir_all(&dir).map_err(ConfigError::Io)?;
+    fs::write(dir.join(file_name), contents).map_err(ConfigError::Io)
+}
+
+/// For `#[serde(default = "one")]` on multipliers, which leave things as they are when
+/// they're missing.
+pub fn one() -> f32 {
+    1.
+}
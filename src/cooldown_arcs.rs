@@ -0,0 +1,188 @@
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{MouseWorldPos, update_mouse_world_pos},
+    crosshair::snap_to_pixel,
+    dash::DashCooldown,
+    flare::FlareInventory,
+    grenade::GrenadeInventory,
+    input::{Action, InputBindings},
+    melee::MeleeAttack,
+    pixel_perfect::{CanvasCoords, HIGH_RES_LAYER, PixelCanvasConfig},
+    player::Player,
+    settings::GameSettings,
+    state::GameState,
+};
+
+/// Around the crosshair's 7 pixels, in canvas pixels.
+const ARC_RADIUS: f32 = 6.;
+/// Between neighboring arcs, in radians.
+const ARC_GAP: f32 = 0.35;
+/// In screen pixels, so the arcs stay thin however far the canvas is scaled up.
+const ARC_WIDTH: f32 = 2.;
+const ARC_TRACK: Color = Color::srgba(0., 0., 0., 0.35);
+const ARC_FLASH: Color = Color::WHITE;
+const FLASH_SECS: f32 = 0.25;
+/// Where the cluster sits from the bottom-right corner in `CooldownArcs::Corner`, in
+/// canvas pixels.
+const CORNER_OFFSET: Vec2 = Vec2::new(-10., 10.);
+
+/// Arcs around the crosshair, one per ability cooldown, that fill up as the cooldowns
+/// run out and flash when the ability is ready again. They're drawn on the high-res
+/// layer, so they stay round at the canvas' low resolution.
+pub struct CooldownArcsPlugin;
+
+impl Plugin for CooldownArcsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_gizmo_config(
+            CooldownArcGizmos,
+            GizmoConfig {
+                line: GizmoLineConfig {
+                    width: ARC_WIDTH,
+                    ..Default::default()
+                },
+                render_layers: HIGH_RES_LAYER,
+                ..Default::default()
+            },
+        );
+        app.add_systems(
+            Update,
+            draw_cooldown_arcs
+                .after(update_mouse_world_pos)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Where the cooldown arcs go.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CooldownArcs {
+    #[default]
+    Crosshair,
+    /// In the bottom-right corner of the canvas, out of the way of aiming.
+    Corner,
+    Off,
+}
+
+impl CooldownArcs {
+    pub const ALL: [CooldownArcs; 3] = [
+        CooldownArcs::Crosshair,
+        CooldownArcs::Corner,
+        CooldownArcs::Off,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CooldownArcs::Crosshair => "Crosshair",
+            CooldownArcs::Corner => "Corner",
+            CooldownArcs::Off => "Off",
+        }
+    }
+}
+
+/// A player ability's cooldown, read from the timer the ability itself runs on.
+pub trait AbilityCooldown: Component {
+    /// The action using the ability.
+    const ACTION: Action;
+    /// Tints its arc.
+    const COLOR: Color;
+
+    /// Finished while the ability is ready.
+    fn cooldown(&self) -> &Timer;
+
+    fn remaining(&self) -> Duration {
+        self.cooldown().remaining()
+    }
+
+    fn total(&self) -> Duration {
+        self.cooldown().duration()
+    }
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct CooldownArcGizmos;
+
+/// How far the cooldown has recovered, from 0 to 1. `None` when the player doesn't
+/// have the ability, or has nothing bound to use it with.
+fn recovered<T: AbilityCooldown>(ability: Option<&T>, bindings: &InputBindings) -> Option<f32> {
+    let ability = ability?;
+    if bindings.bindings(T::ACTION).is_empty() {
+        return None;
+    }
+    if ability.total().is_zero() {
+        return Some(1.);
+    }
+    Some(1. - ability.remaining().as_secs_f32() / ability.total().as_secs_f32())
+}
+
+/// The gamepad moves the crosshair through `MouseWorldPos` too, so it gets the arcs
+/// wherever it orbits to.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn draw_cooldown_arcs(
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    config: Res<PixelCanvasConfig>,
+    bindings: Res<InputBindings>,
+    mouse_world_pos: Res<MouseWorldPos>,
+    coords: CanvasCoords,
+    mut gizmos: Gizmos<CooldownArcGizmos>,
+    mut recovering: Local<[bool; 4]>,
+    mut flashes: Local<[f32; 4]>,
+    player: Single<
+        (
+            Option<&DashCooldown>,
+            Option<&MeleeAttack>,
+            Option<&FlareInventory>,
+            Option<&GrenadeInventory>,
+        ),
+        With<Player>,
+    >,
+) {
+    let (dash, melee, flare, grenade) = *player;
+    // Clockwise from the top: dash, melee, flare, grenade.
+    let arcs = [
+        (recovered(dash, &bindings), DashCooldown::COLOR),
+        (recovered(melee, &bindings), MeleeAttack::COLOR),
+        (recovered(flare, &bindings), FlareInventory::COLOR),
+        (recovered(grenade, &bindings), GrenadeInventory::COLOR),
+    ];
+
+    let center = match settings.cooldown_arcs {
+        CooldownArcs::Crosshair => coords.world_to_canvas(snap_to_pixel(mouse_world_pos.0)),
+        CooldownArcs::Corner => config.size_f32() * Vec2::new(0.5, -0.5) + CORNER_OFFSET,
+        CooldownArcs::Off => return,
+    };
+    let center = coords.canvas_to_high_res(center);
+    let radius = ARC_RADIUS * coords.scale().min_element();
+    let sweep = FRAC_PI_2 - ARC_GAP;
+
+    for (index, (recovered, color)) in arcs.into_iter().enumerate() {
+        let was_recovering = std::mem::replace(
+            &mut recovering[index],
+            recovered.is_some_and(|recovered| recovered < 1.),
+        );
+        let Some(recovered) = recovered else {
+            flashes[index] = 0.;
+            continue;
+        };
+        if was_recovering && recovered >= 1. {
+            flashes[index] = FLASH_SECS;
+        }
+
+        // Arcs start at the top and turn counterclockwise, so each one is laid out
+        // from its clockwise end.
+        let start = -(index as f32 + 1.) * FRAC_PI_2 + ARC_GAP / 2.;
+        let isometry = Isometry2d::new(center, Rot2::radians(start));
+        if recovered < 1. {
+            gizmos.arc_2d(isometry, sweep, radius, ARC_TRACK);
+            gizmos.arc_2d(isometry, sweep * recovered, radius, color);
+        } else if flashes[index] > 0. {
+            let alpha = flashes[index] / FLASH_SECS;
+            gizmos.arc_2d(isometry, sweep, radius, ARC_FLASH.with_alpha(alpha));
+            flashes[index] = (flashes[index] - time.delta_secs()).max(0.);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    window::PrimaryWindow,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{MouseWorldPos, update_mouse_world_pos},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    settings::GameSettings,
+    state::GameState,
+};
+
+/// Above the lightmap, so the crosshair shows in the dark too.
+const CROSSHAIR_Z: f32 = 60.;
+/// Side of every crosshair pattern, in canvas pixels. Odd, so there's a center pixel.
+const CROSSHAIR_SIZE: usize = 7;
+
+/// Draws a crosshair on the canvas at the mouse in place of the OS cursor while playing.
+/// Everywhere else, menus included, the OS cursor is back.
+pub struct CrosshairPlugin;
+
+impl Plugin for CrosshairPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_crosshair);
+        app.add_systems(
+            Update,
+            (
+                show_cursor_outside_gameplay.run_if(state_changed::<GameState>),
+                move_crosshair
+                    .after(update_mouse_world_pos)
+                    .run_if(in_state(GameState::Playing)),
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrosshairStyle {
+    #[default]
+    Cross,
+    Dot,
+    Ring,
+}
+
+impl CrosshairStyle {
+    pub const ALL: [CrosshairStyle; 3] = [
+        CrosshairStyle::Cross,
+        CrosshairStyle::Dot,
+        CrosshairStyle::Ring,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CrosshairStyle::Cross => "Cross",
+            CrosshairStyle::Dot => "Dot",
+            CrosshairStyle::Ring => "Ring",
+        }
+    }
+
+    /// Rows from the top; `#` marks a drawn pixel.
+    #[rustfmt::skip]
+    fn pattern(self) -> [&'static str; CROSSHAIR_SIZE] {
+        match self {
+            CrosshairStyle::Cross => [
+                "   #   ",
+                "   #   ",
+                "       ",
+                "## # ##",
+                "       ",
+                "   #   ",
+                "   #   ",
+            ],
+            CrosshairStyle::Dot => [
+                "       ",
+                "       ",
+                "   #   ",
+                "  ###  ",
+                "   #   ",
+                "       ",
+                "       ",
+            ],
+            CrosshairStyle::Ring => [
+                "  ###  ",
+                " #   # ",
+                "#     #",
+                "#  #  #",
+                "#     #",
+                " #   # ",
+                "  ###  ",
+            ],
+        }
+    }
+
+    fn image(self) -> Image {
+        let data = self
+            .pattern()
+            .iter()
+            .flat_map(|row| row.chars())
+            .flat_map(|pixel| match pixel {
+                '#' => [255; 4],
+                _ => [0; 4],
+            })
+            .collect();
+
+        Image::new(
+            Extent3d {
+                width: CROSSHAIR_SIZE as u32,
+                height: CROSSHAIR_SIZE as u32,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrosshairColor {
+    #[default]
+    White,
+    Red,
+    Green,
+    Yellow,
+}
+
+impl CrosshairColor {
+    pub const ALL: [CrosshairColor; 4] = [
+        CrosshairColor::White,
+        CrosshairColor::Red,
+        CrosshairColor::Green,
+        CrosshairColor::Yellow,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CrosshairColor::White => "White",
+            CrosshairColor::Red => "Red",
+            CrosshairColor::Green => "Green",
+            CrosshairColor::Yellow => "Yellow",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            CrosshairColor::White => Color::WHITE,
+            CrosshairColor::Red => Color::srgb(1., 0.25, 0.25),
+            CrosshairColor::Green => Color::srgb(0.3, 1., 0.4),
+            CrosshairColor::Yellow => Color::srgb(1., 0.9, 0.3),
+        }
+    }
+}
+
+#[derive(Component)]
+struct Crosshair;
+
+/// Respawned every time play resumes, so it's gone from pause and other menus and picks
+/// up any change to the settings.
+fn spawn_crosshair(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    mouse_world_pos: Res<MouseWorldPos>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    commands.spawn((
+        Crosshair,
+        Name::new("Crosshair"),
+        Sprite {
+            color: settings.crosshair_color.color(),
+            ..Sprite::from_image(images.add(settings.crosshair.image()))
+        },
+        Transform::from_translation(snap_to_pixel(mouse_world_pos.0).extend(CROSSHAIR_Z)),
+        PIXEL_PERFECT_LAYER,
+        StateScoped(GameState::Playing),
+    ));
+}
+
+/// Centers on the canvas pixel under `position`, so the crosshair's pixels line up with
+/// the canvas grid.
+pub fn snap_to_pixel(position: Vec2) -> Vec2 {
+    position.floor() + 0.5
+}
+
+fn show_cursor_outside_gameplay(
+    state: Res<State<GameState>>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    window.cursor_options.visible = *state.get() != GameState::Playing;
+}
+
+fn move_crosshair(
+    mouse_world_pos: Res<MouseWorldPos>,
+    mut transform: Single<&mut Transform, With<Crosshair>>,
+) {
+    transform.translation = snap_to_pixel(mouse_world_pos.0).extend(CROSSHAIR_Z);
+}
@@ -0,0 +1,80 @@
+use avian2d::{dynamics::sleeping::WakeUpBody, prelude::*};
+use bevy::prelude::*;
+
+use crate::{
+    ai::{AiSet, AiState},
+    animation::SpriteAnimation,
+    particle::ParticleEmitter,
+    pixel_perfect::{CanvasCoords, PixelCanvasConfig},
+    player::Player,
+    state::GameplaySet,
+};
+
+/// How far outside the view, in pixels, entities get culled.
+const CULL_MARGIN: f32 = 96.;
+/// How far outside the view culled entities wake up again. Less than `CULL_MARGIN`, so
+/// entities right at the edge don't flip back and forth.
+const ACTIVATE_MARGIN: f32 = 64.;
+
+/// Pauses AI, sprite animation and particle emitters far outside the camera's view,
+/// and puts their bodies to sleep, so big levels only cost what's near the player.
+pub struct CullingPlugin;
+
+impl Plugin for CullingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            cull_offscreen_entities
+                .before(AiSet::Transition)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Far enough out of view that the systems that would update it skip it.
+#[derive(Component)]
+pub struct Culled;
+
+/// Enemies already after the player are left running, so they can still catch up.
+#[allow(clippy::type_complexity)]
+fn cull_offscreen_entities(
+    mut commands: Commands,
+    config: Res<PixelCanvasConfig>,
+    coords: CanvasCoords,
+    mut entity_q: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            Has<Culled>,
+            Option<&AiState>,
+            Option<&mut LinearVelocity>,
+        ),
+        (
+            Or<(With<AiState>, With<SpriteAnimation>, With<ParticleEmitter>)>,
+            Without<Player>,
+        ),
+    >,
+) {
+    let view = Rect::from_center_size(Vec2::ZERO, config.size_f32());
+    let cull_area = view.inflate(CULL_MARGIN);
+    let activate_area = view.inflate(ACTIVATE_MARGIN);
+
+    for (entity, transform, culled, state, velocity) in entity_q.iter_mut() {
+        let position = coords.world_to_canvas(transform.translation().truncate());
+        let aggro = state.is_some_and(|state| state.is_aggro());
+
+        if culled && (activate_area.contains(position) || aggro) {
+            commands.entity(entity).remove::<Culled>();
+            commands.queue(WakeUpBody(entity));
+        } else if !culled && !cull_area.contains(position) && !aggro {
+            commands.entity(entity).insert(Culled);
+            // Only enemies sleep; projectiles and thrown flares keep flying.
+            if state.is_some()
+                && let Some(mut velocity) = velocity
+            {
+                velocity.0 = Vec2::ZERO;
+                commands.entity(entity).insert(Sleeping);
+            }
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use std::{collections::HashMap, fmt, io};
+
+use avian2d::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    animation::SpriteAnimation,
+    dialogue::{Dialogue, StartDialogue, start_dialogue},
+    level::{LevelMarkers, MarkerKind},
+    player::Player,
+    screen_shake::AddTrauma,
+    state::{GameplaySet, NewGame, PlayerControlSet},
+};
+
+/// Scripted sequences, like a level's intro or a boss walking in. The rest of the world
+/// keeps going while one plays, but the player can't act until it's over.
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Cutscene>();
+        app.init_asset_loader::<CutsceneLoader>();
+        app.add_event::<PlayCutscene>();
+        app.configure_sets(
+            Update,
+            PlayerControlSet.run_if(not(resource_exists::<ActiveCutscene>)),
+        );
+        app.configure_sets(
+            FixedUpdate,
+            PlayerControlSet.run_if(not(resource_exists::<ActiveCutscene>)),
+        );
+        app.add_systems(NewGame, (stop_cutscene, play_intro).chain());
+        app.add_systems(
+            Update,
+            (start_cutscene, run_cutscene)
+                .chain()
+                .before(start_dialogue)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// A sequence of steps, loaded from a `.cutscene.ron` file and played one after the
+/// other:
+///
+/// ```ron
+/// (
+///     steps: [
+///         Shake(0.6),
+///         Move(actor: Named("Warden"), to: (160., 96.), speed: 40.),
+///         Animate(actor: Named("Warden"), first: 4, last: 7, fps: 8., looping: false),
+///         Wait(0.5),
+///         Dialogue("dialogue/warden.dialogue.ron"),
+///     ],
+/// )
+/// ```
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Cutscene {
+    steps: Vec<CutsceneStep>,
+    /// Conversations the steps show, loaded along with the cutscene.
+    #[serde(skip)]
+    dialogues: HashMap<String, Handle<Dialogue>>,
+}
+
+#[derive(Debug, Deserialize)]
+enum CutsceneStep {
+    /// Walks the actor in a straight line, at `speed` pixels per second.
+    Move {
+        actor: Actor,
+        to: [f32; 2],
+        speed: f32,
+    },
+    /// Pauses the sequence for this many seconds.
+    Wait(f32),
+    /// Switches the actor's sprite animation without waiting for it.
+    Animate {
+        actor: Actor,
+        first: usize,
+        last: usize,
+        fps: f32,
+        looping: bool,
+    },
+    /// Shows a conversation and waits until it's over.
+    Dialogue(String),
+    /// Adds this much screen shake trauma.
+    Shake(f32),
+}
+
+#[derive(Debug, Deserialize)]
+enum Actor {
+    Player,
+    /// The first entity with this `Name`.
+    Named(String),
+}
+
+/// Plays a cutscene, unless one is already playing.
+#[derive(Event, Debug)]
+pub struct PlayCutscene(pub Handle<Cutscene>);
+
+#[derive(Default)]
+pub struct CutsceneLoader;
+
+#[derive(Debug)]
+pub enum CutsceneError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for CutsceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for CutsceneError {}
+
+fn parse_cutscene(bytes: &[u8]) -> Result<Cutscene, CutsceneError> {
+    ron::de::from_bytes(bytes).map_err(CutsceneError::Parse)
+}
+
+impl AssetLoader for CutsceneLoader {
+    type Asset = Cutscene;
+    type Settings = ();
+    type Error = CutsceneError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Cutscene, CutsceneError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(CutsceneError::Io)?;
+        let mut cutscene = parse_cutscene(&bytes)?;
+
+        for step in &cutscene.steps {
+            if let CutsceneStep::Dialogue(path) = step {
+                let handle = load_context.load(path);
+                cutscene.dialogues.insert(path.clone(), handle);
+            }
+        }
+        Ok(cutscene)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cutscene.ron"]
+    }
+}
+
+/// The cutscene playing, which may still be loading.
+#[derive(Resource, Debug)]
+pub struct ActiveCutscene {
+    cutscene: Handle<Cutscene>,
+    step: usize,
+    /// Seconds spent on the current step.
+    elapsed: f32,
+    /// Whether the current step has done what it does once, like opening a
+    /// conversation.
+    started: bool,
+}
+
+fn stop_cutscene(mut commands: Commands) {
+    commands.remove_resource::<ActiveCutscene>();
+}
+
+fn play_intro(
+    asset_server: Res<AssetServer>,
+    markers: Res<LevelMarkers>,
+    mut cutscene_events: EventWriter<PlayCutscene>,
+) {
+    for marker in &markers.0 {
+        if let MarkerKind::Intro { cutscene } = &marker.kind {
+            cutscene_events.write(PlayCutscene(asset_server.load(cutscene)));
+        }
+    }
+}
+
+fn start_cutscene(
+    mut commands: Commands,
+    mut cutscene_events: EventReader<PlayCutscene>,
+    active: Option<Res<ActiveCutscene>>,
+    mut player_velocity: Single<&mut LinearVelocity, With<Player>>,
+) {
+    let mut playing = active.is_some();
+    for PlayCutscene(handle) in cutscene_events.read() {
+        if playing {
+            warn!("a cutscene is already playing");
+            continue;
+        }
+
+        playing = true;
+        // Player input stops driving movement, so the player would drift otherwise.
+        player_velocity.0 = Vec2::ZERO;
+        commands.insert_resource(ActiveCutscene {
+            cutscene: handle.clone(),
+            step: 0,
+            elapsed: 0.,
+            started: false,
+        });
+    }
+}
+
+/// Runs as many steps as finish this frame. Steps whose actor is missing are skipped.
+#[allow(clippy::too_many_arguments)]
+fn run_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    cutscenes: Res<Assets<Cutscene>>,
+    active: Option<ResMut<ActiveCutscene>>,
+    mut trauma_events: EventWriter<AddTrauma>,
+    mut dialogue_events: EventWriter<StartDialogue>,
+    player: Single<Entity, With<Player>>,
+    actor_q: Query<(Entity, &Name)>,
+    mut body_q: Query<(&mut Transform, Option<&mut LinearVelocity>)>,
+) {
+    let Some(mut active) = active else {
+        return;
+    };
+    if asset_server
+        .recursive_dependency_load_state(&active.cutscene)
+        .is_failed()
+    {
+        error!("couldn't load the cutscene");
+        commands.remove_resource::<ActiveCutscene>();
+        return;
+    }
+    let Some(cutscene) = cutscenes.get(&active.cutscene) else {
+        return;
+    };
+    if !asset_server.is_loaded_with_dependencies(&active.cutscene) {
+        return;
+    }
+
+    let find_actor = |actor: &Actor| match actor {
+        Actor::Player => Some(*player),
+        Actor::Named(name) => actor_q
+            .iter()
+            .find(|(_, actor_name)| actor_name.as_str() == name)
+            .map(|(entity, _)| entity),
+    };
+
+    while let Some(step) = cutscene.steps.get(active.step) {
+        let finished = match step {
+            CutsceneStep::Move { actor, to, speed } => {
+                let target = Vec2::from_array(*to);
+                match find_actor(actor).and_then(|entity| body_q.get_mut(entity).ok()) {
+                    Some((mut transform, velocity)) => {
+                        let offset = target - transform.translation.truncate();
+                        let distance = speed * time.delta_secs();
+                        let arrived = offset.length() <= distance;
+                        let direction = offset.normalize_or_zero();
+                        if arrived {
+                            transform.translation = target.extend(transform.translation.z);
+                        }
+                        match velocity {
+                            Some(mut velocity) if arrived => velocity.0 = Vec2::ZERO,
+                            Some(mut velocity) => velocity.0 = direction * *speed,
+                            // Bodies without a velocity, like static ones, are moved
+                            // directly.
+                            None if !arrived => {
+                                transform.translation += (direction * distance).extend(0.)
+                            }
+                            None => {}
+                        }
+                        arrived
+                    }
+                    None => {
+                        warn!("cutscene actor {actor:?} is missing");
+                        true
+                    }
+                }
+            }
+            CutsceneStep::Wait(seconds) => {
+                active.elapsed += time.delta_secs();
+                active.elapsed >= *seconds
+            }
+            CutsceneStep::Animate {
+                actor,
+                first,
+                last,
+                fps,
+                looping,
+            } => {
+                if let Some(entity) = find_actor(actor) {
+                    commands
+                        .entity(entity)
+                        .insert(SpriteAnimation::new(*first, *last, *fps, *looping));
+                } else {
+                    warn!("cutscene actor {actor:?} is missing");
+                }
+                true
+            }
+            // The world is frozen during the conversation, so this runs again only
+            // once it's over.
+            CutsceneStep::Dialogue(path) => {
+                if !active.started {
+                    dialogue_events.write(StartDialogue(cutscene.dialogues[path].clone()));
+                }
+                active.started
+            }
+            CutsceneStep::Shake(trauma) => {
+                trauma_events.write(AddTrauma(*trauma));
+                true
+            }
+        };
+
+        if !finished {
+            active.started = true;
+            return;
+        }
+        active.step += 1;
+        active.elapsed = 0.;
+        active.started = false;
+    }
+
+    commands.remove_resource::<ActiveCutscene>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_cutscene_is_valid() {
+        let cutscene =
+            parse_cutscene(include_bytes!("../assets/cutscenes/warden.cutscene.ron")).unwrap();
+        assert!(cutscene.steps.iter().any(|step| matches!(
+            step,
+            CutsceneStep::Dialogue(path) if path == "dialogue/warden.dialogue.ron"
+        )));
+    }
+}
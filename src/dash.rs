@@ -0,0 +1,130 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    camera::MouseWorldPos,
+    cooldown_arcs::AbilityCooldown,
+    health::Health,
+    input::{Action, PlayerInput},
+    particle::{ParticleEffect, SpawnParticles},
+    player::Player,
+    state::{GameplaySet, PlayerControlSet},
+};
+
+const DASH_SPEED: f32 = 320.;
+const DASH_DURATION: f32 = 0.12;
+/// Extra invulnerability after the dash ends so landing next to an enemy isn't punished.
+const DASH_INVULNERABILITY_GRACE: f32 = 0.08;
+/// Kicked up behind the player on take-off.
+const DASH_DUST: ParticleEffect = ParticleEffect {
+    burst: 10,
+    rate: 0.,
+    min_speed: 10.,
+    max_speed: 40.,
+    spread: 0.9,
+    min_lifetime: 0.2,
+    max_lifetime: 0.45,
+    gravity: Vec2::ZERO,
+    drag: 5.,
+    start_color: Color::srgba(0.75, 0.7, 0.6, 0.8),
+    end_color: Color::srgba(0.5, 0.45, 0.4, 0.),
+};
+
+pub struct DashPlugin;
+
+impl Plugin for DashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            start_dash.in_set(PlayerControlSet).in_set(GameplaySet),
+        );
+        app.add_systems(FixedUpdate, update_dash.in_set(GameplaySet));
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct DashCooldown(pub Timer);
+
+impl DashCooldown {
+    pub fn new(secs: f32) -> Self {
+        let mut timer = Timer::from_seconds(secs, TimerMode::Once);
+        timer.tick(timer.duration());
+        Self(timer)
+    }
+}
+
+impl AbilityCooldown for DashCooldown {
+    const ACTION: Action = Action::Dash;
+    const COLOR: Color = Color::srgb(0.4, 0.8, 1.);
+
+    fn cooldown(&self) -> &Timer {
+        &self.0
+    }
+}
+
+/// Present while a dash is in progress; takes over the entity's velocity.
+#[derive(Component, Debug)]
+pub struct Dashing {
+    pub velocity: Vec2,
+    pub timer: Timer,
+}
+
+#[allow(clippy::type_complexity)]
+fn start_dash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut input: ResMut<PlayerInput>,
+    mouse_world_pos: Res<MouseWorldPos>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    player: Single<
+        (Entity, &Transform, &mut DashCooldown, Option<&mut Health>),
+        (With<Player>, Without<Dashing>),
+    >,
+) {
+    let (entity, transform, mut cooldown, health) = player.into_inner();
+    cooldown.0.tick(time.delta());
+
+    if !cooldown.0.finished() {
+        return;
+    }
+
+    let aim = mouse_world_pos.0 - transform.translation.truncate();
+    let Some(direction) = input
+        .movement()
+        .try_normalize()
+        .or_else(|| aim.try_normalize())
+    else {
+        return;
+    };
+    if !input.take_buffered(Action::Dash) {
+        return;
+    }
+
+    cooldown.0.reset();
+    if let Some(mut health) = health {
+        health.grant_invulnerability(DASH_DURATION + DASH_INVULNERABILITY_GRACE);
+    }
+    particle_events.write(SpawnParticles {
+        effect: DASH_DUST,
+        position: transform.translation.truncate(),
+        direction: -direction,
+    });
+    commands.entity(entity).insert(Dashing {
+        velocity: direction * DASH_SPEED,
+        timer: Timer::from_seconds(DASH_DURATION, TimerMode::Once),
+    });
+}
+
+fn update_dash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut dashing_q: Query<(Entity, &mut Dashing, &mut LinearVelocity)>,
+) {
+    for (entity, mut dashing, mut velocity) in dashing_q.iter_mut() {
+        velocity.0 = dashing.velocity;
+
+        if dashing.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Dashing>();
+        }
+    }
+}
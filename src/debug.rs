@@ -0,0 +1,161 @@
+use avian2d::prelude::*;
+use bevy::{
+    diagnostic::{DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    sprite::Anchor,
+};
+
+use crate::pixel_perfect::{CanvasText, PixelCanvasConfig};
+
+const DIAGNOSTICS_KEY: KeyCode = KeyCode::F2;
+const PHYSICS_DEBUG_KEY: KeyCode = KeyCode::F3;
+/// Refreshing every frame makes the numbers flicker too fast to read.
+const DIAGNOSTICS_REFRESH_SECS: f32 = 0.25;
+const DIAGNOSTICS_FONT_SIZE: f32 = 5.;
+/// Offset from the top-right corner of the canvas, leaving room for the HUD's wave text.
+const DIAGNOSTICS_MARGIN: Vec2 = Vec2::new(4., 12.);
+/// Length of the drawn velocity line per unit of speed.
+const VELOCITY_GIZMO_SCALE: f32 = 0.1;
+const VELOCITY_GIZMO_COLOR: Color = Color::srgb(0.3, 0.7, 1.0);
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            PhysicsDebugPlugin::default(),
+            FrameTimeDiagnosticsPlugin::default(),
+            EntityCountDiagnosticsPlugin,
+        ));
+        app.insert_resource(DebugSettings::default());
+        app.add_systems(Startup, spawn_diagnostics_overlay);
+        app.add_systems(
+            Update,
+            (
+                toggle_debug_views,
+                apply_debug_settings,
+                draw_velocities,
+                update_diagnostics_overlay,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct DebugSettings {
+    /// Collider outlines and velocity lines. Off until toggled.
+    pub physics_gizmos: bool,
+    /// FPS, frame time and entity counts in the top-right corner.
+    pub diagnostics_overlay: bool,
+}
+
+#[derive(Component)]
+struct DiagnosticsOverlay {
+    refresh: Timer,
+}
+
+/// Collider outline color for the physics debug view. The component is left out of
+/// release builds, where the outlines fall back to avian's default color.
+#[cfg(debug_assertions)]
+pub fn debug_render(collider_color: Color) -> impl Bundle {
+    DebugRender::default().with_collider_color(collider_color)
+}
+
+#[cfg(not(debug_assertions))]
+pub fn debug_render(_collider_color: Color) -> impl Bundle {}
+
+fn spawn_diagnostics_overlay(mut commands: Commands) {
+    commands.spawn((
+        DiagnosticsOverlay {
+            refresh: Timer::from_seconds(DIAGNOSTICS_REFRESH_SECS, TimerMode::Repeating),
+        },
+        Name::new("Diagnostics overlay"),
+        Text2d::default(),
+        TextLayout::new_with_justify(JustifyText::Right),
+        Anchor::TopRight,
+        CanvasText::new(Vec2::ZERO, DIAGNOSTICS_FONT_SIZE),
+        Transform::from_xyz(0., 0., 30.),
+        Visibility::Hidden,
+    ));
+}
+
+fn toggle_debug_views(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<DebugSettings>,
+) {
+    if keyboard_input.just_pressed(PHYSICS_DEBUG_KEY) {
+        settings.physics_gizmos = !settings.physics_gizmos;
+    }
+    if keyboard_input.just_pressed(DIAGNOSTICS_KEY) {
+        settings.diagnostics_overlay = !settings.diagnostics_overlay;
+    }
+}
+
+fn apply_debug_settings(settings: Res<DebugSettings>, mut config_store: ResMut<GizmoConfigStore>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let (config, _) = config_store.config_mut::<PhysicsGizmos>();
+    config.enabled = settings.physics_gizmos;
+}
+
+fn draw_velocities(
+    settings: Res<DebugSettings>,
+    mut gizmos: Gizmos,
+    body_q: Query<(&GlobalTransform, &LinearVelocity)>,
+) {
+    if !settings.physics_gizmos {
+        return;
+    }
+
+    for (transform, velocity) in body_q.iter() {
+        let start = transform.translation().truncate();
+        gizmos.line_2d(
+            start,
+            start + velocity.0 * VELOCITY_GIZMO_SCALE,
+            VELOCITY_GIZMO_COLOR,
+        );
+    }
+}
+
+fn update_diagnostics_overlay(
+    time: Res<Time>,
+    settings: Res<DebugSettings>,
+    config: Res<PixelCanvasConfig>,
+    diagnostics: Res<DiagnosticsStore>,
+    body_q: Query<(), With<RigidBody>>,
+    overlay: Single<(
+        &mut DiagnosticsOverlay,
+        &mut Text2d,
+        &mut CanvasText,
+        &mut Visibility,
+    )>,
+) {
+    let (mut overlay, mut text, mut canvas_text, mut visibility) = overlay.into_inner();
+    *visibility = if settings.diagnostics_overlay {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    if !settings.diagnostics_overlay || !overlay.refresh.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    canvas_text.position = config.size_f32() / 2. - DIAGNOSTICS_MARGIN;
+
+    let smoothed = |path| {
+        diagnostics
+            .get(path)
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .unwrap_or_default()
+    };
+    text.0 = format!(
+        "{:.0} fps\n{:.1} ms\n{} entities\n{} bodies",
+        smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+        smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME),
+        smoothed(&EntityCountDiagnosticsPlugin::ENTITY_COUNT),
+        body_q.iter().count(),
+    );
+}
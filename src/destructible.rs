@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    level::{Level, TileChanged, TileKind},
+    particle::{ParticleEffect, SpawnParticles},
+    state::GameplaySet,
+};
+
+/// Damage a cracked wall takes before it breaks.
+const CRACKED_TILE_HEALTH: f32 = 30.;
+const DEBRIS: ParticleEffect = ParticleEffect {
+    burst: 16,
+    rate: 0.,
+    min_speed: 15.,
+    max_speed: 60.,
+    spread: std::f32::consts::PI,
+    min_lifetime: 0.3,
+    max_lifetime: 0.7,
+    gravity: Vec2::ZERO,
+    drag: 5.,
+    start_color: Color::srgb(0.42, 0.4, 0.37),
+    end_color: Color::srgba(0.25, 0.24, 0.22, 0.),
+};
+
+/// Lets cracked walls be worn down by gunfire and blown open by explosions.
+pub struct DestructiblePlugin;
+
+impl Plugin for DestructiblePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageTiles>();
+        app.init_resource::<TileDamage>();
+        app.add_systems(
+            Update,
+            (
+                forget_tile_damage.run_if(resource_changed::<Level>),
+                damage_tiles.in_set(GameplaySet),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Damages the cracked walls within `radius` of `position`, the most where they're
+/// closest, falling off to nothing at the edge. A radius of 0 hits just the tile at
+/// `position` for the full amount.
+#[derive(Event, Debug)]
+pub struct DamageTiles {
+    pub position: Vec2,
+    pub radius: f32,
+    pub amount: f32,
+}
+
+/// Damage taken so far by cracked walls that are still standing.
+#[derive(Resource, Default)]
+struct TileDamage(HashMap<UVec2, f32>);
+
+/// Breaking a wall doesn't count as a change to the level, so this only happens when a
+/// new one replaces it.
+fn forget_tile_damage(mut damage: ResMut<TileDamage>) {
+    damage.0.clear();
+}
+
+fn damage_tiles(
+    mut damage_events: EventReader<DamageTiles>,
+    mut changed_events: EventWriter<TileChanged>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    mut level: ResMut<Level>,
+    mut damage: ResMut<TileDamage>,
+) {
+    // Patched in place; `TileChanged` redraws just what broke.
+    let level = level.bypass_change_detection();
+    let last_tile = UVec2::new(level.width, level.height).saturating_sub(UVec2::ONE);
+    let tile_size = level.tile_size as f32;
+    let origin = level.bounds().min;
+    let to_tile = |position: Vec2| {
+        ((position - origin) / tile_size)
+            .floor()
+            .max(Vec2::ZERO)
+            .as_uvec2()
+            .min(last_tile)
+    };
+
+    for event in damage_events.read() {
+        let reach = Vec2::splat(event.radius);
+        let (min, max) = (
+            to_tile(event.position - reach),
+            to_tile(event.position + reach),
+        );
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let tile = UVec2::new(x, y);
+                if level.get(tile) != TileKind::Cracked {
+                    continue;
+                }
+
+                let rect = level.tile_rect(URect::from_corners(tile, tile + 1));
+                let distance = event
+                    .position
+                    .clamp(rect.min, rect.max)
+                    .distance(event.position);
+                if distance > event.radius {
+                    continue;
+                }
+
+                let falloff = if event.radius > 0. {
+                    1. - distance / event.radius
+                } else {
+                    1.
+                };
+                let taken = damage.0.entry(tile).or_default();
+                *taken += event.amount * falloff;
+                if *taken < CRACKED_TILE_HEALTH {
+                    continue;
+                }
+
+                damage.0.remove(&tile);
+                level.set(tile, TileKind::Floor);
+                changed_events.write(TileChanged { tile });
+                particle_events.write(SpawnParticles {
+                    effect: DEBRIS,
+                    position: rect.center(),
+                    direction: Vec2::Y,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use avian2d::prelude::*;
+use bevy::{
+    ecs::{
+        schedule::{ExecutorKind, ScheduleLabel},
+        system::SystemParam,
+    },
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    health::Health,
+    rng::GameRng,
+    score::Score,
+    state::{GameplaySet, NewGame},
+    wave::WaveManager,
+};
+
+/// Set this to anything to run the simulation deterministically.
+const DETERMINISTIC_ENV_VAR: &str = "UNTITLED_GAME_DETERMINISTIC";
+const STATE_HASH_KEY: KeyCode = KeyCode::F12;
+/// In deterministic mode the state hash is also logged every this many simulation
+/// steps, so the logs of two runs can be compared.
+const STATE_HASH_INTERVAL: u64 = 640;
+
+/// Makes a run play out exactly the same given the same seed and input, as replays need.
+/// Randomness already all comes from `GameRng`, apart from cosmetic particles; this
+/// takes care of time and system order. F12 logs a hash of the simulation's state to
+/// find where two runs diverge.
+pub struct DeterminismPlugin;
+
+impl Plugin for DeterminismPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Determinism(
+            std::env::var_os(DETERMINISTIC_ENV_VAR).is_some(),
+        ));
+        app.init_resource::<SimulationStep>();
+        app.add_systems(
+            First,
+            apply_determinism.run_if(resource_changed::<Determinism>),
+        );
+        app.add_systems(NewGame, reset_simulation_step);
+        app.add_systems(
+            FixedUpdate,
+            (count_simulation_step, log_state_hash_periodically)
+                .chain()
+                .in_set(GameplaySet),
+        );
+        app.add_systems(Update, log_state_hash_on_key);
+    }
+}
+
+/// Whether deterministic mode is on. Playing back a replay recorded with it turns it on.
+///
+/// Each frame then advances time by exactly one fixed timestep, however long it really
+/// took, so the game runs as many simulation steps with the same deltas every time.
+/// Gameplay also runs single-threaded, in the same order every frame, rather than
+/// systems that don't depend on each other running in whichever order threads get to
+/// them.
+#[derive(Resource, Debug)]
+pub struct Determinism(pub bool);
+
+/// Fixed steps the game has simulated since it started.
+#[derive(Resource, Default, Debug)]
+pub struct SimulationStep(pub u64);
+
+/// What has to come out the same in two runs of the same replay.
+#[derive(SystemParam)]
+struct SimulationState<'w, 's> {
+    step: Res<'w, SimulationStep>,
+    rng: Res<'w, GameRng>,
+    score: Res<'w, Score>,
+    waves: Res<'w, WaveManager>,
+    body_q: Query<'w, 's, (&'static Position, &'static LinearVelocity)>,
+    health_q: Query<'w, 's, &'static Health>,
+}
+
+impl SimulationState<'_, '_> {
+    /// Bodies and health are hashed in sorted order, so it doesn't matter in which order
+    /// entities were spawned.
+    fn hash(&self) -> u64 {
+        let mut bodies: Vec<[u32; 4]> = self
+            .body_q
+            .iter()
+            .map(|(position, velocity)| {
+                [
+                    position.x.to_bits(),
+                    position.y.to_bits(),
+                    velocity.x.to_bits(),
+                    velocity.y.to_bits(),
+                ]
+            })
+            .collect();
+        bodies.sort_unstable();
+        let mut healths: Vec<u32> = self
+            .health_q
+            .iter()
+            .map(|health| health.current.to_bits())
+            .collect();
+        healths.sort_unstable();
+
+        let mut hasher = DefaultHasher::new();
+        self.step.0.hash(&mut hasher);
+        self.rng.word_pos().hash(&mut hasher);
+        self.score.points.hash(&mut hasher);
+        self.waves.wave.hash(&mut hasher);
+        bodies.hash(&mut hasher);
+        healths.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn log(&self) {
+        info!("state hash {:016x} at step {}", self.hash(), self.step.0);
+    }
+}
+
+/// Runs in `First`, while the schedules it changes aren't running.
+fn apply_determinism(world: &mut World) {
+    let enabled = world.resource::<Determinism>().0;
+    let strategy = if enabled {
+        TimeUpdateStrategy::ManualDuration(world.resource::<Time<Fixed>>().timestep())
+    } else {
+        TimeUpdateStrategy::Automatic
+    };
+    world.insert_resource(strategy);
+
+    let executor = if enabled {
+        ExecutorKind::SingleThreaded
+    } else {
+        ExecutorKind::default()
+    };
+    let mut schedules = world.resource_mut::<Schedules>();
+    for label in [Update.intern(), FixedUpdate.intern()] {
+        if let Some(schedule) = schedules.get_mut(label) {
+            schedule.set_executor_kind(executor);
+        }
+    }
+    info!("deterministic mode {}", if enabled { "on" } else { "off" });
+}
+
+fn reset_simulation_step(mut step: ResMut<SimulationStep>) {
+    step.0 = 0;
+}
+
+fn count_simulation_step(mut step: ResMut<SimulationStep>) {
+    step.0 += 1;
+}
+
+fn log_state_hash_periodically(determinism: Res<Determinism>, state: SimulationState) {
+    if determinism.0 && state.step.0.is_multiple_of(STATE_HASH_INTERVAL) {
+        state.log();
+    }
+}
+
+fn log_state_hash_on_key(keyboard_input: Res<ButtonInput<KeyCode>>, state: SimulationState) {
+    if keyboard_input.just_pressed(STATE_HASH_KEY) {
+        state.log();
+    }
+}
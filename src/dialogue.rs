@@ -0,0 +1,634 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, io,
+};
+
+use avian2d::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+    sprite::Anchor,
+    text::TextBounds,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collider::{GameLayer, collider_shape},
+    input::{Action, PlayerInput},
+    interaction::{InteractEvent, Interactable},
+    inventory::Inventory,
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    pickup::{Pickup, PlayerSupplies, spawn_pickup},
+    pixel_perfect::{Canvas, CanvasText, HIGH_RES_LAYER, PIXEL_PERFECT_LAYER, PixelCanvasConfig},
+    state::{GameState, GameplaySet, NewGame},
+    transition::RoomScoped,
+};
+
+/// Not bundled; the dialogue box falls back to Bevy's default font without it.
+const PIXEL_FONT: &str = "fonts/pixel.ttf";
+/// Characters revealed per second.
+const REVEAL_SPEED: f32 = 40.;
+/// The most choices a node can offer; the box has a line for each.
+const MAX_CHOICES: usize = 4;
+const BOX_MARGIN: f32 = 2.;
+const BOX_PADDING: f32 = 3.;
+const BOX_HEIGHT: f32 = 26.;
+const BOX_COLOR: Color = Color::srgba(0., 0., 0., 0.85);
+const SPEAKER_COLOR: Color = Color::srgb(1., 0.85, 0.4);
+const TEXT_FONT_SIZE: f32 = 5.;
+const LINE_HEIGHT: f32 = 6.;
+const CHOICE_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const SELECTED_CHOICE_COLOR: Color = Color::WHITE;
+
+/// Conversations with NPCs, shown a character at a time in a text box along the bottom
+/// of the screen. The world is frozen while one is open.
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Dialogue>();
+        app.init_asset_loader::<DialogueLoader>();
+        app.add_event::<DialogueEvent>();
+        app.add_event::<StartDialogue>();
+        app.init_resource::<DialogueFlags>();
+        app.add_systems(Startup, load_dialogue_font);
+        app.add_systems(NewGame, reset_dialogue_flags);
+        app.add_systems(Update, spawn_level_npcs.in_set(MarkerSpawnSet));
+        app.add_systems(
+            Update,
+            (talk_to_speakers, start_dialogue)
+                .chain()
+                .in_set(GameplaySet),
+        );
+        app.add_systems(OnEnter(GameState::Dialogue), spawn_dialogue_box);
+        app.add_systems(
+            Update,
+            (advance_dialogue, layout_dialogue_box, update_dialogue_box)
+                .chain()
+                .run_if(in_state(GameState::Dialogue)),
+        );
+        app.add_systems(Update, apply_dialogue_events);
+    }
+}
+
+/// A conversation, loaded from a `.dialogue.ron` file:
+///
+/// ```ron
+/// (
+///     start: "hello",
+///     nodes: {
+///         "hello": (
+///             speaker: "Warden",
+///             lines: ["You made it down here?", "Take this."],
+///             events: [GiveItem(Health(20.))],
+///             choices: [
+///                 (text: "Thanks.", next: None),
+///                 (text: "Seen anyone else?", next: Some("others")),
+///             ],
+///         ),
+///         "others": (speaker: "Warden", lines: ["No."], events: [SetFlag("asked")]),
+///     },
+/// )
+/// ```
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Dialogue {
+    start: String,
+    nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DialogueNode {
+    speaker: String,
+    lines: Vec<String>,
+    /// Sent when the conversation reaches this node.
+    #[serde(default)]
+    events: Vec<DialogueEvent>,
+    /// Offered after the last line.
+    #[serde(default)]
+    choices: Vec<DialogueChoice>,
+    /// Where to go after the last line when no choice is offered. The conversation
+    /// ends without one.
+    #[serde(default)]
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DialogueChoice {
+    text: String,
+    /// Only offered once this flag is set.
+    #[serde(default)]
+    requires: Option<String>,
+    #[serde(default)]
+    events: Vec<DialogueEvent>,
+    #[serde(default)]
+    next: Option<String>,
+}
+
+impl DialogueNode {
+    fn offered_choices<'a>(
+        &'a self,
+        flags: &'a DialogueFlags,
+    ) -> impl Iterator<Item = &'a DialogueChoice> {
+        self.choices.iter().filter(|choice| {
+            choice
+                .requires
+                .as_ref()
+                .is_none_or(|flag| flags.0.contains(flag))
+        })
+    }
+}
+
+/// Sent as conversations go on, so they can change the game.
+#[derive(Event, Clone, Debug, Deserialize)]
+pub enum DialogueEvent {
+    /// Used right away when the player has room for it, like walking over it would.
+    GiveItem(Pickup),
+    SetFlag(String),
+}
+
+/// Opens a conversation, freezing the world until it's over.
+#[derive(Event, Debug)]
+pub struct StartDialogue(pub Handle<Dialogue>);
+
+/// Flags set by conversations, which later ones can check.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct DialogueFlags(pub HashSet<String>);
+
+#[derive(Default)]
+pub struct DialogueLoader;
+
+#[derive(Debug)]
+pub enum DialogueError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for DialogueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for DialogueError {}
+
+fn parse_dialogue(bytes: &[u8]) -> Result<Dialogue, DialogueError> {
+    let dialogue: Dialogue = ron::de::from_bytes(bytes).map_err(DialogueError::Parse)?;
+    validate(&dialogue)?;
+    Ok(dialogue)
+}
+
+impl AssetLoader for DialogueLoader {
+    type Asset = Dialogue;
+    type Settings = ();
+    type Error = DialogueError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Dialogue, DialogueError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(DialogueError::Io)?;
+        parse_dialogue(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.ron"]
+    }
+}
+
+/// Catches broken links and empty nodes here, so a conversation can't get stuck.
+fn validate(dialogue: &Dialogue) -> Result<(), DialogueError> {
+    let check_link = |from: &str, next: &Option<String>| match next {
+        Some(next) if !dialogue.nodes.contains_key(next) => Err(DialogueError::Invalid(format!(
+            "`{from}` leads to a missing node `{next}`"
+        ))),
+        _ => Ok(()),
+    };
+
+    check_link("start", &Some(dialogue.start.clone()))?;
+    for (name, node) in &dialogue.nodes {
+        if node.lines.is_empty() {
+            return Err(DialogueError::Invalid(format!("`{name}` has no lines")));
+        }
+        if node.choices.len() > MAX_CHOICES {
+            return Err(DialogueError::Invalid(format!(
+                "`{name}` has more than {MAX_CHOICES} choices"
+            )));
+        }
+        check_link(name, &node.next)?;
+        for choice in &node.choices {
+            check_link(name, &choice.next)?;
+        }
+    }
+    Ok(())
+}
+
+/// Starts its conversation when the player interacts with it.
+#[derive(Component, Debug)]
+#[require(Interactable)]
+pub struct Speaker {
+    pub dialogue: Handle<Dialogue>,
+}
+
+/// The conversation on screen.
+#[derive(Resource, Debug)]
+struct ActiveDialogue {
+    dialogue: Handle<Dialogue>,
+    node: String,
+    line: usize,
+    /// How many characters of the line are showing.
+    revealed: f32,
+    /// Index into the offered choices.
+    choice: usize,
+}
+
+impl ActiveDialogue {
+    fn enter(
+        &mut self,
+        dialogue: &Dialogue,
+        node: String,
+        dialogue_events: &mut EventWriter<DialogueEvent>,
+    ) {
+        dialogue_events.write_batch(dialogue.nodes[&node].events.iter().cloned());
+        self.node = node;
+        self.line = 0;
+        self.revealed = 0.;
+        self.choice = 0;
+    }
+}
+
+#[derive(Component)]
+struct DialogueBackdrop;
+
+#[derive(Component)]
+struct DialogueSpeaker;
+
+#[derive(Component)]
+struct DialogueText;
+
+#[derive(Component)]
+struct DialogueChoiceText(usize);
+
+fn reset_dialogue_flags(mut flags: ResMut<DialogueFlags>) {
+    flags.0.clear();
+}
+
+fn spawn_level_npcs(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        let MarkerKind::Npc { dialogue } = &marker.kind else {
+            continue;
+        };
+
+        commands.spawn((
+            Speaker {
+                dialogue: asset_server.load(dialogue),
+            },
+            Name::new("NPC"),
+            RoomScoped,
+            Transform::from_translation(marker.position.extend(0.)),
+            Sprite::from_image(asset_server.load("npc.png")),
+            RigidBody::Static,
+            collider_shape("npc").bundle(),
+            GameLayer::Terrain.collision_layers(),
+            PIXEL_PERFECT_LAYER,
+        ));
+    }
+}
+
+fn talk_to_speakers(
+    mut interact_events: EventReader<InteractEvent>,
+    mut start_events: EventWriter<StartDialogue>,
+    speaker_q: Query<&Speaker>,
+) {
+    for event in interact_events.read() {
+        if let Ok(speaker) = speaker_q.get(event.interactable) {
+            start_events.write(StartDialogue(speaker.dialogue.clone()));
+        }
+    }
+}
+
+/// Only the first conversation started in a frame is shown.
+pub fn start_dialogue(
+    mut commands: Commands,
+    dialogues: Res<Assets<Dialogue>>,
+    mut start_events: EventReader<StartDialogue>,
+    mut dialogue_events: EventWriter<DialogueEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for StartDialogue(handle) in start_events.read() {
+        let Some(dialogue) = dialogues.get(handle) else {
+            warn!("conversation isn't loaded");
+            continue;
+        };
+
+        let mut active = ActiveDialogue {
+            dialogue: handle.clone(),
+            node: String::new(),
+            line: 0,
+            revealed: 0.,
+            choice: 0,
+        };
+        active.enter(dialogue, dialogue.start.clone(), &mut dialogue_events);
+        commands.insert_resource(active);
+        next_state.set(GameState::Dialogue);
+        return;
+    }
+}
+
+/// Loaded ahead of the first conversation, so it's known by then whether it's there.
+#[derive(Resource)]
+struct DialogueFont(Handle<Font>);
+
+fn load_dialogue_font(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DialogueFont(asset_server.load(PIXEL_FONT)));
+}
+
+fn spawn_dialogue_box(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    dialogue_font: Res<DialogueFont>,
+) {
+    let font = TextFont {
+        font: if asset_server.load_state(&dialogue_font.0).is_failed() {
+            Handle::default()
+        } else {
+            dialogue_font.0.clone()
+        },
+        ..Default::default()
+    };
+
+    commands
+        .spawn((
+            Name::new("Dialogue box"),
+            Transform::from_xyz(0., 0., 20.),
+            Visibility::default(),
+            StateScoped(GameState::Dialogue),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                DialogueBackdrop,
+                Sprite {
+                    color: BOX_COLOR,
+                    anchor: Anchor::BottomCenter,
+                    ..Default::default()
+                },
+                Transform::default(),
+                HIGH_RES_LAYER,
+            ));
+            parent.spawn((
+                DialogueSpeaker,
+                Text2d::default(),
+                font.clone(),
+                TextColor(SPEAKER_COLOR),
+                Anchor::TopLeft,
+                CanvasText::new(Vec2::ZERO, TEXT_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+            parent.spawn((
+                DialogueText,
+                Text2d::default(),
+                font.clone(),
+                Anchor::TopLeft,
+                TextBounds::default(),
+                CanvasText::new(Vec2::ZERO, TEXT_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+            for index in 0..MAX_CHOICES {
+                parent.spawn((
+                    DialogueChoiceText(index),
+                    Text2d::default(),
+                    font.clone(),
+                    TextColor(CHOICE_COLOR),
+                    Anchor::BottomLeft,
+                    CanvasText::new(Vec2::ZERO, TEXT_FONT_SIZE),
+                    Transform::from_xyz(0., 0., 0.1),
+                ));
+            }
+        });
+}
+
+/// Confirm shows the rest of a line that's still being revealed, then moves on to the
+/// next line, and after the last one takes the selected choice.
+#[allow(clippy::too_many_arguments)]
+fn advance_dialogue(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: Res<PlayerInput>,
+    dialogues: Res<Assets<Dialogue>>,
+    flags: Res<DialogueFlags>,
+    mut active: ResMut<ActiveDialogue>,
+    mut dialogue_events: EventWriter<DialogueEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(dialogue) = dialogues.get(&active.dialogue) else {
+        commands.remove_resource::<ActiveDialogue>();
+        next_state.set(GameState::Playing);
+        return;
+    };
+    let node = &dialogue.nodes[&active.node];
+    let line_length = node.lines[active.line].chars().count() as f32;
+    let last_line = active.line + 1 == node.lines.len();
+    active.revealed = (active.revealed + REVEAL_SPEED * time.delta_secs()).min(line_length);
+    let revealed = active.revealed >= line_length;
+
+    let choices: Vec<_> = node.offered_choices(&flags).collect();
+    if last_line && revealed && !choices.is_empty() {
+        if input.just_pressed(Action::MoveUp) {
+            active.choice = (active.choice + choices.len() - 1) % choices.len();
+        }
+        if input.just_pressed(Action::MoveDown) {
+            active.choice = (active.choice + 1) % choices.len();
+        }
+    }
+
+    if !input.just_pressed(Action::Confirm) {
+        return;
+    }
+    if !revealed {
+        active.revealed = line_length;
+        return;
+    }
+    if !last_line {
+        active.line += 1;
+        active.revealed = 0.;
+        return;
+    }
+
+    let next = match choices.get(active.choice) {
+        Some(choice) => {
+            dialogue_events.write_batch(choice.events.iter().cloned());
+            choice.next.clone()
+        }
+        None => node.next.clone(),
+    };
+    match next {
+        Some(next) => active.enter(dialogue, next, &mut dialogue_events),
+        None => {
+            commands.remove_resource::<ActiveDialogue>();
+            next_state.set(GameState::Playing);
+        }
+    }
+}
+
+/// Like the HUD bars, the backdrop is scaled through its transform to stay sharp.
+#[allow(clippy::type_complexity)]
+fn layout_dialogue_box(
+    config: Res<PixelCanvasConfig>,
+    canvas_transform: Single<&Transform, (With<Canvas>, Without<DialogueBackdrop>)>,
+    backdrop: Single<(&mut Sprite, &mut Transform), With<DialogueBackdrop>>,
+    mut speaker: Single<&mut CanvasText, With<DialogueSpeaker>>,
+    text: Single<
+        (&mut CanvasText, &mut TextBounds),
+        (With<DialogueText>, Without<DialogueSpeaker>),
+    >,
+    mut choice_q: Query<
+        (&DialogueChoiceText, &mut CanvasText),
+        (Without<DialogueSpeaker>, Without<DialogueText>),
+    >,
+) {
+    let scale = canvas_transform.scale.truncate();
+    let half_size = config.size_f32() / 2.;
+    let width = config.size_f32().x - BOX_MARGIN * 2.;
+
+    let (mut sprite, mut transform) = backdrop.into_inner();
+    sprite.custom_size = Some(Vec2::new(width, BOX_HEIGHT));
+    transform.translation = Vec3::new(0., (BOX_MARGIN - half_size.y) * scale.y, 0.);
+    transform.scale = scale.extend(1.);
+
+    let top_left = Vec2::new(
+        BOX_MARGIN + BOX_PADDING - half_size.x,
+        BOX_MARGIN + BOX_HEIGHT - BOX_PADDING - half_size.y,
+    );
+    speaker.position = top_left;
+    let (mut text, mut bounds) = text.into_inner();
+    text.position = top_left - Vec2::new(0., LINE_HEIGHT);
+    // Wraps at the same place whatever the scaling, since text grows by the smaller axis.
+    bounds.width = Some((width - BOX_PADDING * 2.) * scale.min_element());
+
+    // Choices stack upwards from the top of the box, the first one highest.
+    for (choice, mut canvas_text) in choice_q.iter_mut() {
+        let row = (MAX_CHOICES - 1 - choice.0) as f32;
+        canvas_text.position = Vec2::new(
+            top_left.x,
+            BOX_MARGIN + BOX_HEIGHT + 1. + row * LINE_HEIGHT - half_size.y,
+        );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_dialogue_box(
+    dialogues: Res<Assets<Dialogue>>,
+    flags: Res<DialogueFlags>,
+    active: Option<Res<ActiveDialogue>>,
+    mut speaker: Single<&mut Text2d, With<DialogueSpeaker>>,
+    mut text: Single<&mut Text2d, (With<DialogueText>, Without<DialogueSpeaker>)>,
+    mut choice_q: Query<
+        (&DialogueChoiceText, &mut Text2d, &mut TextColor),
+        (Without<DialogueSpeaker>, Without<DialogueText>),
+    >,
+) {
+    let Some(active) = active else {
+        return;
+    };
+    let Some(node) = dialogues
+        .get(&active.dialogue)
+        .map(|dialogue| &dialogue.nodes[&active.node])
+    else {
+        return;
+    };
+
+    if speaker.0 != node.speaker {
+        speaker.0.clone_from(&node.speaker);
+    }
+    let line: String = node.lines[active.line]
+        .chars()
+        .take(active.revealed as usize)
+        .collect();
+    if text.0 != line {
+        text.0 = line;
+    }
+
+    let line_length = node.lines[active.line].chars().count();
+    let show_choices =
+        active.line + 1 == node.lines.len() && active.revealed as usize >= line_length;
+    let choices: Vec<_> = node.offered_choices(&flags).collect();
+    // Empty slots go at the top, so the choices sit right above the box.
+    let first_slot = MAX_CHOICES - choices.len();
+    for (slot, mut choice_text, mut color) in choice_q.iter_mut() {
+        let choice = slot
+            .0
+            .checked_sub(first_slot)
+            .and_then(|index| choices.get(index).map(|choice| (index, choice)))
+            .filter(|_| show_choices);
+        let label = match choice {
+            Some((index, choice)) if index == active.choice => format!("> {}", choice.text),
+            Some((_, choice)) => format!("  {}", choice.text),
+            None => String::new(),
+        };
+        if choice_text.0 != label {
+            choice_text.0 = label;
+        }
+        color.0 = match choice {
+            Some((index, _)) if index == active.choice => SELECTED_CHOICE_COLOR,
+            _ => CHOICE_COLOR,
+        };
+    }
+}
+
+fn apply_dialogue_events(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut dialogue_events: EventReader<DialogueEvent>,
+    mut flags: ResMut<DialogueFlags>,
+    mut inventory: ResMut<Inventory>,
+    mut supplies: PlayerSupplies,
+) {
+    for event in dialogue_events.read() {
+        match event {
+            DialogueEvent::GiveItem(item) => {
+                if !supplies.apply(*item) && !inventory.add(*item) {
+                    // No room anywhere, so it's left at the player's feet for later.
+                    spawn_pickup(&mut commands, &asset_server, *item, supplies.position());
+                }
+            }
+            DialogueEvent::SetFlag(flag) => {
+                flags.0.insert(flag.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_dialogue_is_valid() {
+        let dialogue =
+            parse_dialogue(include_bytes!("../assets/dialogue/warden.dialogue.ron")).unwrap();
+        assert_eq!(dialogue.nodes[&dialogue.start].speaker, "Warden");
+    }
+
+    #[test]
+    fn missing_node_is_named() {
+        let error = parse_dialogue(
+            br#"(start: "hello", nodes: {"hello": (speaker: "A", lines: ["Hi."], next: Some("bye"))})"#,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "`hello` leads to a missing node `bye`");
+    }
+}
@@ -0,0 +1,296 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    collider::{ColliderShape, GameLayer, collider_shape},
+    interaction::{InteractEvent, Interactable},
+    inventory::Inventory,
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    lighting::LightOccluder,
+    pickup::{Pickup, spawn_pickup},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    prop::Prop,
+    state::GameplaySet,
+    transition::RoomScoped,
+};
+
+/// Doors are used from a little farther than their edge, however wide they are.
+const DOOR_REACH: f32 = 12.;
+
+/// Doors that block the way until opened, the switches and pressure plates that open
+/// them, and the keys that unlock them. They belong together when they share a link.
+pub struct DoorPlugin;
+
+impl Plugin for DoorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_level_doors.in_set(MarkerSpawnSet));
+        app.add_systems(
+            Update,
+            (use_switches, press_plates, use_doors).in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Door {
+    /// Needs the key or switch with this link to open.
+    pub link: Option<u32>,
+    pub open: bool,
+}
+
+#[derive(Component, Debug)]
+pub struct Switch {
+    pub link: u32,
+    pub on: bool,
+}
+
+/// A door shut by a combat room while its fight is on. It can't be used until the
+/// room unseals it, which leaves it as it was before.
+#[derive(Component, Debug)]
+pub struct Sealed {
+    pub was_open: bool,
+}
+
+/// Too stiff for anyone to press by walking over it; it takes the weight of a prop.
+/// Stays down once pressed.
+#[derive(Component, Debug)]
+pub struct PressurePlate {
+    pub link: u32,
+    pub pressed: bool,
+}
+
+/// Doors, switches and pressure plates start out closed and off. Keys are pickups.
+fn spawn_level_doors(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        let position = marker.position;
+        match marker.kind {
+            MarkerKind::Door { link, size } => {
+                let size = size.as_vec2();
+                commands.spawn((
+                    Door { link, open: false },
+                    door_interactable(size),
+                    Name::new("Door"),
+                    RoomScoped,
+                    Transform::from_translation(position.extend(0.)),
+                    Sprite {
+                        image: asset_server.load("door.png"),
+                        custom_size: Some(size),
+                        ..Default::default()
+                    },
+                    RigidBody::Static,
+                    ColliderShape::Rectangle {
+                        width: size.x,
+                        height: size.y,
+                    }
+                    .bundle(),
+                    GameLayer::Terrain.collision_layers(),
+                    LightOccluder,
+                    PIXEL_PERFECT_LAYER,
+                ));
+            }
+            MarkerKind::Switch { link } => {
+                commands.spawn((
+                    Switch { link, on: false },
+                    Interactable::default(),
+                    Name::new("Switch"),
+                    RoomScoped,
+                    Transform::from_translation(position.extend(-1.)),
+                    Sprite::from_image(asset_server.load("switch.png")),
+                    PIXEL_PERFECT_LAYER,
+                ));
+            }
+            MarkerKind::PressurePlate { link } => {
+                commands.spawn((
+                    PressurePlate {
+                        link,
+                        pressed: false,
+                    },
+                    Name::new("Pressure plate"),
+                    RoomScoped,
+                    Transform::from_translation(position.extend(-1.)),
+                    Sprite::from_image(asset_server.load("pressure_plate.png")),
+                    RigidBody::Static,
+                    collider_shape("pressure_plate").bundle(),
+                    // Unlike other triggers, it notices props rather than the player.
+                    CollisionLayers::new(GameLayer::Trigger, GameLayer::Default),
+                    Sensor,
+                    CollidingEntities::default(),
+                    PIXEL_PERFECT_LAYER,
+                ));
+            }
+            MarkerKind::Key { link } => {
+                spawn_pickup(&mut commands, &asset_server, Pickup::Key(link), position);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn door_interactable(size: Vec2) -> Interactable {
+    Interactable {
+        range: size.max_element() / 2. + DOOR_REACH,
+    }
+}
+
+/// Takes the door out of the way for good: it stops colliding, blocking light and
+/// being usable.
+pub fn open_door(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    entity: Entity,
+    door: &mut Door,
+    sprite: &mut Sprite,
+) {
+    door.open = true;
+    sprite.image = asset_server.load("door_open.png");
+    commands
+        .entity(entity)
+        .remove::<(Collider, LightOccluder, Interactable, Sealed)>();
+}
+
+/// Shuts the door, open or not, until `unseal_door`.
+pub fn seal_door(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    entity: Entity,
+    door: &mut Door,
+    sprite: &mut Sprite,
+) {
+    let was_open = std::mem::replace(&mut door.open, false);
+    sprite.image = asset_server.load("door.png");
+    let size = sprite.custom_size.unwrap_or_default();
+    commands.entity(entity).remove::<Interactable>().insert((
+        Sealed { was_open },
+        Collider::rectangle(size.x, size.y),
+        LightOccluder,
+    ));
+}
+
+/// Puts a sealed door back as it was, so a locked one still needs its key or switch.
+pub fn unseal_door(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    entity: Entity,
+    door: &mut Door,
+    sprite: &mut Sprite,
+    sealed: &Sealed,
+) {
+    if sealed.was_open {
+        open_door(commands, asset_server, entity, door, sprite);
+        return;
+    }
+
+    let size = sprite.custom_size.unwrap_or_default();
+    commands.entity(entity).remove::<Sealed>().insert((
+        Collider::rectangle(size.x, size.y),
+        LightOccluder,
+        door_interactable(size),
+    ));
+}
+
+/// Locked doors open while the player carries their key, which is kept.
+fn use_doors(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    inventory: Res<Inventory>,
+    mut interact_events: EventReader<InteractEvent>,
+    mut door_q: Query<(&mut Door, &mut Sprite)>,
+) {
+    for event in interact_events.read() {
+        let Ok((mut door, mut sprite)) = door_q.get_mut(event.interactable) else {
+            continue;
+        };
+        if door.open {
+            continue;
+        }
+
+        match door.link {
+            Some(link) if !inventory.contains(Pickup::Key(link)) => {
+                info!("door {link} is locked");
+            }
+            _ => open_door(
+                &mut commands,
+                &asset_server,
+                event.interactable,
+                &mut door,
+                &mut sprite,
+            ),
+        }
+    }
+}
+
+fn use_switches(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut interact_events: EventReader<InteractEvent>,
+    mut switch_q: Query<(&mut Switch, &mut Sprite), Without<Door>>,
+    mut door_q: Query<(Entity, &mut Door, &mut Sprite, Option<&mut Sealed>)>,
+) {
+    for event in interact_events.read() {
+        let Ok((mut switch, mut sprite)) = switch_q.get_mut(event.interactable) else {
+            continue;
+        };
+        if switch.on {
+            continue;
+        }
+
+        switch.on = true;
+        sprite.image = asset_server.load("switch_on.png");
+        commands.entity(event.interactable).remove::<Interactable>();
+        for (entity, mut door, mut door_sprite, sealed) in door_q.iter_mut() {
+            if door.link != Some(switch.link) || door.open {
+                continue;
+            }
+            // A sealed door opens once its combat room lets go of it.
+            if let Some(mut sealed) = sealed {
+                sealed.was_open = true;
+            } else {
+                open_door(
+                    &mut commands,
+                    &asset_server,
+                    entity,
+                    &mut door,
+                    &mut door_sprite,
+                );
+            }
+        }
+    }
+}
+
+fn press_plates(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut plate_q: Query<(&mut PressurePlate, &mut Sprite, &CollidingEntities), Without<Door>>,
+    mut door_q: Query<(Entity, &mut Door, &mut Sprite, Option<&mut Sealed>)>,
+    prop_q: Query<(), With<Prop>>,
+) {
+    for (mut plate, mut sprite, colliding) in plate_q.iter_mut() {
+        if plate.pressed || !colliding.iter().any(|&entity| prop_q.contains(entity)) {
+            continue;
+        }
+
+        plate.pressed = true;
+        sprite.image = asset_server.load("pressure_plate_down.png");
+        for (entity, mut door, mut door_sprite, sealed) in door_q.iter_mut() {
+            if door.link != Some(plate.link) || door.open {
+                continue;
+            }
+            // A sealed door opens once its combat room lets go of it.
+            if let Some(mut sealed) = sealed {
+                sealed.was_open = true;
+            } else {
+                open_door(
+                    &mut commands,
+                    &asset_server,
+                    entity,
+                    &mut door,
+                    &mut door_sprite,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    ai::{AiMovement, AiSenses, AiState, AttackCycle, PatrolRoute},
+    collider::{GameLayer, collider_shape},
+    combat_room::combat_room_areas,
+    debug::debug_render,
+    health::{Damage, DespawnOnDeath, Health},
+    level::{LevelMarkers, MarkerKind, MarkerSpawnSet, SpawnMarker},
+    particle::{ParticleEffect, SpawnParticles},
+    pickup::{LootDrop, LootTable, Pickup},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    state::GameplaySet,
+    status::StatusEffects,
+    transition::RoomScoped,
+    vision::HiddenWhenUnseen,
+};
+
+const ENEMY_MAX_SPEED: f32 = 120.;
+const ENEMY_SENSES: AiSenses = AiSenses {
+    sight_range: 50.,
+    attack_range: 22.,
+};
+/// Every kind of enemy there is. Wave sets name them, see `WaveSet`.
+pub const ENEMY_ARCHETYPES: [EnemyArchetype; 2] = [GRUNT, BRUTE];
+/// The regular enemy, placed in levels and summoned by bosses.
+pub const GRUNT: EnemyArchetype = EnemyArchetype {
+    name: "grunt",
+    health: 30.,
+    damage: 10.,
+    movement: AiMovement {
+        patrol_speed: 20.,
+        chase_speed: 40.,
+        lunge_speed: 110.,
+    },
+    tint: Color::WHITE,
+};
+/// Slow, tough and hits hard.
+pub const BRUTE: EnemyArchetype = EnemyArchetype {
+    name: "brute",
+    health: 70.,
+    damage: 18.,
+    movement: AiMovement {
+        patrol_speed: 15.,
+        chase_speed: 28.,
+        lunge_speed: 90.,
+    },
+    tint: Color::srgb(1., 0.6, 0.55),
+};
+const ENEMY_ATTACK_INTERVAL: f32 = 1.2;
+const EMERGE_SECS: f32 = 0.5;
+const EMERGE_DUST: ParticleEffect = ParticleEffect {
+    burst: 16,
+    rate: 0.,
+    min_speed: 8.,
+    max_speed: 30.,
+    spread: std::f32::consts::PI,
+    min_lifetime: 0.3,
+    max_lifetime: 0.6,
+    gravity: Vec2::new(0., 10.),
+    drag: 4.,
+    start_color: Color::srgba(0.6, 0.55, 0.5, 0.8),
+    end_color: Color::srgba(0.4, 0.35, 0.3, 0.),
+};
+const ENEMY_LOOT: LootTable = LootTable(&[
+    LootDrop {
+        pickup: Pickup::Health(10.),
+        chance: 0.25,
+    },
+    LootDrop {
+        pickup: Pickup::Ammo(12),
+        chance: 0.3,
+    },
+    LootDrop {
+        pickup: Pickup::Flares(1),
+        chance: 0.1,
+    },
+]);
+/// Patrol waypoints relative to an enemy's spawn marker.
+const PATROL_OFFSETS: [Vec2; 4] = [
+    Vec2::new(0., 20.),
+    Vec2::new(20., 20.),
+    Vec2::new(20., -20.),
+    Vec2::new(0., -20.),
+];
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_level_enemies.in_set(MarkerSpawnSet));
+        app.add_systems(Update, (start_emerging, emerge).chain().in_set(GameplaySet));
+    }
+}
+
+/// Points at the archetype it was spawned from.
+#[derive(Component, Debug)]
+pub struct Enemy(pub &'static EnemyArchetype);
+
+#[derive(Debug)]
+pub struct EnemyArchetype {
+    /// What wave sets and saved games call it.
+    pub name: &'static str,
+    pub health: f32,
+    /// Dealt on contact.
+    pub damage: f32,
+    pub movement: AiMovement,
+    /// Multiplies the sprite's colors, telling apart archetypes that share it.
+    pub tint: Color,
+}
+
+/// Looks an archetype up by its name.
+pub fn enemy_archetype(name: &str) -> Option<&'static EnemyArchetype> {
+    ENEMY_ARCHETYPES
+        .iter()
+        .find(|archetype| archetype.name == name)
+}
+
+/// An enemy appearing mid-fight, which fades in over a puff of dust rather than
+/// popping up. It can't be hurt, move or attack until it's all there.
+#[derive(Component, Debug)]
+pub struct Emerging(Timer);
+
+impl Default for Emerging {
+    fn default() -> Self {
+        Self(Timer::from_seconds(EMERGE_SECS, TimerMode::Once))
+    }
+}
+
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    archetype: &'static EnemyArchetype,
+    position: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            Transform::from_translation(position.extend(0.)),
+            Sprite {
+                image: asset_server.load("enemy.png"),
+                color: archetype.tint,
+                ..Default::default()
+            },
+            Name::new("Enemy"),
+            Enemy(archetype),
+            RoomScoped,
+            HiddenWhenUnseen,
+            debug_render(Color::srgb(1.0, 1.0, 0.0)),
+            PIXEL_PERFECT_LAYER,
+            (
+                RigidBody::Dynamic,
+                collider_shape("enemy").bundle(),
+                GameLayer::Enemy.collision_layers(),
+                LinearVelocity::ZERO,
+                ExternalImpulse::default(),
+                LockedAxes::ROTATION_LOCKED,
+                MaxLinearSpeed(ENEMY_MAX_SPEED),
+            ),
+            (
+                Health::new(archetype.health),
+                Damage {
+                    amount: archetype.damage,
+                    knockback: 150.,
+                },
+                DespawnOnDeath,
+                ENEMY_LOOT,
+                StatusEffects::default(),
+            ),
+            (
+                AiState::default(),
+                ENEMY_SENSES,
+                archetype.movement,
+                AttackCycle(Timer::from_seconds(
+                    ENEMY_ATTACK_INTERVAL,
+                    TimerMode::Repeating,
+                )),
+            ),
+        ))
+        .id()
+}
+
+fn spawn_level_enemies(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    markers: Res<LevelMarkers>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    // Combat rooms bring theirs out when the player steps in.
+    let combat_rooms: Vec<Rect> = combat_room_areas(&markers).collect();
+    for position in marker_events
+        .read()
+        .filter(|SpawnMarker(marker)| marker.kind == MarkerKind::EnemySpawn)
+        .map(|SpawnMarker(marker)| marker.position)
+        .filter(|position| !combat_rooms.iter().any(|area| area.contains(*position)))
+    {
+        let enemy = spawn_enemy(&mut commands, &asset_server, &GRUNT, position);
+        let waypoints = PATROL_OFFSETS.map(|offset| position + offset).to_vec();
+        commands.entity(enemy).insert(PatrolRoute::new(waypoints));
+    }
+}
+
+fn start_emerging(
+    mut particle_events: EventWriter<SpawnParticles>,
+    mut emerging_q: Query<(&Transform, &mut Sprite, &mut Health), Added<Emerging>>,
+) {
+    for (transform, mut sprite, mut health) in emerging_q.iter_mut() {
+        sprite.color.set_alpha(0.);
+        health.grant_invulnerability(EMERGE_SECS);
+        particle_events.write(SpawnParticles {
+            effect: EMERGE_DUST,
+            position: transform.translation.truncate(),
+            direction: Vec2::Y,
+        });
+    }
+}
+
+fn emerge(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut emerging_q: Query<(Entity, &mut Emerging, &mut Sprite)>,
+) {
+    for (entity, mut emerging, mut sprite) in emerging_q.iter_mut() {
+        emerging.0.tick(time.delta());
+        sprite.color.set_alpha(emerging.0.fraction());
+        if emerging.0.finished() {
+            commands.entity(entity).remove::<Emerging>();
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    ai::{Lure, NoiseEvent},
+    animation::SpriteAnimation,
+    audio::{PlaySfx, Sfx},
+    camera::MouseWorldPos,
+    collider::{GameLayer, collider_shape},
+    cooldown_arcs::AbilityCooldown,
+    debug::debug_render,
+    input::{Action, PlayerInput},
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    lighting::Light2d,
+    particle::{ParticleEffect, ParticleEmitter},
+    pickup::{Pickup, spawn_pickup},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::{Aim, Player},
+    pool::EntityPool,
+    screen_shake::AddTrauma,
+    state::{GameplaySet, PlayerControlSet},
+    status::Ignites,
+    transition::RoomScoped,
+    vision::RevealsArea,
+};
+
+const FLARE_THROW_SPEED: f32 = 150.;
+const FLARE_LINEAR_DAMPING: f32 = 2.5;
+const FLARE_ANGULAR_DAMPING: f32 = 1.5;
+const FLARE_COOLDOWN: f32 = 0.5;
+/// What the level's flare pickup markers hold.
+pub const FLARE_PICKUP: Pickup = Pickup::Flares(3);
+const FLARE_IGNITION_TRAUMA: f32 = 0.15;
+/// Enemies this close that can't see the player go to the flare instead.
+const FLARE_LURE_RADIUS: f32 = 140.;
+/// Enemies this close to a flare are standing on it and catch fire.
+const FLARE_IGNITE_RADIUS: f32 = 8.;
+/// How far away enemies hear a flare ignite.
+const FLARE_IGNITION_LOUDNESS: f32 = 120.;
+const FLARE_LIGHT: Light2d = Light2d {
+    radius: 40.,
+    intensity: 1.2,
+};
+const FLARE_FRAME_SIZE: UVec2 = UVec2::splat(8);
+const FLARE_FLICKER_FRAMES: u32 = 3;
+const FLARE_FLICKER_FPS: f32 = 12.;
+const FLARE_SPARKS: ParticleEffect = ParticleEffect {
+    burst: 0,
+    rate: 20.,
+    min_speed: 15.,
+    max_speed: 40.,
+    spread: 0.7,
+    min_lifetime: 0.2,
+    max_lifetime: 0.5,
+    gravity: Vec2::new(0., -120.),
+    drag: 1.,
+    start_color: Color::srgb(1., 0.95, 0.7),
+    end_color: Color::srgba(1., 0.3, 0.1, 0.),
+};
+
+pub struct FlarePlugin;
+
+impl Plugin for FlarePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityPool<Flare>>();
+        app.add_systems(Startup, load_flare_sprites);
+        app.add_systems(Update, spawn_flare_pickups.in_set(MarkerSpawnSet));
+        app.add_systems(
+            Update,
+            (spawn_flares.in_set(PlayerControlSet), burn_flares).in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct FlareInventory {
+    pub count: u32,
+    pub max: u32,
+    pub cooldown: Timer,
+}
+
+impl FlareInventory {
+    pub fn new(count: u32, max: u32) -> Self {
+        let mut cooldown = Timer::from_seconds(FLARE_COOLDOWN, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+
+        Self {
+            count: count.min(max),
+            max,
+            cooldown,
+        }
+    }
+
+    pub fn refill(&mut self, amount: u32) {
+        self.count = (self.count + amount).min(self.max);
+    }
+}
+
+impl AbilityCooldown for FlareInventory {
+    const ACTION: Action = Action::ThrowFlare;
+    const COLOR: Color = Color::srgb(1., 0.55, 0.2);
+
+    fn cooldown(&self) -> &Timer {
+        &self.cooldown
+    }
+}
+
+#[derive(Component)]
+pub struct Flare {
+    pub burn_duration: f32,
+}
+
+impl Default for Flare {
+    fn default() -> Self {
+        Self { burn_duration: 20. }
+    }
+}
+
+#[derive(Component)]
+pub struct FlareLifetime(pub Timer);
+
+/// Shared by every thrown flare, so each one doesn't add its own atlas layout.
+#[derive(Resource)]
+struct FlareSprites {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+fn load_flare_sprites(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let layout =
+        TextureAtlasLayout::from_grid(FLARE_FRAME_SIZE, FLARE_FLICKER_FRAMES, 1, None, None);
+    commands.insert_resource(FlareSprites {
+        image: asset_server.load("flare_sheet.png"),
+        layout: atlas_layouts.add(layout),
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_flares(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Flare>>,
+    flare_sprites: Res<FlareSprites>,
+    time: Res<Time>,
+    mut input: ResMut<PlayerInput>,
+    mouse_world_pos: Res<MouseWorldPos>,
+    mut trauma_events: EventWriter<AddTrauma>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    player: Single<(&Transform, &Aim, &mut FlareInventory), With<Player>>,
+) {
+    let (player_transform, aim, mut inventory) = player.into_inner();
+    inventory.cooldown.tick(time.delta());
+
+    if inventory.count > 0
+        && inventory.cooldown.finished()
+        && input.take_buffered(Action::ThrowFlare)
+    {
+        inventory.count -= 1;
+        inventory.cooldown.reset();
+        trauma_events.write(AddTrauma(FLARE_IGNITION_TRAUMA));
+        sfx_events.write(PlaySfx::at(
+            Sfx::FlareIgnite,
+            player_transform.translation.truncate(),
+        ));
+        noise_events.write(NoiseEvent {
+            position: player_transform.translation.truncate(),
+            loudness: FLARE_IGNITION_LOUDNESS,
+        });
+
+        let throw_direction = (mouse_world_pos.0 - player_transform.translation.truncate())
+            .try_normalize()
+            .unwrap_or(aim.0);
+
+        let flare = Flare::default();
+        let lifetime = FlareLifetime(Timer::from_seconds(flare.burn_duration, TimerMode::Once));
+
+        pool.spawn(
+            &mut commands,
+            (
+                flare,
+                lifetime,
+                Name::new("Flare"),
+                RoomScoped,
+                Transform::from_translation(player_transform.translation)
+                    .with_scale(Vec3::splat(1.)),
+                Sprite::from_atlas_image(
+                    flare_sprites.image.clone(),
+                    TextureAtlas {
+                        layout: flare_sprites.layout.clone(),
+                        index: 0,
+                    },
+                ),
+                SpriteAnimation::new(
+                    0,
+                    FLARE_FLICKER_FRAMES as usize - 1,
+                    FLARE_FLICKER_FPS,
+                    true,
+                ),
+                ParticleEmitter::new(FLARE_SPARKS, Vec2::Y),
+                debug_render(Color::srgb(1.0, 1.0, 0.0)),
+                PIXEL_PERFECT_LAYER,
+                FLARE_LIGHT,
+                RevealsArea {
+                    radius: FLARE_LIGHT.radius,
+                },
+                Lure {
+                    radius: FLARE_LURE_RADIUS,
+                },
+                Ignites::new(FLARE_IGNITE_RADIUS),
+                (
+                    RigidBody::Dynamic,
+                    collider_shape("flare").bundle(),
+                    GameLayer::Flare.collision_layers(),
+                    LinearVelocity(throw_direction * FLARE_THROW_SPEED),
+                    AngularVelocity(-20.),
+                    LinearDamping(FLARE_LINEAR_DAMPING),
+                    AngularDamping(FLARE_ANGULAR_DAMPING),
+                ),
+            ),
+        );
+    }
+}
+
+fn burn_flares(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<EntityPool<Flare>>,
+    mut flare_q: Query<(Entity, &mut FlareLifetime, &mut Sprite, &mut Light2d), With<Flare>>,
+) {
+    for (entity, mut lifetime, mut sprite, mut light) in flare_q.iter_mut() {
+        lifetime.0.tick(time.delta());
+
+        if lifetime.0.finished() {
+            pool.release(&mut commands, entity);
+            continue;
+        }
+
+        // Burn from white-hot down to a dim red ember.
+        let remaining = lifetime.0.fraction_remaining();
+        sprite.color = Color::srgba(1., 0.3 + 0.7 * remaining, 0.2 + 0.8 * remaining, remaining);
+        light.intensity = FLARE_LIGHT.intensity * remaining.sqrt();
+    }
+}
+
+fn spawn_flare_pickups(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        if marker.kind == MarkerKind::FlarePickup {
+            spawn_pickup(&mut commands, &asset_server, FLARE_PICKUP, marker.position);
+        }
+    }
+}
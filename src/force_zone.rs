@@ -0,0 +1,179 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    collider::GameLayer,
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    state::{GameplaySet, PlayerControlSet},
+    status::{ApplyStatus, StatusEffect},
+    transition::RoomScoped,
+};
+
+/// Fraction of its velocity a body in water loses per second.
+const WATER_DRAG: f32 = 4.;
+/// Top speed of anything wading, in px/s.
+const WADING_SPEED: f32 = 55.;
+/// How long the wading slow lingers after leaving the water.
+const WADING_SECS: f32 = 0.25;
+/// How quickly a belt brings what's on it up to its speed, in px/s².
+const CONVEYOR_GRIP: f32 = 600.;
+/// Wind against a body's own footing: bodies that steer themselves drift at the wind's
+/// push divided by this, about where drag would leave a loose body.
+const WIND_FOOTING: f32 = 4.;
+/// Under the hazards and platforms, over the floor.
+const FORCE_ZONE_Z: f32 = -6.;
+
+/// Areas that push, drag or carry the bodies inside them: wind, water and conveyor
+/// belts. What's inside is found with a spatial query each step, so they need no
+/// collision layers of their own.
+pub struct ForceZonePlugin;
+
+impl Plugin for ForceZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_level_force_zones.in_set(MarkerSpawnSet));
+        app.add_systems(
+            FixedUpdate,
+            apply_force_zones
+                .before(PlayerControlSet)
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForceZoneKind {
+    /// Accelerates bodies by `push`, in px/s².
+    Wind { push: IVec2 },
+    /// Slows and drags everything in it.
+    Water,
+    /// A floor moving at `velocity`, in px/s, taking what's on it along.
+    Conveyor { velocity: IVec2 },
+}
+
+impl ForceZoneKind {
+    fn image_path(self) -> &'static str {
+        match self {
+            ForceZoneKind::Wind { .. } => "wind.png",
+            ForceZoneKind::Water => "water.png",
+            ForceZoneKind::Conveyor { .. } => "conveyor.png",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ForceZoneKind::Wind { .. } => "Wind",
+            ForceZoneKind::Water => "Water",
+            ForceZoneKind::Conveyor { .. } => "Conveyor",
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct ForceZone {
+    pub kind: ForceZoneKind,
+    pub size: Vec2,
+}
+
+/// How fast the ground under a body that steers itself, like the player, is moving.
+/// Its own movement is on top of this, so force zones don't just get steered away.
+#[derive(Component, Default, Debug)]
+pub struct SurfaceVelocity(pub Vec2);
+
+/// Each zone covers its marker's rectangle.
+fn spawn_level_force_zones(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        let MarkerKind::ForceZone { kind, size } = marker.kind else {
+            continue;
+        };
+
+        let size = size.as_vec2();
+        commands.spawn((
+            ForceZone { kind, size },
+            Name::new(kind.name()),
+            RoomScoped,
+            Transform::from_translation(marker.position.extend(FORCE_ZONE_Z)),
+            Sprite {
+                image: asset_server.load(kind.image_path()),
+                custom_size: Some(size),
+                image_mode: SpriteImageMode::Tiled {
+                    tile_x: true,
+                    tile_y: true,
+                    stretch_value: 1.,
+                },
+                ..Default::default()
+            },
+            PIXEL_PERFECT_LAYER,
+        ));
+    }
+}
+
+/// Bodies with a `SurfaceVelocity` get the zones' pull through it; the rest have their
+/// velocity changed directly.
+fn apply_force_zones(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut status_events: EventWriter<ApplyStatus>,
+    zone_q: Query<(&ForceZone, &GlobalTransform)>,
+    mut body_q: Query<(
+        &RigidBody,
+        &mut LinearVelocity,
+        Option<&mut SurfaceVelocity>,
+    )>,
+) {
+    let dt = time.delta_secs();
+    for (_, _, surface) in body_q.iter_mut() {
+        if let Some(mut surface) = surface {
+            surface.0 = Vec2::ZERO;
+        }
+    }
+
+    let filter = SpatialQueryFilter::from_mask([
+        GameLayer::Default,
+        GameLayer::Player,
+        GameLayer::Enemy,
+        GameLayer::Flare,
+    ]);
+    for (zone, transform) in zone_q.iter() {
+        let area = Collider::rectangle(zone.size.x, zone.size.y);
+        let center = transform.translation().truncate();
+        for entity in spatial_query.shape_intersections(&area, center, 0., &filter) {
+            let Ok((rigid_body, mut velocity, surface)) = body_q.get_mut(entity) else {
+                continue;
+            };
+            if *rigid_body != RigidBody::Dynamic {
+                continue;
+            }
+
+            match (zone.kind, surface) {
+                (ForceZoneKind::Wind { push }, Some(mut surface)) => {
+                    surface.0 += push.as_vec2() / WIND_FOOTING;
+                }
+                (ForceZoneKind::Wind { push }, None) => {
+                    velocity.0 += push.as_vec2() * dt;
+                }
+                (ForceZoneKind::Water, _) => {
+                    velocity.0 /= 1. + WATER_DRAG * dt;
+                    // Only does anything to bodies with status effects.
+                    status_events.write(ApplyStatus {
+                        target: entity,
+                        effect: StatusEffect::Slow {
+                            max_speed: WADING_SPEED,
+                        },
+                        secs: WADING_SECS,
+                    });
+                }
+                (ForceZoneKind::Conveyor { velocity: belt }, Some(mut surface)) => {
+                    surface.0 += belt.as_vec2();
+                }
+                (ForceZoneKind::Conveyor { velocity: belt }, None) => {
+                    velocity.0 = velocity.0.move_towards(belt.as_vec2(), CONVEYOR_GRIP * dt);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    camera::MouseWorldPos,
+    collider::GameLayer,
+    input::{Action, PlayerInput},
+    player::Player,
+    state::{GameplaySet, PlayerControlSet},
+    transition::RoomScoped,
+};
+
+/// How far the hook flies before giving up.
+const GRAPPLE_RANGE: f32 = 96.;
+/// How fast the rope shortens, in px/s.
+const REEL_SPEED: f32 = 180.;
+/// Close enough to the anchor to let go.
+const RELEASE_LENGTH: f32 = 6.;
+/// Lets go anyway after this long, like when something is in the way.
+const MAX_GRAPPLE_SECS: f32 = 1.5;
+const ROPE_COLOR: Color = Color::srgb(0.75, 0.65, 0.5);
+
+/// Fires a hook at the mouse that catches on terrain and reels the player in on a rope.
+pub struct GrapplePlugin;
+
+impl Plugin for GrapplePlugin {
+    fn build(&self, app: &mut App) {
+        // Gizmos draw on the pixel-perfect layer by default; this keeps the rope to one
+        // canvas pixel wide.
+        app.insert_gizmo_config(
+            RopeGizmos,
+            GizmoConfig {
+                line: GizmoLineConfig {
+                    width: 1.,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        app.add_systems(
+            Update,
+            (fire_grapple.in_set(PlayerControlSet), draw_grapple_rope).in_set(GameplaySet),
+        );
+        app.add_systems(FixedUpdate, reel_in_grapple.in_set(GameplaySet));
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct GrappleCooldown(pub Timer);
+
+impl GrappleCooldown {
+    pub fn new(secs: f32) -> Self {
+        let mut timer = Timer::from_seconds(secs, TimerMode::Once);
+        timer.tick(timer.duration());
+        Self(timer)
+    }
+}
+
+/// Present while hooked onto `anchor`, a static body at the hook's end holding the
+/// rope's `DistanceJoint` as a child.
+#[derive(Component, Debug)]
+pub struct Grappling {
+    anchor: Entity,
+    timer: Timer,
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct RopeGizmos;
+
+/// Pressing the grapple again while hooked lets go.
+#[allow(clippy::type_complexity)]
+fn fire_grapple(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: Res<PlayerInput>,
+    mouse_world_pos: Res<MouseWorldPos>,
+    spatial_query: SpatialQuery,
+    player: Single<(Entity, &Transform, &mut GrappleCooldown, Option<&Grappling>), With<Player>>,
+) {
+    let (entity, transform, mut cooldown, grappling) = player.into_inner();
+    cooldown.0.tick(time.delta());
+
+    if !input.just_pressed(Action::Grapple) {
+        return;
+    }
+    if let Some(grappling) = grappling {
+        release_grapple(&mut commands, entity, grappling);
+        return;
+    }
+    if !cooldown.0.finished() {
+        return;
+    }
+
+    let origin = transform.translation.truncate();
+    let Ok(direction) = Dir2::new(mouse_world_pos.0 - origin) else {
+        return;
+    };
+    let filter = SpatialQueryFilter::from_mask(GameLayer::Terrain);
+    let Some(hit) = spatial_query.cast_ray(origin, direction, GRAPPLE_RANGE, true, &filter) else {
+        return;
+    };
+
+    cooldown.0.reset();
+    let anchor = commands
+        .spawn((
+            Name::new("Grapple anchor"),
+            RoomScoped,
+            RigidBody::Static,
+            Transform::from_translation((origin + direction * hit.distance).extend(0.)),
+        ))
+        .id();
+    commands.spawn((
+        Name::new("Grapple rope"),
+        ChildOf(anchor),
+        // Slack is fine; only stretching past the current length is stopped.
+        DistanceJoint::new(anchor, entity).with_limits(0., hit.distance),
+    ));
+    commands.entity(entity).insert(Grappling {
+        anchor,
+        timer: Timer::from_seconds(MAX_GRAPPLE_SECS, TimerMode::Once),
+    });
+}
+
+fn release_grapple(commands: &mut Commands, player: Entity, grappling: &Grappling) {
+    // Both the player's press and the reel can let go in the same frame.
+    if let Ok(mut anchor) = commands.get_entity(grappling.anchor) {
+        anchor.try_despawn();
+    }
+    commands.entity(player).remove::<Grappling>();
+}
+
+/// Shortens the rope's limit, which the joint pulls the player along with.
+fn reel_in_grapple(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player_q: Query<(Entity, &mut Grappling)>,
+    anchor_q: Query<&Children>,
+    mut joint_q: Query<&mut DistanceJoint>,
+) {
+    for (entity, mut grappling) in player_q.iter_mut() {
+        let joint = anchor_q
+            .get(grappling.anchor)
+            .ok()
+            .and_then(|children| children.first().copied());
+        let Some(mut joint) = joint.and_then(|joint| joint_q.get_mut(joint).ok()) else {
+            // The anchor went away with the room.
+            commands.entity(entity).remove::<Grappling>();
+            continue;
+        };
+
+        let Some(limits) = &mut joint.length_limits else {
+            continue;
+        };
+        limits.max = (limits.max - REEL_SPEED * time.delta_secs()).max(0.);
+
+        if limits.max <= RELEASE_LENGTH || grappling.timer.tick(time.delta()).finished() {
+            release_grapple(&mut commands, entity, &grappling);
+        }
+    }
+}
+
+fn draw_grapple_rope(
+    mut gizmos: Gizmos<RopeGizmos>,
+    player: Single<(&GlobalTransform, &Grappling), With<Player>>,
+    anchor_q: Query<&GlobalTransform>,
+) {
+    let (transform, grappling) = *player;
+    let Ok(anchor_transform) = anchor_q.get(grappling.anchor) else {
+        return;
+    };
+
+    gizmos.line_2d(
+        transform.translation().truncate(),
+        anchor_transform.translation().truncate(),
+        ROPE_COLOR,
+    );
+}
@@ -0,0 +1,338 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    ai::NoiseEvent,
+    aim_line::{AimLineGizmos, draw_radius},
+    audio::{PlaySfx, Sfx},
+    camera::MouseWorldPos,
+    collider::{GameLayer, collider_shape},
+    cooldown_arcs::AbilityCooldown,
+    debug::debug_render,
+    destructible::DamageTiles,
+    health::{DamageEvent, Health},
+    input::{Action, PlayerInput},
+    particle::{ParticleEffect, SpawnParticles},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::{Aim, Player},
+    screen_shake::AddTrauma,
+    settings::GameSettings,
+    state::{GameplaySet, PlayerControlSet},
+    transition::RoomScoped,
+};
+
+const GRENADE_THROW_SPEED: f32 = 170.;
+const GRENADE_LINEAR_DAMPING: f32 = 3.;
+const GRENADE_COOLDOWN: f32 = 0.8;
+const GRENADE_FUSE_SECS: f32 = 1.6;
+const BLAST_RADIUS: f32 = 32.;
+/// Damage right at the grenade, falling off to nothing at the edge of the blast.
+const BLAST_DAMAGE: f32 = 60.;
+/// Velocity change right at the grenade, in px/s, falling off like the damage.
+const BLAST_PUSH: f32 = 260.;
+const BLAST_TRAUMA: f32 = 0.5;
+/// How far away enemies hear the explosion.
+const BLAST_LOUDNESS: f32 = 200.;
+const BLAST_FLASH: ParticleEffect = ParticleEffect {
+    burst: 40,
+    rate: 0.,
+    min_speed: 30.,
+    max_speed: 110.,
+    spread: std::f32::consts::PI,
+    min_lifetime: 0.15,
+    max_lifetime: 0.45,
+    gravity: Vec2::ZERO,
+    drag: 4.,
+    start_color: Color::srgb(1., 0.95, 0.75),
+    end_color: Color::srgba(0.9, 0.3, 0.05, 0.),
+};
+const DANGER_COLOR: Color = Color::srgb(1., 0.3, 0.2);
+/// The warning pulses faster and brighter once this little of the fuse is left.
+const DANGER_URGENT_SECS: f32 = 0.5;
+/// Where the warning mark floats, over the player's head.
+const DANGER_MARK_OFFSET: Vec2 = Vec2::new(0., 11.);
+
+pub struct GrenadePlugin;
+
+impl Plugin for GrenadePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                throw_grenades.in_set(PlayerControlSet),
+                explode_grenades,
+                warn_of_blasts,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct GrenadeInventory {
+    pub count: u32,
+    pub max: u32,
+    pub cooldown: Timer,
+}
+
+impl GrenadeInventory {
+    pub fn new(count: u32, max: u32) -> Self {
+        let mut cooldown = Timer::from_seconds(GRENADE_COOLDOWN, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+
+        Self {
+            count: count.min(max),
+            max,
+            cooldown,
+        }
+    }
+}
+
+impl AbilityCooldown for GrenadeInventory {
+    const ACTION: Action = Action::ThrowGrenade;
+    const COLOR: Color = Color::srgb(0.45, 0.85, 0.35);
+
+    fn cooldown(&self) -> &Timer {
+        &self.cooldown
+    }
+}
+
+/// Explodes once the fuse runs out, wherever it has bounced to by then.
+#[derive(Component)]
+pub struct Grenade {
+    pub fuse: Timer,
+}
+
+fn throw_grenades(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    input: Res<PlayerInput>,
+    mouse_world_pos: Res<MouseWorldPos>,
+    player: Single<(&Transform, &Aim, &mut GrenadeInventory), With<Player>>,
+) {
+    let (player_transform, aim, mut inventory) = player.into_inner();
+    inventory.cooldown.tick(time.delta());
+
+    if !input.just_pressed(Action::ThrowGrenade)
+        || inventory.count == 0
+        || !inventory.cooldown.finished()
+    {
+        return;
+    }
+
+    inventory.count -= 1;
+    inventory.cooldown.reset();
+
+    let throw_direction = (mouse_world_pos.0 - player_transform.translation.truncate())
+        .try_normalize()
+        .unwrap_or(aim.0);
+
+    commands.spawn((
+        Grenade {
+            fuse: Timer::from_seconds(GRENADE_FUSE_SECS, TimerMode::Once),
+        },
+        Name::new("Grenade"),
+        RoomScoped,
+        Transform::from_translation(player_transform.translation),
+        Sprite::from_image(asset_server.load("grenade.png")),
+        debug_render(Color::srgb(0.4, 0.8, 0.3)),
+        PIXEL_PERFECT_LAYER,
+        (
+            RigidBody::Dynamic,
+            collider_shape("grenade").bundle(),
+            GameLayer::Flare.collision_layers(),
+            LinearVelocity(throw_direction * GRENADE_THROW_SPEED),
+            AngularVelocity(-15.),
+            LinearDamping(GRENADE_LINEAR_DAMPING),
+        ),
+    ));
+}
+
+/// Hurts and pushes away everything in the blast radius that isn't behind a wall, the
+/// player included.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn explode_grenades(
+    mut commands: Commands,
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    mut trauma_events: EventWriter<AddTrauma>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut tile_damage_events: EventWriter<DamageTiles>,
+    mut grenade_q: Query<(Entity, &mut Grenade, &Transform)>,
+    mut body_q: Query<
+        (
+            &GlobalTransform,
+            &RigidBody,
+            Option<&ComputedMass>,
+            Option<&mut ExternalImpulse>,
+            Has<Health>,
+        ),
+        Without<Grenade>,
+    >,
+) {
+    for (grenade, mut fuse, transform) in grenade_q.iter_mut() {
+        if !fuse.fuse.tick(time.delta()).finished() {
+            continue;
+        }
+
+        commands.entity(grenade).despawn();
+        let center = transform.translation.truncate();
+        particle_events.write(SpawnParticles {
+            effect: BLAST_FLASH,
+            position: center,
+            direction: Vec2::Y,
+        });
+        trauma_events.write(AddTrauma(BLAST_TRAUMA));
+        sfx_events.write(PlaySfx::at(Sfx::Explosion, center));
+        noise_events.write(NoiseEvent {
+            position: center,
+            loudness: BLAST_LOUDNESS,
+        });
+        tile_damage_events.write(DamageTiles {
+            position: center,
+            radius: BLAST_RADIUS,
+            amount: BLAST_DAMAGE,
+        });
+
+        let blast = Collider::circle(BLAST_RADIUS);
+        let caught = SpatialQueryFilter::from_mask([
+            GameLayer::Default,
+            GameLayer::Player,
+            GameLayer::Enemy,
+            GameLayer::Flare,
+        ]);
+        let walls = SpatialQueryFilter::from_mask(GameLayer::Terrain);
+        for target in spatial_query.shape_intersections(&blast, center, 0., &caught) {
+            let Ok((target_transform, rigid_body, mass, impulse, has_health)) =
+                body_q.get_mut(target)
+            else {
+                continue;
+            };
+
+            let offset = target_transform.translation().truncate() - center;
+            let distance = offset.length();
+            if let Ok(direction) = Dir2::new(offset)
+                && spatial_query
+                    .cast_ray(center, direction, distance, true, &walls)
+                    .is_some()
+            {
+                continue;
+            }
+
+            let falloff = (1. - distance / BLAST_RADIUS).clamp(0., 1.);
+            if has_health {
+                damage_events.write(DamageEvent {
+                    target,
+                    amount: BLAST_DAMAGE * falloff,
+                    knockback: Vec2::ZERO,
+                });
+            }
+
+            if let (RigidBody::Dynamic, Some(mass)) = (rigid_body, mass) {
+                let push = offset.normalize_or(Vec2::Y) * BLAST_PUSH * falloff * mass.value();
+                match impulse {
+                    Some(mut impulse) => {
+                        impulse.apply_impulse(push);
+                    }
+                    None => {
+                        commands.entity(target).insert(ExternalImpulse::new(push));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Where a grenade comes to rest after `secs`, slowed by its damping and stopped short
+/// by the first wall in its way. It ignores bounces, which damping keeps short.
+fn predict_landing(
+    spatial_query: &SpatialQuery,
+    position: Vec2,
+    velocity: Vec2,
+    secs: f32,
+) -> Vec2 {
+    let travel = velocity * (1. - (-GRENADE_LINEAR_DAMPING * secs).exp()) / GRENADE_LINEAR_DAMPING;
+    let Ok(direction) = Dir2::new(travel) else {
+        return position;
+    };
+
+    let walls = SpatialQueryFilter::from_mask(GameLayer::Terrain);
+    let distance = spatial_query
+        .cast_ray(position, direction, travel.length(), true, &walls)
+        .map_or(travel.length(), |hit| hit.distance);
+    position + direction * distance
+}
+
+/// Rings the blast radius of every grenade about to catch the player where it's headed,
+/// and marks the player with a "!". Both pulse, harder at the end of the fuse, and are
+/// gone the frame the player gets clear or the grenade goes off.
+fn warn_of_blasts(
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    spatial_query: SpatialQuery,
+    mut gizmos: Gizmos<AimLineGizmos>,
+    player_transform: Single<&GlobalTransform, With<Player>>,
+    grenade_q: Query<(&Grenade, &Transform, &LinearVelocity)>,
+) {
+    if !settings.blast_warning {
+        return;
+    }
+
+    let player_pos = player_transform.translation().truncate();
+    let walls = SpatialQueryFilter::from_mask(GameLayer::Terrain);
+    let mut strongest: Option<f32> = None;
+    for (grenade, transform, velocity) in grenade_q.iter() {
+        // Already gone off, and despawning.
+        if grenade.fuse.finished() {
+            continue;
+        }
+
+        let remaining = grenade.fuse.remaining_secs();
+        let center = predict_landing(
+            &spatial_query,
+            transform.translation.truncate(),
+            velocity.0,
+            remaining,
+        );
+        let offset = player_pos - center;
+        if offset.length() > BLAST_RADIUS {
+            continue;
+        }
+        // The blast doesn't reach through walls either.
+        if let Ok(direction) = Dir2::new(offset)
+            && spatial_query
+                .cast_ray(center, direction, offset.length(), true, &walls)
+                .is_some()
+        {
+            continue;
+        }
+
+        let (speed, alpha) = if remaining <= DANGER_URGENT_SECS {
+            (24., 0.7)
+        } else {
+            (8., 0.3)
+        };
+        let pulse = alpha + alpha * 0.4 * (time.elapsed_secs() * speed).sin();
+        draw_radius(
+            &mut gizmos,
+            center,
+            BLAST_RADIUS,
+            DANGER_COLOR.with_alpha(pulse),
+        );
+        strongest = Some(strongest.map_or(pulse, |strongest| strongest.max(pulse)));
+    }
+
+    let Some(alpha) = strongest else {
+        return;
+    };
+    // Centered on a pixel column, so the one-pixel lines land on it.
+    let mark = (player_pos + DANGER_MARK_OFFSET).round() + Vec2::new(0.5, 0.);
+    let color = DANGER_COLOR.with_alpha((alpha * 1.5).min(1.));
+    gizmos.line_2d(mark + Vec2::Y * 2., mark + Vec2::Y * 6., color);
+    gizmos.line_2d(mark, mark + Vec2::Y, color);
+}
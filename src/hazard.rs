@@ -0,0 +1,302 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    camera::CameraFollow,
+    collider::{ColliderShape, GameLayer},
+    health::{Damage, DamageEvent, Health},
+    level::{LevelMarkers, MarkerKind, MarkerSpawnSet, SpawnMarker},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    prop::Prop,
+    state::{GameplaySet, NewGame},
+    status::{ApplyStatus, StatusEffect},
+    transition::{RoomEntered, RoomScoped},
+};
+
+/// How often spikes and fire hurt whatever is still in them after the first touch.
+const HAZARD_TICK_SECS: f32 = 0.5;
+const FIRE_BURN_SECS: f32 = 2.;
+/// How long the player takes to drop out of sight down a pit.
+const FALL_SECS: f32 = 0.6;
+/// Smallest the player shrinks to while falling, rather than vanishing outright.
+const FALL_MIN_SCALE: f32 = 0.1;
+const FALL_DAMAGE: f32 = 15.;
+/// The last safe position is only updated this far from any hazard, so the player
+/// isn't put back right at the edge they fell from.
+const SAFE_MARGIN: f32 = 12.;
+/// Under the entities walking over them, over the floor.
+const HAZARD_Z: f32 = -4.;
+
+/// Spikes and fire that hurt what walks into them, and pits that the player falls down
+/// and climbs back out of where they last stood safely.
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SafeGround>();
+        app.add_systems(NewGame, forget_safe_ground);
+        app.add_systems(
+            Update,
+            (
+                forget_safe_ground.run_if(on_event::<RoomEntered>),
+                spawn_level_hazards.in_set(MarkerSpawnSet),
+                (hurt_lingering, ignite_on_contact, fall_into_pits, fall).in_set(GameplaySet),
+            ),
+        );
+        app.add_systems(FixedUpdate, remember_safe_ground.in_set(GameplaySet));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HazardKind {
+    Spikes,
+    /// Sets whatever touches it burning, on top of the contact damage.
+    Fire,
+    /// Swallows whatever has its center over it.
+    Pit,
+}
+
+impl HazardKind {
+    /// Contact damage and knockback in px/s, for the kinds that have any.
+    fn damage(self) -> Option<Damage> {
+        match self {
+            HazardKind::Spikes => Some(Damage {
+                amount: 10.,
+                knockback: 160.,
+            }),
+            HazardKind::Fire => Some(Damage {
+                amount: 4.,
+                knockback: 60.,
+            }),
+            HazardKind::Pit => None,
+        }
+    }
+
+    fn image_path(self) -> &'static str {
+        match self {
+            HazardKind::Spikes => "spikes.png",
+            HazardKind::Fire => "fire.png",
+            HazardKind::Pit => "pit.png",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            HazardKind::Spikes => "Spikes",
+            HazardKind::Fire => "Fire",
+            HazardKind::Pit => "Pit",
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    /// Hurts what's still touching it each time this finishes.
+    pub tick: Timer,
+}
+
+/// Present while the player drops down a pit. They're put back on `SafeGround` when it
+/// finishes.
+#[derive(Component, Debug)]
+pub struct Falling(pub Timer);
+
+/// Where the player last stood well away from any hazard in this room.
+#[derive(Resource, Default, Debug)]
+struct SafeGround(Option<Vec2>);
+
+/// Each hazard covers its marker's rectangle.
+fn spawn_level_hazards(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        let MarkerKind::Hazard { kind, size } = marker.kind else {
+            continue;
+        };
+
+        let size = size.as_vec2();
+        let mut hazard = commands.spawn((
+            Hazard {
+                kind,
+                tick: Timer::from_seconds(HAZARD_TICK_SECS, TimerMode::Repeating),
+            },
+            Name::new(kind.name()),
+            RoomScoped,
+            Transform::from_translation(marker.position.extend(HAZARD_Z)),
+            Sprite {
+                image: asset_server.load(kind.image_path()),
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            RigidBody::Static,
+            ColliderShape::Rectangle {
+                width: size.x,
+                height: size.y,
+            }
+            .bundle(),
+            GameLayer::Hazard.collision_layers(),
+            Sensor,
+            CollidingEntities::default(),
+            PIXEL_PERFECT_LAYER,
+        ));
+        if let Some(damage) = kind.damage() {
+            hazard.insert(damage);
+        }
+    }
+}
+
+fn forget_safe_ground(mut safe_ground: ResMut<SafeGround>) {
+    safe_ground.0 = None;
+}
+
+/// `Damage` only hurts on the first touch; this keeps hurting whatever stays in.
+fn hurt_lingering(
+    time: Res<Time>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut hazard_q: Query<(&mut Hazard, &Damage, &GlobalTransform, &CollidingEntities)>,
+    target_q: Query<&GlobalTransform, With<Health>>,
+) {
+    for (mut hazard, damage, transform, colliding) in hazard_q.iter_mut() {
+        if colliding.is_empty() {
+            hazard.tick.reset();
+            continue;
+        }
+        if !hazard.tick.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        for &target in colliding.iter() {
+            let Ok(target_transform) = target_q.get(target) else {
+                continue;
+            };
+            let normal = (target_transform.translation() - transform.translation())
+                .truncate()
+                .normalize_or_zero();
+            damage_events.write(DamageEvent {
+                target,
+                amount: damage.amount,
+                knockback: normal * damage.knockback,
+            });
+        }
+    }
+}
+
+fn ignite_on_contact(
+    mut collision_events: EventReader<CollisionStarted>,
+    mut status_events: EventWriter<ApplyStatus>,
+    hazard_q: Query<&Hazard>,
+    health_q: Query<(), With<Health>>,
+) {
+    for CollisionStarted(a, b) in collision_events.read() {
+        for (source, target) in [(*a, *b), (*b, *a)] {
+            if hazard_q
+                .get(source)
+                .is_ok_and(|hazard| hazard.kind == HazardKind::Fire)
+                && health_q.contains(target)
+            {
+                status_events.write(ApplyStatus {
+                    target,
+                    effect: StatusEffect::Burn,
+                    secs: FIRE_BURN_SECS,
+                });
+            }
+        }
+    }
+}
+
+/// The player starts falling once their center is over a pit. Enemies go down for good
+/// and props are lost.
+#[allow(clippy::type_complexity)]
+fn fall_into_pits(
+    mut commands: Commands,
+    mut damage_events: EventWriter<DamageEvent>,
+    hazard_q: Query<(&Hazard, &GlobalTransform, &Sprite, &CollidingEntities)>,
+    body_q: Query<(&GlobalTransform, Has<Player>, Has<Prop>, Has<Health>), Without<Falling>>,
+) {
+    for (hazard, transform, sprite, colliding) in hazard_q.iter() {
+        if hazard.kind != HazardKind::Pit {
+            continue;
+        }
+
+        let area = Rect::from_center_size(
+            transform.translation().truncate(),
+            sprite.custom_size.unwrap_or_default(),
+        );
+        for &entity in colliding.iter() {
+            let Ok((body_transform, is_player, is_prop, has_health)) = body_q.get(entity) else {
+                continue;
+            };
+            if !area.contains(body_transform.translation().truncate()) {
+                continue;
+            }
+
+            if is_player {
+                commands
+                    .entity(entity)
+                    .insert(Falling(Timer::from_seconds(FALL_SECS, TimerMode::Once)));
+            } else if is_prop {
+                commands.entity(entity).despawn();
+            } else if has_health {
+                damage_events.write(DamageEvent {
+                    target: entity,
+                    amount: f32::INFINITY,
+                    knockback: Vec2::ZERO,
+                });
+            }
+        }
+    }
+}
+
+/// Shrinks the player away while they fall, then puts them back on safe ground, a
+/// little hurt. Without any yet, they're put back at the level's start.
+fn fall(
+    mut commands: Commands,
+    time: Res<Time>,
+    safe_ground: Res<SafeGround>,
+    markers: Res<LevelMarkers>,
+    mut camera_follow: ResMut<CameraFollow>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut player_q: Query<(Entity, &mut Falling, &mut Transform, &mut LinearVelocity)>,
+) {
+    for (entity, mut falling, mut transform, mut velocity) in player_q.iter_mut() {
+        velocity.0 = Vec2::ZERO;
+        let scale = falling
+            .0
+            .tick(time.delta())
+            .fraction_remaining()
+            .max(FALL_MIN_SCALE);
+        transform.scale = Vec3::new(scale, scale, 1.);
+        if !falling.0.finished() {
+            continue;
+        }
+
+        let position = safe_ground.0.unwrap_or_else(|| markers.player_start());
+        transform.scale = Vec3::ONE;
+        transform.translation = position.extend(transform.translation.z);
+        camera_follow.position = position;
+        commands.entity(entity).remove::<Falling>();
+        damage_events.write(DamageEvent {
+            target: entity,
+            amount: FALL_DAMAGE,
+            knockback: Vec2::ZERO,
+        });
+    }
+}
+
+fn remember_safe_ground(
+    mut safe_ground: ResMut<SafeGround>,
+    player: Single<&Transform, (With<Player>, Without<Falling>)>,
+    hazard_q: Query<(&GlobalTransform, &Sprite), With<Hazard>>,
+) {
+    let position = player.translation.truncate();
+    let near_hazard = hazard_q.iter().any(|(transform, sprite)| {
+        let size = sprite.custom_size.unwrap_or_default() + SAFE_MARGIN * 2.;
+        Rect::from_center_size(transform.translation().truncate(), size).contains(position)
+    });
+    if !near_hazard {
+        safe_ground.0 = Some(position);
+    }
+}
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{enemy::Emerging, state::GameplaySet};
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>();
+        app.add_event::<DeathEvent>();
+        app.add_systems(
+            Update,
+            (
+                tick_invulnerability,
+                apply_contact_damage,
+                apply_damage,
+                despawn_dead,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    /// Damage is ignored until this runs out.
+    pub invulnerability: Timer,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        let mut invulnerability = Timer::default();
+        invulnerability.tick(Duration::ZERO);
+
+        Self {
+            current: max,
+            max,
+            invulnerability,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerability.finished()
+    }
+
+    pub fn grant_invulnerability(&mut self, secs: f32) {
+        let remaining = self.invulnerability.remaining_secs();
+        self.invulnerability = Timer::from_seconds(secs.max(remaining), TimerMode::Once);
+    }
+}
+
+/// Damage dealt to anything with `Health` this entity touches.
+#[derive(Component, Debug)]
+#[require(CollisionEventsEnabled)]
+pub struct Damage {
+    pub amount: f32,
+    /// Speed in px/s the victim is pushed away with.
+    pub knockback: f32,
+}
+
+/// Despawns the entity once its `Health` reaches zero.
+#[derive(Component)]
+pub struct DespawnOnDeath;
+
+#[derive(Event, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    /// Velocity change applied to the target along the hit normal, in px/s.
+    pub knockback: Vec2,
+}
+
+#[derive(Event, Debug)]
+pub struct DeathEvent {
+    pub entity: Entity,
+}
+
+fn tick_invulnerability(time: Res<Time>, mut health_q: Query<&mut Health>) {
+    for mut health in health_q.iter_mut() {
+        health.invulnerability.tick(time.delta());
+    }
+}
+
+pub fn apply_contact_damage(
+    mut collision_events: EventReader<CollisionStarted>,
+    mut damage_events: EventWriter<DamageEvent>,
+    damage_q: Query<(&Damage, &GlobalTransform), Without<Emerging>>,
+    health_q: Query<&GlobalTransform, With<Health>>,
+) {
+    for CollisionStarted(a, b) in collision_events.read() {
+        for (source, target) in [(*a, *b), (*b, *a)] {
+            if let Ok((damage, source_transform)) = damage_q.get(source)
+                && let Ok(target_transform) = health_q.get(target)
+            {
+                let normal = (target_transform.translation() - source_transform.translation())
+                    .truncate()
+                    .normalize_or_zero();
+                damage_events.write(DamageEvent {
+                    target,
+                    amount: damage.amount,
+                    knockback: normal * damage.knockback,
+                });
+            }
+        }
+    }
+}
+
+pub fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut death_events: EventWriter<DeathEvent>,
+    mut health_q: Query<(
+        &mut Health,
+        Option<&mut ExternalImpulse>,
+        Option<&ComputedMass>,
+    )>,
+) {
+    for event in damage_events.read() {
+        let Ok((mut health, impulse, mass)) = health_q.get_mut(event.target) else {
+            continue;
+        };
+
+        if health.is_dead() || health.is_invulnerable() {
+            continue;
+        }
+
+        if event.knockback != Vec2::ZERO
+            && let Some(mass) = mass
+        {
+            let knockback_impulse = event.knockback * mass.value();
+            match impulse {
+                Some(mut impulse) => {
+                    impulse.apply_impulse(knockback_impulse);
+                }
+                None => {
+                    commands
+                        .entity(event.target)
+                        .insert(ExternalImpulse::new(knockback_impulse));
+                }
+            }
+        }
+
+        health.current = (health.current - event.amount).clamp(0., health.max);
+        if health.is_dead() {
+            death_events.write(DeathEvent {
+                entity: event.target,
+            });
+        }
+    }
+}
+
+pub fn despawn_dead(
+    mut commands: Commands,
+    mut death_events: EventReader<DeathEvent>,
+    despawn_q: Query<(), With<DespawnOnDeath>>,
+) {
+    for event in death_events.read() {
+        if despawn_q.contains(event.entity) {
+            commands.entity(event.entity).despawn();
+        }
+    }
+}
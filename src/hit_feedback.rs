@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use crate::{
+    health::{DamageEvent, Health},
+    state::GameplaySet,
+};
+
+/// A few frames at 60 fps.
+const FLASH_SECS: f32 = 0.07;
+/// Sprite colors multiply the texture, so an overbright color washes the sprite out to
+/// white.
+const FLASH_COLOR: Color = Color::linear_rgb(20., 20., 20.);
+/// Real time the game stays slowed after a hit.
+const HITSTOP_SECS: f32 = 0.06;
+const HITSTOP_SPEED: f32 = 0.05;
+
+/// Makes hits read at a glance: the damaged sprite flashes white and the game briefly
+/// slows to a crawl. Hit particles come from the particle plugin.
+pub struct HitFeedbackPlugin;
+
+impl Plugin for HitFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        let mut hitstop = Timer::from_seconds(HITSTOP_SECS, TimerMode::Once);
+        hitstop.tick(hitstop.duration());
+        app.insert_resource(Hitstop(hitstop));
+        app.add_systems(Update, start_hit_feedback.in_set(GameplaySet));
+        // Outside the gameplay set, so pausing mid-hitstop can't leave the game slowed.
+        app.add_systems(Update, (end_hit_flashes, end_hitstop));
+    }
+}
+
+/// Counts down the current hitstop in real time, since virtual time is what it slows.
+#[derive(Resource, Debug)]
+struct Hitstop(Timer);
+
+#[derive(Component, Debug)]
+struct HitFlash {
+    timer: Timer,
+    /// Restored once the flash ends.
+    color: Color,
+}
+
+fn start_hit_feedback(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut hitstop: ResMut<Hitstop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut target_q: Query<(&Health, &mut Sprite, Option<&mut HitFlash>)>,
+) {
+    for event in damage_events.read() {
+        let Ok((health, mut sprite, flash)) = target_q.get_mut(event.target) else {
+            continue;
+        };
+        if health.is_invulnerable() {
+            continue;
+        }
+
+        match flash {
+            Some(mut flash) => flash.timer.reset(),
+            None => {
+                commands.entity(event.target).try_insert(HitFlash {
+                    timer: Timer::from_seconds(FLASH_SECS, TimerMode::Once),
+                    color: sprite.color,
+                });
+            }
+        }
+        sprite.color = FLASH_COLOR;
+
+        hitstop.0.reset();
+        virtual_time.set_relative_speed(HITSTOP_SPEED);
+    }
+}
+
+fn end_hit_flashes(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut flash_q: Query<(Entity, &mut HitFlash, &mut Sprite)>,
+) {
+    for (entity, mut flash, mut sprite) in flash_q.iter_mut() {
+        if flash.timer.tick(time.delta()).finished() {
+            sprite.color = flash.color;
+            commands.entity(entity).remove::<HitFlash>();
+        }
+    }
+}
+
+fn end_hitstop(
+    time: Res<Time<Real>>,
+    mut hitstop: ResMut<Hitstop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    if hitstop.0.finished() {
+        return;
+    }
+    if hitstop.0.tick(time.delta()).finished() {
+        virtual_time.set_relative_speed(1.);
+    }
+}
@@ -0,0 +1,347 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    ai::Perception,
+    boss::Boss,
+    flare::FlareInventory,
+    grenade::GrenadeInventory,
+    health::Health,
+    pixel_perfect::{CanvasCoords, CanvasText, HIGH_RES_LAYER, PixelCanvasConfig},
+    player::Player,
+    portal::Portal,
+    results::RunStats,
+    score::Score,
+    settings::GameSettings,
+    speedrun::{Splits, format_run_time, format_split_delta},
+    stamina::Stamina,
+    state::{GameScoped, NewGame},
+    wave::WaveManager,
+    weapon::{Equipped, Weapon},
+};
+
+/// HUD layout is in canvas pixels; it is scaled up with the canvas.
+const HUD_MARGIN: Vec2 = Vec2::new(4., 4.);
+const BAR_SIZE: Vec2 = Vec2::new(24., 2.);
+/// Vertical distance between stacked bars.
+const BAR_SPACING: f32 = 4.;
+const BAR_BACKGROUND: Color = Color::srgba(0., 0., 0., 0.6);
+const HEALTH_FILL: Color = Color::srgb(0.85, 0.25, 0.25);
+const STAMINA_FILL: Color = Color::srgb(0.35, 0.85, 0.45);
+const STAMINA_FILL_EXHAUSTED: Color = Color::srgb(0.85, 0.35, 0.3);
+const DETECTION_FILL: Color = Color::srgb(0.95, 0.8, 0.3);
+const DETECTION_FILL_DETECTED: Color = Color::srgb(1., 0.3, 0.2);
+const HUD_FONT_SIZE: f32 = 6.;
+const BOSS_BAR_SIZE: Vec2 = Vec2::new(64., 3.);
+/// Distance from the top of the canvas, leaving room for the score.
+const BOSS_BAR_TOP: f32 = 9.;
+const BOSS_FILL: Color = Color::srgb(0.7, 0.2, 0.8);
+/// Distance of the run timer from the top of the canvas, below the bars.
+const TIMER_TOP: f32 = 3. * BAR_SPACING + 2.;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(NewGame, spawn_hud);
+        app.add_systems(
+            Update,
+            (
+                position_hud_bars,
+                update_health_bar,
+                update_stamina_bar,
+                update_detection_bar,
+                update_boss_bar,
+                update_hud_texts,
+            ),
+        );
+    }
+}
+
+/// A bar in the top-left corner, stacked below the bars with a lower `slot`.
+#[derive(Component)]
+struct HudBar {
+    slot: usize,
+}
+
+#[derive(Component)]
+struct HealthBarFill;
+
+#[derive(Component)]
+struct StaminaBarFill;
+
+/// How close the most suspicious enemy is to detecting the player.
+#[derive(Component)]
+struct DetectionBarFill;
+
+/// Across the top of the canvas, shown during boss fights.
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossBarFill;
+
+/// A line of text pinned to a corner of the canvas.
+#[derive(Component, Clone, Copy)]
+enum HudText {
+    Score,
+    Wave,
+    Flares,
+    Ammo,
+    /// The speedrun timer, when turned on in the settings.
+    Timer,
+    /// Flares around the nearest portal, or how long it stays open.
+    Objective,
+}
+
+impl HudText {
+    /// Which corner the text sits in, as a sign per axis.
+    fn corner(self) -> Vec2 {
+        match self {
+            HudText::Score => Vec2::new(0., 1.),
+            HudText::Wave => Vec2::new(1., 1.),
+            HudText::Flares => Vec2::new(-1., -1.),
+            HudText::Ammo => Vec2::new(1., -1.),
+            HudText::Timer => Vec2::new(-1., 1.),
+            HudText::Objective => Vec2::new(0., -1.),
+        }
+    }
+
+    fn anchor(self) -> Anchor {
+        match self {
+            HudText::Score => Anchor::TopCenter,
+            HudText::Wave => Anchor::TopRight,
+            HudText::Flares => Anchor::BottomLeft,
+            HudText::Ammo => Anchor::BottomRight,
+            HudText::Timer => Anchor::TopLeft,
+            HudText::Objective => Anchor::BottomCenter,
+        }
+    }
+}
+
+fn spawn_hud_bar(commands: &mut Commands, slot: usize, fill: impl Bundle, fill_color: Color) {
+    commands
+        .spawn((
+            HudBar { slot },
+            GameScoped,
+            Name::new("HUD bar"),
+            Sprite {
+                color: BAR_BACKGROUND,
+                custom_size: Some(BAR_SIZE),
+                anchor: Anchor::TopLeft,
+                ..Default::default()
+            },
+            Transform::default(),
+            HIGH_RES_LAYER,
+        ))
+        .with_child((
+            fill,
+            Sprite {
+                color: fill_color,
+                custom_size: Some(BAR_SIZE),
+                anchor: Anchor::TopLeft,
+                ..Default::default()
+            },
+            Transform::from_xyz(0., 0., 0.1),
+            HIGH_RES_LAYER,
+        ));
+}
+
+fn spawn_hud(mut commands: Commands) {
+    spawn_hud_bar(&mut commands, 0, HealthBarFill, HEALTH_FILL);
+    spawn_hud_bar(&mut commands, 1, StaminaBarFill, STAMINA_FILL);
+    spawn_hud_bar(&mut commands, 2, DetectionBarFill, DETECTION_FILL);
+
+    commands
+        .spawn((
+            BossBar,
+            GameScoped,
+            Name::new("Boss bar"),
+            Sprite {
+                color: BAR_BACKGROUND,
+                custom_size: Some(BOSS_BAR_SIZE),
+                anchor: Anchor::TopCenter,
+                ..Default::default()
+            },
+            Transform::default(),
+            Visibility::Hidden,
+            HIGH_RES_LAYER,
+        ))
+        .with_child((
+            BossBarFill,
+            Sprite {
+                color: BOSS_FILL,
+                custom_size: Some(BOSS_BAR_SIZE),
+                anchor: Anchor::TopLeft,
+                ..Default::default()
+            },
+            Transform::from_xyz(-BOSS_BAR_SIZE.x / 2., 0., 0.1),
+            HIGH_RES_LAYER,
+        ));
+
+    for hud_text in [
+        HudText::Score,
+        HudText::Wave,
+        HudText::Flares,
+        HudText::Ammo,
+        HudText::Timer,
+        HudText::Objective,
+    ] {
+        commands.spawn((
+            hud_text,
+            GameScoped,
+            Text2d::default(),
+            hud_text.anchor(),
+            CanvasText::new(Vec2::ZERO, HUD_FONT_SIZE),
+            Transform::from_xyz(0., 0., 10.),
+        ));
+    }
+}
+
+/// Bars are scaled through their transform; unlike text, sprites stay sharp that way.
+fn position_hud_bars(
+    config: Res<PixelCanvasConfig>,
+    coords: CanvasCoords,
+    mut bar_q: Query<(&HudBar, &mut Transform)>,
+) {
+    let scale = coords.scale();
+    let corner = config.size_f32() * Vec2::new(-0.5, 0.5) + HUD_MARGIN * Vec2::new(1., -1.);
+    for (bar, mut transform) in bar_q.iter_mut() {
+        let position = corner - Vec2::new(0., bar.slot as f32 * BAR_SPACING);
+        transform.translation = (position * scale).extend(10.);
+        transform.scale = scale.extend(1.);
+    }
+}
+
+fn update_health_bar(
+    health: Single<&Health, With<Player>>,
+    mut fill_sprite: Single<&mut Sprite, With<HealthBarFill>>,
+) {
+    let fraction = (health.current / health.max).clamp(0., 1.);
+    fill_sprite.custom_size = Some(BAR_SIZE * Vec2::new(fraction, 1.));
+}
+
+fn update_stamina_bar(
+    stamina: Single<&Stamina, With<Player>>,
+    mut fill_sprite: Single<&mut Sprite, With<StaminaBarFill>>,
+) {
+    fill_sprite.custom_size = Some(BAR_SIZE * Vec2::new(stamina.fraction(), 1.));
+    fill_sprite.color = if stamina.exhausted {
+        STAMINA_FILL_EXHAUSTED
+    } else {
+        STAMINA_FILL
+    };
+}
+
+fn update_detection_bar(
+    perception_q: Query<&Perception>,
+    mut fill_sprite: Single<&mut Sprite, With<DetectionBarFill>>,
+) {
+    let detection = perception_q
+        .iter()
+        .map(|perception| perception.detection)
+        .fold(0., f32::max);
+    fill_sprite.custom_size = Some(BAR_SIZE * Vec2::new(detection, 1.));
+    fill_sprite.color = if detection >= 1. {
+        DETECTION_FILL_DETECTED
+    } else {
+        DETECTION_FILL
+    };
+}
+
+/// Follows the first boss the player is fighting.
+fn update_boss_bar(
+    config: Res<PixelCanvasConfig>,
+    boss_q: Query<(&Boss, &Health)>,
+    coords: CanvasCoords,
+    mut bar: Single<(&mut Transform, &mut Visibility), With<BossBar>>,
+    mut fill_sprite: Single<&mut Sprite, With<BossBarFill>>,
+) {
+    let (transform, visibility) = &mut *bar;
+    let Some((_, health)) = boss_q.iter().find(|(boss, _)| boss.engaged) else {
+        **visibility = Visibility::Hidden;
+        return;
+    };
+
+    **visibility = Visibility::Inherited;
+    let scale = coords.scale();
+    let top = config.size_f32().y / 2. - BOSS_BAR_TOP;
+    transform.translation = Vec3::new(0., top * scale.y, 10.);
+    transform.scale = scale.extend(1.);
+
+    let fraction = (health.current / health.max).clamp(0., 1.);
+    fill_sprite.custom_size = Some(BOSS_BAR_SIZE * Vec2::new(fraction, 1.));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_hud_texts(
+    config: Res<PixelCanvasConfig>,
+    waves: Res<WaveManager>,
+    score: Res<Score>,
+    settings: Res<GameSettings>,
+    stats: Res<RunStats>,
+    splits: Res<Splits>,
+    player: Single<(Entity, &Transform, &FlareInventory, &GrenadeInventory), With<Player>>,
+    portal_q: Query<(&Transform, &Portal)>,
+    weapon_q: Query<(&Weapon, &ChildOf), With<Equipped>>,
+    mut text_q: Query<(&HudText, &mut Text2d, &mut CanvasText)>,
+) {
+    let (player_entity, player_transform, flares, grenades) = *player;
+    let player_pos = player_transform.translation.truncate();
+    let nearest_portal = portal_q
+        .iter()
+        .min_by(|(a, _), (b, _)| {
+            let distance_a = a.translation.truncate().distance_squared(player_pos);
+            let distance_b = b.translation.truncate().distance_squared(player_pos);
+            distance_a.total_cmp(&distance_b)
+        })
+        .map(|(_, portal)| portal);
+    let half_size = config.size_f32() / 2. - HUD_MARGIN;
+
+    for (hud_text, mut text, mut canvas_text) in text_q.iter_mut() {
+        canvas_text.position = half_size * hud_text.corner();
+        if let HudText::Timer = hud_text {
+            canvas_text.position.y -= TIMER_TOP;
+        }
+
+        let label = match hud_text {
+            HudText::Score if score.combo > 1 => format!("{} x{}", score.points, score.combo),
+            HudText::Score => score.points.to_string(),
+            HudText::Wave if waves.wave > 0 => format!("Wave {}", waves.wave),
+            HudText::Wave => String::new(),
+            HudText::Flares => format!(
+                "Grenades {}/{}\nFlares {}/{}",
+                grenades.count, grenades.max, flares.count, flares.max
+            ),
+            HudText::Ammo => weapon_q
+                .iter()
+                .find(|(_, child_of)| child_of.parent() == player_entity)
+                .map(|(weapon, _)| match weapon.ammo {
+                    Some(ammo) => format!("{} {ammo}", weapon.definition.name),
+                    None => weapon.definition.name.to_string(),
+                })
+                .unwrap_or_default(),
+            HudText::Timer if settings.speedrun_timer => match splits.delta {
+                Some(delta) => format!(
+                    "{}\n{}",
+                    format_run_time(stats.time),
+                    format_split_delta(delta)
+                ),
+                None => format_run_time(stats.time),
+            },
+            HudText::Timer => String::new(),
+            HudText::Objective => match nearest_portal {
+                Some(portal) => match portal.secs_left() {
+                    Some(secs) => format!("Portal open {secs}s"),
+                    None => format!(
+                        "Portal {}/{} flares",
+                        portal.flares_in_range, portal.flares_required
+                    ),
+                },
+                None => String::new(),
+            },
+        };
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
@@ -0,0 +1,656 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::{InputSystem, mouse::MouseMotion},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attract::MenuMode,
+    config::{load_ron, save_ron},
+    replay::playing_back,
+    settings::GameSettings,
+};
+
+const BINDINGS_FILE: &str = "bindings.ron";
+/// Actions whose presses are held on to until they can be acted on, see
+/// `PlayerInput::take_buffered`.
+const BUFFERED_ACTIONS: [Action; 5] = [
+    Action::Dash,
+    Action::Fire,
+    Action::ThrowFlare,
+    Action::Melee,
+    Action::Interact,
+];
+
+/// Keys that can be named in the bindings file. `KeyCode` has no serde support of its
+/// own, so bindings are written by their variant name and looked up here.
+const BINDABLE_KEYS: [KeyCode; 77] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::CapsLock,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Backslash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Backquote,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::NumpadAdd,
+    KeyCode::NumpadSubtract,
+];
+
+const BINDABLE_MOUSE_BUTTONS: [MouseButton; 5] = [
+    MouseButton::Left,
+    MouseButton::Right,
+    MouseButton::Middle,
+    MouseButton::Back,
+    MouseButton::Forward,
+];
+
+const BINDABLE_GAMEPAD_BUTTONS: [GamepadButton; 16] = [
+    GamepadButton::South,
+    GamepadButton::East,
+    GamepadButton::North,
+    GamepadButton::West,
+    GamepadButton::LeftTrigger,
+    GamepadButton::LeftTrigger2,
+    GamepadButton::RightTrigger,
+    GamepadButton::RightTrigger2,
+    GamepadButton::Select,
+    GamepadButton::Start,
+    GamepadButton::LeftThumb,
+    GamepadButton::RightThumb,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+];
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputBindings::load());
+        app.insert_resource(PlayerInput::default());
+        app.init_resource::<ActiveDevice>();
+        app.add_systems(
+            PreUpdate,
+            (
+                update_player_input.run_if(not(playing_back).and(not(in_state(MenuMode::Attract)))),
+                buffer_presses,
+            )
+                .chain()
+                .after(InputSystem),
+        );
+        app.add_systems(PreUpdate, track_active_device.after(InputSystem));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Melee,
+    ThrowFlare,
+    ThrowGrenade,
+    Dash,
+    Grapple,
+    Sprint,
+    ZoomIn,
+    ZoomOut,
+    Pause,
+    Confirm,
+    Interact,
+    Inventory,
+    DropItem,
+}
+
+impl Action {
+    pub const ALL: [Action; 18] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Melee,
+        Action::ThrowFlare,
+        Action::ThrowGrenade,
+        Action::Dash,
+        Action::Grapple,
+        Action::Sprint,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Pause,
+        Action::Confirm,
+        Action::Interact,
+        Action::Inventory,
+        Action::DropItem,
+    ];
+
+    fn default_bindings(self) -> Vec<InputBinding> {
+        use InputBinding::{Gamepad, Key, Mouse};
+
+        match self {
+            Action::MoveUp => vec![
+                Key(KeyCode::KeyW),
+                Key(KeyCode::ArrowUp),
+                Gamepad(GamepadButton::DPadUp),
+            ],
+            Action::MoveDown => vec![
+                Key(KeyCode::KeyS),
+                Key(KeyCode::ArrowDown),
+                Gamepad(GamepadButton::DPadDown),
+            ],
+            Action::MoveLeft => vec![
+                Key(KeyCode::KeyA),
+                Key(KeyCode::ArrowLeft),
+                Gamepad(GamepadButton::DPadLeft),
+            ],
+            Action::MoveRight => vec![
+                Key(KeyCode::KeyD),
+                Key(KeyCode::ArrowRight),
+                Gamepad(GamepadButton::DPadRight),
+            ],
+            Action::Fire => vec![
+                Mouse(MouseButton::Left),
+                Gamepad(GamepadButton::RightTrigger2),
+            ],
+            Action::Melee => vec![
+                Mouse(MouseButton::Right),
+                Gamepad(GamepadButton::RightTrigger),
+            ],
+            Action::ThrowFlare => vec![Key(KeyCode::KeyF), Gamepad(GamepadButton::North)],
+            Action::ThrowGrenade => vec![Key(KeyCode::KeyC), Gamepad(GamepadButton::RightThumb)],
+            Action::Dash => vec![
+                Key(KeyCode::ShiftLeft),
+                Key(KeyCode::ShiftRight),
+                Gamepad(GamepadButton::East),
+            ],
+            Action::Grapple => vec![Key(KeyCode::KeyG), Gamepad(GamepadButton::LeftTrigger)],
+            Action::Sprint => vec![
+                Key(KeyCode::ControlLeft),
+                Gamepad(GamepadButton::LeftTrigger2),
+            ],
+            Action::ZoomIn => vec![Key(KeyCode::Equal), Key(KeyCode::NumpadAdd)],
+            Action::ZoomOut => vec![Key(KeyCode::Minus), Key(KeyCode::NumpadSubtract)],
+            Action::Pause => vec![Key(KeyCode::Escape), Gamepad(GamepadButton::Start)],
+            Action::Confirm => vec![
+                Key(KeyCode::Enter),
+                Key(KeyCode::Space),
+                Gamepad(GamepadButton::South),
+            ],
+            Action::Interact => vec![Key(KeyCode::KeyE), Gamepad(GamepadButton::West)],
+            Action::Inventory => vec![Key(KeyCode::KeyI), Gamepad(GamepadButton::Select)],
+            Action::DropItem => vec![Key(KeyCode::KeyQ), Gamepad(GamepadButton::West)],
+        }
+    }
+}
+
+/// A single physical input. Written to the bindings file as the key's variant name
+/// (`"KeyW"`, `"ShiftLeft"`), or as `"Mouse"` or `"Gamepad"` followed by the button
+/// (`"MouseLeft"`, `"GamepadSouth"`). Gamepad bindings match a button on any gamepad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl InputBinding {
+    /// Whether the bindings file can name this input, see `BINDABLE_KEYS`.
+    pub fn is_bindable(self) -> bool {
+        InputBinding::try_from(String::from(self)).is_ok()
+    }
+
+    /// Short name for showing to the player, like `"Q"` for `KeyQ` or `"LMB"` for the
+    /// left mouse button.
+    pub fn label(self) -> String {
+        match self {
+            InputBinding::Key(key) => {
+                let name = format!("{key:?}");
+                let short = match key {
+                    KeyCode::ArrowUp => "Up",
+                    KeyCode::ArrowDown => "Down",
+                    KeyCode::ArrowLeft => "Left",
+                    KeyCode::ArrowRight => "Right",
+                    KeyCode::Escape => "Esc",
+                    KeyCode::Backspace => "Bksp",
+                    KeyCode::CapsLock => "Caps",
+                    KeyCode::ShiftLeft | KeyCode::ShiftRight => "Shift",
+                    KeyCode::ControlLeft | KeyCode::ControlRight => "Ctrl",
+                    KeyCode::AltLeft | KeyCode::AltRight => "Alt",
+                    KeyCode::Minus | KeyCode::NumpadSubtract => "-",
+                    KeyCode::Equal => "=",
+                    KeyCode::NumpadAdd => "+",
+                    KeyCode::BracketLeft => "[",
+                    KeyCode::BracketRight => "]",
+                    KeyCode::Backslash => "\\",
+                    KeyCode::Semicolon => ";",
+                    KeyCode::Quote => "'",
+                    KeyCode::Backquote => "`",
+                    KeyCode::Comma => ",",
+                    KeyCode::Period => ".",
+                    KeyCode::Slash => "/",
+                    _ => {
+                        return name
+                            .strip_prefix("Key")
+                            .or_else(|| name.strip_prefix("Digit"))
+                            .unwrap_or(&name)
+                            .to_string();
+                    }
+                };
+                short.to_string()
+            }
+            InputBinding::Mouse(button) => match button {
+                MouseButton::Left => "LMB".to_string(),
+                MouseButton::Right => "RMB".to_string(),
+                MouseButton::Middle => "MMB".to_string(),
+                button => format!("Mouse {button:?}"),
+            },
+            InputBinding::Gamepad(button) => match button {
+                GamepadButton::South => "A".to_string(),
+                GamepadButton::East => "B".to_string(),
+                GamepadButton::West => "X".to_string(),
+                GamepadButton::North => "Y".to_string(),
+                GamepadButton::LeftTrigger => "LB".to_string(),
+                GamepadButton::RightTrigger => "RB".to_string(),
+                GamepadButton::LeftTrigger2 => "LT".to_string(),
+                GamepadButton::RightTrigger2 => "RT".to_string(),
+                GamepadButton::DPadUp => "D-Up".to_string(),
+                GamepadButton::DPadDown => "D-Down".to_string(),
+                GamepadButton::DPadLeft => "D-Left".to_string(),
+                GamepadButton::DPadRight => "D-Right".to_string(),
+                button => format!("{button:?}"),
+            },
+        }
+    }
+
+    fn pressed(
+        self,
+        keyboard_input: &ButtonInput<KeyCode>,
+        mouse_input: &ButtonInput<MouseButton>,
+        gamepad_q: &Query<&Gamepad>,
+    ) -> bool {
+        match self {
+            InputBinding::Key(key) => keyboard_input.pressed(key),
+            InputBinding::Mouse(button) => mouse_input.pressed(button),
+            InputBinding::Gamepad(button) => {
+                gamepad_q.iter().any(|gamepad| gamepad.pressed(button))
+            }
+        }
+    }
+}
+
+impl From<InputBinding> for String {
+    fn from(binding: InputBinding) -> Self {
+        match binding {
+            InputBinding::Key(key) => format!("{key:?}"),
+            InputBinding::Mouse(button) => format!("Mouse{button:?}"),
+            InputBinding::Gamepad(button) => format!("Gamepad{button:?}"),
+        }
+    }
+}
+
+impl TryFrom<String> for InputBinding {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        if let Some(button_name) = name.strip_prefix("Mouse")
+            && let Some(button) = BINDABLE_MOUSE_BUTTONS
+                .into_iter()
+                .find(|button| format!("{button:?}") == button_name)
+        {
+            return Ok(InputBinding::Mouse(button));
+        }
+        if let Some(button_name) = name.strip_prefix("Gamepad")
+            && let Some(button) = BINDABLE_GAMEPAD_BUTTONS
+                .into_iter()
+                .find(|button| format!("{button:?}") == button_name)
+        {
+            return Ok(InputBinding::Gamepad(button));
+        }
+
+        BINDABLE_KEYS
+            .into_iter()
+            .find(|key| format!("{key:?}") == name)
+            .map(InputBinding::Key)
+            .ok_or_else(|| format!("unknown input `{name}`"))
+    }
+}
+
+/// Which inputs trigger each action. Loaded from the bindings file in the config
+/// directory, so controls can be remapped without touching the gameplay systems.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct InputBindings(pub BTreeMap<Action, Vec<InputBinding>>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self(
+            Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+        )
+    }
+}
+
+impl InputBindings {
+    /// Loads the bindings file, writing out the defaults on first launch so there is
+    /// something to edit. Actions missing from the file keep their default bindings.
+    pub fn load() -> Self {
+        let mut bindings = Self::default();
+        match load_ron::<Self>(BINDINGS_FILE) {
+            Ok(Some(loaded)) => bindings.0.extend(loaded.0),
+            Ok(None) => bindings.save(),
+            Err(error) => warn!("Failed to load {BINDINGS_FILE}, using default bindings: {error}"),
+        }
+        bindings
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = save_ron(BINDINGS_FILE, self) {
+            warn!("Failed to save {BINDINGS_FILE}: {error}");
+        }
+    }
+
+    pub fn bindings(&self, action: Action) -> &[InputBinding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// The actions held this frame, resolved from the raw inputs through `InputBindings`.
+/// Gameplay systems read this instead of `ButtonInput` directly.
+#[derive(Resource, Default, Debug)]
+pub struct PlayerInput {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+    /// Presses of `BUFFERED_ACTIONS` not yet acted on, with the real time they were
+    /// made at.
+    buffered: HashMap<Action, Duration>,
+}
+
+impl PlayerInput {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    #[allow(dead_code)] // Nothing reacts to a release yet, charged throws will.
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// Takes a press of `action` made within the last `GameSettings::input_buffer_secs`,
+    /// so pressing just before it's allowed, like as a cooldown ends, still counts once
+    /// it is. Only check this when the action can actually happen, as it uses the press
+    /// up.
+    pub fn take_buffered(&mut self, action: Action) -> bool {
+        self.buffered.remove(&action).is_some()
+    }
+
+    /// Buffers this frame's presses of `BUFFERED_ACTIONS`, made at `now`, and forgets
+    /// the ones made longer than `window` ago.
+    pub fn buffer_presses(&mut self, now: Duration, window: Duration) {
+        self.buffered
+            .retain(|_, pressed_at| now.saturating_sub(*pressed_at) <= window);
+        for action in BUFFERED_ACTIONS {
+            if self.just_pressed.contains(&action) {
+                self.buffered.insert(action, now);
+            }
+        }
+    }
+
+    /// Forgets the buffered presses, so ones made before pausing or dying don't go off
+    /// after.
+    pub fn clear_buffer(&mut self) {
+        self.buffered.clear();
+    }
+
+    /// The held actions, in a stable order.
+    pub fn pressed_actions(&self) -> Vec<Action> {
+        let mut actions: Vec<Action> = self.pressed.iter().copied().collect();
+        actions.sort();
+        actions
+    }
+
+    /// Replaces the held actions without any of them counting as just pressed, for
+    /// presses that have already been used up on something else.
+    pub fn swallow(&mut self, pressed: HashSet<Action>) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.pressed = pressed;
+    }
+
+    /// Replaces the held actions, working out which were just pressed or released.
+    pub fn set_pressed(&mut self, pressed: HashSet<Action>) {
+        self.just_pressed = pressed.difference(&self.pressed).copied().collect();
+        self.just_released = self.pressed.difference(&pressed).copied().collect();
+        self.pressed = pressed;
+    }
+
+    /// Normalized direction from the four movement actions.
+    pub fn movement(&self) -> Vec2 {
+        let mut direction = Vec2::ZERO;
+        if self.pressed(Action::MoveLeft) {
+            direction.x -= 1.;
+        };
+        if self.pressed(Action::MoveRight) {
+            direction.x += 1.;
+        };
+        if self.pressed(Action::MoveUp) {
+            direction.y += 1.;
+        };
+        if self.pressed(Action::MoveDown) {
+            direction.y -= 1.;
+        };
+
+        direction.normalize_or_zero()
+    }
+}
+
+/// Actions are edge-detected against the previous frame rather than taken from
+/// `just_pressed` on the raw inputs, so holding one binding and pressing another bound
+/// to the same action doesn't retrigger it.
+///
+/// Enter pressed with Alt switches the window mode instead, see `toggle_window_mode`,
+/// so it's ignored until it's let go rather than also confirming in a menu.
+pub fn update_player_input(
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepad_q: Query<&Gamepad>,
+    mut player_input: ResMut<PlayerInput>,
+    mut swallowing_enter: Local<bool>,
+) {
+    let alt = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    if alt && keyboard_input.just_pressed(KeyCode::Enter) {
+        *swallowing_enter = true;
+    } else if !keyboard_input.pressed(KeyCode::Enter) {
+        *swallowing_enter = false;
+    }
+
+    let pressed: HashSet<Action> = Action::ALL
+        .into_iter()
+        .filter(|action| {
+            bindings.bindings(*action).iter().any(|binding| {
+                !(*swallowing_enter && *binding == InputBinding::Key(KeyCode::Enter))
+                    && binding.pressed(&keyboard_input, &mouse_input, &gamepad_q)
+            })
+        })
+        .collect();
+
+    player_input.set_pressed(pressed);
+}
+
+/// The device the player last used, which on-screen prompts show the inputs of.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ActiveDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
+}
+
+impl ActiveDevice {
+    /// Whether `binding` is an input on this device.
+    pub fn has(self, binding: InputBinding) -> bool {
+        matches!(binding, InputBinding::Gamepad(_)) == (self == ActiveDevice::Gamepad)
+    }
+}
+
+/// Switches on any press, or on moving the mouse or a stick, so the prompts follow the
+/// player picking up the other device mid-game.
+fn track_active_device(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepad_q: Query<&Gamepad>,
+    mut mouse_motion_events: EventReader<MouseMotion>,
+    mut active_device: ResMut<ActiveDevice>,
+) {
+    let mouse_moved = mouse_motion_events
+        .read()
+        .any(|motion| motion.delta.length() > 1.);
+    if mouse_moved
+        || keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+    {
+        active_device.set_if_neq(ActiveDevice::KeyboardMouse);
+    } else if gamepad_q.iter().any(|gamepad| {
+        gamepad.get_just_pressed().next().is_some()
+            || gamepad.left_stick().length() > 0.5
+            || gamepad.right_stick().length() > 0.5
+    }) {
+        active_device.set_if_neq(ActiveDevice::Gamepad);
+    }
+}
+
+/// The devices' input, for noticing a player is there while `PlayerInput` is fed
+/// from somewhere else.
+#[derive(SystemParam)]
+pub struct DeviceInput<'w, 's> {
+    bindings: Res<'w, InputBindings>,
+    keyboard_input: Res<'w, ButtonInput<KeyCode>>,
+    mouse_input: Res<'w, ButtonInput<MouseButton>>,
+    gamepad_q: Query<'w, 's, &'static Gamepad>,
+    mouse_motion_events: EventReader<'w, 's, MouseMotion>,
+}
+
+impl DeviceInput<'_, '_> {
+    /// The actions held on the devices.
+    pub fn held_actions(&self) -> HashSet<Action> {
+        Action::ALL
+            .into_iter()
+            .filter(|action| {
+                self.bindings.bindings(*action).iter().any(|binding| {
+                    binding.pressed(&self.keyboard_input, &self.mouse_input, &self.gamepad_q)
+                })
+            })
+            .collect()
+    }
+
+    /// Whether anything was pressed or moved this frame, bound to an action or not.
+    pub fn any_activity(&mut self) -> bool {
+        let moved = self
+            .mouse_motion_events
+            .read()
+            .any(|motion| motion.delta != Vec2::ZERO);
+        moved
+            || self.keyboard_input.get_just_pressed().next().is_some()
+            || self.mouse_input.get_just_pressed().next().is_some()
+            || self.gamepad_q.iter().any(|gamepad| {
+                gamepad.get_just_pressed().next().is_some()
+                    || gamepad.left_stick().length() > 0.5
+                    || gamepad.right_stick().length() > 0.5
+            })
+    }
+}
+
+/// Times presses in real time, so hitstop slowing the game down doesn't shorten the
+/// window. Runs after the replay's input too, which has its presses buffered the same
+/// way.
+pub fn buffer_presses(
+    time: Res<Time<Real>>,
+    settings: Res<GameSettings>,
+    mut player_input: ResMut<PlayerInput>,
+) {
+    let window = Duration::from_secs_f32(settings.input_buffer_secs.max(0.));
+    player_input.buffer_presses(time.elapsed(), window);
+}
@@ -0,0 +1,228 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    input::{Action, ActiveDevice, InputBinding, InputBindings},
+    pixel_perfect::{CanvasSprite, CanvasText},
+};
+
+/// Size of a cell in `input_glyphs.png`, in texels. Glyphs sit at the left of their
+/// cell, as wide as they need.
+const GLYPH_CELL: UVec2 = UVec2::new(28, 9);
+const GLYPH_COLUMNS: u32 = 8;
+const HINT_FONT_SIZE: f32 = 6.;
+const HINT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+/// The first cells of the sheet, shown for an action that isn't bound and for an input
+/// the sheet has no glyph for.
+const UNBOUND_GLYPH: Glyph = Glyph {
+    index: 0,
+    width: 27,
+};
+const UNKNOWN_GLYPH: Glyph = Glyph { index: 1, width: 7 };
+
+/// The rest of the sheet, left to right and top to bottom: key caps, then the mouse
+/// buttons, then the gamepad buttons. Each is the input's label, as `InputBinding::label`
+/// has it in capitals, with the width of its glyph.
+#[rustfmt::skip]
+const KEY_GLYPHS: &[(&str, u32)] = &[
+    ("A", 7), ("B", 7), ("C", 7), ("D", 7), ("E", 7), ("F", 7), ("G", 7), ("H", 7),
+    ("I", 7), ("J", 7), ("K", 7), ("L", 7), ("M", 7), ("N", 7), ("O", 7), ("P", 7),
+    ("Q", 7), ("R", 7), ("S", 7), ("T", 7), ("U", 7), ("V", 7), ("W", 7), ("X", 7),
+    ("Y", 7), ("Z", 7), ("0", 7), ("1", 7), ("2", 7), ("3", 7), ("4", 7), ("5", 7),
+    ("6", 7), ("7", 7), ("8", 7), ("9", 7), ("UP", 7), ("DOWN", 7), ("LEFT", 7),
+    ("RIGHT", 7), ("SPACE", 23), ("ENTER", 23), ("ESC", 15), ("TAB", 15), ("SHIFT", 23),
+    ("CTRL", 19), ("ALT", 15), ("BKSP", 19), ("CAPS", 19), ("F1", 11), ("F2", 11),
+    ("F3", 11), ("F4", 11), ("F5", 11), ("F6", 11), ("F7", 11), ("F8", 11), ("F9", 11),
+    ("F10", 15), ("F11", 15), ("F12", 15), ("-", 7), ("=", 7), ("+", 7), ("[", 7),
+    ("]", 7), ("\\", 7), (";", 7), ("'", 7), ("`", 7), (",", 7), (".", 7), ("/", 7),
+];
+const MOUSE_GLYPHS: &[(&str, u32)] = &[("LMB", 7), ("RMB", 7), ("MMB", 7)];
+#[rustfmt::skip]
+const GAMEPAD_GLYPHS: &[(&str, u32)] = &[
+    ("A", 9), ("B", 9), ("X", 9), ("Y", 9), ("LB", 11), ("RB", 11), ("LT", 11), ("RT", 11),
+    ("START", 23), ("SELECT", 15), ("LEFTTHUMB", 11), ("RIGHTTHUMB", 11),
+];
+
+/// Shows the input bound to an action on the active device: a key cap, gamepad button
+/// or mouse glyph from `input_glyphs.png`, or a `?` cap for inputs the sheet doesn't
+/// have. It keeps up with rebinding and switching devices.
+///
+/// The glyphs are drawn in texels, so the prompt works on the canvas as it is, and on
+/// the high-res layer with a `CanvasSprite` to scale it up.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Sprite)]
+pub struct InputPrompt(pub Action);
+
+pub struct InputPromptPlugin;
+
+impl Plugin for InputPromptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_glyph_sheet);
+        app.add_systems(PostUpdate, update_input_prompts);
+    }
+}
+
+/// A cell of the glyph sheet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Glyph {
+    index: usize,
+    width: u32,
+}
+
+impl Glyph {
+    fn for_binding(binding: Option<InputBinding>) -> Self {
+        let Some(binding) = binding else {
+            return UNBOUND_GLYPH;
+        };
+
+        // The D-pad shares the arrow key caps.
+        let arrow = match binding {
+            InputBinding::Gamepad(GamepadButton::DPadUp) => Some("UP"),
+            InputBinding::Gamepad(GamepadButton::DPadDown) => Some("DOWN"),
+            InputBinding::Gamepad(GamepadButton::DPadLeft) => Some("LEFT"),
+            InputBinding::Gamepad(GamepadButton::DPadRight) => Some("RIGHT"),
+            _ => None,
+        };
+        if let Some(arrow) = arrow {
+            return Self::find(KEY_GLYPHS, 0, arrow);
+        }
+
+        let label = binding.label().to_uppercase();
+        match binding {
+            InputBinding::Key(_) => Self::find(KEY_GLYPHS, 0, &label),
+            InputBinding::Mouse(_) => Self::find(MOUSE_GLYPHS, KEY_GLYPHS.len(), &label),
+            InputBinding::Gamepad(_) => Self::find(
+                GAMEPAD_GLYPHS,
+                KEY_GLYPHS.len() + MOUSE_GLYPHS.len(),
+                &label,
+            ),
+        }
+    }
+
+    /// Looks `label` up in a group of cells `offset` cells past the unbound and unknown
+    /// glyphs.
+    fn find(glyphs: &[(&str, u32)], offset: usize, label: &str) -> Self {
+        glyphs
+            .iter()
+            .position(|(glyph_label, _)| *glyph_label == label)
+            .map_or(UNKNOWN_GLYPH, |position| Glyph {
+                index: UNKNOWN_GLYPH.index + 1 + offset + position,
+                width: glyphs[position].1,
+            })
+    }
+}
+
+fn glyph_rows() -> u32 {
+    let glyphs = 2 + KEY_GLYPHS.len() + MOUSE_GLYPHS.len() + GAMEPAD_GLYPHS.len();
+    (glyphs as u32).div_ceil(GLYPH_COLUMNS)
+}
+
+/// Loaded once and shared by every prompt.
+#[derive(Resource)]
+struct GlyphSheet {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+fn load_glyph_sheet(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let layout = TextureAtlasLayout::from_grid(GLYPH_CELL, GLYPH_COLUMNS, glyph_rows(), None, None);
+    commands.insert_resource(GlyphSheet {
+        image: asset_server.load("input_glyphs.png"),
+        layout: atlas_layouts.add(layout),
+    });
+}
+
+/// Spawns a prompt for `action` followed by what it does, as a footer hint on a
+/// high-res screen. `position` is the gap between the two, in canvas pixels.
+pub fn spawn_prompt_hint(
+    parent: &mut ChildSpawnerCommands,
+    action: Action,
+    text: &str,
+    position: Vec2,
+) {
+    parent.spawn((
+        InputPrompt(action),
+        Sprite {
+            anchor: Anchor::CenterRight,
+            ..Default::default()
+        },
+        CanvasSprite {
+            position: position - Vec2::X,
+        },
+        Transform::from_xyz(0., 0., 0.1),
+    ));
+    parent.spawn((
+        Text2d::new(text),
+        TextColor(HINT_COLOR),
+        Anchor::CenterLeft,
+        CanvasText::new(position + Vec2::X * 2., HINT_FONT_SIZE),
+        Transform::from_xyz(0., 0., 0.1),
+    ));
+}
+
+/// The binding for the active device, falling back to any there is.
+fn prompt_binding(
+    bindings: &InputBindings,
+    device: ActiveDevice,
+    action: Action,
+) -> Option<InputBinding> {
+    let action_bindings = bindings.bindings(action);
+    action_bindings
+        .iter()
+        .find(|binding| device.has(**binding))
+        .or_else(|| action_bindings.first())
+        .copied()
+}
+
+fn update_input_prompts(
+    bindings: Res<InputBindings>,
+    active_device: Res<ActiveDevice>,
+    sheet: Res<GlyphSheet>,
+    mut prompt_q: Query<(Ref<InputPrompt>, &mut Sprite)>,
+) {
+    let refresh_all = bindings.is_changed() || active_device.is_changed();
+    for (prompt, mut sprite) in prompt_q.iter_mut() {
+        if !refresh_all && !prompt.is_changed() {
+            continue;
+        }
+
+        let glyph = Glyph::for_binding(prompt_binding(&bindings, *active_device, prompt.0));
+        sprite.image = sheet.image.clone();
+        sprite.texture_atlas = Some(TextureAtlas {
+            layout: sheet.layout.clone(),
+            index: glyph.index,
+        });
+        sprite.rect = Some(Rect::new(0., 0., glyph.width as f32, GLYPH_CELL.y as f32));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_glyph_sheet_fits_every_glyph() {
+        let png = include_bytes!("../assets/input_glyphs.png");
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+        let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+        assert_eq!(
+            UVec2::new(width, height),
+            GLYPH_CELL * UVec2::new(GLYPH_COLUMNS, glyph_rows())
+        );
+
+        let glyphs = [KEY_GLYPHS, MOUSE_GLYPHS, GAMEPAD_GLYPHS].concat();
+        assert!(glyphs.iter().all(|(_, width)| *width <= GLYPH_CELL.x));
+        assert_eq!(
+            Glyph::for_binding(Some(InputBinding::Gamepad(GamepadButton::DPadLeft))),
+            Glyph::for_binding(Some(InputBinding::Key(KeyCode::ArrowLeft)))
+        );
+        assert_eq!(
+            Glyph::for_binding(Some(InputBinding::Gamepad(GamepadButton::North))).index,
+            2 + KEY_GLYPHS.len() + MOUSE_GLYPHS.len() + 3
+        );
+    }
+}
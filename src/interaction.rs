@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::{
+    input::{Action, PlayerInput},
+    input_prompt::InputPrompt,
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    state::{GameplaySet, PlayerControlSet},
+};
+
+/// How far above the focused interactable the prompt floats.
+const PROMPT_OFFSET: Vec2 = Vec2::new(0., 12.);
+const PROMPT_Z: f32 = 6.;
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<InteractEvent>();
+        app.init_resource::<Focus>();
+        app.add_systems(Startup, spawn_interact_prompt);
+        app.add_systems(
+            Update,
+            (
+                focus_nearest_interactable,
+                show_interact_prompt,
+                interact.in_set(PlayerControlSet),
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Something the player can use by walking up to it and pressing interact, like a
+/// door or a chest.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Interactable {
+    /// How close the player has to be, from center to center.
+    pub range: f32,
+}
+
+impl Default for Interactable {
+    fn default() -> Self {
+        Self { range: 16. }
+    }
+}
+
+/// Sent when the player interacts with an `Interactable`, for whatever it belongs to
+/// to react to.
+#[derive(Event, Debug)]
+pub struct InteractEvent {
+    pub interactable: Entity,
+}
+
+/// The interactable the player would use by pressing interact now.
+#[derive(Resource, Default, Debug)]
+struct Focus(Option<Entity>);
+
+/// Floats over the focused interactable.
+#[derive(Component)]
+struct InteractPrompt;
+
+fn spawn_interact_prompt(mut commands: Commands) {
+    commands.spawn((
+        InteractPrompt,
+        InputPrompt(Action::Interact),
+        Name::new("Interact prompt"),
+        Transform::from_xyz(0., 0., PROMPT_Z),
+        Visibility::Hidden,
+        PIXEL_PERFECT_LAYER,
+    ));
+}
+
+fn focus_nearest_interactable(
+    player_transform: Single<&Transform, With<Player>>,
+    interactable_q: Query<(Entity, &Interactable, &GlobalTransform)>,
+    mut focus: ResMut<Focus>,
+) {
+    let player_pos = player_transform.translation.truncate();
+    focus.0 = interactable_q
+        .iter()
+        .map(|(entity, interactable, transform)| {
+            let distance = transform.translation().truncate().distance(player_pos);
+            (entity, interactable, distance)
+        })
+        .filter(|(_, interactable, distance)| *distance <= interactable.range)
+        .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+        .map(|(entity, _, _)| entity);
+}
+
+fn show_interact_prompt(
+    focus: Res<Focus>,
+    interactable_q: Query<&GlobalTransform, With<Interactable>>,
+    prompt: Single<(&mut Transform, &mut Visibility), With<InteractPrompt>>,
+) {
+    let (mut transform, mut visibility) = prompt.into_inner();
+    let Some(target) = focus.0.and_then(|entity| interactable_q.get(entity).ok()) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let position = target.translation().truncate() + PROMPT_OFFSET;
+    transform.translation = position.extend(PROMPT_Z);
+    *visibility = Visibility::Inherited;
+}
+
+fn interact(
+    mut input: ResMut<PlayerInput>,
+    focus: Res<Focus>,
+    mut interact_events: EventWriter<InteractEvent>,
+) {
+    if let Some(interactable) = focus.0
+        && input.take_buffered(Action::Interact)
+    {
+        interact_events.write(InteractEvent { interactable });
+    }
+}
@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{Action, PlayerInput},
+    input_prompt::spawn_prompt_hint,
+    pickup::{DroppedPickup, Pickup, PlayerSupplies, spawn_pickup},
+    pixel_perfect::{CanvasText, HIGH_RES_LAYER},
+    state::{GameState, NewGame, PlayerControlSet, ScreenOverlay},
+};
+
+const INVENTORY_COLUMNS: usize = 3;
+const INVENTORY_ROWS: usize = 3;
+const INVENTORY_SLOTS: usize = INVENTORY_COLUMNS * INVENTORY_ROWS;
+const MAX_STACK: u32 = 5;
+const OVERLAY_COLOR: Color = Color::srgba(0., 0., 0., 0.8);
+const TITLE_FONT_SIZE: f32 = 8.;
+const SLOT_FONT_SIZE: f32 = 6.;
+/// Distance between slot centers, in canvas pixels.
+const SLOT_SPACING: Vec2 = Vec2::new(38., 9.);
+const SLOT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const SELECTED_SLOT_COLOR: Color = Color::WHITE;
+
+/// Pickups the player had no room for are kept here. The inventory screen uses them up
+/// or drops them again.
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inventory>();
+        app.init_resource::<InventorySelection>();
+        app.add_systems(NewGame, reset_inventory);
+        app.add_systems(OnEnter(GameState::Inventory), spawn_inventory_screen);
+        app.add_systems(Update, open_inventory.in_set(PlayerControlSet));
+        app.add_systems(
+            Update,
+            (navigate_inventory, use_or_drop_item, update_inventory_slots)
+                .chain()
+                .run_if(in_state(GameState::Inventory)),
+        );
+    }
+}
+
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Inventory {
+    /// At most `INVENTORY_SLOTS`, in the order they were picked up.
+    pub stacks: Vec<ItemStack>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: Pickup,
+    pub count: u32,
+}
+
+impl Inventory {
+    pub fn has_room_for(&self, item: Pickup) -> bool {
+        self.stacks.len() < INVENTORY_SLOTS
+            || self
+                .stacks
+                .iter()
+                .any(|stack| stack.item == item && stack.count < MAX_STACK)
+    }
+
+    pub fn contains(&self, item: Pickup) -> bool {
+        self.stacks.iter().any(|stack| stack.item == item)
+    }
+
+    /// Adds one `item`, onto a stack of the same item if one has room. Returns false
+    /// when the inventory is full.
+    pub fn add(&mut self, item: Pickup) -> bool {
+        if let Some(stack) = self
+            .stacks
+            .iter_mut()
+            .find(|stack| stack.item == item && stack.count < MAX_STACK)
+        {
+            stack.count += 1;
+            return true;
+        }
+        if self.stacks.len() < INVENTORY_SLOTS {
+            self.stacks.push(ItemStack { item, count: 1 });
+            return true;
+        }
+        false
+    }
+
+    /// Removes one item from the stack at `index`, dropping the stack once it's empty.
+    fn take(&mut self, index: usize) -> Option<Pickup> {
+        let stack = self.stacks.get_mut(index)?;
+        let item = stack.item;
+        stack.count -= 1;
+        if stack.count == 0 {
+            self.stacks.remove(index);
+        }
+        Some(item)
+    }
+}
+
+/// Slot index, kept between visits.
+#[derive(Resource, Default, Debug)]
+struct InventorySelection(usize);
+
+#[derive(Component)]
+struct InventorySlot(usize);
+
+/// Describes the selected item.
+#[derive(Component)]
+struct InventoryDetail;
+
+fn reset_inventory(mut inventory: ResMut<Inventory>, mut selection: ResMut<InventorySelection>) {
+    *inventory = Inventory::default();
+    selection.0 = 0;
+}
+
+fn open_inventory(input: Res<PlayerInput>, mut next_state: ResMut<NextState<GameState>>) {
+    if input.just_pressed(Action::Inventory) {
+        next_state.set(GameState::Inventory);
+    }
+}
+
+fn slot_position(index: usize) -> Vec2 {
+    let column = (index % INVENTORY_COLUMNS) as f32 - (INVENTORY_COLUMNS - 1) as f32 / 2.;
+    let row = (index / INVENTORY_COLUMNS) as f32;
+    Vec2::new(column * SLOT_SPACING.x, 16. - row * SLOT_SPACING.y)
+}
+
+fn spawn_inventory_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            ScreenOverlay,
+            Name::new("Inventory screen"),
+            Sprite::from_color(OVERLAY_COLOR, Vec2::ONE),
+            Transform::from_xyz(0., 0., 20.),
+            HIGH_RES_LAYER,
+            StateScoped(GameState::Inventory),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2d::new("Inventory"),
+                CanvasText::new(Vec2::new(0., 30.), TITLE_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+
+            for index in 0..INVENTORY_SLOTS {
+                parent.spawn((
+                    InventorySlot(index),
+                    Text2d::default(),
+                    TextColor(SLOT_COLOR),
+                    CanvasText::new(slot_position(index), SLOT_FONT_SIZE),
+                    Transform::from_xyz(0., 0., 0.1),
+                ));
+            }
+
+            parent.spawn((
+                InventoryDetail,
+                Text2d::default(),
+                CanvasText::new(Vec2::new(0., -18.), SLOT_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+            spawn_prompt_hint(parent, Action::Confirm, "use", Vec2::new(-20., -30.));
+            spawn_prompt_hint(parent, Action::DropItem, "drop", Vec2::new(20., -30.));
+        });
+}
+
+fn navigate_inventory(
+    input: Res<PlayerInput>,
+    mut selection: ResMut<InventorySelection>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if input.just_pressed(Action::Inventory) || input.just_pressed(Action::Pause) {
+        next_state.set(GameState::Playing);
+        return;
+    }
+
+    let (mut column, mut row) = (
+        selection.0 % INVENTORY_COLUMNS,
+        selection.0 / INVENTORY_COLUMNS,
+    );
+    if input.just_pressed(Action::MoveLeft) {
+        column = (column + INVENTORY_COLUMNS - 1) % INVENTORY_COLUMNS;
+    }
+    if input.just_pressed(Action::MoveRight) {
+        column = (column + 1) % INVENTORY_COLUMNS;
+    }
+    if input.just_pressed(Action::MoveUp) {
+        row = (row + INVENTORY_ROWS - 1) % INVENTORY_ROWS;
+    }
+    if input.just_pressed(Action::MoveDown) {
+        row = (row + 1) % INVENTORY_ROWS;
+    }
+    selection.0 = row * INVENTORY_COLUMNS + column;
+}
+
+fn use_or_drop_item(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    input: Res<PlayerInput>,
+    selection: Res<InventorySelection>,
+    mut inventory: ResMut<Inventory>,
+    mut supplies: PlayerSupplies,
+) {
+    let Some(stack) = inventory.stacks.get(selection.0) else {
+        return;
+    };
+
+    if input.just_pressed(Action::Confirm) && supplies.apply(stack.item) {
+        inventory.take(selection.0);
+    } else if input.just_pressed(Action::DropItem)
+        && let Some(item) = inventory.take(selection.0)
+    {
+        let pickup = spawn_pickup(&mut commands, &asset_server, item, supplies.position());
+        commands.entity(pickup).insert(DroppedPickup);
+    }
+}
+
+fn update_inventory_slots(
+    inventory: Res<Inventory>,
+    selection: Res<InventorySelection>,
+    mut slot_q: Query<(&InventorySlot, &mut Text2d, &mut TextColor)>,
+    mut detail: Single<&mut Text2d, (With<InventoryDetail>, Without<InventorySlot>)>,
+) {
+    for (slot, mut text, mut color) in slot_q.iter_mut() {
+        let label = match inventory.stacks.get(slot.0) {
+            Some(stack) => format!("{} {}", stack.item.label(), stack.count),
+            None => "-".to_string(),
+        };
+        if text.0 != label {
+            text.0 = label;
+        }
+        color.0 = if slot.0 == selection.0 {
+            SELECTED_SLOT_COLOR
+        } else {
+            SLOT_COLOR
+        };
+    }
+
+    let description = inventory
+        .stacks
+        .get(selection.0)
+        .map(|stack| stack.item.description())
+        .unwrap_or_default();
+    if detail.0 != description {
+        detail.0 = description;
+    }
+}
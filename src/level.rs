@@ -0,0 +1,815 @@
+use avian2d::prelude::*;
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    sprite::Anchor,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::CameraBounds,
+    collider::GameLayer,
+    force_zone::ForceZoneKind,
+    hazard::HazardKind,
+    lighting::LightOccluder,
+    pixel_perfect::{Canvas, CanvasText, HIGH_RES_LAYER, PIXEL_PERFECT_LAYER},
+    prop::PropKind,
+    state::{GameState, NewGame, ScreenOverlay},
+    tiled::TiledMap,
+    transition::{RoomEntered, RoomScoped},
+};
+
+/// Map loaded at startup. The built-in arena is used until it finishes loading, or if
+/// it fails to.
+const LEVEL_PATH: &str = "levels/arena.tmx";
+
+/// Tiles per chunk side. Each chunk is drawn as a single sprite.
+const CHUNK_SIZE: u32 = 16;
+const TILE_SIZE: u32 = 8;
+/// Below everything else on the canvas.
+const TILEMAP_Z: f32 = -10.;
+const FLOOR_COLOR: [u8; 4] = [40, 38, 36, 255];
+const FLOOR_ALT_COLOR: [u8; 4] = [44, 42, 39, 255];
+const WALL_COLOR: [u8; 4] = [88, 84, 78, 255];
+const WALL_EDGE_COLOR: [u8; 4] = [58, 55, 51, 255];
+const WALL_CRACK_COLOR: [u8; 4] = [52, 49, 45, 255];
+/// Tilemap chunks, wall colliders and markers spawned per frame while a level is being
+/// built.
+const SPAWN_BUDGET: usize = 200;
+/// Above the HUD and the room fade, below the pause and game over overlays.
+const LOADING_SCREEN_Z: f32 = 16.;
+const LOADING_BAR_SIZE: Vec2 = Vec2::new(48., 3.);
+const LOADING_BAR_BACKGROUND: Color = Color::srgb(0.15, 0.15, 0.15);
+const LOADING_BAR_FILL: Color = Color::srgb(0.8, 0.8, 0.8);
+const LOADING_FONT_SIZE: f32 = 6.;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Level::arena(40, 24));
+        app.insert_resource(LevelMarkers::arena());
+        app.init_resource::<LevelExits>();
+        app.init_resource::<LevelLoadProgress>();
+        app.add_event::<TileChanged>();
+        app.add_event::<SpawnMarker>();
+        app.insert_resource(LevelSource::Map(LEVEL_PATH.to_string()));
+        app.add_systems(Startup, (load_level_file, spawn_loading_screen));
+        app.add_systems(
+            NewGame,
+            (resume_cancelled_build, queue_level_markers).chain(),
+        );
+        app.configure_sets(Update, MarkerSpawnSet.after(build_level));
+        app.add_systems(OnEnter(GameState::MainMenu), cancel_level_build);
+        app.add_systems(
+            Update,
+            (
+                apply_loaded_level.run_if(resource_exists::<LevelHandle>),
+                start_level_build.run_if(resource_changed::<Level>),
+                queue_level_markers.run_if(on_event::<RoomEntered>),
+                build_level.run_if(not(level_built)),
+                patch_changed_tiles.run_if(on_event::<TileChanged>),
+                update_loading_screen,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TileKind {
+    /// Outside the level; shows the clear color.
+    #[default]
+    Empty,
+    Floor,
+    Wall,
+    /// A wall that gunfire and explosions can break through, leaving floor behind.
+    Cracked,
+}
+
+impl TileKind {
+    pub fn is_solid(self) -> bool {
+        matches!(self, TileKind::Wall | TileKind::Cracked)
+    }
+}
+
+/// The current level's tile grid. Tile (0, 0) is the bottom-left one, and the grid is
+/// centered on the world origin. Replacing or mutating the resource redraws the map;
+/// single tiles changed during play bypass that and send `TileChanged` instead.
+#[derive(Resource, Clone, Debug)]
+pub struct Level {
+    /// Side length of a tile in canvas pixels.
+    pub tile_size: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Row-major, starting from the bottom row.
+    pub tiles: Vec<TileKind>,
+}
+
+impl Level {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            tile_size: TILE_SIZE,
+            width,
+            height,
+            tiles: vec![TileKind::Empty; (width * height) as usize],
+        }
+    }
+
+    /// A floor surrounded by walls, with a few pillars to take cover behind until they're
+    /// shot down.
+    pub fn arena(width: u32, height: u32) -> Self {
+        let mut level = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+                let kind = if border {
+                    TileKind::Wall
+                } else {
+                    TileKind::Floor
+                };
+                level.set(UVec2::new(x, y), kind);
+            }
+        }
+
+        let pillars = [
+            UVec2::new(width / 4, height / 4),
+            UVec2::new(width * 3 / 4, height / 4),
+            UVec2::new(width / 4, height * 3 / 4),
+            UVec2::new(width * 3 / 4, height * 3 / 4),
+        ];
+        for pillar in pillars {
+            for offset in [UVec2::ZERO, UVec2::X, UVec2::Y, UVec2::ONE] {
+                level.set(pillar + offset, TileKind::Cracked);
+            }
+        }
+        level
+    }
+
+    fn index(&self, tile: UVec2) -> Option<usize> {
+        (tile.x < self.width && tile.y < self.height)
+            .then(|| (tile.y * self.width + tile.x) as usize)
+    }
+
+    /// Out-of-bounds tiles read as `Empty`.
+    pub fn get(&self, tile: UVec2) -> TileKind {
+        self.index(tile)
+            .map_or(TileKind::Empty, |index| self.tiles[index])
+    }
+
+    pub fn set(&mut self, tile: UVec2, kind: TileKind) {
+        if let Some(index) = self.index(tile) {
+            self.tiles[index] = kind;
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        UVec2::new(self.width, self.height).as_vec2() * self.tile_size as f32
+    }
+
+    /// World rectangle covered by the grid.
+    pub fn bounds(&self) -> Rect {
+        Rect::from_center_size(Vec2::ZERO, self.size())
+    }
+
+    /// World rectangle covered by a range of tiles, `max` exclusive.
+    pub fn tile_rect(&self, tiles: URect) -> Rect {
+        let tile_size = self.tile_size as f32;
+        let origin = self.bounds().min;
+        Rect::from_corners(
+            origin + tiles.min.as_vec2() * tile_size,
+            origin + tiles.max.as_vec2() * tile_size,
+        )
+    }
+
+    /// Covers the solid tiles with few non-overlapping rectangles, `max` exclusive. Each
+    /// rectangle grows right from its first free tile, then up for as long as the whole
+    /// row below is solid too. Not always the fewest possible, but close for walls.
+    pub fn solid_rects(&self) -> Vec<URect> {
+        let mut covered = vec![false; self.tiles.len()];
+        let free = |covered: &[bool], tile: UVec2| {
+            self.get(tile).is_solid() && self.index(tile).is_some_and(|index| !covered[index])
+        };
+
+        let mut rects = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let min = UVec2::new(x, y);
+                if !free(&covered, min) {
+                    continue;
+                }
+
+                let mut max = min + UVec2::ONE;
+                while free(&covered, UVec2::new(max.x, y)) {
+                    max.x += 1;
+                }
+                while (min.x..max.x).all(|x| free(&covered, UVec2::new(x, max.y))) {
+                    max.y += 1;
+                }
+
+                for cy in min.y..max.y {
+                    for cx in min.x..max.x {
+                        covered[(cy * self.width + cx) as usize] = true;
+                    }
+                }
+                rects.push(URect::from_corners(min, max));
+            }
+        }
+        rects
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkerKind {
+    PlayerStart,
+    EnemySpawn,
+    FlarePickup,
+    Checkpoint,
+    /// Locked when it has a `link`, opened by a key or switch with the same one.
+    /// Without one it opens when used.
+    Door {
+        link: Option<u32>,
+        /// In pixels.
+        size: UVec2,
+    },
+    /// Opens the doors sharing its `link`.
+    Switch {
+        link: u32,
+    },
+    /// Opens the doors sharing its `link` once a prop is pushed onto it.
+    PressurePlate {
+        link: u32,
+    },
+    /// Unlocks the doors sharing its `link`.
+    Key {
+        link: u32,
+    },
+    /// Someone to talk to.
+    Npc {
+        /// Asset path of the `.dialogue.ron` conversation.
+        dialogue: String,
+    },
+    /// Plays when a new game starts in this level. Only its `cutscene` matters, not
+    /// where it's placed.
+    Intro {
+        /// Asset path of the `.cutscene.ron` sequence.
+        cutscene: String,
+    },
+    /// Floor that slides along a path, starting from where it's placed. Waits for the
+    /// switch or pressure plate with its `link`, when it has one.
+    Platform {
+        link: Option<u32>,
+        /// In pixels.
+        size: UVec2,
+        /// Waypoints after the starting point, as pixel offsets from it.
+        path: Vec<IVec2>,
+    },
+    /// Spikes, fire or a pit.
+    Hazard {
+        kind: HazardKind,
+        /// In pixels.
+        size: UVec2,
+    },
+    /// Wind, water or a conveyor belt.
+    ForceZone {
+        kind: ForceZoneKind,
+        /// In pixels.
+        size: UVec2,
+    },
+    /// Sends what steps onto it to the other teleporter with the same `link`.
+    Teleporter {
+        link: u32,
+        /// Leaves props and flares behind.
+        player_only: bool,
+    },
+    /// A crate or barrel the player can push around.
+    Prop {
+        kind: PropKind,
+    },
+    /// Shuts its doors while the player fights the enemies placed inside, see
+    /// `CombatRoom`.
+    CombatRoom {
+        /// In pixels.
+        size: UVec2,
+    },
+    /// An exit that opens once enough flares burn around it, see `Portal`.
+    Portal {
+        flares: u32,
+        /// How close the flares have to be, in pixels.
+        radius: u32,
+    },
+    /// Where a boss waits for the player.
+    Boss {
+        /// Asset path of the `.cutscene.ron` sequence to play as the fight starts.
+        cutscene: Option<String>,
+    },
+}
+
+impl MarkerKind {
+    /// Matches the class or name given to marker objects in the level editor.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "PlayerStart" => Some(MarkerKind::PlayerStart),
+            "EnemySpawn" => Some(MarkerKind::EnemySpawn),
+            "FlarePickup" => Some(MarkerKind::FlarePickup),
+            "Checkpoint" => Some(MarkerKind::Checkpoint),
+            "Crate" => Some(MarkerKind::Prop {
+                kind: PropKind::Crate,
+            }),
+            "Barrel" => Some(MarkerKind::Prop {
+                kind: PropKind::Barrel,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A point of interest placed in the level, in world coordinates.
+#[derive(Clone, Debug)]
+pub struct LevelMarker {
+    pub kind: MarkerKind,
+    pub position: Vec2,
+}
+
+/// Markers of the current level, read when a new game spawns the player, enemies and
+/// pickups.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LevelMarkers(pub Vec<LevelMarker>);
+
+impl LevelMarkers {
+    fn arena() -> Self {
+        let marker = |kind, x, y| LevelMarker {
+            kind,
+            position: Vec2::new(x, y),
+        };
+        Self(vec![
+            marker(MarkerKind::PlayerStart, 0., 0.),
+            marker(MarkerKind::EnemySpawn, 30., 0.),
+            marker(MarkerKind::FlarePickup, 40., 20.),
+            marker(MarkerKind::FlarePickup, -45., -25.),
+            marker(
+                MarkerKind::Prop {
+                    kind: PropKind::Crate,
+                },
+                -30.,
+                10.,
+            ),
+            marker(
+                MarkerKind::Prop {
+                    kind: PropKind::Barrel,
+                },
+                20.,
+                -30.,
+            ),
+        ])
+    }
+
+    pub fn positions(&self, kind: MarkerKind) -> impl Iterator<Item = Vec2> + '_ {
+        self.0
+            .iter()
+            .filter(move |marker| marker.kind == kind)
+            .map(|marker| marker.position)
+    }
+
+    pub fn player_start(&self) -> Vec2 {
+        self.positions(MarkerKind::PlayerStart)
+            .next()
+            .unwrap_or(Vec2::ZERO)
+    }
+}
+
+/// The map file the level comes from. Removing it keeps the current level when the
+/// file is reloaded.
+#[derive(Resource)]
+pub struct LevelHandle(pub Handle<TiledMap>);
+
+/// Where the current level came from, so a saved game can rebuild it.
+#[derive(Resource, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LevelSource {
+    /// Asset path of a map file.
+    Map(String),
+    Generated {
+        seed: u64,
+    },
+}
+
+/// A trigger zone that takes the player to another map.
+#[derive(Clone, Debug)]
+pub struct LevelExit {
+    pub area: Rect,
+    /// Asset path of the map to load.
+    pub map: String,
+    /// Name of the `Entry` object to arrive at in that map.
+    pub entry: String,
+}
+
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LevelExits(pub Vec<LevelExit>);
+
+/// Sent after a tile is changed in place through `bypass_change_detection`, so only the
+/// chunks around it are redrawn, and the rest of the game keeps what it knows about the
+/// level.
+#[derive(Event, Debug)]
+pub struct TileChanged {
+    pub tile: UVec2,
+}
+
+/// How far the current level is from being built. Its tilemap, walls and markers are
+/// spawned a few at a time, so a big level doesn't stall the game for a frame. Gameplay
+/// waits for it, see `level_built`.
+#[derive(Resource, Default, Debug)]
+pub struct LevelLoadProgress {
+    pending: Vec<LevelPiece>,
+    total: usize,
+    /// Left unfinished by going back to the main menu, to be built again with the next
+    /// game.
+    cancelled: bool,
+}
+
+impl LevelLoadProgress {
+    /// From 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            1. - self.pending.len() as f32 / self.total as f32
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pending.is_empty() && !self.cancelled
+    }
+
+    /// Lines `markers` up to be spawned after everything already pending, through
+    /// `SpawnMarker`.
+    pub fn queue_markers(&mut self, markers: impl IntoIterator<Item = LevelMarker>) {
+        if self.pending.is_empty() {
+            self.total = 0;
+        }
+        let markers: Vec<LevelPiece> = markers.into_iter().map(LevelPiece::Marker).collect();
+        self.total += markers.len();
+        // Built from the back.
+        self.pending.splice(0..0, markers.into_iter().rev());
+    }
+}
+
+#[derive(Clone, Debug)]
+enum LevelPiece {
+    Chunk(UVec2),
+    /// Solid tiles, `max` exclusive.
+    Collider(URect),
+    Marker(LevelMarker),
+}
+
+/// Sent as a level marker's turn comes up in the level build, for whatever spawns its
+/// kind of marker to do so. Handled in `MarkerSpawnSet`.
+#[derive(Event, Clone, Debug)]
+pub struct SpawnMarker(pub LevelMarker);
+
+/// Where `SpawnMarker` is handled, right after the build sends it, so a level counts as
+/// built only with its markers in place.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MarkerSpawnSet;
+
+/// Whether the current level is fully in place, including the navigation grid built
+/// from it. A level replaced since the last check isn't, even before its build starts.
+pub fn level_built(progress: Res<LevelLoadProgress>, level: Res<Level>) -> bool {
+    progress.is_done() && !level.is_changed()
+}
+
+/// Static collider covering a rectangle of solid tiles.
+#[derive(Component)]
+struct TileCollider;
+
+/// Which chunk of the grid the sprite shows.
+#[derive(Component)]
+struct TilemapChunk(UVec2);
+
+/// Covers the canvas with a progress bar while a level is being built.
+#[derive(Component)]
+struct LoadingScreen;
+
+/// Laid out in canvas pixels like `CanvasText`.
+#[derive(Component)]
+struct LoadingBar {
+    fill: bool,
+}
+
+/// Pixel color for a point inside a tile. Walls get a darker bottom edge so they read
+/// as raised, floors a faint checkerboard.
+fn tile_pixel(level: &Level, tile: UVec2, pixel: UVec2) -> [u8; 4] {
+    match level.get(tile) {
+        TileKind::Empty => [0; 4],
+        TileKind::Floor if (tile.x + tile.y).is_multiple_of(2) => FLOOR_COLOR,
+        TileKind::Floor => FLOOR_ALT_COLOR,
+        kind @ (TileKind::Wall | TileKind::Cracked) => {
+            let below = tile
+                .y
+                .checked_sub(1)
+                .map(|y| level.get(UVec2::new(tile.x, y)));
+            // One pixel of diagonal crack per row.
+            let crack = pixel.x == (pixel.y + level.tile_size / 2) % level.tile_size;
+            if pixel.y < 2 && below.is_some_and(|kind| !kind.is_solid()) {
+                WALL_EDGE_COLOR
+            } else if kind == TileKind::Cracked && crack {
+                WALL_CRACK_COLOR
+            } else {
+                WALL_COLOR
+            }
+        }
+    }
+}
+
+fn chunk_image(level: &Level, chunk: UVec2) -> Image {
+    let size = CHUNK_SIZE * level.tile_size;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    // Image rows run top to bottom, tile rows bottom to top.
+    for row in (0..size).rev() {
+        for column in 0..size {
+            let pixel = UVec2::new(column, row);
+            let tile = chunk * CHUNK_SIZE + pixel / level.tile_size;
+            data.extend_from_slice(&tile_pixel(level, tile, pixel % level.tile_size));
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+fn load_level_file(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LevelHandle(asset_server.load(LEVEL_PATH)));
+}
+
+/// Also picks up edits to the map file when asset hot reloading is on.
+fn apply_loaded_level(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<TiledMap>>,
+    level_handle: Res<LevelHandle>,
+    maps: Res<Assets<TiledMap>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != level_handle.0.id() {
+            continue;
+        }
+
+        if let Some(map) = maps.get(*id) {
+            apply_map(&mut commands, map);
+        }
+    }
+}
+
+/// Makes a loaded map the current level.
+pub fn apply_map(commands: &mut Commands, map: &TiledMap) {
+    commands.insert_resource(map.level.clone());
+    commands.insert_resource(LevelMarkers(map.markers.clone()));
+    commands.insert_resource(LevelExits(map.exits.clone()));
+}
+
+/// Queues up every marker of the current level, for a new game or a room just entered.
+/// Restoring a saved game queues only the ones it doesn't save, see `save.rs`.
+fn queue_level_markers(markers: Res<LevelMarkers>, mut progress: ResMut<LevelLoadProgress>) {
+    progress.queue_markers(markers.0.iter().cloned());
+}
+
+/// Clears away the old level's tiles and queues up the new one's, ahead of any markers
+/// already queued.
+fn start_level_build(
+    mut commands: Commands,
+    mut camera_bounds: ResMut<CameraBounds>,
+    mut progress: ResMut<LevelLoadProgress>,
+    level: Res<Level>,
+    chunk_q: Query<Entity, With<TilemapChunk>>,
+    tile_collider_q: Query<Entity, With<TileCollider>>,
+) {
+    for entity in chunk_q.iter().chain(tile_collider_q.iter()) {
+        commands.entity(entity).despawn();
+    }
+
+    let chunks = (UVec2::new(level.width, level.height) + (CHUNK_SIZE - 1)) / CHUNK_SIZE;
+    let tiles: Vec<LevelPiece> = (0..chunks.y)
+        .flat_map(|y| (0..chunks.x).map(move |x| LevelPiece::Chunk(UVec2::new(x, y))))
+        .chain(level.solid_rects().into_iter().map(LevelPiece::Collider))
+        .collect();
+    // Built from the back, so the markers go in front.
+    let mut pending: Vec<LevelPiece> = progress
+        .pending
+        .drain(..)
+        .filter(|piece| matches!(piece, LevelPiece::Marker(_)))
+        .collect();
+    pending.extend(tiles.into_iter().rev());
+    debug!("building a level of {} pieces", pending.len());
+    *progress = LevelLoadProgress {
+        total: pending.len(),
+        pending,
+        cancelled: false,
+    };
+    camera_bounds.0 = Some(level.bounds());
+}
+
+fn build_level(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut progress: ResMut<LevelLoadProgress>,
+    mut marker_events: EventWriter<SpawnMarker>,
+    level: Res<Level>,
+) {
+    if progress.cancelled {
+        return;
+    }
+
+    for _ in 0..SPAWN_BUDGET {
+        match progress.pending.pop() {
+            Some(LevelPiece::Chunk(chunk)) => {
+                spawn_chunk(&mut commands, &mut images, &level, chunk);
+            }
+            Some(LevelPiece::Collider(tiles)) => spawn_tile_collider(&mut commands, &level, tiles),
+            Some(LevelPiece::Marker(marker)) => {
+                marker_events.write(SpawnMarker(marker));
+            }
+            None => break,
+        }
+    }
+    if progress.pending.is_empty() {
+        info!("level built");
+    }
+}
+
+/// Backing out of a game before its level is built throws away what there is of it,
+/// the markers spawned so far included.
+fn cancel_level_build(
+    mut commands: Commands,
+    mut progress: ResMut<LevelLoadProgress>,
+    chunk_q: Query<Entity, With<TilemapChunk>>,
+    tile_collider_q: Query<Entity, With<TileCollider>>,
+    room_entity_q: Query<Entity, With<RoomScoped>>,
+) {
+    if progress.pending.is_empty() {
+        return;
+    }
+
+    for entity in chunk_q
+        .iter()
+        .chain(tile_collider_q.iter())
+        .chain(room_entity_q.iter())
+    {
+        commands.entity(entity).despawn();
+    }
+    progress.pending.clear();
+    progress.cancelled = true;
+    info!("level build cancelled");
+}
+
+fn resume_cancelled_build(progress: Res<LevelLoadProgress>, mut level: ResMut<Level>) {
+    if progress.cancelled {
+        level.set_changed();
+    }
+}
+
+fn spawn_chunk(commands: &mut Commands, images: &mut Assets<Image>, level: &Level, chunk: UVec2) {
+    let chunk_world_size = (CHUNK_SIZE * level.tile_size) as f32;
+    let origin = level.bounds().min + chunk.as_vec2() * chunk_world_size;
+    commands.spawn((
+        TilemapChunk(chunk),
+        Name::new(format!("Tilemap chunk {},{}", chunk.x, chunk.y)),
+        Sprite {
+            image: images.add(chunk_image(level, chunk)),
+            anchor: Anchor::BottomLeft,
+            ..Default::default()
+        },
+        Transform::from_translation(origin.extend(TILEMAP_Z)),
+        PIXEL_PERFECT_LAYER,
+    ));
+}
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            LoadingScreen,
+            ScreenOverlay,
+            Name::new("Loading screen"),
+            Sprite::from_color(Color::BLACK, Vec2::ONE),
+            Transform::from_xyz(0., 0., LOADING_SCREEN_Z),
+            Visibility::Hidden,
+            HIGH_RES_LAYER,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2d::new("Loading"),
+                CanvasText::new(Vec2::new(0., 6.), LOADING_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+            for (fill, color, z) in [
+                (false, LOADING_BAR_BACKGROUND, 0.1),
+                (true, LOADING_BAR_FILL, 0.2),
+            ] {
+                parent.spawn((
+                    LoadingBar { fill },
+                    Sprite {
+                        color,
+                        anchor: Anchor::CenterLeft,
+                        ..Default::default()
+                    },
+                    Transform::from_xyz(0., 0., z),
+                    HIGH_RES_LAYER,
+                ));
+            }
+        });
+}
+
+fn update_loading_screen(
+    progress: Res<LevelLoadProgress>,
+    level: Res<Level>,
+    state: Res<State<GameState>>,
+    canvas_transform: Single<&Transform, (With<Canvas>, Without<LoadingBar>)>,
+    mut screen_visibility: Single<&mut Visibility, With<LoadingScreen>>,
+    mut bar_q: Query<(&LoadingBar, &mut Sprite, &mut Transform)>,
+) {
+    // Only over the game itself: the menus hide it well enough on their own.
+    let building = !progress.is_done() || level.is_changed();
+    let shown = building && *state.get() == GameState::Playing;
+    screen_visibility.set_if_neq(if shown {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if !shown {
+        return;
+    }
+
+    let scale = canvas_transform.scale.truncate();
+    let fraction = progress.fraction();
+    for (bar, mut sprite, mut transform) in bar_q.iter_mut() {
+        let width = if bar.fill {
+            LOADING_BAR_SIZE.x * fraction
+        } else {
+            LOADING_BAR_SIZE.x
+        };
+        sprite.custom_size = Some(Vec2::new(width, LOADING_BAR_SIZE.y) * scale);
+        let left = Vec2::new(-LOADING_BAR_SIZE.x / 2., -4.);
+        transform.translation = (left * scale).extend(transform.translation.z);
+    }
+}
+
+/// Redraws the chunks holding changed tiles, and the ones above them, whose wall edges
+/// depend on the tile below. The wall colliders are merged across the whole level, so
+/// they're all rebuilt.
+fn patch_changed_tiles(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut changed_events: EventReader<TileChanged>,
+    level: Res<Level>,
+    mut chunk_q: Query<(&TilemapChunk, &mut Sprite)>,
+    tile_collider_q: Query<Entity, With<TileCollider>>,
+) {
+    let mut chunks = Vec::new();
+    for TileChanged { tile } in changed_events.read() {
+        for tile in [*tile, *tile + UVec2::Y] {
+            let chunk = tile / CHUNK_SIZE;
+            if !chunks.contains(&chunk) {
+                chunks.push(chunk);
+            }
+        }
+    }
+
+    for (chunk, mut sprite) in chunk_q.iter_mut() {
+        if chunks.contains(&chunk.0) {
+            sprite.image = images.add(chunk_image(&level, chunk.0));
+        }
+    }
+
+    for entity in tile_collider_q.iter() {
+        commands.entity(entity).despawn();
+    }
+    let solid_rects = level.solid_rects();
+    debug!("merged level walls into {} colliders", solid_rects.len());
+    for tiles in solid_rects {
+        spawn_tile_collider(&mut commands, &level, tiles);
+    }
+}
+
+fn spawn_tile_collider(commands: &mut Commands, level: &Level, tiles: URect) {
+    let rect = level.tile_rect(tiles);
+    commands.spawn((
+        TileCollider,
+        Name::new("Wall collider"),
+        Transform::from_translation(rect.center().extend(0.)),
+        RigidBody::Static,
+        Collider::rectangle(rect.width(), rect.height()),
+        GameLayer::Terrain.collision_layers(),
+        LightOccluder,
+    ));
+}
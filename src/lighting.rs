@@ -0,0 +1,183 @@
+use std::f32::consts::TAU;
+
+use avian2d::prelude::*;
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{TextureDimension, TextureFormat},
+    transform::TransformSystem,
+};
+
+use crate::{
+    pixel_perfect::{PIXEL_PERFECT_LAYER, PixelCamera, PixelCanvasConfig},
+    vision::{VisionField, update_vision_field},
+};
+
+pub const SHADOW_RAY_COUNT: usize = 128;
+const LIGHTMAP_Z: f32 = 50.;
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AmbientLight2d(0.1));
+        app.add_systems(Startup, spawn_lightmap);
+        app.add_systems(
+            PostUpdate,
+            render_lightmap
+                .after(TransformSystem::TransformPropagate)
+                .after(update_vision_field),
+        );
+    }
+}
+
+/// Light level everything receives before any `Light2d` is added, from 0 (pitch black) to 1.
+#[derive(Resource)]
+pub struct AmbientLight2d(pub f32);
+
+#[derive(Component, Clone, Copy)]
+pub struct Light2d {
+    pub radius: f32,
+    pub intensity: f32,
+}
+
+/// Colliders on entities with this component cast shadows.
+#[derive(Component)]
+pub struct LightOccluder;
+
+/// Darkness overlay drawn over the pixel canvas, one texel per canvas pixel.
+#[derive(Component)]
+struct Lightmap;
+
+fn spawn_lightmap(
+    mut commands: Commands,
+    config: Res<PixelCanvasConfig>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let lightmap_image = Image::new_fill(
+        config.extent(),
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+
+    commands.spawn((
+        Sprite::from_image(images.add(lightmap_image)),
+        Transform::from_xyz(0., 0., LIGHTMAP_Z),
+        Lightmap,
+        PIXEL_PERFECT_LAYER,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_lightmap(
+    config: Res<PixelCanvasConfig>,
+    ambient: Res<AmbientLight2d>,
+    vision: Res<VisionField>,
+    spatial_query: SpatialQuery,
+    mut images: ResMut<Assets<Image>>,
+    camera_transform: Single<&GlobalTransform, With<PixelCamera>>,
+    lightmap: Single<(&Sprite, &mut Transform), With<Lightmap>>,
+    light_q: Query<(Entity, &Light2d, &GlobalTransform)>,
+    occluder_q: Query<(), With<LightOccluder>>,
+) {
+    let (sprite, mut lightmap_transform) = lightmap.into_inner();
+    let camera_pos = camera_transform.translation().truncate();
+    lightmap_transform.translation = camera_pos.extend(LIGHTMAP_Z);
+
+    let Some(image) = images.get_mut(&sprite.image) else {
+        return;
+    };
+    if image.size() != config.size() {
+        image.resize(config.extent());
+    }
+    let Some(data) = image.data.as_mut() else {
+        return;
+    };
+
+    let (width, height) = (config.width, config.height);
+    let canvas_size = config.size_f32();
+    let canvas_min = camera_pos - canvas_size / 2.;
+    let mut light_levels = vec![ambient.0; (width * height) as usize];
+
+    for (entity, light, transform) in light_q.iter() {
+        let light_pos = transform.translation().truncate();
+        let shadow_distances =
+            cast_shadow_rays(&spatial_query, entity, light_pos, light.radius, &|entity| {
+                occluder_q.contains(entity)
+            });
+
+        let min = (light_pos - light.radius - canvas_min)
+            .floor()
+            .clamp(Vec2::ZERO, canvas_size);
+        let max = (light_pos + light.radius - canvas_min)
+            .ceil()
+            .clamp(Vec2::ZERO, canvas_size);
+
+        for y in min.y as u32..max.y as u32 {
+            for x in min.x as u32..max.x as u32 {
+                let offset = canvas_min + Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - light_pos;
+                let distance = offset.length();
+                if distance >= light.radius || distance > shadow_distances[ray_index(offset)] {
+                    continue;
+                }
+
+                let falloff = 1. - distance / light.radius;
+                let row = height - 1 - y;
+                light_levels[(row * width + x) as usize] += falloff * falloff * light.intensity;
+            }
+        }
+    }
+
+    for (i, (level, pixel)) in light_levels
+        .iter()
+        .zip(data.chunks_exact_mut(4))
+        .enumerate()
+    {
+        let (x, row) = (i as u32 % width, i as u32 / width);
+        let pixel_center = canvas_min + Vec2::new(x as f32 + 0.5, (height - row) as f32 - 0.5);
+        let level = if vision.sees(pixel_center) {
+            *level
+        } else {
+            0.
+        };
+        pixel[3] = ((1. - level.clamp(0., 1.)) * 255.) as u8;
+    }
+}
+
+/// Distance each of the evenly spaced shadow rays travels from the light before hitting an occluder.
+pub fn cast_shadow_rays(
+    spatial_query: &SpatialQuery,
+    light_entity: Entity,
+    light_pos: Vec2,
+    radius: f32,
+    is_occluder: &dyn Fn(Entity) -> bool,
+) -> [f32; SHADOW_RAY_COUNT] {
+    let filter = SpatialQueryFilter::from_excluded_entities([light_entity]);
+    let mut distances = [radius; SHADOW_RAY_COUNT];
+
+    for (i, distance) in distances.iter_mut().enumerate() {
+        let angle = i as f32 / SHADOW_RAY_COUNT as f32 * TAU;
+        let direction = Dir2::from_xy(angle.cos(), angle.sin()).unwrap();
+
+        if let Some(hit) = spatial_query.cast_ray_predicate(
+            light_pos,
+            direction,
+            radius,
+            true,
+            &filter,
+            is_occluder,
+        ) {
+            // Let the light spill one pixel onto the occluder so its edge stays visible.
+            *distance = hit.distance + 1.;
+        }
+    }
+
+    distances
+}
+
+pub fn ray_index(offset: Vec2) -> usize {
+    let angle = offset.y.atan2(offset.x).rem_euclid(TAU);
+    (angle / TAU * SHADOW_RAY_COUNT as f32).round() as usize % SHADOW_RAY_COUNT
+}
@@ -1,244 +1,254 @@
-use avian2d::prelude::*;
-use bevy::{
-    color::palettes::css::GRAY,
-    prelude::*,
-    render::{
-        camera::RenderTarget,
-        render_resource::{
-            Extent3d, TextureDescriptor, TextureFormat::Bgra8UnormSrgb, TextureUsages,
-        },
-        view::RenderLayers,
-    },
-    window::{PrimaryWindow, WindowResized},
-};
-
-const RES_HEIGHT: u32 = 80;
-const RES_WIDTH: u32 = 128;
+mod ai;
+mod aim_line;
+mod animation;
+mod attract;
+mod audio;
+mod boss;
+mod camera;
+mod checkpoint;
+mod collider;
+mod combat_room;
+mod config;
+mod cooldown_arcs;
+mod crosshair;
+mod culling;
+mod cutscene;
+mod dash;
+mod debug;
+mod destructible;
+mod determinism;
+mod dialogue;
+mod door;
+mod enemy;
+mod flare;
+mod force_zone;
+mod grapple;
+mod grenade;
+mod hazard;
+mod health;
+mod hit_feedback;
+mod hud;
+mod input;
+mod input_prompt;
+mod interaction;
+mod inventory;
+mod level;
+mod lighting;
+mod melee;
+mod menu;
+mod minimap;
+mod music;
+mod outline;
+mod parallax;
+mod particle;
+mod pathfinding;
+mod pickup;
+mod pixel_perfect;
+mod platform;
+mod player;
+mod pool;
+mod portal;
+mod post_process;
+mod procgen;
+mod projectile;
+mod prop;
+mod replay;
+mod results;
+mod rng;
+mod save;
+mod score;
+mod screen_shake;
+mod settings;
+mod settings_menu;
+mod speedrun;
+mod stamina;
+mod state;
+mod status;
+mod teleporter;
+mod tiled;
+mod transition;
+mod vision;
+mod wave;
+mod weapon;
+mod world_clock;
 
-const PIXEL_PERFECT_LAYER: RenderLayers = RenderLayers::layer(0);
-const HIGH_RES_LAYER: RenderLayers = RenderLayers::layer(1);
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use ai::AiPlugin;
+use aim_line::AimLinePlugin;
+use animation::AnimationPlugin;
+use attract::AttractPlugin;
+use audio::AudioPlugin;
+use boss::BossPlugin;
+use camera::CameraPlugin;
+use checkpoint::CheckpointPlugin;
+use collider::ColliderPlugin;
+use combat_room::CombatRoomPlugin;
+use cooldown_arcs::CooldownArcsPlugin;
+use crosshair::CrosshairPlugin;
+use culling::CullingPlugin;
+use cutscene::CutscenePlugin;
+use dash::DashPlugin;
+use debug::DebugPlugin;
+use destructible::DestructiblePlugin;
+use determinism::DeterminismPlugin;
+use dialogue::DialoguePlugin;
+use door::DoorPlugin;
+use enemy::EnemyPlugin;
+use flare::FlarePlugin;
+use force_zone::ForceZonePlugin;
+use grapple::GrapplePlugin;
+use grenade::GrenadePlugin;
+use hazard::HazardPlugin;
+use health::HealthPlugin;
+use hit_feedback::HitFeedbackPlugin;
+use hud::HudPlugin;
+use input::InputPlugin;
+use input_prompt::InputPromptPlugin;
+use interaction::InteractionPlugin;
+use inventory::InventoryPlugin;
+use level::LevelPlugin;
+use lighting::LightingPlugin;
+use melee::MeleePlugin;
+use menu::MenuPlugin;
+use minimap::MinimapPlugin;
+use music::MusicPlugin;
+use outline::OutlinePlugin;
+use parallax::ParallaxPlugin;
+use particle::ParticlePlugin;
+use pathfinding::PathfindingPlugin;
+use pickup::PickupPlugin;
+use pixel_perfect::{PixelCanvasConfig, PixelPerfectRenderPlugin};
+use platform::PlatformPlugin;
+use player::PlayerPlugin;
+use portal::PortalPlugin;
+use post_process::PostProcessPlugin;
+use procgen::ProcgenPlugin;
+use projectile::ProjectilePlugin;
+use prop::PropPlugin;
+use replay::ReplayPlugin;
+use results::ResultsPlugin;
+use rng::RngPlugin;
+use save::SavePlugin;
+use score::ScorePlugin;
+use screen_shake::ScreenShakePlugin;
+use settings::{GameSettings, SettingsPlugin};
+use settings_menu::SettingsMenuPlugin;
+use speedrun::SpeedrunPlugin;
+use stamina::StaminaPlugin;
+use state::StatePlugin;
+use status::StatusPlugin;
+use teleporter::TeleporterPlugin;
+use tiled::TiledPlugin;
+use transition::TransitionPlugin;
+use vision::VisionPlugin;
+use wave::WavePlugin;
+use weapon::WeaponPlugin;
+use world_clock::WorldClockPlugin;
 
 fn main() {
+    let canvas_size = PixelCanvasConfig::default().size_f32();
+    let settings = GameSettings::load();
+
     let mut app = App::new();
+    wave::register_mods_source(&mut app);
     app.add_plugins((
         DefaultPlugins
             .set(WindowPlugin {
                 primary_window: Some(Window {
-                    resolution: (RES_WIDTH as f32 * 10., RES_HEIGHT as f32 * 10.).into(),
+                    resolution: (canvas_size.x * 10., canvas_size.y * 10.).into(),
                     title: "Untitled Game".into(),
+                    mode: settings.window_mode.window_mode(),
                     ..Default::default()
                 }),
                 ..Default::default()
             })
             .set(ImagePlugin::default_nearest()),
-        PhysicsPlugins::default(),
-        PhysicsDebugPlugin::default(),
+        PhysicsPlugins::default().set(PhysicsInterpolationPlugin::interpolate_all()),
     ));
-    app.add_systems(Startup, setup);
-    app.add_systems(Update, fit_canvas);
-    app.add_systems(
-        Update,
-        (
-            move_player,
-            update_mouse_world_pos,
-            rotate_to_mouse,
-            spawn_flares,
-        ),
-    );
-    app.insert_resource(MouseWorldPos(Vec2::new(0., 0.)));
-    app.insert_resource(Gravity::ZERO);
-    app.run();
-}
-
-fn setup(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut images: ResMut<Assets<Image>>,
-) {
-    let canvas_size = Extent3d {
-        width: RES_WIDTH,
-        height: RES_HEIGHT,
-        depth_or_array_layers: 1,
-    };
-
-    let mut canvas_texture = Image {
-        texture_descriptor: TextureDescriptor {
-            label: None,
-            mip_level_count: 1,
-            sample_count: 1,
-            size: canvas_size,
-            dimension: bevy::render::render_resource::TextureDimension::D2,
-            format: Bgra8UnormSrgb,
-            usage: TextureUsages::TEXTURE_BINDING
-                | TextureUsages::COPY_DST
-                | TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[],
-        },
-        ..Default::default()
-    };
-
-    canvas_texture.resize(canvas_size);
-    let image_handle = images.add(canvas_texture);
-
-    commands.spawn((
-        Camera2d,
-        Camera {
-            order: -1,
-            target: RenderTarget::Image(image_handle.clone().into()),
-            clear_color: ClearColorConfig::Custom(GRAY.into()),
-            ..Default::default()
-        },
-        PIXEL_PERFECT_LAYER,
+    app.insert_resource(settings);
+    app.add_plugins((
+        RngPlugin,
+        SettingsPlugin,
+        DebugPlugin,
+        InputPlugin,
+        StatePlugin,
+        MenuPlugin,
+        SettingsMenuPlugin,
+        PixelPerfectRenderPlugin,
+        CameraPlugin,
+        LevelPlugin,
+        TiledPlugin,
+        ProcgenPlugin,
+        ScreenShakePlugin,
+        LightingPlugin,
+        ColliderPlugin,
     ));
-
-    commands.spawn((Sprite::from_image(image_handle), Canvas, HIGH_RES_LAYER));
-    commands.spawn((Camera2d, Msaa::Off, HIGH_RES_LAYER, MainCamera));
-
-    commands.spawn((
-        Transform::from_xyz(0., 0., 0.).with_scale(Vec3::splat(1.)),
-        Sprite::from_image(asset_server.load("player.png")),
-        Player,
-        RotateToMouse,
-        RigidBody::Dynamic,
-        Collider::circle(9.),
-        DebugRender::default().with_collider_color(Color::srgb(1.0, 0.0, 0.0)),
-        PIXEL_PERFECT_LAYER,
-        LinearVelocity::ZERO,
-        AngularVelocity::ZERO,
-        MaxLinearSpeed(400.),
+    app.add_plugins((
+        PlayerPlugin,
+        DashPlugin,
+        StaminaPlugin,
+        FlarePlugin,
+        WeaponPlugin,
+        ProjectilePlugin,
+        MeleePlugin,
+        HealthPlugin,
+        PickupPlugin,
+        InventoryPlugin,
+        InteractionPlugin,
+        DoorPlugin,
+        DialoguePlugin,
+        HudPlugin,
+        PostProcessPlugin,
     ));
-
-    // commands.spawn((
-    //     Transform::from_xyz(30., 0., 0.).with_scale(Vec3::splat(1.)),
-    //     Sprite::from_image(asset_server.load("player.png")),
-    //     RigidBody::Kinematic,
-    //     Collider::circle(9.),
-    //     DebugRender::default().with_collider_color(Color::srgb(1.0, 1.0, 0.0)),
-    //     PIXEL_PERFECT_LAYER,
-    // ));
-}
-
-#[derive(Component)]
-struct MainCamera;
-
-#[derive(Component)]
-struct Canvas;
-
-fn fit_canvas(
-    mut resize_events: EventReader<WindowResized>,
-    mut canvas_transform: Single<&mut Transform, With<Canvas>>,
-) {
-    for event in resize_events.read() {
-        let scale_x = event.width / RES_WIDTH as f32;
-        let scale_y = event.height / RES_HEIGHT as f32;
-        let scale = scale_x.min(scale_y).floor();
-
-        canvas_transform.scale = Vec3::splat(scale);
-    }
-}
-
-#[derive(Resource, Debug)]
-struct MouseWorldPos(Vec2);
-
-fn update_mouse_world_pos(
-    mut mouse_world_pos: ResMut<MouseWorldPos>,
-    camera_q: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
-    window: Single<&Window, With<PrimaryWindow>>,
-) {
-    let (camera, camera_pos) = *camera_q;
-
-    let cursor_pos = match window.cursor_position() {
-        Some(pos) => pos,
-        None => return,
-    };
-
-    let cursor_world_pos = match camera.viewport_to_world_2d(camera_pos, cursor_pos) {
-        Ok(pos) => pos,
-        Err(_) => return,
-    };
-
-    let cursor_ndc_world_pos = match camera.world_to_ndc(camera_pos, cursor_world_pos.extend(0.)) {
-        Some(pos) => pos,
-        None => return,
-    };
-
-    let scaled_ndc_world_pos = Vec2::new(
-        cursor_ndc_world_pos.x * RES_WIDTH as f32,
-        cursor_ndc_world_pos.y * RES_HEIGHT as f32,
-    );
-
-    mouse_world_pos.0 = scaled_ndc_world_pos;
-}
-
-#[derive(Component)]
-struct RotateToMouse;
-
-#[derive(Component)]
-struct Player;
-
-fn move_player(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    player_velocity: Single<&mut LinearVelocity, With<Player>>,
-) {
-    let mut direction = Vec2::ZERO;
-    if keyboard_input.pressed(KeyCode::KeyA) {
-        direction.x -= 1.;
-    };
-    if keyboard_input.pressed(KeyCode::KeyD) {
-        direction.x += 1.;
-    };
-    if keyboard_input.pressed(KeyCode::KeyW) {
-        direction.y += 1.;
-    };
-    if keyboard_input.pressed(KeyCode::KeyS) {
-        direction.y -= 1.;
-    };
-
-    let speed = 100.;
-
-    direction = direction.normalize_or_zero() * speed;
-
-    let mut velocity = player_velocity.into_inner();
-    velocity.0 = direction;
-}
-
-fn rotate_to_mouse(
-    mouse_world_pos: Res<MouseWorldPos>,
-    mut transform_q: Query<&mut Transform, With<RotateToMouse>>,
-) {
-    for mut transform in transform_q.iter_mut() {
-        let direction = mouse_world_pos.0 - transform.translation.truncate();
-        let angle = direction.y.atan2(direction.x);
-        transform.rotation = Quat::from_rotation_z(angle);
-    }
-}
-
-#[derive(Component)]
-struct Flare;
-
-fn spawn_flares(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    player_transform: Single<&Transform, With<Player>>,
-) {
-    if keyboard_input.just_pressed(KeyCode::KeyF) {
-        commands.spawn((
-            Flare,
-            Transform::from_translation(player_transform.translation).with_scale(Vec3::splat(1.)),
-            Sprite::from_image(asset_server.load("flare.png")),
-            RigidBody::Dynamic,
-            Collider::circle(5.),
-            DebugRender::default().with_collider_color(Color::srgb(1.0, 1.0, 0.0)),
-            PIXEL_PERFECT_LAYER,
-            LinearVelocity(
-                ((player_transform.rotation
-                    * Quat::from_rotation_z(3. * std::f32::consts::PI / 2.))
-                    * Vec3::Y)
-                    .xy()
-                    * 100.,
-            ),
-            AngularVelocity(-20.),
-        ));
-    }
+    app.add_plugins((
+        EnemyPlugin,
+        AiPlugin,
+        WavePlugin,
+        TransitionPlugin,
+        SavePlugin,
+        CheckpointPlugin,
+        AudioPlugin,
+        MusicPlugin,
+        AnimationPlugin,
+        ParticlePlugin,
+        HitFeedbackPlugin,
+        WorldClockPlugin,
+        VisionPlugin,
+        PathfindingPlugin,
+        CutscenePlugin,
+    ));
+    app.add_plugins((
+        ResultsPlugin,
+        ScorePlugin,
+        BossPlugin,
+        StatusPlugin,
+        CullingPlugin,
+        CrosshairPlugin,
+        AimLinePlugin,
+        MinimapPlugin,
+        ParallaxPlugin,
+        GrapplePlugin,
+        GrenadePlugin,
+    ));
+    app.add_plugins((
+        DestructiblePlugin,
+        PropPlugin,
+        PlatformPlugin,
+        HazardPlugin,
+        ForceZonePlugin,
+        TeleporterPlugin,
+        SpeedrunPlugin,
+        ReplayPlugin,
+        DeterminismPlugin,
+        CombatRoomPlugin,
+        PortalPlugin,
+        OutlinePlugin,
+        AttractPlugin,
+        InputPromptPlugin,
+        CooldownArcsPlugin,
+    ));
+    app.insert_resource(Gravity::ZERO);
+    app.run();
 }
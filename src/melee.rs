@@ -0,0 +1,235 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    collider::{GameLayer, collider_shape},
+    cooldown_arcs::AbilityCooldown,
+    health::{DamageEvent, Health},
+    input::{Action, PlayerInput},
+    particle::{ParticleEffect, SpawnParticles},
+    player::{Aim, Player},
+    projectile::{EnemyProjectile, Homing, Projectile, detect_projectile_hits},
+    state::{GameplaySet, PlayerControlSet},
+    status::{ApplyStatus, StatusEffect},
+};
+
+/// Distance in front of the attacker where the swing hitbox is centered.
+const SWING_REACH: f32 = 13.;
+const SWING_DURATION: f32 = 0.15;
+/// How long whatever a swing hits is stunned for.
+const SWING_STUN_SECS: f32 = 0.4;
+/// Enemy projectiles faster than this, in px/s, go through a swing.
+const DEFLECT_MAX_SPEED: f32 = 120.;
+const DEFLECT_DAMAGE_MULTIPLIER: f32 = 1.5;
+/// Deflected projectiles fly back this much faster than they came.
+const DEFLECT_SPEED_MULTIPLIER: f32 = 1.5;
+const DEFLECTED_COLOR: Color = Color::srgb(1., 0.85, 0.3);
+const DEFLECT_FLASH: ParticleEffect = ParticleEffect {
+    burst: 12,
+    rate: 0.,
+    min_speed: 30.,
+    max_speed: 80.,
+    spread: 0.9,
+    min_lifetime: 0.1,
+    max_lifetime: 0.25,
+    gravity: Vec2::ZERO,
+    drag: 4.,
+    start_color: Color::srgb(1., 1., 0.8),
+    end_color: Color::srgba(1., 0.7, 0.2, 0.),
+};
+
+pub struct MeleePlugin;
+
+impl Plugin for MeleePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                start_melee_swings.in_set(PlayerControlSet),
+                resolve_melee_hits,
+                deflect_projectiles.before(detect_projectile_hits),
+                end_melee_swings,
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct MeleeAttack {
+    pub damage: f32,
+    pub knockback: f32,
+    pub cooldown: Timer,
+}
+
+impl MeleeAttack {
+    pub fn new(damage: f32, knockback: f32, cooldown_secs: f32) -> Self {
+        let mut cooldown = Timer::from_seconds(cooldown_secs, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+
+        Self {
+            damage,
+            knockback,
+            cooldown,
+        }
+    }
+}
+
+impl AbilityCooldown for MeleeAttack {
+    const ACTION: Action = Action::Melee;
+    const COLOR: Color = Color::srgb(0.9, 0.9, 0.85);
+
+    fn cooldown(&self) -> &Timer {
+        &self.cooldown
+    }
+}
+
+/// Short-lived sensor hitbox spawned as a child of the attacker.
+#[derive(Component, Debug)]
+pub struct MeleeSwing {
+    pub attacker: Entity,
+    pub damage: f32,
+    pub knockback: f32,
+    pub lifetime: Timer,
+    pub already_hit: Vec<Entity>,
+}
+
+fn start_melee_swings(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut input: ResMut<PlayerInput>,
+    player: Single<(Entity, &mut MeleeAttack, &Aim), With<Player>>,
+) {
+    let (player_entity, mut melee, aim) = player.into_inner();
+    melee.cooldown.tick(time.delta());
+
+    if !melee.cooldown.finished() || !input.take_buffered(Action::Melee) {
+        return;
+    }
+    melee.cooldown.reset();
+
+    // The player itself doesn't turn, so the swing is placed and turned along the aim.
+    commands.entity(player_entity).with_child((
+        MeleeSwing {
+            attacker: player_entity,
+            damage: melee.damage,
+            knockback: melee.knockback,
+            lifetime: Timer::from_seconds(SWING_DURATION, TimerMode::Once),
+            already_hit: Vec::new(),
+        },
+        Name::new("Melee Swing"),
+        Transform::from_translation((aim.0 * SWING_REACH).extend(0.))
+            .with_rotation(Quat::from_rotation_z(aim.0.to_angle())),
+        collider_shape("melee_swing").bundle(),
+        GameLayer::MeleeSwing.collision_layers(),
+        ColliderDensity(0.),
+        Sensor,
+        CollisionEventsEnabled,
+    ));
+}
+
+fn resolve_melee_hits(
+    mut collision_events: EventReader<CollisionStarted>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut status_events: EventWriter<ApplyStatus>,
+    mut swing_q: Query<&mut MeleeSwing>,
+    target_q: Query<&GlobalTransform, With<Health>>,
+    attacker_q: Query<&GlobalTransform>,
+) {
+    for CollisionStarted(a, b) in collision_events.read() {
+        for (swing_entity, target) in [(*a, *b), (*b, *a)] {
+            let Ok(mut swing) = swing_q.get_mut(swing_entity) else {
+                continue;
+            };
+            if target == swing.attacker || swing.already_hit.contains(&target) {
+                continue;
+            }
+            let (Ok(target_transform), Ok(attacker_transform)) =
+                (target_q.get(target), attacker_q.get(swing.attacker))
+            else {
+                continue;
+            };
+
+            let normal = (target_transform.translation() - attacker_transform.translation())
+                .truncate()
+                .normalize_or_zero();
+            swing.already_hit.push(target);
+            damage_events.write(DamageEvent {
+                target,
+                amount: swing.damage,
+                knockback: normal * swing.knockback,
+            });
+            status_events.write(ApplyStatus {
+                target,
+                effect: StatusEffect::Stun,
+                secs: SWING_STUN_SECS,
+            });
+        }
+    }
+}
+
+/// Bats slow enough enemy projectiles back along the attacker's aim, as the
+/// attacker's own. The fastest shots go through. A deflected shot is no longer an enemy
+/// projectile, so it can't be batted back again.
+#[allow(clippy::type_complexity)]
+fn deflect_projectiles(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionStarted>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    swing_q: Query<&MeleeSwing>,
+    aim_q: Query<&Aim>,
+    mut projectile_q: Query<
+        (
+            &mut Projectile,
+            &mut Transform,
+            &mut LinearVelocity,
+            &mut Sprite,
+        ),
+        With<EnemyProjectile>,
+    >,
+) {
+    for CollisionStarted(a, b) in collision_events.read() {
+        for (swing_entity, projectile_entity) in [(*a, *b), (*b, *a)] {
+            let (Ok(swing), Ok((mut projectile, mut transform, mut velocity, mut sprite))) = (
+                swing_q.get(swing_entity),
+                projectile_q.get_mut(projectile_entity),
+            ) else {
+                continue;
+            };
+            let speed = velocity.length();
+            if speed > DEFLECT_MAX_SPEED {
+                continue;
+            }
+            let Ok(aim) = aim_q.get(swing.attacker) else {
+                continue;
+            };
+
+            projectile.owner = swing.attacker;
+            projectile.damage *= DEFLECT_DAMAGE_MULTIPLIER;
+            velocity.0 = aim.0 * speed * DEFLECT_SPEED_MULTIPLIER;
+            transform.rotation = Quat::from_rotation_z(aim.0.to_angle());
+            sprite.color = DEFLECTED_COLOR;
+            commands
+                .entity(projectile_entity)
+                .remove::<(EnemyProjectile, Homing)>()
+                .insert(GameLayer::Projectile.collision_layers());
+            particle_events.write(SpawnParticles {
+                effect: DEFLECT_FLASH,
+                position: transform.translation.truncate(),
+                direction: aim.0,
+            });
+        }
+    }
+}
+
+fn end_melee_swings(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut swing_q: Query<(Entity, &mut MeleeSwing)>,
+) {
+    for (entity, mut swing) in swing_q.iter_mut() {
+        if swing.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+
+use crate::{
+    attract::MenuMode,
+    input::{Action, PlayerInput},
+    input_prompt::spawn_prompt_hint,
+    pixel_perfect::{CanvasText, HIGH_RES_LAYER},
+    results::LifetimeStats,
+    state::{GameState, ScreenOverlay},
+    wave::WaveSets,
+};
+
+const MENU_BACKGROUND: Color = Color::srgb(0.04, 0.04, 0.06);
+/// Lets the attract mode's game show through.
+const ATTRACT_BACKGROUND: Color = Color::srgba(0.04, 0.04, 0.06, 0.6);
+const TITLE_FONT_SIZE: f32 = 14.;
+const ENTRY_FONT_SIZE: f32 = 8.;
+/// Vertical distance between entries, in canvas pixels.
+const ENTRY_SPACING: f32 = 12.;
+const STATS_FONT_SIZE: f32 = 5.;
+/// Vertical distance between lifetime stat lines, in canvas pixels.
+const STATS_SPACING: f32 = 6.;
+const ENTRY_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const SELECTED_ENTRY_COLOR: Color = Color::WHITE;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MenuSelection::default());
+        app.add_systems(OnEnter(GameState::MainMenu), spawn_main_menu);
+        app.add_systems(OnEnter(MenuMode::Attract), fade_menu_backdrop);
+        app.add_systems(OnExit(MenuMode::Attract), restore_menu_backdrop);
+        app.add_systems(
+            Update,
+            (
+                navigate_menu.run_if(not(showing_lifetime_stats)),
+                confirm_menu_entry,
+                label_wave_set_entry,
+                highlight_selected_entry,
+            )
+                .chain()
+                .run_if(in_state(MenuMode::Interactive)),
+        );
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuEntry {
+    NewGame,
+    /// Cycles through the wave sets survival games can be played with.
+    Waves,
+    /// Totals over every game played.
+    Stats,
+    Settings,
+    Quit,
+}
+
+impl MenuEntry {
+    const ALL: [MenuEntry; 5] = [
+        MenuEntry::NewGame,
+        MenuEntry::Waves,
+        MenuEntry::Stats,
+        MenuEntry::Settings,
+        MenuEntry::Quit,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            MenuEntry::NewGame => "New Game",
+            MenuEntry::Waves => "Waves",
+            MenuEntry::Stats => "Stats",
+            MenuEntry::Settings => "Settings",
+            MenuEntry::Quit => "Quit",
+        }
+    }
+}
+
+/// Index into `MenuEntry::ALL`. Kept across visits so returning to the menu lands on
+/// the entry that was used last.
+#[derive(Resource, Default, Debug)]
+struct MenuSelection(usize);
+
+#[derive(Component)]
+struct MenuBackdrop;
+
+/// Covers the menu while the lifetime stats are up.
+#[derive(Component)]
+struct LifetimeStatsPanel;
+
+fn spawn_main_menu(mut commands: Commands, lifetime_stats: Res<LifetimeStats>) {
+    commands
+        .spawn((
+            MenuBackdrop,
+            ScreenOverlay,
+            Name::new("Main menu"),
+            Sprite::from_color(MENU_BACKGROUND, Vec2::ONE),
+            Transform::from_xyz(0., 0., 20.),
+            HIGH_RES_LAYER,
+            StateScoped(GameState::MainMenu),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2d::new("Untitled Game"),
+                CanvasText::new(Vec2::new(0., 20.), TITLE_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+
+            for (index, entry) in MenuEntry::ALL.into_iter().enumerate() {
+                parent.spawn((
+                    entry,
+                    Text2d::new(entry.label()),
+                    TextColor(ENTRY_COLOR),
+                    CanvasText::new(
+                        Vec2::new(0., -(index as f32) * ENTRY_SPACING),
+                        ENTRY_FONT_SIZE,
+                    ),
+                    Transform::from_xyz(0., 0., 0.1),
+                ));
+            }
+            spawn_prompt_hint(parent, Action::Confirm, "select", Vec2::new(-4., -66.));
+
+            parent
+                .spawn((
+                    LifetimeStatsPanel,
+                    Sprite::from_color(MENU_BACKGROUND, Vec2::ONE),
+                    Transform::from_xyz(0., 0., 0.2),
+                    Visibility::Hidden,
+                    HIGH_RES_LAYER,
+                ))
+                .with_children(|panel| {
+                    let lines = lifetime_stats.lines();
+                    let top = (lines.len() - 1) as f32 * STATS_SPACING / 2.;
+                    for (index, line) in lines.into_iter().enumerate() {
+                        panel.spawn((
+                            Text2d::new(line),
+                            CanvasText::new(
+                                Vec2::new(0., top - index as f32 * STATS_SPACING),
+                                STATS_FONT_SIZE,
+                            ),
+                            Transform::from_xyz(0., 0., 0.1),
+                        ));
+                    }
+                });
+        });
+}
+
+fn fade_menu_backdrop(mut backdrop: Single<&mut Sprite, With<MenuBackdrop>>) {
+    backdrop.color = ATTRACT_BACKGROUND;
+}
+
+/// The menu may already be gone, when leaving it ended the attract mode.
+fn restore_menu_backdrop(mut backdrop_q: Query<&mut Sprite, With<MenuBackdrop>>) {
+    for mut sprite in backdrop_q.iter_mut() {
+        sprite.color = MENU_BACKGROUND;
+    }
+}
+
+fn showing_lifetime_stats(panel: Single<&Visibility, With<LifetimeStatsPanel>>) -> bool {
+    **panel != Visibility::Hidden
+}
+
+fn navigate_menu(input: Res<PlayerInput>, mut selection: ResMut<MenuSelection>) {
+    let count = MenuEntry::ALL.len();
+    if input.just_pressed(Action::MoveUp) {
+        selection.0 = (selection.0 + count - 1) % count;
+    }
+    if input.just_pressed(Action::MoveDown) {
+        selection.0 = (selection.0 + 1) % count;
+    }
+}
+
+/// While the lifetime stats are up, confirm or pause closes them instead.
+fn confirm_menu_entry(
+    input: Res<PlayerInput>,
+    selection: Res<MenuSelection>,
+    mut wave_sets: ResMut<WaveSets>,
+    mut stats_panel: Single<&mut Visibility, With<LifetimeStatsPanel>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    if **stats_panel != Visibility::Hidden {
+        if input.just_pressed(Action::Confirm) || input.just_pressed(Action::Pause) {
+            **stats_panel = Visibility::Hidden;
+        }
+        return;
+    }
+    if !input.just_pressed(Action::Confirm) {
+        return;
+    }
+
+    match MenuEntry::ALL[selection.0] {
+        MenuEntry::NewGame => next_state.set(GameState::Playing),
+        MenuEntry::Waves => wave_sets.select_next(),
+        MenuEntry::Stats => **stats_panel = Visibility::Inherited,
+        MenuEntry::Settings => next_state.set(GameState::Settings),
+        MenuEntry::Quit => {
+            exit_events.write(AppExit::Success);
+        }
+    }
+}
+
+/// Names the selected wave set, and says so when it couldn't be loaded, since the
+/// default waves are played instead.
+fn label_wave_set_entry(
+    asset_server: Res<AssetServer>,
+    wave_sets: Res<WaveSets>,
+    mut entry_q: Query<(&MenuEntry, &mut Text2d)>,
+) {
+    let selected = wave_sets.selected();
+    let label = if asset_server.load_state(&selected.handle).is_failed() {
+        format!("Waves: {} (broken)", selected.name)
+    } else {
+        format!("Waves: {}", selected.name)
+    };
+    for (entry, mut text) in entry_q.iter_mut() {
+        if *entry == MenuEntry::Waves && text.0 != label {
+            text.0.clone_from(&label);
+        }
+    }
+}
+
+fn highlight_selected_entry(
+    selection: Res<MenuSelection>,
+    mut entry_q: Query<(&MenuEntry, &mut TextColor)>,
+) {
+    for (entry, mut color) in entry_q.iter_mut() {
+        color.0 = if *entry == MenuEntry::ALL[selection.0] {
+            SELECTED_ENTRY_COLOR
+        } else {
+            ENTRY_COLOR
+        };
+    }
+}
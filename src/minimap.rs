@@ -0,0 +1,294 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        view::RenderLayers,
+    },
+    sprite::Anchor,
+};
+
+use crate::{
+    boss::Boss,
+    camera::CameraFollow,
+    enemy::Enemy,
+    level::{Level, TileChanged},
+    pixel_perfect::{CanvasCoords, HIGH_RES_LAYER, PixelCanvasConfig},
+    player::Player,
+    state::{GameScoped, GameplaySet, NewGame},
+    vision::VisionField,
+};
+
+/// Only the minimap camera sees this layer.
+const MINIMAP_LAYER: RenderLayers = RenderLayers::layer(2);
+/// In minimap pixels, one per tile, which is also its size on the canvas.
+const MINIMAP_SIZE: UVec2 = UVec2::new(32, 20);
+/// Offset from the top-right corner of the canvas, below the wave text.
+const MINIMAP_MARGIN: Vec2 = Vec2::new(2., 9.);
+const MINIMAP_BACKGROUND: Color = Color::srgba(0., 0., 0., 0.6);
+const MINIMAP_FLOOR_COLOR: [u8; 4] = [70, 66, 62, 200];
+const MINIMAP_WALL_COLOR: [u8; 4] = [170, 164, 152, 255];
+/// Blips are this many tiles across, so they show up as more than a lone pixel.
+const BLIP_TILES: f32 = 2.;
+const PLAYER_BLIP_COLOR: Color = Color::WHITE;
+const ENEMY_BLIP_COLOR: Color = Color::srgb(1., 0.3, 0.25);
+const BOSS_BLIP_COLOR: Color = Color::srgb(0.8, 0.3, 0.9);
+
+/// A small map of the level around the player in the top-right corner. A camera of its
+/// own renders it at one pixel per tile: the walls and floors the player has seen, and
+/// blips for the player and the enemies that are in sight.
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExploredTiles>();
+        app.add_systems(Startup, setup_minimap);
+        app.add_systems(NewGame, (forget_explored_tiles, spawn_minimap_display));
+        app.add_systems(
+            Update,
+            (
+                forget_explored_tiles.run_if(resource_changed::<Level>),
+                explore_seen_tiles.in_set(GameplaySet),
+                draw_explored_tiles
+                    .run_if(resource_changed::<ExploredTiles>.or(on_event::<TileChanged>)),
+            )
+                .chain(),
+        );
+        app.add_systems(
+            Update,
+            (add_minimap_blips, follow_minimap_camera, position_minimap),
+        );
+    }
+}
+
+/// Which tiles of the current level the player has seen, in the same order as
+/// `Level::tiles`. Forgotten when the level changes.
+#[derive(Resource, Default)]
+struct ExploredTiles(Vec<bool>);
+
+#[derive(Component)]
+struct MinimapCamera;
+
+/// The explored tiles, one texel each, laid over the level grid in the world.
+#[derive(Component)]
+struct MinimapTiles;
+
+/// Shows the minimap camera's image on the HUD.
+#[derive(Component)]
+struct MinimapDisplay;
+
+#[derive(Component)]
+struct MinimapBlip;
+
+fn setup_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut target = Image::new_fill(
+        Extent3d {
+            width: MINIMAP_SIZE.x,
+            height: MINIMAP_SIZE.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    target.texture_descriptor.usage |= TextureUsages::RENDER_ATTACHMENT;
+    let target = images.add(target);
+
+    commands.insert_resource(MinimapImage(target.clone()));
+    commands.spawn((
+        MinimapCamera,
+        Name::new("Minimap camera"),
+        Camera2d,
+        Camera {
+            order: -2,
+            target: RenderTarget::Image(target.into()),
+            clear_color: ClearColorConfig::Custom(MINIMAP_BACKGROUND),
+            ..Default::default()
+        },
+        // Set to the tile size once a level is drawn, so a tile covers one pixel.
+        Projection::Orthographic(OrthographicProjection::default_2d()),
+        MINIMAP_LAYER,
+    ));
+    commands.spawn((
+        MinimapTiles,
+        Name::new("Minimap tiles"),
+        Sprite::default(),
+        MINIMAP_LAYER,
+    ));
+}
+
+/// What the minimap camera renders to.
+#[derive(Resource)]
+struct MinimapImage(Handle<Image>);
+
+fn spawn_minimap_display(mut commands: Commands, image: Res<MinimapImage>) {
+    commands.spawn((
+        MinimapDisplay,
+        GameScoped,
+        Name::new("Minimap"),
+        Sprite {
+            image: image.0.clone(),
+            custom_size: Some(MINIMAP_SIZE.as_vec2()),
+            anchor: Anchor::TopRight,
+            ..Default::default()
+        },
+        Transform::default(),
+        HIGH_RES_LAYER,
+    ));
+}
+
+fn forget_explored_tiles(level: Res<Level>, mut explored: ResMut<ExploredTiles>) {
+    explored.0 = vec![false; level.tiles.len()];
+}
+
+/// Everything on screen the player can see counts as explored, along with the walls
+/// right next to it: the vision field stops at a wall's edge, so wall centers are never
+/// seen themselves.
+fn explore_seen_tiles(
+    config: Res<PixelCanvasConfig>,
+    follow: Res<CameraFollow>,
+    field: Res<VisionField>,
+    level: Res<Level>,
+    mut explored: ResMut<ExploredTiles>,
+) {
+    let tile_size = level.tile_size as f32;
+    let grid_size = UVec2::new(level.width, level.height);
+    let view = Rect::from_center_size(follow.position, config.size_f32());
+    let to_tile = |position: Vec2| {
+        ((position - level.bounds().min) / tile_size)
+            .floor()
+            .max(Vec2::ZERO)
+            .as_uvec2()
+            .min(grid_size)
+    };
+    let (min, max) = (to_tile(view.min), to_tile(view.max + tile_size));
+
+    let mut seen = Vec::new();
+    for y in min.y..max.y {
+        for x in min.x..max.x {
+            let tile = UVec2::new(x, y);
+            let index = (y * level.width + x) as usize;
+            if explored.0.get(index) != Some(&false)
+                || !field.sees(
+                    level
+                        .tile_rect(URect::from_corners(tile, tile + 1))
+                        .center(),
+                )
+            {
+                continue;
+            }
+
+            seen.push(index);
+            for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                // Wraps past the grid's edge, where `get` reads `Empty`.
+                let neighbour = tile.wrapping_add_signed(offset);
+                if level.get(neighbour).is_solid() {
+                    seen.push((neighbour.y * level.width + neighbour.x) as usize);
+                }
+            }
+        }
+    }
+
+    // Only touched when something is new, so the map isn't redrawn every frame.
+    if seen.is_empty() {
+        return;
+    }
+    for index in seen {
+        explored.0[index] = true;
+    }
+}
+
+fn draw_explored_tiles(
+    level: Res<Level>,
+    explored: Res<ExploredTiles>,
+    mut images: ResMut<Assets<Image>>,
+    tiles: Single<&mut Sprite, With<MinimapTiles>>,
+    mut projection: Single<&mut Projection, With<MinimapCamera>>,
+) {
+    // Image rows go top to bottom, the level's bottom to top.
+    let mut data = Vec::with_capacity(level.tiles.len() * 4);
+    for row in (0..level.height).rev() {
+        for x in 0..level.width {
+            let index = (row * level.width + x) as usize;
+            let color = match level.tiles[index] {
+                _ if !explored.0.get(index).copied().unwrap_or(false) => [0; 4],
+                kind if kind.is_solid() => MINIMAP_WALL_COLOR,
+                _ => MINIMAP_FLOOR_COLOR,
+            };
+            data.extend_from_slice(&color);
+        }
+    }
+
+    let image = Image::new(
+        Extent3d {
+            width: level.width,
+            height: level.height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+
+    let mut sprite = tiles.into_inner();
+    sprite.image = images.add(image);
+    sprite.custom_size = Some(level.size());
+
+    if let Projection::Orthographic(orthographic) = &mut **projection {
+        orthographic.scale = level.tile_size as f32;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn add_minimap_blips(
+    mut commands: Commands,
+    new_q: Query<(Entity, Has<Player>, Has<Boss>), Or<(Added<Player>, Added<Enemy>, Added<Boss>)>>,
+    level: Res<Level>,
+) {
+    for (entity, is_player, is_boss) in new_q.iter() {
+        let color = if is_player {
+            PLAYER_BLIP_COLOR
+        } else if is_boss {
+            BOSS_BLIP_COLOR
+        } else {
+            ENEMY_BLIP_COLOR
+        };
+
+        // A child, so it follows the entity, goes with it, and is hidden along with it
+        // while out of sight.
+        commands.entity(entity).with_child((
+            MinimapBlip,
+            Sprite::from_color(color, Vec2::splat(level.tile_size as f32 * BLIP_TILES)),
+            Transform::from_xyz(0., 0., 1.),
+            MINIMAP_LAYER,
+        ));
+    }
+}
+
+/// Moves in whole tiles, so the map doesn't shimmer as the player walks.
+fn follow_minimap_camera(
+    follow: Res<CameraFollow>,
+    level: Res<Level>,
+    mut camera_transform: Single<&mut Transform, With<MinimapCamera>>,
+) {
+    let tile_size = level.tile_size as f32;
+    let snapped = (follow.position / tile_size).round() * tile_size;
+    camera_transform.translation = snapped.extend(camera_transform.translation.z);
+}
+
+/// Like the HUD bars, scaled through its transform to stay sharp.
+fn position_minimap(
+    config: Res<PixelCanvasConfig>,
+    coords: CanvasCoords,
+    mut display_q: Query<&mut Transform, With<MinimapDisplay>>,
+) {
+    let scale = coords.scale();
+    let corner = config.size_f32() / 2. - MINIMAP_MARGIN;
+    for mut transform in display_q.iter_mut() {
+        transform.translation = (corner * scale).extend(10.);
+        transform.scale = scale.extend(1.);
+    }
+}
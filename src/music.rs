@@ -0,0 +1,167 @@
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    ai::{AggroChanged, AiState},
+    settings::GameSettings,
+    state::{GameState, GameplaySet},
+};
+
+const CROSSFADE_SECS: f32 = 1.5;
+/// How long the combat theme keeps playing after the last enemy gives up the chase.
+const COMBAT_LINGER_SECS: f32 = 4.;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Combat {
+            active: false,
+            calm: Timer::from_seconds(COMBAT_LINGER_SECS, TimerMode::Once),
+        });
+        // Also leaving it, as the attract mode plays behind the menu.
+        app.add_systems(OnEnter(GameState::MainMenu), end_combat);
+        app.add_systems(OnExit(GameState::MainMenu), end_combat);
+        app.add_systems(
+            Update,
+            (
+                track_combat.in_set(GameplaySet),
+                start_theme,
+                crossfade_music,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Theme {
+    Menu,
+    Exploration,
+    Combat,
+}
+
+impl Theme {
+    fn path(self) -> &'static str {
+        match self {
+            Theme::Menu => "music/menu.ogg",
+            Theme::Exploration => "music/exploration.ogg",
+            Theme::Combat => "music/combat.ogg",
+        }
+    }
+}
+
+/// Whether enemies are after the player. Set by aggro events, and cleared once no
+/// enemy has been aggro for a while.
+#[derive(Resource, Debug)]
+struct Combat {
+    active: bool,
+    calm: Timer,
+}
+
+/// A playing theme. Every track fades toward silence except the current theme's,
+/// and is despawned once silent.
+#[derive(Component, Debug)]
+struct MusicTrack {
+    theme: Theme,
+    /// From 0 (silent) to 1 (full volume).
+    fade: f32,
+}
+
+fn end_combat(mut combat: ResMut<Combat>) {
+    combat.active = false;
+}
+
+fn track_combat(
+    time: Res<Time>,
+    mut aggro_events: EventReader<AggroChanged>,
+    mut combat: ResMut<Combat>,
+    ai_q: Query<&AiState>,
+) {
+    if aggro_events.read().any(|event| event.aggro) {
+        combat.active = true;
+        combat.calm.reset();
+        return;
+    }
+    if !combat.active {
+        return;
+    }
+
+    if ai_q.iter().any(|state| state.is_aggro()) {
+        combat.calm.reset();
+    } else if combat.calm.tick(time.delta()).finished() {
+        combat.active = false;
+    }
+}
+
+fn current_theme(state: GameState, combat: &Combat) -> Theme {
+    match state {
+        GameState::MainMenu | GameState::Settings => Theme::Menu,
+        GameState::Playing
+        | GameState::Paused
+        | GameState::Inventory
+        | GameState::Dialogue
+        | GameState::GameOver
+        | GameState::Victory
+            if combat.active =>
+        {
+            Theme::Combat
+        }
+        GameState::Playing
+        | GameState::Paused
+        | GameState::Inventory
+        | GameState::Dialogue
+        | GameState::GameOver
+        | GameState::Victory => Theme::Exploration,
+    }
+}
+
+fn start_theme(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<State<GameState>>,
+    combat: Res<Combat>,
+    track_q: Query<&MusicTrack>,
+) {
+    let theme = current_theme(*state.get(), &combat);
+    if track_q.iter().any(|track| track.theme == theme) {
+        return;
+    }
+
+    // Starts silent and fades in as the other tracks fade out.
+    commands.spawn((
+        MusicTrack { theme, fade: 0. },
+        Name::new(format!("{theme:?} music")),
+        AudioPlayer::new(asset_server.load(theme.path())),
+        PlaybackSettings::LOOP.with_volume(Volume::Linear(0.)),
+    ));
+}
+
+fn crossfade_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    state: Res<State<GameState>>,
+    combat: Res<Combat>,
+    mut track_q: Query<(Entity, &mut MusicTrack, Option<&mut AudioSink>)>,
+) {
+    let theme = current_theme(*state.get(), &combat);
+    let step = time.delta_secs() / CROSSFADE_SECS;
+
+    for (entity, mut track, sink) in track_q.iter_mut() {
+        if track.theme == theme {
+            track.fade = (track.fade + step).min(1.);
+        } else {
+            track.fade -= step;
+            if track.fade <= 0. {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        }
+
+        // The sink only shows up once the track has loaded and started playing.
+        if let Some(mut sink) = sink {
+            let volume = track.fade * settings.music_volume * settings.master_volume;
+            sink.set_volume(Volume::Linear(volume));
+        }
+    }
+}
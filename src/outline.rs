@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    camera::MouseWorldPos, enemy::Enemy, pixel_perfect::PIXEL_PERFECT_LAYER,
+    settings::GameSettings, state::GameplaySet,
+};
+
+/// Just behind the outlined sprite, so its own half transparent edges aren't covered.
+const OUTLINE_Z: f32 = -0.01;
+/// How close to the crosshair an enemy has to be to become the lock-on target.
+const LOCK_ON_RADIUS: f32 = 10.;
+const LOCK_ON_OUTLINE: Color = Color::srgb(1., 0.95, 0.6);
+
+/// 1-pixel outlines around sprites, so the player and enemies stand out from busy
+/// tiles at the canvas' low resolution. Each outline is a child sprite drawn from an
+/// outline variant of its parent's image, made once per image and atlas layout. The
+/// enemy nearest the crosshair gets one as the lock-on target.
+pub struct OutlinePlugin;
+
+impl Plugin for OutlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OutlineImages>();
+        app.add_systems(Update, outline_lock_on_target.in_set(GameplaySet));
+        // After everything that animates, flips or tints sprites.
+        app.add_systems(PostUpdate, (add_outlines, sync_outlines).chain());
+    }
+}
+
+/// Draws a 1-pixel outline of `color` around the entity's sprite, following its
+/// flipping, atlas frame and transparency. Can be turned off in the settings.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Outlined {
+    pub color: Color,
+}
+
+/// The enemy under the crosshair.
+#[derive(Component)]
+pub struct LockOnTarget;
+
+/// The child sprite drawing an `Outlined` entity's outline.
+#[derive(Component)]
+struct Outline {
+    /// The parent's image the outline was made from.
+    source: AssetId<Image>,
+}
+
+/// The image and atlas layout an outline image is made from.
+type OutlineSource = (AssetId<Image>, Option<AssetId<TextureAtlasLayout>>);
+
+#[derive(Resource, Default)]
+struct OutlineImages(HashMap<OutlineSource, Handle<Image>>);
+
+/// An image of white texels wherever `source` is transparent next to an opaque texel,
+/// to be tinted by the sprite color. Texels only count as neighbors within the same
+/// atlas frame, so frames don't bleed into each other's outlines. `None` for formats
+/// the texels can't be read from.
+fn outline_image(source: &Image, frames: &[URect]) -> Option<Image> {
+    let size = source.size();
+    let whole = [URect::from_corners(UVec2::ZERO, size)];
+    let frames = if frames.is_empty() {
+        &whole[..]
+    } else {
+        frames
+    };
+
+    let mut opaque = vec![false; (size.x * size.y) as usize];
+    for y in 0..size.y {
+        for x in 0..size.x {
+            opaque[(y * size.x + x) as usize] = source.get_color_at(x, y).ok()?.alpha() > 0.;
+        }
+    }
+
+    let mut data = vec![0; opaque.len() * 4];
+    for frame in frames {
+        for y in frame.min.y..frame.max.y.min(size.y) {
+            for x in frame.min.x..frame.max.x.min(size.x) {
+                let index = (y * size.x + x) as usize;
+                if opaque[index] {
+                    continue;
+                }
+                let touches_opaque = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+                    .into_iter()
+                    .map(|offset| UVec2::new(x, y).as_ivec2() + offset)
+                    .filter(|neighbor| {
+                        neighbor.cmpge(frame.min.as_ivec2()).all()
+                            && neighbor.cmplt(frame.max.as_ivec2()).all()
+                    })
+                    .any(|neighbor| {
+                        let neighbor = neighbor.as_uvec2();
+                        opaque
+                            .get((neighbor.y * size.x + neighbor.x) as usize)
+                            .copied()
+                            .unwrap_or(false)
+                    });
+                if touches_opaque {
+                    data[index * 4..index * 4 + 4].copy_from_slice(&[255; 4]);
+                }
+            }
+        }
+    }
+
+    Some(Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    ))
+}
+
+/// Waits for the parent's image to load before making its outline.
+fn add_outlines(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    atlas_layouts: Res<Assets<TextureAtlasLayout>>,
+    mut outline_images: ResMut<OutlineImages>,
+    outlined_q: Query<(Entity, &Sprite, Option<&Children>), With<Outlined>>,
+    outline_q: Query<(), With<Outline>>,
+) {
+    for (entity, sprite, children) in outlined_q.iter() {
+        if children.is_some_and(|children| children.iter().any(|child| outline_q.contains(child))) {
+            continue;
+        }
+
+        let layout = sprite.texture_atlas.as_ref().map(|atlas| atlas.layout.id());
+        let key = (sprite.image.id(), layout);
+        let image = match outline_images.0.get(&key) {
+            Some(image) => image.clone(),
+            None => {
+                let Some(source) = images.get(&sprite.image) else {
+                    continue;
+                };
+                let frames = layout
+                    .and_then(|layout| atlas_layouts.get(layout))
+                    .map_or(&[][..], |layout| &layout.textures[..]);
+                let Some(outline) = outline_image(source, frames) else {
+                    warn!(
+                        "can't outline a sprite with a {:?} image",
+                        source.texture_descriptor.format
+                    );
+                    commands.entity(entity).remove::<Outlined>();
+                    continue;
+                };
+                let image = images.add(outline);
+                outline_images.0.insert(key, image.clone());
+                image
+            }
+        };
+
+        commands.entity(entity).with_child((
+            Outline {
+                source: sprite.image.id(),
+            },
+            Name::new("Outline"),
+            Sprite {
+                image,
+                texture_atlas: sprite.texture_atlas.clone(),
+                ..Default::default()
+            },
+            Transform::from_xyz(0., 0., OUTLINE_Z),
+            PIXEL_PERFECT_LAYER,
+        ));
+    }
+}
+
+/// Outlines whose parent lost `Outlined` or switched images go; the latter get made
+/// again for the new image.
+fn sync_outlines(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    parent_q: Query<(Option<&Outlined>, &Sprite), Without<Outline>>,
+    mut outline_q: Query<(Entity, &Outline, &ChildOf, &mut Sprite, &mut Visibility)>,
+) {
+    for (entity, outline, child_of, mut sprite, mut visibility) in outline_q.iter_mut() {
+        let Ok((Some(outlined), parent_sprite)) = parent_q.get(child_of.parent()) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if parent_sprite.image.id() != outline.source {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        visibility.set_if_neq(if settings.outlines {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        // Fades in and out with the sprite.
+        let alpha = outlined.color.alpha() * parent_sprite.color.alpha();
+        sprite.color = outlined.color.with_alpha(alpha);
+        if let (Some(atlas), Some(parent_atlas)) =
+            (&mut sprite.texture_atlas, &parent_sprite.texture_atlas)
+        {
+            atlas.index = parent_atlas.index;
+        }
+        sprite.flip_x = parent_sprite.flip_x;
+        sprite.flip_y = parent_sprite.flip_y;
+        sprite.custom_size = parent_sprite.custom_size;
+        sprite.rect = parent_sprite.rect;
+        sprite.anchor = parent_sprite.anchor;
+    }
+}
+
+fn outline_lock_on_target(
+    mut commands: Commands,
+    mouse_world_pos: Res<MouseWorldPos>,
+    enemy_q: Query<(Entity, &Transform), With<Enemy>>,
+    target_q: Query<Entity, With<LockOnTarget>>,
+) {
+    let target = enemy_q
+        .iter()
+        .map(|(entity, transform)| {
+            let distance = transform.translation.truncate().distance(mouse_world_pos.0);
+            (entity, distance)
+        })
+        .filter(|(_, distance)| *distance <= LOCK_ON_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity);
+
+    for previous in target_q.iter() {
+        if Some(previous) != target {
+            commands
+                .entity(previous)
+                .remove::<(LockOnTarget, Outlined)>();
+        }
+    }
+    if let Some(target) = target.filter(|target| !target_q.contains(*target)) {
+        commands.entity(target).insert((
+            LockOnTarget,
+            Outlined {
+                color: LOCK_ON_OUTLINE,
+            },
+        ));
+    }
+}
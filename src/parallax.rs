@@ -0,0 +1,157 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    sprite::SpriteImageMode,
+    transform::TransformSystem,
+};
+use rand::Rng;
+
+use crate::{
+    camera::follow_player,
+    pixel_perfect::{PIXEL_PERFECT_LAYER, PixelCamera, ZOOM_LEVELS},
+    rng::GameRng,
+};
+
+/// Side of each layer's repeating image, in canvas pixels.
+const BACKDROP_TILE: u32 = 64;
+/// Fixed, so the backdrop looks the same every run without drawing from the game's RNG.
+const BACKDROP_SEED: u64 = 0x5eed;
+const SPACE_COLOR: [u8; 4] = [10, 10, 18, 255];
+const STAR_CHANCE: f64 = 0.012;
+const ROCK_COLOR: [u8; 3] = [30, 27, 25];
+const ROCKS_PER_TILE: usize = 7;
+
+/// Backdrop layers behind the level that scroll slower than the world the further away
+/// they are. The level is drawn over them, so they show wherever it has no tiles.
+pub struct ParallaxPlugin;
+
+impl Plugin for ParallaxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_backdrop);
+        app.add_systems(
+            PostUpdate,
+            scroll_parallax_layers
+                .after(follow_player)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// A repeating backdrop image that scrolls past at `factor` times the speed of the world:
+/// 0 stays fixed on screen like something infinitely far away, 1 moves with the world.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ParallaxLayer {
+    pub factor: f32,
+}
+
+fn spawn_backdrop(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut rng = GameRng::new(BACKDROP_SEED);
+    let layers = [
+        ("Stars", 0.1, -30., star_image(&mut rng)),
+        ("Cave walls", 0.4, -20., rock_image(&mut rng)),
+    ];
+
+    // Whole tiles covering the most zoomed-out view with one to spare on every side, for
+    // the snapping in `scroll_parallax_layers`.
+    let tile = BACKDROP_TILE as f32;
+    let size = ((ZOOM_LEVELS[ZOOM_LEVELS.len() - 1].as_vec2() / tile).ceil() + 2.) * tile;
+    for (name, factor, z, image) in layers {
+        commands.spawn((
+            ParallaxLayer { factor },
+            Name::new(name),
+            Sprite {
+                image: images.add(image),
+                custom_size: Some(size),
+                image_mode: SpriteImageMode::Tiled {
+                    tile_x: true,
+                    tile_y: true,
+                    stretch_value: 1.,
+                },
+                ..Default::default()
+            },
+            Transform::from_xyz(0., 0., z),
+            PIXEL_PERFECT_LAYER,
+        ));
+    }
+}
+
+fn backdrop_image(data: Vec<u8>) -> Image {
+    Image::new(
+        Extent3d {
+            width: BACKDROP_TILE,
+            height: BACKDROP_TILE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// Opaque, so it also stands in for the sky behind everything.
+fn star_image(rng: &mut GameRng) -> Image {
+    let pixels = (BACKDROP_TILE * BACKDROP_TILE) as usize;
+    let data = (0..pixels)
+        .flat_map(|_| {
+            if rng.gen_bool(STAR_CHANCE) {
+                let brightness = rng.gen_range(90..=230u8);
+                [brightness, brightness, brightness.saturating_add(20), 255]
+            } else {
+                SPACE_COLOR
+            }
+        })
+        .collect();
+    backdrop_image(data)
+}
+
+/// Round boulders, wrapping around the edges so the tiles join up seamlessly.
+fn rock_image(rng: &mut GameRng) -> Image {
+    let tile = BACKDROP_TILE as f32;
+    let rocks: Vec<(Vec2, f32)> = (0..ROCKS_PER_TILE)
+        .map(|_| {
+            let center = Vec2::new(rng.gen_range(0. ..tile), rng.gen_range(0. ..tile));
+            (center, rng.gen_range(5. ..14.))
+        })
+        .collect();
+
+    let mut data = Vec::with_capacity((BACKDROP_TILE * BACKDROP_TILE * 4) as usize);
+    for y in 0..BACKDROP_TILE {
+        for x in 0..BACKDROP_TILE {
+            let pixel = Vec2::new(x as f32, y as f32) + 0.5;
+            let shade = rocks.iter().find_map(|&(center, radius)| {
+                let offset = (pixel - center).abs();
+                let wrapped = offset.min(Vec2::splat(tile) - offset);
+                // Lit from above: the lower half of each boulder is darker.
+                let lower = (pixel.y - center.y).rem_euclid(tile) < tile / 2.;
+                (wrapped.length() < radius).then_some(if lower { 0.8 } else { 1. })
+            });
+
+            data.extend_from_slice(&match shade {
+                Some(shade) => {
+                    let [r, g, b] = ROCK_COLOR.map(|channel| (channel as f32 * shade) as u8);
+                    [r, g, b, 255]
+                }
+                None => [0; 4],
+            });
+        }
+    }
+    backdrop_image(data)
+}
+
+/// Drags each layer's pattern along with the camera by `1 - factor` of its movement,
+/// while snapping the sprite itself to whole tiles near the camera, so the finite sprite
+/// always covers the view.
+fn scroll_parallax_layers(
+    camera_transform: Single<&Transform, (With<PixelCamera>, Without<ParallaxLayer>)>,
+    mut layer_q: Query<(&ParallaxLayer, &mut Transform)>,
+) {
+    let camera = camera_transform.translation.truncate();
+    let tile = BACKDROP_TILE as f32;
+    for (layer, mut transform) in layer_q.iter_mut() {
+        let origin = (camera * (1. - layer.factor)).round();
+        let snapped = origin + ((camera - origin) / tile).round() * tile;
+        transform.translation = snapped.extend(transform.translation.z);
+    }
+}
@@ -0,0 +1,233 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    culling::Culled, health::DamageEvent, pixel_perfect::PIXEL_PERFECT_LAYER, pool::EntityPool,
+    projectile::ProjectileHitEvent, state::GameplaySet, transition::RoomScoped,
+};
+
+/// Above characters and pickups, below the HUD.
+const PARTICLE_Z: f32 = 5.;
+
+const BLOOD: ParticleEffect = ParticleEffect {
+    burst: 8,
+    rate: 0.,
+    min_speed: 20.,
+    max_speed: 60.,
+    spread: 0.8,
+    min_lifetime: 0.2,
+    max_lifetime: 0.5,
+    gravity: Vec2::ZERO,
+    drag: 6.,
+    start_color: Color::srgb(0.8, 0.1, 0.1),
+    end_color: Color::srgb(0.3, 0.02, 0.02),
+};
+const IMPACT_SPARKS: ParticleEffect = ParticleEffect {
+    burst: 5,
+    rate: 0.,
+    min_speed: 30.,
+    max_speed: 80.,
+    spread: PI,
+    min_lifetime: 0.1,
+    max_lifetime: 0.25,
+    gravity: Vec2::ZERO,
+    drag: 8.,
+    start_color: Color::srgb(1., 0.9, 0.6),
+    end_color: Color::srgba(1., 0.4, 0.1, 0.),
+};
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnParticles>();
+        app.init_resource::<EntityPool<Particle>>();
+        app.add_systems(
+            Update,
+            (
+                emit_hit_particles,
+                run_emitters,
+                spawn_particle_bursts,
+                update_particles,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// What a particle effect looks like and how its particles move. Every particle is a
+/// single canvas pixel.
+#[derive(Clone, Copy, Debug)]
+pub struct ParticleEffect {
+    /// Particles per `SpawnParticles` burst.
+    pub burst: u32,
+    /// Particles per second from a `ParticleEmitter`.
+    pub rate: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Largest angle from the emission direction, in radians. `PI` sprays all around.
+    pub spread: f32,
+    pub min_lifetime: f32,
+    pub max_lifetime: f32,
+    /// Acceleration in px/s², e.g. upward for rising smoke.
+    pub gravity: Vec2,
+    /// Fraction of its velocity a particle loses per second.
+    pub drag: f32,
+    pub start_color: Color,
+    /// Faded to over the particle's lifetime.
+    pub end_color: Color,
+}
+
+/// Emits a burst of particles once.
+#[derive(Event, Debug)]
+pub struct SpawnParticles {
+    pub effect: ParticleEffect,
+    pub position: Vec2,
+    /// The middle of the spray, as a unit vector.
+    pub direction: Vec2,
+}
+
+/// Emits particles continuously from the entity's position, at the effect's rate.
+#[derive(Component, Debug)]
+pub struct ParticleEmitter {
+    pub effect: ParticleEffect,
+    pub direction: Vec2,
+    /// Fractional particles carried over between frames.
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: ParticleEffect, direction: Vec2) -> Self {
+        Self {
+            effect,
+            direction,
+            pending: 0.,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct Particle {
+    velocity: Vec2,
+    gravity: Vec2,
+    drag: f32,
+    start_color: Color,
+    end_color: Color,
+    lifetime: Timer,
+}
+
+fn emit_hit_particles(
+    mut damage_events: EventReader<DamageEvent>,
+    mut projectile_hit_events: EventReader<ProjectileHitEvent>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    transform_q: Query<&GlobalTransform>,
+) {
+    for event in damage_events.read() {
+        let Ok(transform) = transform_q.get(event.target) else {
+            continue;
+        };
+        particle_events.write(SpawnParticles {
+            effect: BLOOD,
+            position: transform.translation().truncate(),
+            direction: event.knockback.try_normalize().unwrap_or(Vec2::Y),
+        });
+    }
+    for event in projectile_hit_events.read() {
+        particle_events.write(SpawnParticles {
+            effect: IMPACT_SPARKS,
+            position: event.position,
+            direction: Vec2::Y,
+        });
+    }
+}
+
+fn run_emitters(
+    time: Res<Time>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    mut emitter_q: Query<(&mut ParticleEmitter, &GlobalTransform), Without<Culled>>,
+) {
+    for (mut emitter, transform) in emitter_q.iter_mut() {
+        emitter.pending += emitter.effect.rate * time.delta_secs();
+        let count = emitter.pending.floor();
+        if count < 1. {
+            continue;
+        }
+        emitter.pending -= count;
+
+        particle_events.write(SpawnParticles {
+            effect: ParticleEffect {
+                burst: count as u32,
+                ..emitter.effect
+            },
+            position: transform.translation().truncate(),
+            direction: emitter.direction,
+        });
+    }
+}
+
+/// Particles are cosmetic, so they're rolled with the thread's RNG rather than
+/// `GameRng`, leaving seeded runs unaffected by how many were drawn.
+fn spawn_particle_bursts(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Particle>>,
+    mut particle_events: EventReader<SpawnParticles>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for event in particle_events.read() {
+        let effect = &event.effect;
+        for _ in 0..effect.burst {
+            let angle = rng.gen_range(-effect.spread..=effect.spread);
+            let speed = rng.gen_range(effect.min_speed..=effect.max_speed);
+            let lifetime = rng.gen_range(effect.min_lifetime..=effect.max_lifetime);
+
+            pool.spawn(
+                &mut commands,
+                (
+                    Particle {
+                        velocity: Vec2::from_angle(angle).rotate(event.direction) * speed,
+                        gravity: effect.gravity,
+                        drag: effect.drag,
+                        start_color: effect.start_color,
+                        end_color: effect.end_color,
+                        lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+                    },
+                    Name::new("Particle"),
+                    RoomScoped,
+                    Transform::from_translation(event.position.extend(PARTICLE_Z)),
+                    Sprite::from_color(effect.start_color, Vec2::ONE),
+                    PIXEL_PERFECT_LAYER,
+                ),
+            );
+        }
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<EntityPool<Particle>>,
+    mut particle_q: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_secs();
+
+    for (entity, mut particle, mut transform, mut sprite) in particle_q.iter_mut() {
+        if particle.lifetime.tick(time.delta()).finished() {
+            pool.release(&mut commands, entity);
+            continue;
+        }
+
+        let gravity = particle.gravity;
+        let drag = particle.drag;
+        particle.velocity += gravity * dt;
+        particle.velocity *= (1. - drag * dt).max(0.);
+        transform.translation += (particle.velocity * dt).extend(0.);
+
+        sprite.color = particle
+            .start_color
+            .mix(&particle.end_color, particle.lifetime.fraction());
+    }
+}
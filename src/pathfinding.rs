@@ -0,0 +1,339 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+
+use crate::level::{Level, TileChanged};
+
+/// Time between path searches while following a moving goal.
+const REPLAN_SECS: f32 = 0.5;
+/// Distance at which a waypoint counts as reached.
+const WAYPOINT_REACHED_DISTANCE: f32 = 3.;
+/// Tiles this close to a wall are left out, so bodies about a tile and a half wide
+/// don't snag on corners.
+const WALL_CLEARANCE: i32 = 1;
+/// How far around an unwalkable start or goal to look for a walkable tile instead.
+const SNAP_RADIUS: i32 = 2;
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+/// Most tiles a search looks at before giving up, so a goal that can't be reached
+/// doesn't cost a flood of the whole level.
+const MAX_SEARCHED_TILES: usize = 4096;
+
+pub struct PathfindingPlugin;
+
+impl Plugin for PathfindingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>();
+        app.add_systems(
+            Update,
+            build_nav_grid.run_if(resource_changed::<Level>.or(on_event::<TileChanged>)),
+        );
+    }
+}
+
+/// Which level tiles a body can stand on, rebuilt whenever the level or a tile of it
+/// changes. The level's wall colliders are made from the same solid tiles.
+#[derive(Resource, Default, Debug)]
+pub struct NavGrid {
+    width: i32,
+    height: i32,
+    tile_size: f32,
+    /// World position of the grid's bottom-left corner.
+    origin: Vec2,
+    walkable: Vec<bool>,
+}
+
+impl NavGrid {
+    fn from_level(level: &Level) -> Self {
+        let (width, height) = (level.width as i32, level.height as i32);
+        let solid = |x: i32, y: i32| {
+            x < 0
+                || y < 0
+                || x >= width
+                || y >= height
+                || level.get(UVec2::new(x as u32, y as u32)).is_solid()
+        };
+
+        let mut walkable = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let near_wall = (-WALL_CLEARANCE..=WALL_CLEARANCE)
+                    .any(|dy| (-WALL_CLEARANCE..=WALL_CLEARANCE).any(|dx| solid(x + dx, y + dy)));
+                walkable.push(!near_wall);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            tile_size: level.tile_size as f32,
+            origin: level.bounds().min,
+            walkable,
+        }
+    }
+
+    fn is_walkable(&self, tile: IVec2) -> bool {
+        tile.x >= 0
+            && tile.y >= 0
+            && tile.x < self.width
+            && tile.y < self.height
+            && self.walkable[(tile.y * self.width + tile.x) as usize]
+    }
+
+    fn tile_at(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / self.tile_size)
+            .floor()
+            .as_ivec2()
+    }
+
+    fn tile_center(&self, tile: IVec2) -> Vec2 {
+        self.origin + (tile.as_vec2() + 0.5) * self.tile_size
+    }
+
+    /// The walkable tile nearest to `position`, for bodies pressed up against a wall.
+    fn nearest_walkable(&self, position: Vec2) -> Option<IVec2> {
+        let center = self.tile_at(position);
+        (-SNAP_RADIUS..=SNAP_RADIUS)
+            .flat_map(|dy| (-SNAP_RADIUS..=SNAP_RADIUS).map(move |dx| center + IVec2::new(dx, dy)))
+            .filter(|tile| self.is_walkable(*tile))
+            .min_by(|a, b| {
+                let distance = |tile: &IVec2| self.tile_center(*tile).distance_squared(position);
+                distance(a).total_cmp(&distance(b))
+            })
+    }
+
+    /// Whether a body could walk in a straight line between two points.
+    fn is_clear(&self, from: Vec2, to: Vec2) -> bool {
+        let steps = (from.distance(to) / (self.tile_size / 4.)).ceil().max(1.) as u32;
+        (0..=steps).all(|step| {
+            let point = from.lerp(to, step as f32 / steps as f32);
+            self.is_walkable(self.tile_at(point))
+        })
+    }
+
+    /// Waypoints from `from` to `to` around walls, ending at `to`, or `None` when
+    /// there's no way through. Uses A* over the tiles, then drops every waypoint that
+    /// can be skipped by walking straight past it.
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        let start = self.nearest_walkable(from)?;
+        let goal = self.nearest_walkable(to)?;
+        let tiles = self.search(start, goal)?;
+
+        let mut waypoints = Vec::new();
+        let mut anchor = from;
+        let centers: Vec<Vec2> = tiles.iter().map(|tile| self.tile_center(*tile)).collect();
+        for pair in centers.windows(2) {
+            if !self.is_clear(anchor, pair[1]) {
+                waypoints.push(pair[0]);
+                anchor = pair[0];
+            }
+        }
+        waypoints.push(to);
+        Some(waypoints)
+    }
+
+    /// Eight-way A* with an octile distance heuristic. Diagonal steps need both
+    /// neighboring tiles walkable, so paths never cut corners. Gives up after
+    /// `MAX_SEARCHED_TILES`.
+    fn search(&self, start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+        let heuristic = |tile: IVec2| {
+            let delta = (goal - tile).abs();
+            let (short, long) = (delta.min_element() as u32, delta.max_element() as u32);
+            DIAGONAL_COST * short + STRAIGHT_COST * (long - short)
+        };
+
+        let mut open = BinaryHeap::from([Reverse((heuristic(start), start.x, start.y))]);
+        let mut came_from = HashMap::new();
+        let mut cost_so_far = HashMap::from([(start, 0)]);
+        let mut searched = 0;
+
+        while let Some(Reverse((_, x, y))) = open.pop() {
+            searched += 1;
+            if searched > MAX_SEARCHED_TILES {
+                return None;
+            }
+
+            let tile = IVec2::new(x, y);
+            if tile == goal {
+                let mut path = vec![goal];
+                while let Some(&previous) = came_from.get(path.last()?) {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let cost = cost_so_far[&tile];
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let step = IVec2::new(dx, dy);
+                    let next = tile + step;
+                    if step == IVec2::ZERO || !self.is_walkable(next) {
+                        continue;
+                    }
+                    let diagonal = dx != 0 && dy != 0;
+                    if diagonal
+                        && !(self.is_walkable(tile + IVec2::new(dx, 0))
+                            && self.is_walkable(tile + IVec2::new(0, dy)))
+                    {
+                        continue;
+                    }
+
+                    let next_cost = cost
+                        + if diagonal {
+                            DIAGONAL_COST
+                        } else {
+                            STRAIGHT_COST
+                        };
+                    if cost_so_far
+                        .get(&next)
+                        .is_none_or(|&known| next_cost < known)
+                    {
+                        cost_so_far.insert(next, next_cost);
+                        came_from.insert(next, tile);
+                        open.push(Reverse((next_cost + heuristic(next), next.x, next.y)));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Steers a body along a path to a goal, searching again every so often as the goal
+/// moves.
+#[derive(Component, Debug)]
+pub struct PathFollower {
+    /// `None` until there's something to search around. Empty when the last search
+    /// found no way through, which isn't tried again until `replan` comes round.
+    waypoints: Option<Vec<Vec2>>,
+    next: usize,
+    replan: Timer,
+}
+
+impl Default for PathFollower {
+    fn default() -> Self {
+        Self {
+            waypoints: None,
+            next: 0,
+            replan: Timer::from_seconds(REPLAN_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+impl PathFollower {
+    /// Unit vector to head in to reach `goal` from `position`. Heads straight for the
+    /// goal when there's no path, or when nothing is in the way.
+    pub fn direction(
+        &mut self,
+        grid: &NavGrid,
+        position: Vec2,
+        goal: Vec2,
+        delta: Duration,
+    ) -> Vec2 {
+        if grid.is_clear(position, goal) {
+            self.waypoints = None;
+            return (goal - position).normalize_or_zero();
+        }
+
+        if self.waypoints.is_none() || self.replan.tick(delta).just_finished() {
+            self.waypoints = Some(grid.find_path(position, goal).unwrap_or_default());
+            self.next = 0;
+            self.replan.reset();
+        }
+        let waypoints = self.waypoints.as_deref().unwrap_or_default();
+        while let Some(waypoint) = waypoints.get(self.next)
+            && position.distance(*waypoint) <= WAYPOINT_REACHED_DISTANCE
+        {
+            self.next += 1;
+        }
+
+        let target = waypoints.get(self.next).copied().unwrap_or(goal);
+        (target - position).normalize_or_zero()
+    }
+}
+
+fn build_nav_grid(mut commands: Commands, level: Res<Level>) {
+    commands.insert_resource(NavGrid::from_level(&level));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A grid of one-pixel tiles from rows of `.` (walkable) and `#`, top row first.
+    fn grid(rows: &[&str]) -> NavGrid {
+        let height = rows.len() as i32;
+        let width = rows[0].len() as i32;
+        let walkable = rows
+            .iter()
+            .rev()
+            .flat_map(|row| row.chars().map(|tile| tile == '.'))
+            .collect();
+        NavGrid {
+            width,
+            height,
+            tile_size: 1.,
+            origin: Vec2::ZERO,
+            walkable,
+        }
+    }
+
+    #[test]
+    fn path_goes_around_a_wall() {
+        let grid = grid(&[
+            ".........",
+            "....#....",
+            "....#....",
+            "....#....",
+            "....#....",
+        ]);
+        let from = Vec2::new(1.5, 0.5);
+        let to = Vec2::new(7.5, 0.5);
+        assert!(!grid.is_clear(from, to));
+
+        let waypoints = grid
+            .find_path(from, to)
+            .expect("there's a way over the wall");
+        assert_eq!(waypoints.last(), Some(&to));
+        let mut position = from;
+        for waypoint in waypoints {
+            assert!(grid.is_clear(position, waypoint));
+            position = waypoint;
+        }
+    }
+
+    #[test]
+    fn unreachable_goal_has_no_path() {
+        let grid = grid(&[
+            "..........",
+            "..........",
+            ".....#####",
+            ".....#...#",
+            ".....#...#",
+            ".....#####",
+        ]);
+        assert_eq!(
+            grid.find_path(Vec2::new(1.5, 1.5), Vec2::new(7.5, 1.5)),
+            None
+        );
+    }
+
+    #[test]
+    fn failed_search_waits_for_replan() {
+        let grid = grid(&[".....#...", ".....#...", ".....#..."]);
+        let (position, goal) = (Vec2::new(1.5, 1.5), Vec2::new(7.5, 1.5));
+        let mut follower = PathFollower::default();
+
+        follower.direction(&grid, position, goal, Duration::ZERO);
+        assert_eq!(follower.waypoints, Some(Vec::new()));
+        follower.direction(&grid, position, goal, Duration::from_secs_f32(0.1));
+        assert!(!follower.replan.just_finished());
+        assert_eq!(follower.replan.elapsed_secs(), 0.1);
+    }
+}
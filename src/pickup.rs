@@ -0,0 +1,275 @@
+use avian2d::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collider::{GameLayer, collider_shape},
+    flare::FlareInventory,
+    health::{DeathEvent, Health, despawn_dead},
+    inventory::Inventory,
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    rng::GameRng,
+    state::GameplaySet,
+    transition::RoomScoped,
+    weapon::{Equipped, Weapon},
+};
+
+/// Pickups the player can use drift toward them from this close.
+const MAGNET_RADIUS: f32 = 28.;
+/// Speed at the edge of the magnet radius; it doubles by the time a pickup arrives.
+const MAGNET_SPEED: f32 = 60.;
+/// How far from the body loot lands, so several drops don't stack.
+const LOOT_SCATTER: f32 = 6.;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (drop_loot.before(despawn_dead), collect_pickups).in_set(GameplaySet),
+        );
+        app.add_systems(FixedUpdate, attract_pickups.in_set(GameplaySet));
+    }
+}
+
+/// Something lying around for the player to walk over. It's only picked up while the
+/// player has room for it.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Pickup {
+    Health(f32),
+    Flares(u32),
+    /// Rounds for the equipped weapon, or the first other weapon short on ammo.
+    Ammo(u32),
+    /// Unlocks the doors with this link. Only ever kept in the inventory.
+    Key(u32),
+}
+
+impl Pickup {
+    fn image_path(self) -> &'static str {
+        match self {
+            Pickup::Health(_) => "health_pickup.png",
+            Pickup::Flares(_) => "flare_pickup.png",
+            Pickup::Ammo(_) => "ammo_pickup.png",
+            Pickup::Key(_) => "key_pickup.png",
+        }
+    }
+
+    /// Short enough to fit an inventory slot.
+    pub fn label(self) -> &'static str {
+        match self {
+            Pickup::Health(_) => "Medkit",
+            Pickup::Flares(_) => "Flares",
+            Pickup::Ammo(_) => "Ammo",
+            Pickup::Key(_) => "Key",
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Pickup::Health(amount) => format!("Restores {amount:.0} health"),
+            Pickup::Flares(amount) => format!("{amount} flares"),
+            Pickup::Ammo(amount) => format!("{amount} rounds"),
+            Pickup::Key(link) => format!("Opens door {link}"),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Pickup::Health(_) => "Health Pickup",
+            Pickup::Flares(_) => "Flare Pickup",
+            Pickup::Ammo(_) => "Ammo Pickup",
+            Pickup::Key(_) => "Key Pickup",
+        }
+    }
+}
+
+/// Dropped from the inventory. The magnet leaves it alone, and it isn't picked back
+/// up until the player has stepped off it.
+#[derive(Component)]
+pub struct DroppedPickup;
+
+/// Pickups this entity may drop when it dies, each rolled for separately.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LootTable(pub &'static [LootDrop]);
+
+#[derive(Clone, Copy, Debug)]
+pub struct LootDrop {
+    pub pickup: Pickup,
+    /// From 0 to 1.
+    pub chance: f32,
+}
+
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    pickup: Pickup,
+    position: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            pickup,
+            Name::new(pickup.name()),
+            RoomScoped,
+            Transform::from_translation(position.extend(0.)),
+            Sprite::from_image(asset_server.load(pickup.image_path())),
+            // Kinematic so the magnet can move it by velocity.
+            RigidBody::Kinematic,
+            LinearVelocity::ZERO,
+            collider_shape("pickup").bundle(),
+            GameLayer::Pickup.collision_layers(),
+            Sensor,
+            CollidingEntities::default(),
+            PIXEL_PERFECT_LAYER,
+        ))
+        .id()
+}
+
+/// The player's health, flares and ammo, which pickups top up.
+#[derive(SystemParam)]
+pub struct PlayerSupplies<'w, 's> {
+    player: Single<
+        'w,
+        (
+            Entity,
+            &'static Transform,
+            &'static mut Health,
+            &'static mut FlareInventory,
+        ),
+        With<Player>,
+    >,
+    weapon_q: Query<'w, 's, (&'static mut Weapon, &'static ChildOf, Has<Equipped>)>,
+}
+
+impl PlayerSupplies<'_, '_> {
+    pub fn player(&self) -> Entity {
+        self.player.0
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.player.1.translation.truncate()
+    }
+
+    /// Whether the player has room for what `pickup` gives.
+    pub fn wants(&self, pickup: Pickup) -> bool {
+        let (player, _, health, flares) = &*self.player;
+        match pickup {
+            Pickup::Health(_) => health.current < health.max,
+            Pickup::Flares(_) => flares.count < flares.max,
+            Pickup::Ammo(_) => self.weapon_q.iter().any(|(weapon, child_of, _)| {
+                child_of.parent() == *player && is_short_on_ammo(weapon)
+            }),
+            Pickup::Key(_) => false,
+        }
+    }
+
+    /// Tops up what `pickup` is for. Returns false, changing nothing, when the player
+    /// has no room for it.
+    pub fn apply(&mut self, pickup: Pickup) -> bool {
+        if !self.wants(pickup) {
+            return false;
+        }
+
+        let (player, _, health, flares) = &mut *self.player;
+        match pickup {
+            Pickup::Health(amount) => {
+                health.current = (health.current + amount).min(health.max);
+            }
+            Pickup::Flares(amount) => flares.refill(amount),
+            Pickup::Ammo(amount) => {
+                // The equipped weapon first, so the player sees the count go up.
+                let weapon = self
+                    .weapon_q
+                    .iter_mut()
+                    .filter(|(weapon, child_of, _)| {
+                        child_of.parent() == *player && is_short_on_ammo(weapon)
+                    })
+                    .max_by_key(|(_, _, equipped)| *equipped);
+                if let Some((mut weapon, _, _)) = weapon {
+                    let max = weapon.definition.max_ammo.unwrap_or_default();
+                    weapon.ammo = weapon.ammo.map(|ammo| (ammo + amount).min(max));
+                }
+            }
+            Pickup::Key(_) => {}
+        }
+        true
+    }
+}
+
+fn is_short_on_ammo(weapon: &Weapon) -> bool {
+    matches!((weapon.ammo, weapon.definition.max_ammo), (Some(ammo), Some(max)) if ammo < max)
+}
+
+fn drop_loot(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut rng: ResMut<GameRng>,
+    mut death_events: EventReader<DeathEvent>,
+    loot_q: Query<(&LootTable, &Transform)>,
+) {
+    for event in death_events.read() {
+        let Ok((loot_table, transform)) = loot_q.get(event.entity) else {
+            continue;
+        };
+
+        let position = transform.translation.truncate();
+        for drop in loot_table.0 {
+            if rng.gen_bool(drop.chance.clamp(0., 1.) as f64) {
+                let offset = Vec2::new(
+                    rng.gen_range(-LOOT_SCATTER..=LOOT_SCATTER),
+                    rng.gen_range(-LOOT_SCATTER..=LOOT_SCATTER),
+                );
+                spawn_pickup(&mut commands, &asset_server, drop.pickup, position + offset);
+            }
+        }
+    }
+}
+
+fn attract_pickups(
+    supplies: PlayerSupplies,
+    inventory: Res<Inventory>,
+    mut pickup_q: Query<(&Pickup, &Transform, &mut LinearVelocity), Without<DroppedPickup>>,
+) {
+    let player_pos = supplies.position();
+
+    for (pickup, transform, mut velocity) in pickup_q.iter_mut() {
+        let offset = player_pos - transform.translation.truncate();
+        let distance = offset.length();
+        let wanted = supplies.wants(*pickup) || inventory.has_room_for(*pickup);
+        velocity.0 = if distance <= MAGNET_RADIUS && wanted {
+            offset.normalize_or_zero() * MAGNET_SPEED * (2. - distance / MAGNET_RADIUS)
+        } else {
+            Vec2::ZERO
+        };
+    }
+}
+
+/// Used right away when the player has room for it, and kept in the inventory
+/// otherwise. Checks overlaps every frame rather than on collision start, so a pickup
+/// the player is standing on is taken as soon as there's room for it.
+fn collect_pickups(
+    mut commands: Commands,
+    mut supplies: PlayerSupplies,
+    mut inventory: ResMut<Inventory>,
+    pickup_q: Query<(Entity, &Pickup, &CollidingEntities, Has<DroppedPickup>)>,
+) {
+    let player = supplies.player();
+
+    for (pickup_entity, pickup, colliding, dropped) in pickup_q.iter() {
+        if !colliding.contains(&player) {
+            if dropped {
+                commands.entity(pickup_entity).remove::<DroppedPickup>();
+            }
+            continue;
+        }
+        if dropped {
+            continue;
+        }
+
+        if supplies.apply(*pickup) || inventory.add(*pickup) {
+            commands.entity(pickup_entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use bevy::{
+    color::palettes::css::GRAY,
+    ecs::system::SystemParam,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureFormat::Bgra8UnormSrgb, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    window::{PrimaryWindow, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::GameSettings;
+
+/// Internal resolutions the zoom control steps through, from most to least zoomed in.
+pub const ZOOM_LEVELS: [UVec2; 3] = [
+    UVec2::new(128, 80),
+    UVec2::new(192, 120),
+    UVec2::new(256, 160),
+];
+
+pub const PIXEL_PERFECT_LAYER: RenderLayers = RenderLayers::layer(0);
+pub const HIGH_RES_LAYER: RenderLayers = RenderLayers::layer(1);
+
+/// Above everything else on the high-res layer, fades and overlays included.
+const LETTERBOX_Z: f32 = 100.;
+
+pub struct PixelPerfectRenderPlugin;
+
+impl Plugin for PixelPerfectRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PixelCanvasConfig>();
+        app.add_systems(Startup, setup_canvas);
+        app.add_systems(
+            Update,
+            (
+                apply_resolution,
+                resize_canvas,
+                fit_canvas,
+                fit_canvas_text,
+                fit_canvas_sprites,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Text on the high-res layer laid out in canvas pixels. Its font size and position
+/// follow the canvas scale, so it's rasterized at the final size instead of being
+/// upscaled blurry like a transform scale would.
+#[derive(Component, Debug, Clone, Copy)]
+#[require(Text2d, RenderLayers = HIGH_RES_LAYER)]
+pub struct CanvasText {
+    /// Relative to the parent, in canvas pixels.
+    pub position: Vec2,
+    pub font_size: f32,
+}
+
+impl CanvasText {
+    pub fn new(position: Vec2, font_size: f32) -> Self {
+        Self {
+            position,
+            font_size,
+        }
+    }
+}
+
+/// A sprite on the high-res layer laid out and sized in canvas pixels, like
+/// `CanvasText`. Scaled up by the transform, so its texels stay as sharp as the
+/// canvas' own.
+#[derive(Component, Debug, Clone, Copy)]
+#[require(Sprite, RenderLayers = HIGH_RES_LAYER)]
+pub struct CanvasSprite {
+    /// Relative to the parent, in canvas pixels.
+    pub position: Vec2,
+}
+
+/// How the canvas is scaled up to fill the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScalingMode {
+    /// Whole multiples only, so every canvas pixel covers the same number of screen
+    /// pixels. Leaves the widest borders.
+    #[default]
+    Integer,
+    /// As large as fits while keeping the aspect ratio. Fills more of the screen at the
+    /// cost of slightly uneven pixel sizes.
+    Fit,
+    /// Fills the whole window, squashing or stretching the canvas to its aspect ratio.
+    Stretch,
+}
+
+impl ScalingMode {
+    pub const ALL: [ScalingMode; 3] =
+        [ScalingMode::Integer, ScalingMode::Fit, ScalingMode::Stretch];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ScalingMode::Integer => "Integer",
+            ScalingMode::Fit => "Fit",
+            ScalingMode::Stretch => "Stretch",
+        }
+    }
+}
+
+/// Internal resolution of the low-res canvas, in canvas pixels. Follows
+/// `GameSettings::resolution`; changing it recreates the canvas texture.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PixelCanvasConfig {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for PixelCanvasConfig {
+    fn default() -> Self {
+        Self {
+            width: ZOOM_LEVELS[0].x,
+            height: ZOOM_LEVELS[0].y,
+        }
+    }
+}
+
+impl PixelCanvasConfig {
+    pub fn size(&self) -> UVec2 {
+        UVec2::new(self.width, self.height)
+    }
+
+    pub fn size_f32(&self) -> Vec2 {
+        self.size().as_vec2()
+    }
+
+    pub fn extent(&self) -> Extent3d {
+        Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MainCamera;
+
+#[derive(Component)]
+pub struct PixelCamera;
+
+#[derive(Component)]
+pub struct Canvas;
+
+/// Maps between the three spaces a point can be in: the window, in logical pixels from
+/// the top left like `Window::cursor_position`; the canvas, in canvas pixels from its
+/// center with y up, like `CanvasText`; and the world the pixel camera looks at. Reads
+/// global transforms, so it sees the layout as of the last transform propagation.
+#[derive(SystemParam)]
+pub struct CanvasCoords<'w> {
+    window: Single<'w, &'static Window, With<PrimaryWindow>>,
+    main_camera: Single<'w, &'static GlobalTransform, With<MainCamera>>,
+    pixel_camera: Single<'w, &'static GlobalTransform, With<PixelCamera>>,
+    canvas: Single<'w, &'static GlobalTransform, With<Canvas>>,
+}
+
+impl CanvasCoords<'_> {
+    /// Screen pixels per canvas pixel along each axis.
+    pub fn scale(&self) -> Vec2 {
+        self.canvas.scale().truncate()
+    }
+
+    /// Follows the canvas wherever it ends up in the window, letterboxed, shifted by its
+    /// sub-pixel remainder or shaken. Points over the letterbox bars land outside the
+    /// canvas rather than being clamped.
+    pub fn screen_to_canvas(&self, screen: Vec2) -> Vec2 {
+        let from_center = (screen - self.window.size() / 2.) * Vec2::new(1., -1.);
+        let high_res = self.main_camera.translation().truncate() + from_center;
+        (high_res - self.canvas.translation().truncate()) / self.scale()
+    }
+
+    pub fn canvas_to_world(&self, canvas: Vec2) -> Vec2 {
+        self.pixel_camera.translation().truncate() + canvas
+    }
+
+    pub fn world_to_canvas(&self, world: Vec2) -> Vec2 {
+        world - self.pixel_camera.translation().truncate()
+    }
+
+    /// Where a canvas point shows up to the main camera, for drawing over it on the
+    /// high-res layer.
+    pub fn canvas_to_high_res(&self, canvas: Vec2) -> Vec2 {
+        self.canvas.translation().truncate() + canvas * self.scale()
+    }
+}
+
+/// Blacks out one side of the window around the canvas. Children of the main camera,
+/// so they stay put while the canvas shifts by its sub-pixel remainder or shakes.
+#[derive(Component)]
+struct LetterboxBar {
+    /// Points from the canvas towards the window edge the bar covers.
+    side: Vec2,
+}
+
+fn setup_canvas(
+    mut commands: Commands,
+    config: Res<PixelCanvasConfig>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let canvas_size = config.extent();
+
+    let mut canvas_texture = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            mip_level_count: 1,
+            sample_count: 1,
+            size: canvas_size,
+            dimension: bevy::render::render_resource::TextureDimension::D2,
+            format: Bgra8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..Default::default()
+    };
+
+    canvas_texture.resize(canvas_size);
+    let image_handle = images.add(canvas_texture);
+
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: -1,
+            target: RenderTarget::Image(image_handle.clone().into()),
+            clear_color: ClearColorConfig::Custom(GRAY.into()),
+            ..Default::default()
+        },
+        PIXEL_PERFECT_LAYER,
+        PixelCamera,
+    ));
+
+    commands.spawn((Sprite::from_image(image_handle), Canvas, HIGH_RES_LAYER));
+    commands
+        .spawn((Camera2d, Msaa::Off, HIGH_RES_LAYER, MainCamera))
+        .with_children(|parent| {
+            for side in [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y] {
+                parent.spawn((
+                    LetterboxBar { side },
+                    Name::new("Letterbox bar"),
+                    Sprite::from_color(Color::BLACK, Vec2::ZERO),
+                    Transform::from_xyz(0., 0., LETTERBOX_Z),
+                    HIGH_RES_LAYER,
+                ));
+            }
+        });
+}
+
+fn apply_resolution(settings: Res<GameSettings>, mut config: ResMut<PixelCanvasConfig>) {
+    if !settings.is_changed() || config.size() == settings.resolution {
+        return;
+    }
+
+    config.width = settings.resolution.x;
+    config.height = settings.resolution.y;
+}
+
+fn resize_canvas(
+    config: Res<PixelCanvasConfig>,
+    mut images: ResMut<Assets<Image>>,
+    canvas_sprite: Single<&Sprite, With<Canvas>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+
+    // Resizing the asset recreates the GPU texture, and the pixel camera picks up the new
+    // target size from it.
+    if let Some(image) = images.get_mut(&canvas_sprite.image)
+        && image.size() != config.size()
+    {
+        image.resize(config.extent());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn fit_canvas(
+    config: Res<PixelCanvasConfig>,
+    settings: Res<GameSettings>,
+    mut resize_events: EventReader<WindowResized>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut canvas_transform: Single<&mut Transform, (With<Canvas>, Without<LetterboxBar>)>,
+    mut bar_q: Query<(&LetterboxBar, &mut Sprite, &mut Transform)>,
+) {
+    if resize_events.read().last().is_none() && !config.is_changed() && !settings.is_changed() {
+        return;
+    }
+
+    let scale_x = window.width() / config.width as f32;
+    let scale_y = window.height() / config.height as f32;
+    let scale = match settings.scaling_mode {
+        ScalingMode::Integer => Vec2::splat(scale_x.min(scale_y).floor().max(1.)),
+        ScalingMode::Fit => Vec2::splat(scale_x.min(scale_y)),
+        ScalingMode::Stretch => Vec2::new(scale_x, scale_y),
+    };
+
+    canvas_transform.scale = scale.extend(1.);
+
+    // Each bar spans the window along its side and fills the gap between the canvas
+    // and the window edge across it, so the four overlap in the corners.
+    let window_size = window.size();
+    let canvas_size = config.size_f32() * scale;
+    let gap = ((window_size - canvas_size) / 2.).max(Vec2::ZERO);
+    for (bar, mut sprite, mut transform) in bar_q.iter_mut() {
+        let across = bar.side.abs();
+        let along = Vec2::ONE - across;
+        sprite.custom_size = Some(window_size * along + gap * across);
+        transform.translation = (bar.side * (canvas_size + gap) / 2.).extend(LETTERBOX_Z);
+    }
+}
+
+fn fit_canvas_sprites(
+    canvas_transform: Single<&Transform, With<Canvas>>,
+    mut sprite_q: Query<(&CanvasSprite, &mut Transform), Without<Canvas>>,
+) {
+    let scale = canvas_transform.scale.truncate();
+    for (canvas_sprite, mut transform) in sprite_q.iter_mut() {
+        let translation = (canvas_sprite.position * scale).extend(transform.translation.z);
+        if transform.translation != translation || transform.scale.truncate() != scale {
+            transform.translation = translation;
+            transform.scale = scale.extend(1.);
+        }
+    }
+}
+
+fn fit_canvas_text(
+    canvas_transform: Single<&Transform, With<Canvas>>,
+    mut text_q: Query<(&CanvasText, &mut Transform, &mut TextFont), Without<Canvas>>,
+) {
+    // Stretched glyphs would need a transform scale and come out blurry, so text only
+    // grows by the smaller axis.
+    let scale = canvas_transform.scale.truncate();
+    let font_scale = scale.min_element();
+    for (canvas_text, mut transform, mut font) in text_q.iter_mut() {
+        let translation = (canvas_text.position * scale).extend(transform.translation.z);
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+        if font.font_size != canvas_text.font_size * font_scale {
+            font.font_size = canvas_text.font_size * font_scale;
+        }
+    }
+}
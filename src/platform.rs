@@ -0,0 +1,139 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    door::{PressurePlate, Switch},
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    prop::Prop,
+    state::GameplaySet,
+    transition::RoomScoped,
+};
+
+/// In px/s.
+const PLATFORM_SPEED: f32 = 30.;
+/// Over the floor, under everything standing on it.
+const PLATFORM_Z: f32 = -5.;
+
+/// Floor sections that slide along a path, carrying the player and props standing on
+/// them. Linked ones wait for a switch or pressure plate with the same link.
+pub struct PlatformPlugin;
+
+impl Plugin for PlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_level_platforms.in_set(MarkerSpawnSet));
+        app.add_systems(
+            FixedUpdate,
+            (move_platforms, carry_riders).chain().in_set(GameplaySet),
+        );
+    }
+}
+
+/// A kinematic body that travels between `waypoints` in order, then back to the first.
+#[derive(Component, Debug)]
+pub struct MovingPlatform {
+    pub waypoints: Vec<Vec2>,
+    /// Index of the waypoint it's heading for.
+    pub next: usize,
+    pub size: Vec2,
+    /// Stays put until the switch or pressure plate with this link is used.
+    pub link: Option<u32>,
+}
+
+/// Platforms start at the start of their paths.
+fn spawn_level_platforms(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        let MarkerKind::Platform { link, size, path } = &marker.kind else {
+            continue;
+        };
+
+        let size = size.as_vec2();
+        let waypoints = std::iter::once(marker.position)
+            .chain(path.iter().map(|offset| marker.position + offset.as_vec2()))
+            .collect();
+        commands.spawn((
+            MovingPlatform {
+                waypoints,
+                next: 0,
+                size,
+                link: *link,
+            },
+            Name::new("Moving platform"),
+            RoomScoped,
+            Transform::from_translation(marker.position.extend(PLATFORM_Z)),
+            Sprite {
+                image: asset_server.load("platform.png"),
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            // Moved by velocity, so physics knows how fast it's going. It has no
+            // collider: what rides it is found by position.
+            RigidBody::Kinematic,
+            LinearVelocity::ZERO,
+            PIXEL_PERFECT_LAYER,
+        ));
+    }
+}
+
+/// Heads for the next waypoint at a speed that lands exactly on it, rather than
+/// overshooting, and turns for the one after once there.
+fn move_platforms(
+    time: Res<Time>,
+    switch_q: Query<&Switch>,
+    plate_q: Query<&PressurePlate>,
+    mut platform_q: Query<(&mut MovingPlatform, &Position, &mut LinearVelocity)>,
+) {
+    let dt = time.delta_secs();
+    if dt == 0. {
+        return;
+    }
+
+    for (mut platform, position, mut velocity) in platform_q.iter_mut() {
+        let powered = platform.link.is_none_or(|link| {
+            switch_q
+                .iter()
+                .any(|switch| switch.link == link && switch.on)
+                || plate_q
+                    .iter()
+                    .any(|plate| plate.link == link && plate.pressed)
+        });
+        if !powered || platform.waypoints.len() < 2 {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
+
+        let mut offset = platform.waypoints[platform.next] - position.0;
+        if offset.length() < 0.01 {
+            platform.next = (platform.next + 1) % platform.waypoints.len();
+            offset = platform.waypoints[platform.next] - position.0;
+        }
+        velocity.0 = offset.clamp_length_max(PLATFORM_SPEED * dt) / dt;
+    }
+}
+
+/// Moves whatever stands on a platform by as much as the platform moves this step, on
+/// top of its own movement.
+#[allow(clippy::type_complexity)]
+fn carry_riders(
+    time: Res<Time>,
+    platform_q: Query<(&MovingPlatform, &Position, &LinearVelocity)>,
+    mut rider_q: Query<&mut Position, (Or<(With<Player>, With<Prop>)>, Without<MovingPlatform>)>,
+) {
+    for (platform, platform_position, velocity) in platform_q.iter() {
+        if velocity.0 == Vec2::ZERO {
+            continue;
+        }
+
+        let area = Rect::from_center_size(platform_position.0, platform.size);
+        for mut position in rider_q.iter_mut() {
+            if area.contains(position.0) {
+                position.0 += velocity.0 * time.delta_secs();
+            }
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    ai::NoiseEvent,
+    animation::{DirectionalSprite, SpriteAnimation},
+    audio::{PlaySfx, Sfx},
+    camera::MouseWorldPos,
+    collider::{GameLayer, collider_shape},
+    dash::{DashCooldown, Dashing},
+    debug::debug_render,
+    flare::FlareInventory,
+    force_zone::SurfaceVelocity,
+    grapple::GrappleCooldown,
+    grenade::GrenadeInventory,
+    hazard::Falling,
+    health::Health,
+    input::PlayerInput,
+    level::LevelMarkers,
+    lighting::Light2d,
+    melee::MeleeAttack,
+    outline::Outlined,
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    stamina::Stamina,
+    state::{GameScoped, GameplaySet, NewGame, PlayerControlSet},
+    status::StatusEffects,
+    vision::VisionCone,
+};
+
+const PLAYER_MOVEMENT: MovementConfig = MovementConfig {
+    walk_speed: 100.,
+    sprint_speed: 160.,
+    acceleration: 900.,
+    deceleration: 1200.,
+    friction: 250.,
+};
+/// Distance covered per footstep sound.
+const FOOTSTEP_STRIDE: f32 = 14.;
+/// How far away enemies hear footsteps while sprinting. Walking is silent to them.
+const SPRINT_FOOTSTEP_LOUDNESS: f32 = 90.;
+/// Below this speed the player is standing still, even if knockback is still
+/// settling.
+const WALKING_MIN_SPEED: f32 = 20.;
+const PLAYER_FRAME_SIZE: UVec2 = UVec2::splat(20);
+/// Idle frames, then walk frames, in each direction's row.
+const PLAYER_FRAMES_PER_DIRECTION: usize = 6;
+const PLAYER_DIRECTIONS: usize = 8;
+const PLAYER_LIGHT: Light2d = Light2d {
+    radius: 18.,
+    intensity: 0.5,
+};
+const PLAYER_VISION: VisionCone = VisionCone {
+    range: 110.,
+    half_angle: 0.7,
+    near_radius: PLAYER_LIGHT.radius,
+};
+/// Subtle, just enough to keep the player from blending into dark tiles.
+const PLAYER_OUTLINE: Outlined = Outlined {
+    color: Color::srgba(0.02, 0.02, 0.05, 0.7),
+};
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(NewGame, spawn_player);
+        app.add_systems(
+            FixedUpdate,
+            move_player.in_set(PlayerControlSet).in_set(GameplaySet),
+        );
+        app.add_systems(
+            Update,
+            (
+                face_mouse.in_set(PlayerControlSet),
+                play_footsteps,
+                animate_player,
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Player;
+
+/// How the player gets up to speed and comes to a stop. Speeds are in px/s and the
+/// rates in px/s².
+#[derive(Component, Clone, Copy, Debug)]
+pub struct MovementConfig {
+    pub walk_speed: f32,
+    pub sprint_speed: f32,
+    /// Toward the direction being held.
+    pub acceleration: f32,
+    /// Down to a stop once nothing is held.
+    pub deceleration: f32,
+    /// Back down to top speed when going faster, like after knockback. Low, so outside
+    /// pushes carry even while the player is steering.
+    pub friction: f32,
+}
+
+/// How an entity turns toward the mouse.
+// Nothing rotates yet; turrets will.
+#[allow(dead_code)]
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Aim)]
+pub enum FaceMouse {
+    /// Rotates the whole transform, for turret-like entities whose sprite can spin
+    /// freely.
+    Rotate,
+    /// Keeps the transform upright and turns a `DirectionalSprite` instead, so
+    /// characters stay the right way up and their colliders don't turn.
+    Directional,
+}
+
+/// Which way an entity is aiming, as a unit vector. Kept from the last frame when the
+/// mouse is right on top of the entity.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Aim(pub Vec2);
+
+impl Default for Aim {
+    fn default() -> Self {
+        Self(Vec2::X)
+    }
+}
+
+/// Distance walked since the last footstep sound.
+#[derive(Component, Default)]
+struct Footsteps {
+    distance: f32,
+}
+
+fn idle_animation() -> SpriteAnimation {
+    SpriteAnimation::new(0, 1, 2., true)
+}
+
+fn walk_animation() -> SpriteAnimation {
+    SpriteAnimation::new(2, 5, 10., true)
+}
+
+fn spawn_player(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    markers: Res<LevelMarkers>,
+) {
+    let layout = TextureAtlasLayout::from_grid(
+        PLAYER_FRAME_SIZE,
+        PLAYER_FRAMES_PER_DIRECTION as u32,
+        PLAYER_DIRECTIONS as u32,
+        None,
+        None,
+    );
+
+    commands.spawn((
+        Transform::from_translation(markers.player_start().extend(0.)),
+        Sprite::from_atlas_image(
+            asset_server.load("player_sheet.png"),
+            TextureAtlas {
+                layout: atlas_layouts.add(layout),
+                index: 0,
+            },
+        ),
+        idle_animation(),
+        DirectionalSprite::new(PLAYER_DIRECTIONS, PLAYER_FRAMES_PER_DIRECTION),
+        Name::new("Player"),
+        Player,
+        GameScoped,
+        FaceMouse::Directional,
+        Footsteps::default(),
+        debug_render(Color::srgb(1.0, 0.0, 0.0)),
+        PIXEL_PERFECT_LAYER,
+        (PLAYER_LIGHT, PLAYER_VISION, PLAYER_OUTLINE),
+        (
+            RigidBody::Dynamic,
+            collider_shape("player").bundle(),
+            GameLayer::Player.collision_layers(),
+            LinearVelocity::ZERO,
+            LockedAxes::ROTATION_LOCKED,
+            ExternalImpulse::default(),
+            MaxLinearSpeed(400.),
+            PLAYER_MOVEMENT,
+        ),
+        (
+            FlareInventory::new(5, 8),
+            GrenadeInventory::new(3, 5),
+            Health::new(100.),
+            MeleeAttack::new(15., 180., 0.4),
+            DashCooldown::new(0.8),
+            GrappleCooldown::new(1.2),
+            Stamina::new(100.),
+            StatusEffects::default(),
+            SurfaceVelocity::default(),
+        ),
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+fn move_player(
+    time: Res<Time>,
+    input: Res<PlayerInput>,
+    player: Single<
+        (
+            &mut LinearVelocity,
+            &MovementConfig,
+            &SurfaceVelocity,
+            Option<&Stamina>,
+        ),
+        (With<Player>, Without<Dashing>, Without<Falling>),
+    >,
+) {
+    let (mut velocity, movement, surface, stamina) = player.into_inner();
+    let speed = if stamina.is_some_and(|stamina| stamina.sprinting) {
+        movement.sprint_speed
+    } else {
+        movement.walk_speed
+    };
+
+    // Speeds are measured against the ground, which may be moving itself.
+    let desired = input.movement() * speed + surface.0;
+    let rate = if (velocity.0 - surface.0).length() > speed {
+        movement.friction
+    } else if desired == Vec2::ZERO {
+        movement.deceleration
+    } else {
+        movement.acceleration
+    };
+    velocity.0 = velocity.0.move_towards(desired, rate * time.delta_secs());
+}
+
+#[allow(clippy::type_complexity)]
+fn play_footsteps(
+    time: Res<Time>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    player: Single<
+        (&Transform, &LinearVelocity, &MovementConfig, &mut Footsteps),
+        (With<Player>, Without<Dashing>),
+    >,
+) {
+    let (transform, velocity, movement, mut footsteps) = player.into_inner();
+    let speed = velocity.length();
+    if speed < WALKING_MIN_SPEED {
+        // The next step sounds as soon as the player starts moving again.
+        footsteps.distance = FOOTSTEP_STRIDE;
+        return;
+    }
+
+    footsteps.distance += speed * time.delta_secs();
+    if footsteps.distance >= FOOTSTEP_STRIDE {
+        footsteps.distance %= FOOTSTEP_STRIDE;
+        sfx_events.write(PlaySfx::new(Sfx::Footstep));
+        if speed > movement.walk_speed {
+            noise_events.write(NoiseEvent {
+                position: transform.translation.truncate(),
+                loudness: SPRINT_FOOTSTEP_LOUDNESS,
+            });
+        }
+    }
+}
+
+fn animate_player(player: Single<(&LinearVelocity, &mut SpriteAnimation), With<Player>>) {
+    let (velocity, mut animation) = player.into_inner();
+    let wanted = if velocity.length() < WALKING_MIN_SPEED {
+        idle_animation()
+    } else {
+        walk_animation()
+    };
+
+    if !animation.plays_same_frames(&wanted) {
+        *animation = wanted;
+    }
+}
+
+fn face_mouse(
+    mouse_world_pos: Res<MouseWorldPos>,
+    mut facing_q: Query<(
+        &FaceMouse,
+        &mut Aim,
+        &mut Transform,
+        Option<&mut DirectionalSprite>,
+    )>,
+) {
+    for (face_mouse, mut aim, mut transform, sprite) in facing_q.iter_mut() {
+        if let Some(direction) =
+            (mouse_world_pos.0 - transform.translation.truncate()).try_normalize()
+        {
+            aim.0 = direction;
+        }
+
+        match face_mouse {
+            FaceMouse::Rotate => transform.rotation = Quat::from_rotation_z(aim.0.to_angle()),
+            FaceMouse::Directional => {
+                if let Some(mut sprite) = sprite {
+                    sprite.face(aim.0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::{entity_disabling::Disabled, system::EntityCommands},
+    prelude::*,
+};
+
+/// Finished entities of one kind, marked by the component `T`, kept for reuse instead
+/// of despawned. Pooled entities lose `T` and are disabled, which hides them from
+/// queries, so they sit out of gameplay, physics, rendering and room teardown until
+/// they're handed out again.
+#[derive(Resource)]
+pub struct EntityPool<T: Component> {
+    free: Vec<Entity>,
+    kind: PhantomData<T>,
+}
+
+impl<T: Component> Default for EntityPool<T> {
+    fn default() -> Self {
+        Self {
+            free: Vec::new(),
+            kind: PhantomData,
+        }
+    }
+}
+
+impl<T: Component> EntityPool<T> {
+    /// Reuses a pooled entity if there is one, overwriting its components with
+    /// `bundle`, which should include `T`. Components the bundle doesn't have are
+    /// left over from last time.
+    pub fn spawn<'a>(
+        &mut self,
+        commands: &'a mut Commands,
+        bundle: impl Bundle,
+    ) -> EntityCommands<'a> {
+        while let Some(entity) = self.free.pop() {
+            // Skips entities that were despawned after being pooled, like by a room
+            // change in the same frame.
+            if commands.get_entity(entity).is_ok() {
+                let mut entity_commands = commands.entity(entity);
+                entity_commands.insert(bundle).remove::<Disabled>();
+                return entity_commands;
+            }
+        }
+        commands.spawn(bundle)
+    }
+
+    /// Takes the place of despawning. Releasing an entity twice only pools it once.
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        if self.free.contains(&entity) {
+            return;
+        }
+        commands.entity(entity).remove::<T>().insert(Disabled);
+        self.free.push(entity);
+    }
+}
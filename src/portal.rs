@@ -0,0 +1,278 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    flare::Flare,
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    lighting::Light2d,
+    particle::{ParticleEffect, SpawnParticles},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    state::{GameState, GameplaySet},
+    transition::RoomScoped,
+};
+
+/// How close flares have to be, for portals that don't say.
+pub const DEFAULT_PORTAL_RADIUS: u32 = 32;
+/// Seconds to charge fully with enough flares around.
+const CHARGE_SECS: f32 = 3.;
+/// Seconds to lose a full charge once there aren't.
+const DRAIN_SECS: f32 = 6.;
+/// How long a portal stays open, including the time it spends closing.
+const OPEN_SECS: f32 = 10.;
+/// The end of the open time, when the portal flickers to warn it's about to shut.
+const CLOSING_SECS: f32 = 3.;
+const CLOSING_FLICKER_HZ: f32 = 6.;
+/// The player goes through once this close to the middle.
+const ENTER_RADIUS: f32 = 6.;
+const PORTAL_Z: f32 = -4.;
+const DORMANT_COLOR: Color = Color::srgb(0.3, 0.3, 0.35);
+const CHARGING_COLOR: Color = Color::srgb(0.55, 0.6, 0.9);
+const OPEN_COLOR: Color = Color::srgb(0.7, 0.9, 1.);
+/// Under the portal, showing its charge.
+const METER_SIZE: Vec2 = Vec2::new(16., 2.);
+const METER_OFFSET: Vec2 = Vec2::new(0., -12.);
+const METER_BACKGROUND: Color = Color::srgba(0., 0., 0., 0.6);
+const METER_FILL: Color = Color::srgb(1., 0.8, 0.4);
+const PORTAL_LIGHT: Light2d = Light2d {
+    radius: 48.,
+    intensity: 1.,
+};
+const PORTAL_BURST: ParticleEffect = ParticleEffect {
+    burst: 32,
+    rate: 0.,
+    min_speed: 20.,
+    max_speed: 60.,
+    spread: std::f32::consts::PI,
+    min_lifetime: 0.3,
+    max_lifetime: 0.6,
+    gravity: Vec2::ZERO,
+    drag: 2.,
+    start_color: Color::srgb(0.8, 0.95, 1.),
+    end_color: Color::srgba(0.4, 0.5, 1., 0.),
+};
+
+/// Exits that stay shut until enough flares burn around them at once. That charges
+/// them up, and once full they open for a while for the player to walk through, which
+/// wins the game.
+pub struct PortalPlugin;
+
+impl Plugin for PortalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_level_portals.in_set(MarkerSpawnSet),
+                (charge_portals, enter_portals, show_portal_states)
+                    .chain()
+                    .in_set(GameplaySet),
+            ),
+        );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Portal {
+    /// How many flares have to burn within `radius` at once to charge it.
+    pub flares_required: u32,
+    pub radius: f32,
+    /// The flares within `radius` right now.
+    pub flares_in_range: u32,
+    /// From 0 to 1, opening it when full.
+    pub charge: f32,
+    pub state: PortalState,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortalState {
+    Dormant,
+    Charging,
+    Open(Timer),
+    /// Still open, for the rest of its time.
+    Closing(Timer),
+}
+
+impl Portal {
+    pub fn is_open(&self) -> bool {
+        matches!(self.state, PortalState::Open(_) | PortalState::Closing(_))
+    }
+
+    /// Whole seconds until it shuts, while it's open.
+    pub fn secs_left(&self) -> Option<u32> {
+        match &self.state {
+            PortalState::Open(timer) => Some((timer.remaining_secs() + CLOSING_SECS).ceil() as u32),
+            PortalState::Closing(timer) => Some(timer.remaining_secs().ceil() as u32),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct PortalMeter;
+
+#[derive(Component)]
+struct PortalMeterFill;
+
+/// Portals start out dormant.
+fn spawn_level_portals(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        let MarkerKind::Portal { flares, radius } = marker.kind else {
+            continue;
+        };
+
+        commands
+            .spawn((
+                Portal {
+                    flares_required: flares,
+                    radius: radius as f32,
+                    flares_in_range: 0,
+                    charge: 0.,
+                    state: PortalState::Dormant,
+                },
+                Name::new("Portal"),
+                RoomScoped,
+                Transform::from_translation(marker.position.extend(PORTAL_Z)),
+                Sprite {
+                    color: DORMANT_COLOR,
+                    ..Sprite::from_image(asset_server.load("portal.png"))
+                },
+                PIXEL_PERFECT_LAYER,
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn((
+                        PortalMeter,
+                        Sprite::from_color(METER_BACKGROUND, METER_SIZE),
+                        Transform::from_translation(METER_OFFSET.extend(0.1)),
+                        Visibility::Hidden,
+                        PIXEL_PERFECT_LAYER,
+                    ))
+                    .with_child((
+                        PortalMeterFill,
+                        Sprite {
+                            color: METER_FILL,
+                            custom_size: Some(Vec2::new(0., METER_SIZE.y)),
+                            anchor: Anchor::CenterLeft,
+                            ..Default::default()
+                        },
+                        Transform::from_xyz(-METER_SIZE.x / 2., 0., 0.1),
+                        PIXEL_PERFECT_LAYER,
+                    ));
+            });
+    }
+}
+
+/// Flares are counted afresh every frame, so ones that burn out or are picked up by the
+/// pool mid-charge simply stop counting.
+fn charge_portals(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    flare_q: Query<&Transform, With<Flare>>,
+    mut portal_q: Query<(Entity, &Transform, &mut Portal)>,
+) {
+    for (entity, transform, mut portal) in portal_q.iter_mut() {
+        let position = transform.translation.truncate();
+        portal.flares_in_range = flare_q
+            .iter()
+            .filter(|flare| flare.translation.truncate().distance(position) <= portal.radius)
+            .count() as u32;
+
+        let portal = &mut *portal;
+        match &mut portal.state {
+            PortalState::Dormant | PortalState::Charging => {
+                if portal.flares_in_range >= portal.flares_required {
+                    portal.charge += time.delta_secs() / CHARGE_SECS;
+                } else {
+                    portal.charge -= time.delta_secs() / DRAIN_SECS;
+                }
+                portal.charge = portal.charge.clamp(0., 1.);
+
+                if portal.charge >= 1. {
+                    portal.state = PortalState::Open(Timer::from_seconds(
+                        OPEN_SECS - CLOSING_SECS,
+                        TimerMode::Once,
+                    ));
+                    commands.entity(entity).insert(PORTAL_LIGHT);
+                    particle_events.write(SpawnParticles {
+                        effect: PORTAL_BURST,
+                        position,
+                        direction: Vec2::Y,
+                    });
+                    info!("portal opened");
+                } else if portal.charge > 0. {
+                    portal.state = PortalState::Charging;
+                } else {
+                    portal.state = PortalState::Dormant;
+                }
+            }
+            PortalState::Open(timer) => {
+                if timer.tick(time.delta()).finished() {
+                    portal.state =
+                        PortalState::Closing(Timer::from_seconds(CLOSING_SECS, TimerMode::Once));
+                }
+            }
+            PortalState::Closing(timer) => {
+                if timer.tick(time.delta()).finished() {
+                    portal.state = PortalState::Dormant;
+                    portal.charge = 0.;
+                    commands.entity(entity).remove::<Light2d>();
+                    info!("portal closed");
+                }
+            }
+        }
+    }
+}
+
+fn enter_portals(
+    player_transform: Single<&Transform, With<Player>>,
+    portal_q: Query<(&Transform, &Portal)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let player_pos = player_transform.translation.truncate();
+    let entered = portal_q.iter().any(|(transform, portal)| {
+        portal.is_open() && transform.translation.truncate().distance(player_pos) <= ENTER_RADIUS
+    });
+    if entered {
+        info!("went through the portal");
+        next_state.set(GameState::Victory);
+    }
+}
+
+fn show_portal_states(
+    time: Res<Time>,
+    mut portal_q: Query<(&Portal, &mut Sprite, &Children)>,
+    mut meter_q: Query<(&mut Visibility, &Children), With<PortalMeter>>,
+    mut fill_q: Query<&mut Sprite, (With<PortalMeterFill>, Without<Portal>)>,
+) {
+    for (portal, mut sprite, children) in portal_q.iter_mut() {
+        sprite.color = match portal.state {
+            PortalState::Dormant => DORMANT_COLOR,
+            PortalState::Charging => DORMANT_COLOR.mix(&CHARGING_COLOR, portal.charge),
+            PortalState::Open(_) => OPEN_COLOR,
+            PortalState::Closing(_) => {
+                let flicker = (time.elapsed_secs() * CLOSING_FLICKER_HZ).fract() < 0.5;
+                if flicker { OPEN_COLOR } else { CHARGING_COLOR }
+            }
+        };
+
+        for child in children {
+            let Ok((mut visibility, meter_children)) = meter_q.get_mut(*child) else {
+                continue;
+            };
+            *visibility = if portal.state == PortalState::Charging {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            for meter_child in meter_children {
+                if let Ok(mut fill) = fill_q.get_mut(*meter_child) {
+                    fill.custom_size = Some(METER_SIZE * Vec2::new(portal.charge, 1.));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pixel_perfect::{Canvas, HIGH_RES_LAYER, PixelCanvasConfig},
+    settings::GameSettings,
+};
+
+const CANVAS_SHADER_PATH: &str = "shaders/canvas.wgsl";
+/// Cycles the palette at runtime, for trying out moods. Not saved; the settings menu
+/// is where the palette sticks.
+const PALETTE_KEY: KeyCode = KeyCode::F7;
+/// How far the corners bulge out, as a fraction of the canvas.
+const CURVATURE: f32 = 0.04;
+/// How much darker the gaps between scanlines are, from 0 to 1.
+const SCANLINE_STRENGTH: f32 = 0.35;
+/// How much darker the corners are than the center, from 0 to 1.
+const VIGNETTE_STRENGTH: f32 = 0.4;
+
+/// Optional effects for the upscaled canvas, switched on in the settings: a CRT look,
+/// and limiting the colors to a palette. A quad drawing the canvas texture through the
+/// canvas shader sits just in front of the canvas sprite and follows it around. It's
+/// hidden while no effect is on.
+pub struct PostProcessPlugin;
+
+impl Plugin for PostProcessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<CanvasMaterial>::default());
+        app.add_systems(Startup, load_palettes);
+        // The canvas is spawned during startup.
+        app.add_systems(PostStartup, spawn_canvas_screen);
+        app.add_systems(Update, (cycle_palette, apply_canvas_effects).chain());
+    }
+}
+
+/// Colors the canvas can be limited to. Each is a one-pixel-high image with one
+/// pixel per color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Palette {
+    /// Every color as rendered.
+    #[default]
+    Full,
+    /// PICO-8's 16 colors.
+    Pico8,
+    /// 32 colors in four cold-to-warm ramps.
+    Twilight,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Full, Palette::Pico8, Palette::Twilight];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Palette::Full => "Full",
+            Palette::Pico8 => "PICO-8",
+            Palette::Twilight => "Twilight",
+        }
+    }
+
+    fn path(self) -> Option<&'static str> {
+        match self {
+            Palette::Full => None,
+            Palette::Pico8 => Some("palettes/pico8.png"),
+            Palette::Twilight => Some("palettes/twilight.png"),
+        }
+    }
+}
+
+/// The uniform fields make up the shader's `CanvasSettings` struct, in order.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+struct CanvasMaterial {
+    #[uniform(0)]
+    canvas_size: Vec2,
+    #[uniform(0)]
+    curvature: f32,
+    #[uniform(0)]
+    scanline_strength: f32,
+    #[uniform(0)]
+    vignette_strength: f32,
+    /// 0 while there's no palette, or it hasn't loaded yet.
+    #[uniform(0)]
+    palette_size: u32,
+    #[uniform(0)]
+    crt: u32,
+    #[texture(1)]
+    #[sampler(2)]
+    canvas: Handle<Image>,
+    #[texture(3)]
+    palette: Option<Handle<Image>>,
+}
+
+impl Material2d for CanvasMaterial {
+    fn fragment_shader() -> ShaderRef {
+        CANVAS_SHADER_PATH.into()
+    }
+
+    // Sorted by depth along with the canvas sprite, rather than drawn before it.
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+#[derive(Component)]
+struct CanvasScreen;
+
+/// Loaded up front so swapping palettes doesn't flash unquantized colors.
+#[derive(Resource)]
+struct PaletteHandles(HashMap<Palette, Handle<Image>>);
+
+fn load_palettes(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = Palette::ALL
+        .into_iter()
+        .filter_map(|palette| Some((palette, asset_server.load(palette.path()?))))
+        .collect();
+    commands.insert_resource(PaletteHandles(handles));
+}
+
+fn spawn_canvas_screen(
+    mut commands: Commands,
+    config: Res<PixelCanvasConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CanvasMaterial>>,
+    canvas: Single<(Entity, &Sprite), With<Canvas>>,
+) {
+    let (canvas_entity, canvas_sprite) = *canvas;
+    let material = CanvasMaterial {
+        canvas_size: config.size_f32(),
+        curvature: CURVATURE,
+        scanline_strength: SCANLINE_STRENGTH,
+        vignette_strength: VIGNETTE_STRENGTH,
+        palette_size: 0,
+        crt: 0,
+        canvas: canvas_sprite.image.clone(),
+        palette: None,
+    };
+
+    commands.entity(canvas_entity).with_child((
+        CanvasScreen,
+        Name::new("Canvas screen"),
+        Mesh2d(meshes.add(Rectangle::new(1., 1.))),
+        MeshMaterial2d(materials.add(material)),
+        // Scaled up to the canvas size in canvas pixels, like the sprite it covers.
+        Transform::from_scale(config.size_f32().extend(1.)).with_translation(Vec3::Z * 0.01),
+        Visibility::Hidden,
+        HIGH_RES_LAYER,
+    ));
+}
+
+fn cycle_palette(keyboard_input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<GameSettings>) {
+    if !keyboard_input.just_pressed(PALETTE_KEY) {
+        return;
+    }
+
+    let index = Palette::ALL
+        .iter()
+        .position(|palette| *palette == settings.palette)
+        .unwrap_or(0);
+    settings.palette = Palette::ALL[(index + 1) % Palette::ALL.len()];
+    info!("palette {}", settings.palette.label());
+}
+
+/// Checked every frame rather than on settings changes, since the palette's size is
+/// only known once its image has loaded.
+fn apply_canvas_effects(
+    settings: Res<GameSettings>,
+    config: Res<PixelCanvasConfig>,
+    palettes: Res<PaletteHandles>,
+    images: Res<Assets<Image>>,
+    mut materials: ResMut<Assets<CanvasMaterial>>,
+    screen: Single<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &MeshMaterial2d<CanvasMaterial>,
+        ),
+        With<CanvasScreen>,
+    >,
+) {
+    let (mut transform, mut visibility, material_handle) = screen.into_inner();
+
+    let palette = palettes.0.get(&settings.palette);
+    let palette_size = palette
+        .and_then(|handle| images.get(handle))
+        .map_or(0, |image| image.width());
+    let crt = u32::from(settings.crt_effect);
+    let canvas_size = config.size_f32();
+
+    let wanted_visibility = if crt != 0 || palette_size != 0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != wanted_visibility {
+        *visibility = wanted_visibility;
+    }
+    if transform.scale.truncate() != canvas_size {
+        transform.scale = canvas_size.extend(1.);
+    }
+
+    // Only touched when something differs, since any change re-uploads the material.
+    let Some(material) = materials.get(&material_handle.0) else {
+        return;
+    };
+    if material.canvas_size == canvas_size
+        && material.palette_size == palette_size
+        && material.crt == crt
+        && material.palette.as_ref() == palette
+    {
+        return;
+    }
+    if let Some(material) = materials.get_mut(&material_handle.0) {
+        material.canvas_size = canvas_size;
+        material.palette_size = palette_size;
+        material.crt = crt;
+        material.palette = palette.cloned();
+    }
+}
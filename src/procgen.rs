@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use rand::{Rng, RngCore, seq::SliceRandom};
+
+use crate::{
+    level::{
+        Level, LevelExits, LevelHandle, LevelMarker, LevelMarkers, LevelSource, MarkerKind,
+        TileKind,
+    },
+    player::Player,
+    rng::GameRng,
+};
+
+/// Generates a new level in place of the current one, even mid-game.
+const REROLL_KEY: KeyCode = KeyCode::F4;
+const GENERATED_SIZE: UVec2 = UVec2::new(64, 48);
+const ROOM_ATTEMPTS: u32 = 60;
+const MAX_ROOMS: usize = 9;
+const ROOM_MIN_SIDE: u32 = 6;
+const ROOM_MAX_SIDE: u32 = 14;
+/// Tiles of wall kept between neighbouring rooms.
+const ROOM_SPACING: u32 = 2;
+/// Odd, so corridors can be centered on a tile. Three tiles fit the player's and
+/// enemies' colliders with some room to spare.
+const CORRIDOR_WIDTH: u32 = 3;
+const FLARE_PICKUPS: usize = 3;
+
+pub struct ProcgenPlugin;
+
+impl Plugin for ProcgenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, reroll_level);
+    }
+}
+
+/// Rooms connected by corridors, each to the one placed before it, and walled in.
+/// The player starts in the first room, an enemy spawns in each of the others, and
+/// flare pickups are scattered through random rooms.
+pub fn generate_level(rng: &mut impl Rng, size: UVec2) -> (Level, LevelMarkers) {
+    let mut level = Level::new(size.x, size.y);
+    let mut rooms: Vec<URect> = Vec::new();
+    for _ in 0..ROOM_ATTEMPTS {
+        if rooms.len() == MAX_ROOMS {
+            break;
+        }
+
+        let room_size = UVec2::new(
+            rng.gen_range(ROOM_MIN_SIDE..=ROOM_MAX_SIDE),
+            rng.gen_range(ROOM_MIN_SIDE..=ROOM_MAX_SIDE),
+        );
+        // Leave a tile on every side of the map for the outer walls.
+        let min = UVec2::new(
+            rng.gen_range(1..size.x - room_size.x),
+            rng.gen_range(1..size.y - room_size.y),
+        );
+        let room = URect::from_corners(min, min + room_size);
+        let overlaps = rooms.iter().any(|other| {
+            !room
+                .inflate(ROOM_SPACING as i32)
+                .intersect(*other)
+                .is_empty()
+        });
+        if !overlaps {
+            rooms.push(room);
+        }
+    }
+
+    for room in &rooms {
+        carve_floor(&mut level, *room);
+    }
+    for pair in rooms.windows(2) {
+        let (from, to) = (pair[0].center(), pair[1].center());
+        let corner = if rng.gen_bool(0.5) {
+            UVec2::new(to.x, from.y)
+        } else {
+            UVec2::new(from.x, to.y)
+        };
+        carve_corridor(&mut level, from, corner);
+        carve_corridor(&mut level, corner, to);
+    }
+    surround_with_walls(&mut level);
+
+    let mut markers = Vec::new();
+    let room_center = |room: &URect| level.tile_rect(*room).center();
+    if let Some((first, others)) = rooms.split_first() {
+        markers.push(LevelMarker {
+            kind: MarkerKind::PlayerStart,
+            position: room_center(first),
+        });
+        markers.extend(others.iter().map(|room| LevelMarker {
+            kind: MarkerKind::EnemySpawn,
+            position: room_center(room),
+        }));
+    }
+    for _ in 0..FLARE_PICKUPS {
+        let Some(room) = rooms.choose(rng) else {
+            break;
+        };
+        // Keep pickups off the walls.
+        let tile = UVec2::new(
+            rng.gen_range(room.min.x + 1..room.max.x - 1),
+            rng.gen_range(room.min.y + 1..room.max.y - 1),
+        );
+        markers.push(LevelMarker {
+            kind: MarkerKind::FlarePickup,
+            position: level
+                .tile_rect(URect::from_corners(tile, tile + UVec2::ONE))
+                .center(),
+        });
+    }
+
+    (level, LevelMarkers(markers))
+}
+
+/// Makes the level generated from `seed` the current one, in place of the map file.
+pub fn apply_generated_level(commands: &mut Commands, seed: u64) -> LevelMarkers {
+    let (level, markers) = generate_level(&mut GameRng::new(seed), GENERATED_SIZE);
+    // Stop the map file from replacing the generated level when it's reloaded.
+    commands.remove_resource::<LevelHandle>();
+    commands.insert_resource(level);
+    commands.insert_resource(markers.clone());
+    commands.insert_resource(LevelExits::default());
+    commands.insert_resource(LevelSource::Generated { seed });
+    markers
+}
+
+fn carve_floor(level: &mut Level, tiles: URect) {
+    for y in tiles.min.y..tiles.max.y {
+        for x in tiles.min.x..tiles.max.x {
+            level.set(UVec2::new(x, y), TileKind::Floor);
+        }
+    }
+}
+
+/// A straight corridor between two tiles on the same row or column.
+fn carve_corridor(level: &mut Level, from: UVec2, to: UVec2) {
+    let half_width = UVec2::splat(CORRIDOR_WIDTH / 2);
+    carve_floor(
+        level,
+        URect::from_corners(
+            from.min(to) - half_width,
+            from.max(to) + half_width + UVec2::ONE,
+        ),
+    );
+}
+
+/// Turns every empty tile next to a floor, diagonals included, into a wall.
+fn surround_with_walls(level: &mut Level) {
+    let mut walls = Vec::new();
+    for y in 0..level.height {
+        for x in 0..level.width {
+            let tile = UVec2::new(x, y);
+            if level.get(tile) != TileKind::Empty {
+                continue;
+            }
+
+            let next_to_floor = (-1..=1)
+                .flat_map(|dy| (-1..=1).map(move |dx| IVec2::new(dx, dy)))
+                .filter_map(|offset| {
+                    let neighbour = tile.as_ivec2() + offset;
+                    neighbour
+                        .cmpge(IVec2::ZERO)
+                        .all()
+                        .then(|| neighbour.as_uvec2())
+                })
+                .any(|neighbour| level.get(neighbour) == TileKind::Floor);
+            if next_to_floor {
+                walls.push(tile);
+            }
+        }
+    }
+
+    for tile in walls {
+        level.set(tile, TileKind::Wall);
+    }
+}
+
+fn reroll_level(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut rng: ResMut<GameRng>,
+    mut player_q: Query<&mut Transform, With<Player>>,
+) {
+    if !keyboard_input.just_pressed(REROLL_KEY) {
+        return;
+    }
+
+    // Drawing the level's seed from the game's keeps a whole session reproducible,
+    // while the logged seed alone reproduces the level.
+    let seed = rng.next_u64();
+    info!("generating level with seed {seed}");
+    let markers = apply_generated_level(&mut commands, seed);
+
+    // A game in progress carries on from the new level's start.
+    for mut transform in player_q.iter_mut() {
+        transform.translation = markers.player_start().extend(transform.translation.z);
+    }
+}
@@ -0,0 +1,236 @@
+use std::f32::consts::PI;
+
+use avian2d::prelude::*;
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+};
+
+use crate::{
+    collider::{GameLayer, collider_shape},
+    destructible::DamageTiles,
+    health::{DamageEvent, Health},
+    particle::{ParticleEffect, ParticleEmitter},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    pool::EntityPool,
+    state::GameplaySet,
+    status::{ApplyStatus, Inflicts},
+    transition::RoomScoped,
+};
+
+const PROJECTILE_LIFETIME: f32 = 1.5;
+/// How far past a projectile that hit a wall to look for the tile it hit. Reaches just
+/// past its radius, which is as close as it gets before the hit registers.
+const WALL_PROBE: f32 = 2.;
+/// Speed in px/s that a hit pushes the target along the projectile's path.
+const PROJECTILE_KNOCKBACK: f32 = 40.;
+const SMOKE_TRAIL: ParticleEffect = ParticleEffect {
+    burst: 0,
+    rate: 40.,
+    min_speed: 2.,
+    max_speed: 8.,
+    spread: PI,
+    min_lifetime: 0.2,
+    max_lifetime: 0.4,
+    gravity: Vec2::new(0., 10.),
+    drag: 2.,
+    start_color: Color::srgba(0.7, 0.7, 0.7, 0.6),
+    end_color: Color::srgba(0.4, 0.4, 0.4, 0.),
+};
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ProjectileHitEvent>();
+        app.init_resource::<EntityPool<Projectile>>();
+        app.add_systems(Startup, load_projectile_image);
+        app.add_systems(
+            Update,
+            (
+                steer_homing_projectiles,
+                detect_projectile_hits,
+                expire_projectiles,
+            )
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Projectile {
+    pub damage: f32,
+    pub owner: Entity,
+}
+
+/// Fired by an enemy, on the `EnemyProjectile` layer, and so can be deflected.
+#[derive(Component, Debug)]
+pub struct EnemyProjectile;
+
+/// Turns the projectile toward `target` as it flies.
+#[derive(Component, Debug)]
+pub struct Homing {
+    pub target: Entity,
+    /// In radians per second.
+    pub turn_rate: f32,
+}
+
+#[derive(Component)]
+pub struct ProjectileLifetime(pub Timer);
+
+// Impact effects will read the projectile.
+#[allow(dead_code)]
+#[derive(Event, Debug)]
+pub struct ProjectileHitEvent {
+    pub projectile: Entity,
+    /// Who fired it.
+    pub owner: Entity,
+    pub target: Entity,
+    pub position: Vec2,
+}
+
+/// Loaded once, rather than for every shot.
+#[derive(Resource)]
+struct ProjectileImage(Handle<Image>);
+
+/// Fires projectiles, reusing finished ones.
+#[derive(SystemParam)]
+pub struct Projectiles<'w> {
+    pool: ResMut<'w, EntityPool<Projectile>>,
+    image: Res<'w, ProjectileImage>,
+}
+
+impl Projectiles<'_> {
+    pub fn spawn<'a>(
+        &mut self,
+        commands: &'a mut Commands,
+        owner: Entity,
+        origin: Vec2,
+        velocity: Vec2,
+        damage: f32,
+    ) -> EntityCommands<'a> {
+        let mut projectile = self.pool.spawn(
+            commands,
+            (
+                Projectile { damage, owner },
+                ProjectileLifetime(Timer::from_seconds(PROJECTILE_LIFETIME, TimerMode::Once)),
+                Name::new("Projectile"),
+                RoomScoped,
+                Transform::from_translation(origin.extend(0.))
+                    .with_rotation(Quat::from_rotation_z(velocity.to_angle())),
+                Sprite::from_image(self.image.0.clone()),
+                RigidBody::Dynamic,
+                collider_shape("projectile").bundle(),
+                GameLayer::Projectile.collision_layers(),
+                Sensor,
+                CollisionEventsEnabled,
+                LinearVelocity(velocity),
+                ParticleEmitter::new(SMOKE_TRAIL, Vec2::Y),
+                PIXEL_PERFECT_LAYER,
+            ),
+        );
+        // From whoever fired it last, if it's been used before.
+        projectile.remove::<(Inflicts, EnemyProjectile, Homing)>();
+        projectile
+    }
+}
+
+fn load_projectile_image(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ProjectileImage(asset_server.load("projectile.png")));
+}
+
+/// Keeps the speed, only changing the heading. A target that's gone leaves the
+/// projectile flying straight.
+fn steer_homing_projectiles(
+    time: Res<Time>,
+    target_q: Query<&GlobalTransform>,
+    mut projectile_q: Query<(&Homing, &mut Transform, &mut LinearVelocity)>,
+) {
+    for (homing, mut transform, mut velocity) in projectile_q.iter_mut() {
+        let Ok(target) = target_q.get(homing.target) else {
+            continue;
+        };
+        let to_target = target.translation().truncate() - transform.translation.truncate();
+        let angle = velocity.0.angle_to(to_target);
+        if !angle.is_finite() {
+            continue;
+        }
+
+        let max_turn = homing.turn_rate * time.delta_secs();
+        velocity.0 = Vec2::from_angle(angle.clamp(-max_turn, max_turn)).rotate(velocity.0);
+        transform.rotation = Quat::from_rotation_z(velocity.0.to_angle());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn detect_projectile_hits(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionStarted>,
+    mut hit_events: EventWriter<ProjectileHitEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut status_events: EventWriter<ApplyStatus>,
+    mut tile_damage_events: EventWriter<DamageTiles>,
+    mut pool: ResMut<EntityPool<Projectile>>,
+    projectile_q: Query<(&Projectile, &Transform, &LinearVelocity, Option<&Inflicts>)>,
+    health_q: Query<(), With<Health>>,
+    sensor_q: Query<(), With<Sensor>>,
+) {
+    for CollisionStarted(a, b) in collision_events.read() {
+        for (projectile_entity, target) in [(*a, *b), (*b, *a)] {
+            let Ok((projectile, transform, velocity, inflicts)) =
+                projectile_q.get(projectile_entity)
+            else {
+                continue;
+            };
+
+            // Only bodies that can be hurt or block movement stop projectiles.
+            let damageable = health_q.contains(target);
+            if target == projectile.owner || (!damageable && sensor_q.contains(target)) {
+                continue;
+            }
+
+            if damageable {
+                damage_events.write(DamageEvent {
+                    target,
+                    amount: projectile.damage,
+                    knockback: velocity.0.normalize_or_zero() * PROJECTILE_KNOCKBACK,
+                });
+                if let Some(inflicts) = inflicts {
+                    status_events.write(ApplyStatus {
+                        target,
+                        effect: inflicts.effect,
+                        secs: inflicts.secs,
+                    });
+                }
+            } else {
+                tile_damage_events.write(DamageTiles {
+                    position: transform.translation.truncate()
+                        + velocity.0.normalize_or_zero() * WALL_PROBE,
+                    radius: 0.,
+                    amount: projectile.damage,
+                });
+            }
+
+            hit_events.write(ProjectileHitEvent {
+                projectile: projectile_entity,
+                owner: projectile.owner,
+                target,
+                position: transform.translation.truncate(),
+            });
+            pool.release(&mut commands, projectile_entity);
+        }
+    }
+}
+
+fn expire_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<EntityPool<Projectile>>,
+    mut projectile_q: Query<(Entity, &mut ProjectileLifetime)>,
+) {
+    for (entity, mut lifetime) in projectile_q.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            pool.release(&mut commands, entity);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    collider::{ColliderShape, GameLayer, collider_shape},
+    debug::debug_render,
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    lighting::LightOccluder,
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    transition::RoomScoped,
+};
+
+/// Loose objects lying around the level that the player can shove about: into doorways
+/// to hold enemies back, or onto pressure plates.
+pub struct PropPlugin;
+
+impl Plugin for PropPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_level_props.in_set(MarkerSpawnSet));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropKind {
+    /// Square and heavy, it blocks light and stops where it's pushed.
+    Crate,
+    /// Round and lighter, it slides farther.
+    Barrel,
+}
+
+impl PropKind {
+    fn collider(self) -> ColliderShape {
+        match self {
+            PropKind::Crate => collider_shape("crate"),
+            PropKind::Barrel => collider_shape("barrel"),
+        }
+    }
+
+    /// Heavier than the player's 250 or so, so pushing one is slow going.
+    fn mass(self) -> f32 {
+        match self {
+            PropKind::Crate => 600.,
+            PropKind::Barrel => 300.,
+        }
+    }
+
+    /// Fraction of its velocity lost per second, standing in for friction with the
+    /// floor.
+    fn damping(self) -> f32 {
+        match self {
+            PropKind::Crate => 8.,
+            PropKind::Barrel => 3.,
+        }
+    }
+
+    fn image_path(self) -> &'static str {
+        match self {
+            PropKind::Crate => "crate.png",
+            PropKind::Barrel => "barrel.png",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PropKind::Crate => "Crate",
+            PropKind::Barrel => "Barrel",
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Prop;
+
+pub fn spawn_prop(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    kind: PropKind,
+    position: Vec2,
+) -> Entity {
+    let mut prop = commands.spawn((
+        Prop,
+        Name::new(kind.name()),
+        RoomScoped,
+        Transform::from_translation(position.extend(0.)),
+        Sprite::from_image(asset_server.load(kind.image_path())),
+        debug_render(Color::srgb(0.7, 0.5, 0.3)),
+        PIXEL_PERFECT_LAYER,
+        (
+            RigidBody::Dynamic,
+            kind.collider().bundle(),
+            GameLayer::Default.collision_layers(),
+            Mass(kind.mass()),
+            LinearDamping(kind.damping()),
+            Friction::new(0.6),
+            // Kept upright, so crates stay lined up with the tiles.
+            LockedAxes::ROTATION_LOCKED,
+        ),
+    ));
+    if kind == PropKind::Crate {
+        prop.insert(LightOccluder);
+    }
+    prop.id()
+}
+
+/// Props go where the level places them. They aren't saved, so loading a game puts
+/// them back there too.
+fn spawn_level_props(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        if let MarkerKind::Prop { kind } = marker.kind {
+            spawn_prop(&mut commands, &asset_server, kind, marker.position);
+        }
+    }
+}
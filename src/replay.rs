@@ -0,0 +1,445 @@
+use std::{collections::HashSet, fmt, io};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    input::InputSystem,
+    prelude::*,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{MouseWorldPos, update_mouse_world_pos},
+    config::{load_ron, save_ron},
+    determinism::Determinism,
+    input::{Action, PlayerInput, buffer_presses},
+    level::LevelSource,
+    procgen::apply_generated_level,
+    rng::{GameRng, RunSeed},
+    state::{GameState, NewGame},
+};
+
+/// Set this to a replay file, relative to the config directory, to play it back
+/// instead of taking input from the devices.
+const REPLAY_ENV_VAR: &str = "UNTITLED_GAME_REPLAY";
+/// Where the game in progress is recorded to, in the config directory.
+const REPLAY_FILE: &str = "replay.ron";
+const SAVE_REPLAY_KEY: KeyCode = KeyCode::F10;
+
+/// Records the player's input through each game, so it can be played back to reproduce
+/// what happened. The recording is saved when the game ends, or with F10 at any point.
+///
+/// Only the actions and the aim are recorded: keys read straight from the keyboard,
+/// like saving and loading, aren't. Playback only matches the recording exactly when
+/// it's made in deterministic mode, see `Determinism`.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Replay>();
+        app.init_asset_loader::<ReplayLoader>();
+        app.insert_resource(ReplayMode::from_env());
+        app.add_systems(NewGame, begin_replay);
+        app.add_systems(
+            PreUpdate,
+            play_back_input
+                .after(InputSystem)
+                .before(buffer_presses)
+                .run_if(playing_back),
+        );
+        app.add_systems(
+            Update,
+            (
+                start_playback.run_if(in_state(GameState::MainMenu).and(playing_back)),
+                (
+                    record_input.after(update_mouse_world_pos),
+                    save_replay_on_key,
+                )
+                    .chain()
+                    .run_if(not(in_state(GameState::MainMenu))),
+            ),
+        );
+        for state in [GameState::GameOver, GameState::Victory] {
+            app.add_systems(OnEnter(state), save_replay);
+        }
+    }
+}
+
+/// A recorded game: enough to start it over the same way, and the input from then on.
+/// Bundled ones load as `.replay.ron` assets.
+#[derive(Asset, TypePath, Serialize, Deserialize, Default, Debug)]
+pub struct Replay {
+    /// `GameRng` is reseeded with this as the game starts.
+    pub seed: u64,
+    pub level: Option<LevelSource>,
+    /// Whether it was recorded in deterministic mode, which playback then uses too.
+    #[serde(default)]
+    pub deterministic: bool,
+    /// Only the frames where the input changed.
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// Positions are stored as arrays, since glam's serde support isn't enabled.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReplayFrame {
+    /// Counted from the start of the game.
+    pub frame: u32,
+    /// Seconds since the start of the game, for reading the file rather than playback.
+    pub time: f32,
+    pub pressed: Vec<Action>,
+    /// Where the player aimed, in world coordinates.
+    pub aim: [f32; 2],
+}
+
+#[derive(Default)]
+pub struct ReplayLoader;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl AssetLoader for ReplayLoader {
+    type Asset = Replay;
+    type Settings = ();
+    type Error = ReplayError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Replay, ReplayError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(ReplayError::Io)?;
+        parse_replay(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["replay.ron"]
+    }
+}
+
+fn parse_replay(bytes: &[u8]) -> Result<Replay, ReplayError> {
+    let replay: Replay = ron::de::from_bytes(bytes).map_err(ReplayError::Parse)?;
+    validate(&replay)?;
+    Ok(replay)
+}
+
+/// Playback steps through the frames in order, so one out of order would hold up every
+/// frame after it.
+fn validate(replay: &Replay) -> Result<(), ReplayError> {
+    if replay.frames.is_empty() {
+        return Err(ReplayError::Invalid("the replay has no frames".to_string()));
+    }
+    for pair in replay.frames.windows(2) {
+        if pair[1].frame <= pair[0].frame {
+            return Err(ReplayError::Invalid(format!(
+                "frame {} comes after frame {}",
+                pair[1].frame, pair[0].frame
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Resource, Debug)]
+pub enum ReplayMode {
+    Recording {
+        replay: Replay,
+        frame: u32,
+        started: f32,
+    },
+    Playback {
+        replay: Replay,
+        frame: u32,
+        /// Index of the next entry in `replay.frames` to apply.
+        next: usize,
+        started: bool,
+    },
+    /// A playback has run out. Recording starts again with the next game.
+    Off,
+}
+
+impl ReplayMode {
+    fn from_env() -> Self {
+        let Ok(file_name) = std::env::var(REPLAY_ENV_VAR) else {
+            return Self::recording();
+        };
+
+        match load_ron::<Replay>(&file_name) {
+            Ok(Some(replay)) => {
+                info!("playing back {file_name}");
+                ReplayMode::Playback {
+                    replay,
+                    frame: 0,
+                    next: 0,
+                    started: false,
+                }
+            }
+            Ok(None) => {
+                warn!("no replay at {file_name}, recording instead");
+                Self::recording()
+            }
+            Err(error) => {
+                warn!("Failed to load {file_name}, recording instead: {error}");
+                Self::recording()
+            }
+        }
+    }
+
+    fn recording() -> Self {
+        ReplayMode::Recording {
+            replay: Replay::default(),
+            frame: 0,
+            started: 0.,
+        }
+    }
+}
+
+/// Run condition for systems that take input from the devices, which playback
+/// replaces.
+pub fn playing_back(mode: Res<ReplayMode>) -> bool {
+    matches!(*mode, ReplayMode::Playback { .. })
+}
+
+/// Starts the recorded game straight from the main menu, in the level it was recorded
+/// in.
+fn start_playback(
+    mut commands: Commands,
+    mut mode: ResMut<ReplayMode>,
+    mut determinism: ResMut<Determinism>,
+    level_source: Res<LevelSource>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let ReplayMode::Playback {
+        replay, started, ..
+    } = &mut *mode
+    else {
+        return;
+    };
+    if *started {
+        return;
+    }
+
+    *started = true;
+    if determinism.0 != replay.deterministic {
+        determinism.0 = replay.deterministic;
+    }
+    match &replay.level {
+        Some(LevelSource::Generated { seed }) => {
+            apply_generated_level(&mut commands, *seed);
+        }
+        Some(level) if *level != *level_source => {
+            warn!("the replay was recorded in {level:?}, playing it in {level_source:?}");
+        }
+        _ => {}
+    }
+    next_state.set(GameState::Playing);
+}
+
+/// Reseeds `GameRng`, so the game plays out the same from here on. A recording draws the
+/// new seed from the old one, as rerolling a level does, unless it's a rematch.
+#[allow(clippy::too_many_arguments)]
+fn begin_replay(
+    time: Res<Time>,
+    level_source: Res<LevelSource>,
+    determinism: Res<Determinism>,
+    mut rng: ResMut<GameRng>,
+    mut run_seed: ResMut<RunSeed>,
+    mut mode: ResMut<ReplayMode>,
+    mut player_input: ResMut<PlayerInput>,
+    mut mouse_world_pos: ResMut<MouseWorldPos>,
+) {
+    if let ReplayMode::Off = *mode {
+        *mode = ReplayMode::recording();
+    }
+
+    match &mut *mode {
+        ReplayMode::Recording {
+            replay,
+            frame,
+            started,
+        } => {
+            let rematch = run_seed.next.take();
+            let seed = rematch.unwrap_or_else(|| rng.next_u64());
+            *run_seed = RunSeed {
+                seed,
+                rematch: rematch.is_some(),
+                next: None,
+            };
+            *rng = GameRng::new(seed);
+            *replay = Replay {
+                seed,
+                level: Some(level_source.clone()),
+                deterministic: determinism.0,
+                frames: Vec::new(),
+            };
+            *frame = 0;
+            *started = time.elapsed_secs();
+        }
+        ReplayMode::Playback {
+            replay,
+            frame,
+            next,
+            ..
+        } => {
+            *run_seed = RunSeed {
+                seed: replay.seed,
+                ..Default::default()
+            };
+            *rng = GameRng::new(replay.seed);
+            *frame = 0;
+            *next = 0;
+            // Gameplay this frame has to see the first recorded frame's input.
+            apply_frames(replay, frame, next, &mut player_input, &mut mouse_world_pos);
+        }
+        ReplayMode::Off => {}
+    }
+}
+
+/// Applies the recorded input up to `frame`, then moves on to the next frame.
+pub fn apply_frames(
+    replay: &Replay,
+    frame: &mut u32,
+    next: &mut usize,
+    player_input: &mut PlayerInput,
+    mouse_world_pos: &mut MouseWorldPos,
+) {
+    while let Some(entry) = replay.frames.get(*next) {
+        if entry.frame > *frame {
+            break;
+        }
+
+        player_input.set_pressed(entry.pressed.iter().copied().collect::<HashSet<_>>());
+        mouse_world_pos.0 = Vec2::from(entry.aim);
+        *next += 1;
+    }
+    *frame += 1;
+}
+
+/// Feeds the recording in where the devices' input would go. Once it runs out, control
+/// goes back to the player.
+fn play_back_input(
+    mut mode: ResMut<ReplayMode>,
+    mut player_input: ResMut<PlayerInput>,
+    mut mouse_world_pos: ResMut<MouseWorldPos>,
+) {
+    let ReplayMode::Playback {
+        replay,
+        frame,
+        next,
+        started: true,
+    } = &mut *mode
+    else {
+        return;
+    };
+
+    // Waiting for the game to start, which applies the first frame.
+    if *frame == 0 {
+        return;
+    }
+    if *next >= replay.frames.len() {
+        info!("replay finished");
+        *mode = ReplayMode::Off;
+        return;
+    }
+
+    apply_frames(replay, frame, next, &mut player_input, &mut mouse_world_pos);
+}
+
+fn record_input(
+    time: Res<Time>,
+    player_input: Res<PlayerInput>,
+    mouse_world_pos: Res<MouseWorldPos>,
+    mut mode: ResMut<ReplayMode>,
+) {
+    let ReplayMode::Recording {
+        replay,
+        frame,
+        started,
+    } = &mut *mode
+    else {
+        return;
+    };
+
+    let pressed = player_input.pressed_actions();
+    let aim = mouse_world_pos.0.to_array();
+    let changed = replay
+        .frames
+        .last()
+        .is_none_or(|last| last.pressed != pressed || last.aim != aim);
+    if changed {
+        replay.frames.push(ReplayFrame {
+            frame: *frame,
+            time: time.elapsed_secs() - *started,
+            pressed,
+            aim,
+        });
+    }
+    *frame += 1;
+}
+
+fn save_replay_on_key(keyboard_input: Res<ButtonInput<KeyCode>>, mode: Res<ReplayMode>) {
+    if keyboard_input.just_pressed(SAVE_REPLAY_KEY) {
+        save_replay(mode);
+    }
+}
+
+fn save_replay(mode: Res<ReplayMode>) {
+    let ReplayMode::Recording { replay, .. } = &*mode else {
+        return;
+    };
+    if replay.frames.is_empty() {
+        return;
+    }
+
+    match save_ron(REPLAY_FILE, replay) {
+        Ok(()) => info!("saved the replay to {REPLAY_FILE}"),
+        Err(error) => warn!("Failed to save {REPLAY_FILE}: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_attract_replay_is_valid() {
+        let replay = parse_replay(include_bytes!("../assets/replays/attract.replay.ron")).unwrap();
+        assert_eq!(
+            replay.level,
+            Some(LevelSource::Map("levels/arena.tmx".to_string()))
+        );
+    }
+
+    #[test]
+    fn out_of_order_frame_is_named() {
+        let error = parse_replay(
+            br#"(seed: 1, level: None, frames: [
+                (frame: 0, time: 0.0, pressed: [], aim: (0.0, 0.0)),
+                (frame: 5, time: 0.1, pressed: [Fire], aim: (0.0, 0.0)),
+                (frame: 3, time: 0.2, pressed: [], aim: (0.0, 0.0)),
+            ])"#,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "frame 3 comes after frame 5");
+    }
+}
@@ -0,0 +1,738 @@
+use std::{collections::BTreeMap, io};
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    boss::Boss,
+    config::{load_ron, save_ron},
+    enemy::Enemy,
+    flare::Flare,
+    health::{DamageEvent, DeathEvent, Health, apply_contact_damage, apply_damage, despawn_dead},
+    input::{Action, PlayerInput},
+    level::{LevelHandle, LevelSource, apply_map},
+    pixel_perfect::{CanvasText, HIGH_RES_LAYER},
+    player::Player,
+    procgen::apply_generated_level,
+    projectile::{Projectile, ProjectileHitEvent},
+    rng::RunSeed,
+    score::{HighScores, Score, record_high_score, score_kills},
+    speedrun::{PersonalBest, format_run_time, record_personal_best},
+    state::{GameScoped, GameState, GameplaySet, NewGame, ScreenOverlay},
+    tiled::TiledMap,
+    wave::{WaveCleared, WaveStarted},
+};
+
+const LIFETIME_STATS_FILE: &str = "lifetime_stats.ron";
+/// Distances are shown in tiles.
+const TILE_SIZE: f32 = 8.;
+
+const OVERLAY_COLOR: Color = Color::srgba(0., 0., 0., 0.8);
+const TITLE_FONT_SIZE: f32 = 12.;
+const STAT_FONT_SIZE: f32 = 5.;
+const ENTRY_FONT_SIZE: f32 = 8.;
+/// Vertical distance between stat lines, in canvas pixels.
+const STAT_SPACING: f32 = 6.;
+/// Horizontal distance of the two columns of stats from the middle.
+const STAT_COLUMN_OFFSET: f32 = 30.;
+const WAVE_SUMMARY_FONT_SIZE: f32 = 6.;
+/// Vertical distance between entries, in canvas pixels.
+const ENTRY_SPACING: f32 = 9.;
+/// Horizontal distance of the two columns of entries from the middle.
+const ENTRY_COLUMN_OFFSET: f32 = 26.;
+const ENTRY_COLUMNS: usize = 2;
+const SEED_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+/// The seed is shown this big when it couldn't be copied, to be written down instead.
+const SEED_FALLBACK_FONT_SIZE: f32 = 8.;
+const SEED_FALLBACK_COLOR: Color = Color::srgb(1., 0.9, 0.4);
+const ENTRY_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const SELECTED_ENTRY_COLOR: Color = Color::WHITE;
+
+/// Keeps count of how the game went, and shows it once the player dies or wins, with
+/// a shorter summary after each wave. Every game's counts add up to the lifetime totals
+/// on the main menu.
+///
+/// The results screen shows the game's seed too, and can start a rematch: a new game
+/// with the same seed in the level the game started in, which plays out the same until
+/// the player does something different.
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>();
+        app.init_resource::<WaveStartStats>();
+        app.insert_resource(LifetimeStats::load());
+        app.init_resource::<ResultsSelection>();
+        app.add_systems(
+            NewGame,
+            (reset_run_stats, spawn_wave_summary, remember_start_level),
+        );
+        app.add_systems(
+            Update,
+            (
+                track_run_stats.before(despawn_dead).after(score_kills),
+                track_damage
+                    .after(apply_contact_damage)
+                    .before(apply_damage),
+                show_wave_summary.after(track_run_stats),
+                win_after_final_wave,
+            )
+                .in_set(GameplaySet),
+        );
+        for state in [GameState::GameOver, GameState::Victory] {
+            app.add_systems(
+                OnEnter(state),
+                (
+                    spawn_results_screen
+                        .after(record_high_score)
+                        .after(record_personal_best),
+                    record_lifetime_stats,
+                ),
+            );
+        }
+        app.add_systems(
+            Update,
+            (
+                navigate_results,
+                confirm_results_entry,
+                highlight_selected_entry,
+            )
+                .chain()
+                .run_if(in_state(GameState::GameOver).or(in_state(GameState::Victory))),
+        );
+    }
+}
+
+/// Counted while the game runs, so time spent paused doesn't add up.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct RunStats {
+    /// In seconds.
+    pub time: f32,
+    pub kills: u32,
+    /// By enemy archetype, with bosses as `"boss"`.
+    pub kills_by_type: BTreeMap<String, u32>,
+    pub flares_thrown: u32,
+    /// Projectiles the player fired.
+    pub shots: u32,
+    /// Projectiles of the player's that hit an enemy, once for each enemy one hits.
+    pub hits: u32,
+    /// To enemies, leaving out what went past their remaining health.
+    pub damage_dealt: f32,
+    pub damage_taken: f32,
+    /// In pixels. Worked out from the player's velocity, so being moved by teleporters
+    /// or into the next room doesn't count.
+    pub distance: f32,
+    pub longest_combo: u32,
+}
+
+impl RunStats {
+    pub fn accuracy(&self) -> f32 {
+        accuracy(self.hits, self.shots)
+    }
+
+    /// The counts since `earlier`, another snapshot of the same game.
+    fn since(&self, earlier: &RunStats) -> RunStats {
+        RunStats {
+            time: self.time - earlier.time,
+            kills: self.kills - earlier.kills,
+            kills_by_type: self
+                .kills_by_type
+                .iter()
+                .map(|(name, kills)| {
+                    let before = earlier.kills_by_type.get(name).copied().unwrap_or(0);
+                    (name.clone(), kills - before)
+                })
+                .filter(|(_, kills)| *kills > 0)
+                .collect(),
+            flares_thrown: self.flares_thrown - earlier.flares_thrown,
+            shots: self.shots - earlier.shots,
+            hits: self.hits - earlier.hits,
+            damage_dealt: self.damage_dealt - earlier.damage_dealt,
+            damage_taken: self.damage_taken - earlier.damage_taken,
+            distance: self.distance - earlier.distance,
+            longest_combo: self.longest_combo,
+        }
+    }
+}
+
+/// The `RunStats` as the current wave started, for its summary once it's cleared.
+#[derive(Resource, Default, Debug)]
+struct WaveStartStats(RunStats);
+
+/// Every finished game's `RunStats` added up. Kept in the config directory.
+#[derive(Resource, Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub games: u32,
+    pub victories: u32,
+    /// In seconds.
+    pub time: f32,
+    pub kills_by_type: BTreeMap<String, u32>,
+    pub flares_thrown: u32,
+    pub shots: u32,
+    pub hits: u32,
+    pub damage_dealt: f32,
+    pub damage_taken: f32,
+    /// In pixels.
+    pub distance: f32,
+    /// The longest of any game.
+    pub longest_combo: u32,
+}
+
+impl LifetimeStats {
+    fn load() -> Self {
+        match load_ron(LIFETIME_STATS_FILE) {
+            Ok(Some(stats)) => stats,
+            Ok(None) => Self::default(),
+            Err(error) => {
+                warn!("Failed to load {LIFETIME_STATS_FILE}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        if let Err(error) = save_ron(LIFETIME_STATS_FILE, self) {
+            warn!("Failed to save {LIFETIME_STATS_FILE}: {error}");
+        }
+    }
+
+    fn add(&mut self, run: &RunStats, won: bool) {
+        self.games += 1;
+        self.victories += u32::from(won);
+        self.time += run.time;
+        for (name, kills) in &run.kills_by_type {
+            *self.kills_by_type.entry(name.clone()).or_default() += kills;
+        }
+        self.flares_thrown += run.flares_thrown;
+        self.shots += run.shots;
+        self.hits += run.hits;
+        self.damage_dealt += run.damage_dealt;
+        self.damage_taken += run.damage_taken;
+        self.distance += run.distance;
+        self.longest_combo = self.longest_combo.max(run.longest_combo);
+    }
+
+    /// One stat per line, for the main menu.
+    pub fn lines(&self) -> Vec<String> {
+        let kills: u32 = self.kills_by_type.values().sum();
+        let mut lines = vec![
+            format!("Games {}  Won {}", self.games, self.victories),
+            format!("Time {}", format_duration(self.time)),
+            format!("Kills {kills}"),
+        ];
+        if !self.kills_by_type.is_empty() {
+            lines.push(format_kills_by_type(&self.kills_by_type));
+        }
+        lines.extend([
+            format!(
+                "Shots {}  Accuracy {:.0}%",
+                self.shots,
+                accuracy(self.hits, self.shots) * 100.
+            ),
+            format!(
+                "Damage {:.0} dealt  {:.0} taken",
+                self.damage_dealt, self.damage_taken
+            ),
+            format!(
+                "Flares {}  Distance {:.0}",
+                self.flares_thrown,
+                self.distance / TILE_SIZE
+            ),
+            format!("Best combo x{}", self.longest_combo.max(1)),
+        ]);
+        lines
+    }
+}
+
+/// Centered text between the waves, saying how the last one went.
+#[derive(Component)]
+struct WaveSummary;
+
+/// The level the game in progress started in, for a rematch to go back to.
+#[derive(Resource)]
+struct StartLevel {
+    source: LevelSource,
+    /// Keeps a map file loaded while the game has moved on to others.
+    map: Option<Handle<TiledMap>>,
+}
+
+/// Shows the game's seed on the results screen.
+#[derive(Component)]
+struct SeedLine;
+
+/// Laid out in a grid, `ENTRY_COLUMNS` wide.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+enum ResultsEntry {
+    Retry,
+    /// Starts over with the same seed and level.
+    Rematch,
+    CopySeed,
+    /// Back to the main menu.
+    Quit,
+}
+
+impl ResultsEntry {
+    const ALL: [ResultsEntry; 4] = [
+        ResultsEntry::Retry,
+        ResultsEntry::Rematch,
+        ResultsEntry::CopySeed,
+        ResultsEntry::Quit,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ResultsEntry::Retry => "Retry",
+            ResultsEntry::Rematch => "Rematch",
+            ResultsEntry::CopySeed => "Copy seed",
+            ResultsEntry::Quit => "Quit",
+        }
+    }
+
+    fn position(index: usize) -> Vec2 {
+        let column = (index % ENTRY_COLUMNS) as f32 - (ENTRY_COLUMNS - 1) as f32 / 2.;
+        let row = (index / ENTRY_COLUMNS) as f32;
+        Vec2::new(
+            column * 2. * ENTRY_COLUMN_OFFSET,
+            -27. - row * ENTRY_SPACING,
+        )
+    }
+}
+
+/// Index into `ResultsEntry::ALL`.
+#[derive(Resource, Default, Debug)]
+struct ResultsSelection(usize);
+
+/// Hits for each shot, which can be more than one when a shot hits several enemies at
+/// once, so it's capped to show as a percentage.
+fn accuracy(hits: u32, shots: u32) -> f32 {
+    if shots == 0 {
+        0.
+    } else {
+        (hits as f32 / shots as f32).min(1.)
+    }
+}
+
+fn format_duration(secs: f32) -> String {
+    let secs = secs as u32;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn format_kills_by_type(kills_by_type: &BTreeMap<String, u32>) -> String {
+    kills_by_type
+        .iter()
+        .map(|(name, kills)| format!("{kills} {name}"))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+fn reset_run_stats(mut stats: ResMut<RunStats>, mut wave_start: ResMut<WaveStartStats>) {
+    *stats = RunStats::default();
+    wave_start.0 = RunStats::default();
+}
+
+fn remember_start_level(
+    mut commands: Commands,
+    level_source: Res<LevelSource>,
+    level_handle: Option<Res<LevelHandle>>,
+) {
+    let map = match *level_source {
+        LevelSource::Map(_) => level_handle.map(|handle| handle.0.clone()),
+        LevelSource::Generated { .. } => None,
+    };
+    commands.insert_resource(StartLevel {
+        source: level_source.clone(),
+        map,
+    });
+}
+
+/// Puts the level the game started in back in place, before the rematch's new game
+/// sets up in it.
+fn restore_start_level(
+    commands: &mut Commands,
+    start: &StartLevel,
+    level_source: &LevelSource,
+    maps: &Assets<TiledMap>,
+) {
+    if start.source == *level_source {
+        return;
+    }
+
+    match &start.source {
+        LevelSource::Generated { seed } => {
+            apply_generated_level(commands, *seed);
+        }
+        LevelSource::Map(path) => {
+            let Some((handle, map)) = start
+                .map
+                .as_ref()
+                .and_then(|handle| Some((handle, maps.get(handle)?)))
+            else {
+                warn!("{path} isn't loaded, the rematch starts in {level_source:?}");
+                return;
+            };
+            apply_map(commands, map);
+            commands.insert_resource(LevelHandle(handle.clone()));
+            commands.insert_resource(start.source.clone());
+        }
+    }
+}
+
+/// Hands `text` to the first clipboard tool the system has.
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    const TOOLS: [(&str, &[&str]); 5] = [
+        ("pbcopy", &[]),
+        ("clip", &[]),
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+    ];
+    let mut error = io::Error::new(io::ErrorKind::NotFound, "no clipboard tool found");
+    for (program, args) in TOOLS {
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(spawn_error) if spawn_error.kind() == io::ErrorKind::NotFound => continue,
+            Err(spawn_error) => {
+                error = spawn_error;
+                continue;
+            }
+        };
+        // Closing stdin lets the tool know it has everything.
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if status.success() {
+            return Ok(());
+        }
+        error = io::Error::other(format!("{program} exited with {status}"));
+    }
+    Err(error)
+}
+
+/// Browsers only allow it from a page's own text fields, so the seed stays up on the
+/// results screen to be copied from there instead.
+#[cfg(target_arch = "wasm32")]
+fn copy_to_clipboard(_text: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the browser's clipboard isn't available",
+    ))
+}
+
+/// Pooled projectiles get their `Projectile` again each time they're fired, so every
+/// shot shows up as added.
+#[allow(clippy::too_many_arguments)]
+fn track_run_stats(
+    time: Res<Time>,
+    score: Res<Score>,
+    mut stats: ResMut<RunStats>,
+    mut death_events: EventReader<DeathEvent>,
+    mut hit_events: EventReader<ProjectileHitEvent>,
+    enemy_q: Query<(Option<&Enemy>, Has<Boss>)>,
+    player: Single<(Entity, &LinearVelocity), With<Player>>,
+    thrown_flare_q: Query<(), Added<Flare>>,
+    fired_q: Query<&Projectile, Added<Projectile>>,
+) {
+    let (player_entity, velocity) = *player;
+    stats.time += time.delta_secs();
+    stats.distance += velocity.length() * time.delta_secs();
+    stats.longest_combo = stats.longest_combo.max(score.combo);
+    stats.flares_thrown += thrown_flare_q.iter().count() as u32;
+    stats.shots += fired_q
+        .iter()
+        .filter(|projectile| projectile.owner == player_entity)
+        .count() as u32;
+    stats.hits += hit_events
+        .read()
+        .filter(|event| {
+            event.owner == player_entity
+                && enemy_q
+                    .get(event.target)
+                    .is_ok_and(|(enemy, boss)| enemy.is_some() || boss)
+        })
+        .count() as u32;
+
+    for event in death_events.read() {
+        let name = match enemy_q.get(event.entity) {
+            Ok((Some(enemy), _)) => enemy.0.name,
+            Ok((None, true)) => "boss",
+            _ => continue,
+        };
+        stats.kills += 1;
+        *stats.kills_by_type.entry(name.to_string()).or_default() += 1;
+    }
+}
+
+/// Reads the damage before it's applied, to leave out what's lost on invulnerable or
+/// dead targets.
+#[allow(clippy::type_complexity)]
+fn track_damage(
+    mut stats: ResMut<RunStats>,
+    mut damage_events: EventReader<DamageEvent>,
+    health_q: Query<(&Health, Has<Player>, Has<Enemy>, Has<Boss>)>,
+) {
+    for event in damage_events.read() {
+        let Ok((health, player, enemy, boss)) = health_q.get(event.target) else {
+            continue;
+        };
+        if health.is_dead() || health.is_invulnerable() {
+            continue;
+        }
+
+        let amount = event.amount.min(health.current);
+        if player {
+            stats.damage_taken += amount;
+        } else if enemy || boss {
+            stats.damage_dealt += amount;
+        }
+    }
+}
+
+fn spawn_wave_summary(mut commands: Commands) {
+    commands.spawn((
+        WaveSummary,
+        GameScoped,
+        Text2d::default(),
+        CanvasText::new(Vec2::new(0., 12.), WAVE_SUMMARY_FONT_SIZE),
+        Transform::from_xyz(0., 0., 10.),
+        Visibility::Hidden,
+    ));
+}
+
+/// Shown through the intermission after each wave but the last, which ends the game.
+fn show_wave_summary(
+    stats: Res<RunStats>,
+    mut wave_start: ResMut<WaveStartStats>,
+    mut started_events: EventReader<WaveStarted>,
+    mut cleared_events: EventReader<WaveCleared>,
+    summary: Single<(&mut Text2d, &mut Visibility), With<WaveSummary>>,
+) {
+    let (mut text, mut visibility) = summary.into_inner();
+    if started_events.read().last().is_some() {
+        wave_start.0 = stats.clone();
+        *visibility = Visibility::Hidden;
+    }
+
+    let Some(cleared) = cleared_events.read().last() else {
+        return;
+    };
+    if cleared.last {
+        return;
+    }
+    let wave = stats.since(&wave_start.0);
+    text.0 = format!(
+        "Wave {} cleared\n{} kills  {:.0}% accuracy\n{:.0} damage taken",
+        cleared.wave,
+        wave.kills,
+        wave.accuracy() * 100.,
+        wave.damage_taken
+    );
+    *visibility = Visibility::Inherited;
+}
+
+fn record_lifetime_stats(
+    state: Res<State<GameState>>,
+    stats: Res<RunStats>,
+    mut lifetime: ResMut<LifetimeStats>,
+) {
+    lifetime.add(&stats, *state.get() == GameState::Victory);
+    lifetime.save();
+}
+
+fn win_after_final_wave(
+    mut wave_cleared: EventReader<WaveCleared>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if wave_cleared.read().any(|event| event.last) {
+        next_state.set(GameState::Victory);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_results_screen(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    stats: Res<RunStats>,
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    personal_best: Res<PersonalBest>,
+    run_seed: Res<RunSeed>,
+    mut selection: ResMut<ResultsSelection>,
+) {
+    let state = *state.get();
+    let title = if state == GameState::Victory {
+        "Victory"
+    } else {
+        "Game Over"
+    };
+    let seconds = stats.time as u32;
+    let left_column = [
+        format!("Time {}:{:02}", seconds / 60, seconds % 60),
+        format!("Kills {}", stats.kills),
+        format!("Shots {}", stats.shots),
+        format!("Accuracy {:.0}%", stats.accuracy() * 100.),
+        format!("Dealt {:.0}", stats.damage_dealt),
+        format!("Taken {:.0}", stats.damage_taken),
+    ];
+    let mut right_column = vec![
+        match score.placement {
+            Some(placement) => format!("Score {} #{}", score.points, placement + 1),
+            None => format!("Score {}", score.points),
+        },
+        format!("Best {}", high_scores.best()),
+        format!("Flares {}", stats.flares_thrown),
+        format!("Distance {:.0}", stats.distance / TILE_SIZE),
+        format!("Combo x{}", stats.longest_combo.max(1)),
+    ];
+    if let Some(run) = &personal_best.0 {
+        right_column.push(format!("PB {}", format_run_time(run.time)));
+    }
+    let mut stat_lines: Vec<(Vec2, String)> = [
+        (-STAT_COLUMN_OFFSET, left_column.to_vec()),
+        (STAT_COLUMN_OFFSET, right_column),
+    ]
+    .into_iter()
+    .flat_map(|(x, lines)| {
+        lines
+            .into_iter()
+            .enumerate()
+            .map(move |(index, line)| (Vec2::new(x, 22. - index as f32 * STAT_SPACING), line))
+    })
+    .collect();
+    stat_lines.push((
+        Vec2::new(0., 22. - 6. * STAT_SPACING),
+        format_kills_by_type(&stats.kills_by_type),
+    ));
+    let seed_line = if run_seed.rematch {
+        format!("Rematch of seed {}", run_seed.seed)
+    } else {
+        format!("Seed {}", run_seed.seed)
+    };
+    selection.0 = 0;
+
+    commands
+        .spawn((
+            ScreenOverlay,
+            Name::new(format!("{state:?} screen")),
+            Sprite::from_color(OVERLAY_COLOR, Vec2::ONE),
+            Transform::from_xyz(0., 0., 20.),
+            HIGH_RES_LAYER,
+            StateScoped(state),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2d::new(title),
+                CanvasText::new(Vec2::new(0., 32.), TITLE_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+
+            for (position, line) in stat_lines {
+                parent.spawn((
+                    Text2d::new(line),
+                    CanvasText::new(position, STAT_FONT_SIZE),
+                    Transform::from_xyz(0., 0., 0.1),
+                ));
+            }
+
+            parent.spawn((
+                SeedLine,
+                Text2d::new(seed_line),
+                TextColor(SEED_COLOR),
+                CanvasText::new(Vec2::new(0., 22. - 7. * STAT_SPACING), STAT_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+
+            for (index, entry) in ResultsEntry::ALL.into_iter().enumerate() {
+                parent.spawn((
+                    entry,
+                    Text2d::new(entry.label()),
+                    TextColor(ENTRY_COLOR),
+                    CanvasText::new(ResultsEntry::position(index), ENTRY_FONT_SIZE),
+                    Transform::from_xyz(0., 0., 0.1),
+                ));
+            }
+        });
+}
+
+fn navigate_results(input: Res<PlayerInput>, mut selection: ResMut<ResultsSelection>) {
+    let count = ResultsEntry::ALL.len();
+    for (action, step) in [
+        (Action::MoveUp, count - ENTRY_COLUMNS),
+        (Action::MoveDown, ENTRY_COLUMNS),
+        (Action::MoveLeft, count - 1),
+        (Action::MoveRight, 1),
+    ] {
+        if input.just_pressed(action) {
+            selection.0 = (selection.0 + step) % count;
+        }
+    }
+}
+
+/// Leaving the results screen either way ends the game, see `GameScoped`. A rematch
+/// goes through the same new game as retrying, only with the seed and level forced.
+/// A seed that can't be copied is made to stand out instead, to be copied by hand.
+#[allow(clippy::too_many_arguments)]
+fn confirm_results_entry(
+    mut commands: Commands,
+    input: Res<PlayerInput>,
+    selection: Res<ResultsSelection>,
+    start: Res<StartLevel>,
+    level_source: Res<LevelSource>,
+    maps: Res<Assets<TiledMap>>,
+    mut run_seed: ResMut<RunSeed>,
+    mut next_state: ResMut<NextState<GameState>>,
+    seed_line: Single<(&mut Text2d, &mut TextColor, &mut CanvasText), With<SeedLine>>,
+) {
+    if !input.just_pressed(Action::Confirm) {
+        return;
+    }
+
+    match ResultsEntry::ALL[selection.0] {
+        ResultsEntry::Retry => next_state.set(GameState::Playing),
+        ResultsEntry::Rematch => {
+            run_seed.next = Some(run_seed.seed);
+            restore_start_level(&mut commands, &start, &level_source, &maps);
+            next_state.set(GameState::Playing);
+        }
+        ResultsEntry::CopySeed => {
+            let (mut text, mut color, mut canvas_text) = seed_line.into_inner();
+            match copy_to_clipboard(&run_seed.seed.to_string()) {
+                Ok(()) => text.0 = format!("Seed {} copied", run_seed.seed),
+                Err(error) => {
+                    warn!("couldn't copy the seed: {error}");
+                    text.0 = format!("Seed {}", run_seed.seed);
+                    color.0 = SEED_FALLBACK_COLOR;
+                    canvas_text.font_size = SEED_FALLBACK_FONT_SIZE;
+                }
+            }
+        }
+        ResultsEntry::Quit => next_state.set(GameState::MainMenu),
+    }
+}
+
+fn highlight_selected_entry(
+    selection: Res<ResultsSelection>,
+    mut entry_q: Query<(&ResultsEntry, &mut TextColor)>,
+) {
+    for (entry, mut color) in entry_q.iter_mut() {
+        color.0 = if *entry == ResultsEntry::ALL[selection.0] {
+            SELECTED_ENTRY_COLOR
+        } else {
+            ENTRY_COLOR
+        };
+    }
+}
@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Set this to a number to replay a run with the same seed.
+const SEED_ENV_VAR: &str = "UNTITLED_GAME_SEED";
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = std::env::var(SEED_ENV_VAR)
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(rand::random);
+        info!("game seed {seed}");
+        app.insert_resource(GameRng::new(seed));
+        app.init_resource::<RunSeed>();
+    }
+}
+
+/// The seed `GameRng` was reseeded with as the game in progress started. Every new game
+/// draws its own, unless it's set up to play an earlier one's again.
+#[derive(Resource, Default, Debug)]
+pub struct RunSeed {
+    pub seed: u64,
+    /// The game in progress plays an earlier game's seed again, so its score isn't a
+    /// fresh record.
+    pub rematch: bool,
+    /// Makes the next new game a rematch with this seed.
+    pub next: Option<u64>,
+}
+
+/// The one source of randomness for gameplay and level generation, so a seed
+/// reproduces a run. Use it through the `rand::Rng` methods.
+#[derive(Resource, Debug)]
+pub struct GameRng(ChaCha8Rng);
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// How far into its stream the generator is, which tells two runs apart once they
+    /// drew a different amount.
+    pub fn word_pos(&self) -> u128 {
+        self.0.get_word_pos()
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
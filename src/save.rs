@@ -0,0 +1,500 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::{AiMovement, PatrolRoute},
+    boss::{Boss, spawn_boss},
+    camera::CameraFollow,
+    checkpoint::{RespawnPoint, RespawnSnapshot},
+    combat_room::{ClearedRooms, CombatRoomMember},
+    config::{load_ron, one, save_ron},
+    dialogue::DialogueFlags,
+    enemy::{Enemy, EnemyArchetype, GRUNT, enemy_archetype, spawn_enemy},
+    flare::{FLARE_PICKUP, FlareInventory},
+    health::Health,
+    inventory::Inventory,
+    level::{LevelHandle, LevelLoadProgress, LevelMarkers, LevelSource, MarkerKind, apply_map},
+    pickup::{Pickup, spawn_pickup},
+    player::Player,
+    procgen::apply_generated_level,
+    state::GameplaySet,
+    tiled::TiledMap,
+    transition::RoomScoped,
+    wave::{WaveEnemy, WaveManager, WaveMember, wave_movement},
+    world_clock::WorldClock,
+};
+
+const SAVE_KEY: KeyCode = KeyCode::F5;
+const NEXT_SLOT_KEY: KeyCode = KeyCode::F6;
+const LOAD_KEY: KeyCode = KeyCode::F9;
+const SAVE_SLOTS: u32 = 3;
+
+/// F5 saves to the selected slot, F9 loads from it, and F6 selects the next slot.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveSlot(1));
+        app.add_systems(
+            Update,
+            (
+                select_save_slot,
+                save_game,
+                load_game,
+                load_saved_level,
+                restore_saved_game,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Which of the save files F5 and F9 use, from 1 to `SAVE_SLOTS`.
+#[derive(Resource, Debug)]
+pub struct SaveSlot(pub u32);
+
+impl SaveSlot {
+    fn file_name(&self) -> String {
+        format!("save{}.ron", self.0)
+    }
+}
+
+/// Positions are stored as arrays, since glam's serde support isn't enabled.
+#[derive(Serialize, Deserialize, Debug)]
+struct SaveGame {
+    level: LevelSource,
+    player: SavedPlayer,
+    enemies: Vec<SavedEnemy>,
+    #[serde(default)]
+    pickups: Vec<SavedPickup>,
+    /// Only in saves made back when flares were the only pickup.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    flare_pickups: Vec<[f32; 2]>,
+    wave: u32,
+    /// The wave set's asset path. Missing from saves made before wave sets could be
+    /// picked, which go on with the one being played.
+    #[serde(default)]
+    wave_set: Option<String>,
+    /// The wave's enemies that hadn't come out yet, next first.
+    #[serde(default)]
+    wave_queue: Vec<SavedWaveEnemy>,
+    #[serde(default)]
+    spawn_interval: f32,
+    /// Bosses that came with a wave. Level bosses come back from the level instead.
+    #[serde(default)]
+    wave_bosses: Vec<SavedBoss>,
+    #[serde(default)]
+    flags: DialogueFlags,
+    #[serde(default)]
+    cleared_rooms: ClearedRooms,
+    /// Missing from saves made before the day cycle, which load at noon.
+    #[serde(default = "noon")]
+    time_of_day: f32,
+}
+
+fn noon() -> f32 {
+    0.5
+}
+
+fn grunt() -> String {
+    GRUNT.name.to_string()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SavedPickup {
+    pickup: Pickup,
+    position: [f32; 2],
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SavedPlayer {
+    position: [f32; 2],
+    health: f32,
+    flares: u32,
+    #[serde(default)]
+    inventory: Inventory,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SavedEnemy {
+    /// Missing from saves made when there was only the one kind of enemy.
+    #[serde(default = "grunt")]
+    archetype: String,
+    position: [f32; 2],
+    health: f32,
+    max_health: f32,
+    patrol: Option<Vec<[f32; 2]>>,
+    wave_member: bool,
+    /// How much faster than its archetype a wave made it.
+    #[serde(default = "one")]
+    speed: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+enum SavedWaveEnemy {
+    Enemy {
+        archetype: String,
+        speed: f32,
+        health: f32,
+    },
+    Boss,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SavedBoss {
+    position: [f32; 2],
+    health: f32,
+    max_health: f32,
+}
+
+/// A save being restored. Its level may have to load first.
+#[derive(Resource)]
+struct PendingLoad {
+    save: SaveGame,
+    map: Option<Handle<TiledMap>>,
+}
+
+type EnemyState<'a> = (
+    &'a Enemy,
+    &'a Transform,
+    &'a Health,
+    &'a AiMovement,
+    Option<&'a PatrolRoute>,
+    Has<WaveMember>,
+);
+
+fn select_save_slot(keyboard_input: Res<ButtonInput<KeyCode>>, mut slot: ResMut<SaveSlot>) {
+    if keyboard_input.just_pressed(NEXT_SLOT_KEY) {
+        slot.0 = slot.0 % SAVE_SLOTS + 1;
+        info!("save slot {}", slot.0);
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn save_game(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    slot: Res<SaveSlot>,
+    level_source: Res<LevelSource>,
+    waves: Res<WaveManager>,
+    clock: Res<WorldClock>,
+    inventory: Res<Inventory>,
+    flags: Res<DialogueFlags>,
+    cleared_rooms: Res<ClearedRooms>,
+    player: Single<(&Transform, &Health, &FlareInventory), With<Player>>,
+    // A combat room's enemies come back with its fight when the player steps in.
+    enemy_q: Query<EnemyState, (With<Enemy>, Without<CombatRoomMember>)>,
+    wave_boss_q: Query<(&Transform, &Health), (With<Boss>, With<WaveMember>)>,
+    pickup_q: Query<(&Pickup, &Transform)>,
+) {
+    if !keyboard_input.just_pressed(SAVE_KEY) {
+        return;
+    }
+
+    let position = |transform: &Transform| transform.translation.truncate().to_array();
+    let (player_transform, player_health, flares) = *player;
+    let save = SaveGame {
+        level: level_source.clone(),
+        player: SavedPlayer {
+            position: position(player_transform),
+            health: player_health.current,
+            flares: flares.count,
+            inventory: inventory.clone(),
+        },
+        enemies: enemy_q
+            .iter()
+            .map(
+                |(enemy, transform, health, movement, patrol, wave_member)| SavedEnemy {
+                    archetype: enemy.0.name.to_string(),
+                    position: position(transform),
+                    health: health.current,
+                    max_health: health.max,
+                    patrol: patrol
+                        .map(|patrol| patrol.waypoints.iter().map(Vec2::to_array).collect()),
+                    wave_member,
+                    speed: movement.chase_speed / enemy.0.movement.chase_speed,
+                },
+            )
+            .collect(),
+        pickups: pickup_q
+            .iter()
+            .map(|(pickup, transform)| SavedPickup {
+                pickup: *pickup,
+                position: position(transform),
+            })
+            .collect(),
+        flare_pickups: Vec::new(),
+        wave: waves.wave,
+        wave_set: asset_server
+            .get_path(&waves.wave_set)
+            .map(|path| path.to_string()),
+        wave_queue: waves
+            .queued()
+            .map(|wave_enemy| match wave_enemy {
+                WaveEnemy::Enemy {
+                    archetype,
+                    speed,
+                    health,
+                } => SavedWaveEnemy::Enemy {
+                    archetype: archetype.name.to_string(),
+                    speed,
+                    health,
+                },
+                WaveEnemy::Boss => SavedWaveEnemy::Boss,
+            })
+            .collect(),
+        spawn_interval: waves.spawn_interval(),
+        wave_bosses: wave_boss_q
+            .iter()
+            .map(|(transform, health)| SavedBoss {
+                position: position(transform),
+                health: health.current,
+                max_health: health.max,
+            })
+            .collect(),
+        flags: flags.clone(),
+        cleared_rooms: cleared_rooms.clone(),
+        time_of_day: clock.time_of_day,
+    };
+
+    match save_ron(&slot.file_name(), &save) {
+        Ok(()) => info!("saved to slot {}", slot.0),
+        Err(error) => error!("couldn't save to slot {}: {error}", slot.0),
+    }
+}
+
+fn load_game(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    slot: Res<SaveSlot>,
+    level_source: Res<LevelSource>,
+    mut physics_time: ResMut<Time<Physics>>,
+    room_entity_q: Query<Entity, With<RoomScoped>>,
+) {
+    if !keyboard_input.just_pressed(LOAD_KEY) {
+        return;
+    }
+
+    let save = match load_ron::<SaveGame>(&slot.file_name()) {
+        Ok(Some(save)) => save,
+        Ok(None) => {
+            warn!("save slot {} is empty", slot.0);
+            return;
+        }
+        Err(error) => {
+            error!("couldn't load slot {}: {error}", slot.0);
+            return;
+        }
+    };
+
+    info!("loading slot {}", slot.0);
+    for entity in room_entity_q.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let mut map = None;
+    if save.level != *level_source {
+        match &save.level {
+            LevelSource::Map(path) => map = Some(asset_server.load(path)),
+            LevelSource::Generated { seed } => {
+                apply_generated_level(&mut commands, *seed);
+            }
+        }
+    }
+
+    // Nothing moves until the saved game is back in place.
+    physics_time.pause();
+    commands.insert_resource(PendingLoad { save, map });
+}
+
+fn load_saved_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    maps: Res<Assets<TiledMap>>,
+    pending: Option<ResMut<PendingLoad>>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    let Some(mut pending) = pending else {
+        return;
+    };
+    let Some(handle) = &pending.map else {
+        return;
+    };
+
+    if let Some(map) = maps.get(handle) {
+        apply_map(&mut commands, map);
+        commands.insert_resource(LevelHandle(handle.clone()));
+        commands.insert_resource(pending.save.level.clone());
+        pending.map = None;
+    } else if asset_server.load_state(handle).is_failed() {
+        error!("couldn't load the saved game's level");
+        commands.remove_resource::<PendingLoad>();
+        physics_time.unpause();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn restore_saved_game(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pending: Option<Res<PendingLoad>>,
+    markers: Res<LevelMarkers>,
+    mut progress: ResMut<LevelLoadProgress>,
+    mut waves: ResMut<WaveManager>,
+    mut clock: ResMut<WorldClock>,
+    mut inventory: ResMut<Inventory>,
+    mut flags: ResMut<DialogueFlags>,
+    mut cleared_rooms: ResMut<ClearedRooms>,
+    mut camera_follow: ResMut<CameraFollow>,
+    mut respawn_point: ResMut<RespawnPoint>,
+    mut physics_time: ResMut<Time<Physics>>,
+    player: Single<
+        (
+            &mut Transform,
+            &mut LinearVelocity,
+            &mut Health,
+            &mut FlareInventory,
+        ),
+        With<Player>,
+    >,
+) {
+    let Some(pending) = pending else {
+        return;
+    };
+    if pending.map.is_some() {
+        return;
+    }
+
+    let save = &pending.save;
+    let (mut transform, mut velocity, mut health, mut flares) = player.into_inner();
+    let player_position = Vec2::from_array(save.player.position);
+    transform.translation = player_position.extend(transform.translation.z);
+    velocity.0 = Vec2::ZERO;
+    health.current = save.player.health;
+    flares.count = save.player.flares.min(flares.max);
+    *inventory = save.player.inventory.clone();
+    *flags = save.flags.clone();
+    *cleared_rooms = save.cleared_rooms.clone();
+    camera_follow.position = player_position;
+    // The last checkpoint may be in another level, so respawn where the save was made.
+    respawn_point.0 = Some(RespawnSnapshot {
+        position: player_position,
+        flares: flares.count,
+    });
+
+    for saved in &save.enemies {
+        let archetype = saved_archetype(&saved.archetype);
+        let enemy = spawn_enemy(
+            &mut commands,
+            &asset_server,
+            archetype,
+            Vec2::from_array(saved.position),
+        );
+        let mut health = Health::new(saved.max_health);
+        health.current = saved.health;
+        commands.entity(enemy).insert(health);
+        if let Some(waypoints) = &saved.patrol {
+            let waypoints = waypoints.iter().copied().map(Vec2::from_array).collect();
+            commands.entity(enemy).insert(PatrolRoute::new(waypoints));
+        }
+        if saved.wave_member {
+            commands
+                .entity(enemy)
+                .insert((WaveMember, wave_movement(archetype, saved.speed)));
+        }
+    }
+    for saved in &save.wave_bosses {
+        let boss = spawn_boss(
+            &mut commands,
+            &asset_server,
+            Vec2::from_array(saved.position),
+            None,
+            true,
+        );
+        let mut health = Health::new(saved.max_health);
+        health.current = saved.health;
+        commands.entity(boss).insert((health, WaveMember));
+    }
+    for saved in &save.pickups {
+        spawn_pickup(
+            &mut commands,
+            &asset_server,
+            saved.pickup,
+            Vec2::from_array(saved.position),
+        );
+    }
+    for position in &save.flare_pickups {
+        spawn_pickup(
+            &mut commands,
+            &asset_server,
+            FLARE_PICKUP,
+            Vec2::from_array(*position),
+        );
+    }
+    // Doors and the other level fixtures aren't saved, and come back closed and where
+    // the level put them. Keys stay in the inventory, so locked doors can still be opened.
+    // So are bosses, which start the fight over.
+    progress.queue_markers(
+        markers
+            .0
+            .iter()
+            .filter(|marker| {
+                matches!(
+                    marker.kind,
+                    MarkerKind::Door { .. }
+                        | MarkerKind::Switch { .. }
+                        | MarkerKind::PressurePlate { .. }
+                        | MarkerKind::Prop { .. }
+                        | MarkerKind::Platform { .. }
+                        | MarkerKind::Hazard { .. }
+                        | MarkerKind::ForceZone { .. }
+                        | MarkerKind::Teleporter { .. }
+                        | MarkerKind::Portal { .. }
+                        | MarkerKind::Npc { .. }
+                        | MarkerKind::CombatRoom { .. }
+                        | MarkerKind::Boss { .. }
+                )
+            })
+            .cloned(),
+    );
+
+    if let Some(path) = &save.wave_set {
+        waves.wave_set = asset_server.load(path);
+    }
+    waves.wave = save.wave;
+    let queued = save
+        .wave_queue
+        .iter()
+        .map(|saved| match saved {
+            SavedWaveEnemy::Enemy {
+                archetype,
+                speed,
+                health,
+            } => WaveEnemy::Enemy {
+                archetype: saved_archetype(archetype),
+                speed: *speed,
+                health: *health,
+            },
+            SavedWaveEnemy::Boss => WaveEnemy::Boss,
+        })
+        .collect();
+    waves.resume(queued, save.spawn_interval);
+    waves.in_progress = save.enemies.iter().any(|enemy| enemy.wave_member)
+        || !save.wave_bosses.is_empty()
+        || !save.wave_queue.is_empty();
+    waves.intermission.reset();
+    clock.time_of_day = save.time_of_day;
+
+    commands.remove_resource::<PendingLoad>();
+    physics_time.unpause();
+}
+
+fn saved_archetype(name: &str) -> &'static EnemyArchetype {
+    enemy_archetype(name).unwrap_or_else(|| {
+        warn!("no enemy archetype `{name}`, restoring a grunt");
+        &GRUNT
+    })
+}
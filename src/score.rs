@@ -0,0 +1,228 @@
+use std::cmp::Reverse;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{load_ron, save_ron},
+    enemy::Enemy,
+    health::{DeathEvent, despawn_dead},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    rng::RunSeed,
+    state::{GameState, GameplaySet, NewGame},
+    transition::RoomScoped,
+    wave::{WaveCleared, WaveManager},
+};
+
+const HIGH_SCORES_FILE: &str = "highscores.ron";
+const HIGH_SCORE_COUNT: usize = 5;
+const KILL_POINTS: u32 = 100;
+/// Clearing a wave is worth this much per wave number, without the combo.
+const WAVE_CLEAR_POINTS: u32 = 250;
+const MAX_COMBO: u32 = 8;
+/// How long the combo holds after a kill, and then after each step it falls back.
+const COMBO_SECS: f32 = 3.;
+const POPUP_FONT_SIZE: f32 = 6.;
+const POPUP_SECS: f32 = 0.8;
+const POPUP_RISE_SPEED: f32 = 16.;
+const POPUP_Z: f32 = 7.;
+const POPUP_COLOR: Color = Color::srgb(1., 0.9, 0.4);
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>();
+        app.insert_resource(HighScores::load());
+        app.add_systems(NewGame, reset_score);
+        app.add_systems(
+            Update,
+            (
+                score_kills.before(despawn_dead),
+                score_cleared_waves,
+                decay_combo,
+                float_score_popups,
+            )
+                .in_set(GameplaySet),
+        );
+        for state in [GameState::GameOver, GameState::Victory] {
+            app.add_systems(OnEnter(state), record_high_score);
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct Score {
+    pub points: u32,
+    /// Multiplies the points for kills. Every kill raises it, and it falls back a step
+    /// at a time once kills stop coming.
+    pub combo: u32,
+    combo_timer: Timer,
+    /// Where the game's score went in the high scores, once it's over. `None` when it
+    /// didn't make it.
+    pub placement: Option<usize>,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            points: 0,
+            combo: 1,
+            combo_timer: Timer::from_seconds(COMBO_SECS, TimerMode::Once),
+            placement: None,
+        }
+    }
+}
+
+/// The best games so far, highest first. Kept in the config directory.
+#[derive(Resource, Default, Debug, Serialize, Deserialize)]
+pub struct HighScores(pub Vec<HighScore>);
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub points: u32,
+    pub wave: u32,
+    /// Scored replaying an earlier game's seed.
+    #[serde(default)]
+    pub rematch: bool,
+}
+
+impl HighScores {
+    fn load() -> Self {
+        match load_ron(HIGH_SCORES_FILE) {
+            Ok(Some(high_scores)) => high_scores,
+            Ok(None) => Self::default(),
+            Err(error) => {
+                warn!("Failed to load {HIGH_SCORES_FILE}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        if let Err(error) = save_ron(HIGH_SCORES_FILE, self) {
+            warn!("Failed to save {HIGH_SCORES_FILE}: {error}");
+        }
+    }
+
+    /// Rematches don't count, as they knew what was coming.
+    pub fn best(&self) -> u32 {
+        self.0
+            .iter()
+            .find(|high_score| !high_score.rematch)
+            .map_or(0, |high_score| high_score.points)
+    }
+}
+
+/// Rises from where the points were scored and fades out.
+#[derive(Component)]
+struct ScorePopup(Timer);
+
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+fn spawn_score_popup(commands: &mut Commands, points: u32, position: Vec2) {
+    commands.spawn((
+        ScorePopup(Timer::from_seconds(POPUP_SECS, TimerMode::Once)),
+        Name::new("Score popup"),
+        RoomScoped,
+        Text2d::new(format!("+{points}")),
+        TextFont::from_font_size(POPUP_FONT_SIZE),
+        TextColor(POPUP_COLOR),
+        Transform::from_translation(position.extend(POPUP_Z)),
+        PIXEL_PERFECT_LAYER,
+    ));
+}
+
+pub fn score_kills(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut death_events: EventReader<DeathEvent>,
+    enemy_q: Query<&Transform, With<Enemy>>,
+) {
+    for event in death_events.read() {
+        let Ok(transform) = enemy_q.get(event.entity) else {
+            continue;
+        };
+
+        let points = KILL_POINTS * score.combo;
+        score.points += points;
+        score.combo = (score.combo + 1).min(MAX_COMBO);
+        score.combo_timer.reset();
+        spawn_score_popup(&mut commands, points, transform.translation.truncate());
+    }
+}
+
+fn score_cleared_waves(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut wave_cleared: EventReader<WaveCleared>,
+    player_transform: Single<&Transform, With<Player>>,
+) {
+    for event in wave_cleared.read() {
+        let points = WAVE_CLEAR_POINTS * event.wave;
+        score.points += points;
+        spawn_score_popup(
+            &mut commands,
+            points,
+            player_transform.translation.truncate(),
+        );
+    }
+}
+
+fn decay_combo(time: Res<Time>, mut score: ResMut<Score>) {
+    if score.combo > 1 && score.combo_timer.tick(time.delta()).finished() {
+        score.combo -= 1;
+        score.combo_timer.reset();
+    }
+}
+
+fn float_score_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut popup_q: Query<(Entity, &mut ScorePopup, &mut Transform, &mut TextColor)>,
+) {
+    for (entity, mut popup, mut transform, mut color) in popup_q.iter_mut() {
+        if popup.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation.y += POPUP_RISE_SPEED * time.delta_secs();
+        color.0 = POPUP_COLOR.with_alpha(popup.0.fraction_remaining());
+    }
+}
+
+pub fn record_high_score(
+    run_seed: Res<RunSeed>,
+    waves: Res<WaveManager>,
+    mut score: ResMut<Score>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    if score.points == 0 {
+        return;
+    }
+
+    high_scores.0.push(HighScore {
+        points: score.points,
+        wave: waves.wave,
+        rematch: run_seed.rematch,
+    });
+    // Stable, so this game goes after earlier ones with the same points.
+    high_scores
+        .0
+        .sort_by_key(|high_score| Reverse(high_score.points));
+    score.placement = high_scores
+        .0
+        .iter()
+        .rposition(|high_score| {
+            high_score.points == score.points
+                && high_score.wave == waves.wave
+                && high_score.rematch == run_seed.rematch
+        })
+        .filter(|placement| *placement < HIGH_SCORE_COUNT);
+    high_scores.0.truncate(HIGH_SCORE_COUNT);
+    high_scores.save();
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::{
+    health::DamageEvent,
+    pixel_perfect::{Canvas, MainCamera},
+    player::Player,
+    state::GameplaySet,
+};
+
+const PLAYER_HIT_TRAUMA: f32 = 0.4;
+const ENEMY_HIT_TRAUMA: f32 = 0.1;
+
+pub struct ScreenShakePlugin;
+
+impl Plugin for ScreenShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ScreenShake {
+            trauma: 0.,
+            decay: 1.5,
+            max_offset: 3.,
+        });
+        app.add_event::<AddTrauma>();
+        app.add_systems(
+            Update,
+            (shake_on_hits, add_trauma, apply_screen_shake)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Trauma-based shake applied to the high-res camera, so the pixel canvas moves as a whole
+/// and the pixel grid stays intact.
+#[derive(Resource, Debug)]
+pub struct ScreenShake {
+    /// 0 to 1; the shake strength is trauma squared.
+    pub trauma: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+    /// Largest offset in canvas pixels, at full trauma.
+    pub max_offset: f32,
+}
+
+#[derive(Event, Debug)]
+pub struct AddTrauma(pub f32);
+
+fn shake_on_hits(
+    mut damage_events: EventReader<DamageEvent>,
+    mut trauma_events: EventWriter<AddTrauma>,
+    player_q: Query<(), With<Player>>,
+) {
+    for event in damage_events.read() {
+        trauma_events.write(AddTrauma(if player_q.contains(event.target) {
+            PLAYER_HIT_TRAUMA
+        } else {
+            ENEMY_HIT_TRAUMA
+        }));
+    }
+}
+
+fn add_trauma(mut trauma_events: EventReader<AddTrauma>, mut shake: ResMut<ScreenShake>) {
+    for AddTrauma(amount) in trauma_events.read() {
+        shake.trauma = (shake.trauma + amount).clamp(0., 1.);
+    }
+}
+
+fn apply_screen_shake(
+    time: Res<Time>,
+    mut shake: ResMut<ScreenShake>,
+    canvas_transform: Single<&Transform, (With<Canvas>, Without<MainCamera>)>,
+    mut camera_transform: Single<&mut Transform, With<MainCamera>>,
+) {
+    shake.trauma = (shake.trauma - shake.decay * time.delta_secs()).max(0.);
+
+    // Layered sines give a smooth but irregular wobble without per-frame jitter.
+    let t = time.elapsed_secs();
+    let noise = Vec2::new(
+        (t * 37.).sin() * 0.6 + (t * 61.).sin() * 0.4,
+        (t * 43.).cos() * 0.6 + (t * 71.).sin() * 0.4,
+    );
+    let strength = shake.trauma * shake.trauma;
+    let offset = noise * strength * shake.max_offset * canvas_transform.scale.truncate();
+
+    camera_transform.translation = offset.extend(camera_transform.translation.z);
+}
@@ -0,0 +1,177 @@
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, PrimaryWindow, VideoModeSelection, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{load_ron, save_ron},
+    cooldown_arcs::CooldownArcs,
+    crosshair::{CrosshairColor, CrosshairStyle},
+    pixel_perfect::{ScalingMode, ZOOM_LEVELS},
+    post_process::Palette,
+};
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameSettings>();
+        app.add_systems(Update, (toggle_window_mode, apply_window_mode).chain());
+    }
+}
+
+/// Player-facing options, persisted in the config directory. Loaded in `main` before
+/// the app is built so the window opens in the saved mode.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    pub window_mode: WindowModeSetting,
+    /// Internal resolution of the canvas, one of `ZOOM_LEVELS`. Zooming changes it too.
+    pub resolution: UVec2,
+    pub scaling_mode: ScalingMode,
+    /// Scanlines, curvature and vignette over the canvas.
+    pub crt_effect: bool,
+    pub palette: Palette,
+    pub crosshair: CrosshairStyle,
+    pub crosshair_color: CrosshairColor,
+    pub cooldown_arcs: CooldownArcs,
+    /// Sight line from the player to the first wall they're aiming at.
+    pub aim_line: bool,
+    /// Blast radius and a mark over the player while they're in reach of a grenade.
+    pub blast_warning: bool,
+    /// 1-pixel outlines around the player, bosses and the enemy under the crosshair.
+    pub outlines: bool,
+    /// A replay playing behind the main menu when it's left alone.
+    pub attract_mode: bool,
+    /// Run timer and splits on the HUD.
+    pub speedrun_timer: bool,
+    /// How long a press of dash, fire, flare, melee or interact is held on to when it
+    /// can't happen yet, in real seconds.
+    pub input_buffer_secs: f32,
+    /// Real seconds from one midnight to the next.
+    pub day_length_secs: f32,
+    /// Volumes are fractions from 0 to 1.
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowModeSetting::Windowed,
+            resolution: ZOOM_LEVELS[0],
+            scaling_mode: ScalingMode::Integer,
+            crt_effect: false,
+            palette: Palette::Full,
+            crosshair: CrosshairStyle::Cross,
+            crosshair_color: CrosshairColor::White,
+            cooldown_arcs: CooldownArcs::Crosshair,
+            aim_line: false,
+            blast_warning: true,
+            outlines: true,
+            attract_mode: true,
+            speedrun_timer: false,
+            input_buffer_secs: 0.12,
+            day_length_secs: 300.,
+            master_volume: 1.,
+            music_volume: 0.8,
+            sfx_volume: 0.8,
+        }
+    }
+}
+
+impl GameSettings {
+    /// Falls back to the defaults when the file is missing or unreadable, so a broken
+    /// settings file never keeps the game from starting.
+    pub fn load() -> Self {
+        match load_ron(SETTINGS_FILE) {
+            Ok(Some(settings)) => settings,
+            Ok(None) => Self::default(),
+            Err(error) => {
+                warn!("Failed to load {SETTINGS_FILE}, using default settings: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = save_ron(SETTINGS_FILE, self) {
+            warn!("Failed to save {SETTINGS_FILE}: {error}");
+        }
+    }
+}
+
+/// Serializable mirror of the window modes the game offers; `WindowMode` itself also
+/// carries monitor and video mode selections the settings don't expose.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowModeSetting {
+    #[default]
+    Windowed,
+    BorderlessFullscreen,
+    Fullscreen,
+}
+
+impl WindowModeSetting {
+    pub const ALL: [WindowModeSetting; 3] = [
+        WindowModeSetting::Windowed,
+        WindowModeSetting::BorderlessFullscreen,
+        WindowModeSetting::Fullscreen,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WindowModeSetting::Windowed => "Windowed",
+            WindowModeSetting::BorderlessFullscreen => "Borderless",
+            WindowModeSetting::Fullscreen => "Fullscreen",
+        }
+    }
+
+    /// The mode after this one in `ALL`, wrapping around.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn window_mode(self) -> WindowMode {
+        match self {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::BorderlessFullscreen => {
+                WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+            }
+            WindowModeSetting::Fullscreen => {
+                WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+            }
+        }
+    }
+}
+
+/// F11 or Alt+Enter steps to the next window mode from anywhere, menus included. Read
+/// straight from the keyboard since the rebindable actions don't do key combinations.
+fn toggle_window_mode(keys: Res<ButtonInput<KeyCode>>, mut settings: ResMut<GameSettings>) {
+    let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let toggled = keys.just_pressed(KeyCode::F11) || (alt && keys.just_pressed(KeyCode::Enter));
+    if !toggled {
+        return;
+    }
+
+    settings.window_mode = settings.window_mode.next();
+    settings.save();
+}
+
+fn apply_window_mode(
+    settings: Res<GameSettings>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let mode = settings.window_mode.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+}
@@ -0,0 +1,376 @@
+use bevy::prelude::*;
+
+use crate::{
+    cooldown_arcs::CooldownArcs,
+    crosshair::{CrosshairColor, CrosshairStyle},
+    input::{Action, InputBinding, InputBindings, PlayerInput},
+    pixel_perfect::{CanvasText, HIGH_RES_LAYER, ScalingMode, ZOOM_LEVELS},
+    post_process::Palette,
+    settings::{GameSettings, WindowModeSetting},
+    state::{GameState, ScreenOverlay},
+};
+
+const MENU_BACKGROUND: Color = Color::srgb(0.04, 0.04, 0.06);
+const TITLE_FONT_SIZE: f32 = 10.;
+const ENTRY_FONT_SIZE: f32 = 6.;
+/// Vertical distance between entries, in canvas pixels.
+const ENTRY_SPACING: f32 = 7.;
+/// The canvas only fits a handful of rows, so the list scrolls to keep the selection
+/// in view.
+const VISIBLE_ENTRIES: usize = 7;
+const ENTRY_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const SELECTED_ENTRY_COLOR: Color = Color::WHITE;
+const LISTENING_ENTRY_COLOR: Color = Color::srgb(1., 0.8, 0.3);
+const VOLUME_STEP: f32 = 0.1;
+const DAY_LENGTH_STEP_SECS: f32 = 60.;
+const MAX_DAY_LENGTH_SECS: f32 = 1200.;
+
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SettingsMenu::default());
+        app.add_systems(OnEnter(GameState::Settings), spawn_settings_menu);
+        app.add_systems(OnExit(GameState::Settings), save_settings);
+        app.add_systems(
+            Update,
+            (capture_binding, navigate_settings, update_settings_entries)
+                .chain()
+                .run_if(in_state(GameState::Settings)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SettingsEntry {
+    WindowMode,
+    Resolution,
+    ScalingMode,
+    CrtEffect,
+    Palette,
+    Crosshair,
+    CrosshairColor,
+    CooldownArcs,
+    AimLine,
+    BlastWarning,
+    Outlines,
+    AttractMode,
+    SpeedrunTimer,
+    DayLength,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Binding(Action),
+    Back,
+}
+
+impl SettingsEntry {
+    fn all() -> Vec<SettingsEntry> {
+        let mut entries = vec![
+            SettingsEntry::WindowMode,
+            SettingsEntry::Resolution,
+            SettingsEntry::ScalingMode,
+            SettingsEntry::CrtEffect,
+            SettingsEntry::Palette,
+            SettingsEntry::Crosshair,
+            SettingsEntry::CrosshairColor,
+            SettingsEntry::CooldownArcs,
+            SettingsEntry::AimLine,
+            SettingsEntry::BlastWarning,
+            SettingsEntry::Outlines,
+            SettingsEntry::AttractMode,
+            SettingsEntry::SpeedrunTimer,
+            SettingsEntry::DayLength,
+            SettingsEntry::MasterVolume,
+            SettingsEntry::MusicVolume,
+            SettingsEntry::SfxVolume,
+        ];
+        entries.extend(Action::ALL.into_iter().map(SettingsEntry::Binding));
+        entries.push(SettingsEntry::Back);
+        entries
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+struct SettingsMenu {
+    selected: usize,
+    /// First entry shown in the scrolled list.
+    scroll: usize,
+    /// Set while waiting for the input to bind to this action.
+    listening: Option<Action>,
+}
+
+/// One row of the list; which entry it shows depends on the scroll position.
+#[derive(Component)]
+struct SettingsRow(usize);
+
+fn spawn_settings_menu(mut commands: Commands, mut menu: ResMut<SettingsMenu>) {
+    *menu = SettingsMenu::default();
+
+    commands
+        .spawn((
+            ScreenOverlay,
+            Name::new("Settings menu"),
+            Sprite::from_color(MENU_BACKGROUND, Vec2::ONE),
+            Transform::from_xyz(0., 0., 20.),
+            HIGH_RES_LAYER,
+            StateScoped(GameState::Settings),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2d::new("Settings"),
+                CanvasText::new(Vec2::new(0., 30.), TITLE_FONT_SIZE),
+                Transform::from_xyz(0., 0., 0.1),
+            ));
+
+            for row in 0..VISIBLE_ENTRIES {
+                parent.spawn((
+                    SettingsRow(row),
+                    Text2d::default(),
+                    TextColor(ENTRY_COLOR),
+                    CanvasText::new(
+                        Vec2::new(0., 18. - row as f32 * ENTRY_SPACING),
+                        ENTRY_FONT_SIZE,
+                    ),
+                    Transform::from_xyz(0., 0., 0.1),
+                ));
+            }
+        });
+}
+
+fn save_settings(settings: Res<GameSettings>, bindings: Res<InputBindings>) {
+    settings.save();
+    bindings.save();
+}
+
+fn next_in<T: Copy + PartialEq>(options: &[T], current: T, step: isize) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0);
+    let next = (index as isize + step).rem_euclid(options.len() as isize);
+    options[next as usize]
+}
+
+fn step_volume(volume: &mut f32, step: isize) {
+    *volume = ((*volume + step as f32 * VOLUME_STEP) * 10.).round() / 10.;
+    *volume = volume.clamp(0., 1.);
+}
+
+fn step_day_length(day_length_secs: &mut f32, step: isize) {
+    *day_length_secs = (*day_length_secs + step as f32 * DAY_LENGTH_STEP_SECS)
+        .clamp(DAY_LENGTH_STEP_SECS, MAX_DAY_LENGTH_SECS);
+}
+
+fn navigate_settings(
+    input: Res<PlayerInput>,
+    mut menu: ResMut<SettingsMenu>,
+    mut settings: ResMut<GameSettings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Also skip the frame a binding was captured or cancelled in, so that press doesn't
+    // navigate as well.
+    if menu.listening.is_some() || menu.is_changed() {
+        return;
+    }
+
+    if input.just_pressed(Action::Pause) {
+        next_state.set(GameState::MainMenu);
+        return;
+    }
+
+    let entries = SettingsEntry::all();
+    if input.just_pressed(Action::MoveUp) {
+        menu.selected = (menu.selected + entries.len() - 1) % entries.len();
+    }
+    if input.just_pressed(Action::MoveDown) {
+        menu.selected = (menu.selected + 1) % entries.len();
+    }
+    if menu.selected < menu.scroll {
+        menu.scroll = menu.selected;
+    } else if menu.selected >= menu.scroll + VISIBLE_ENTRIES {
+        menu.scroll = menu.selected + 1 - VISIBLE_ENTRIES;
+    }
+
+    // Left and right step a value; confirming steps it forward.
+    let step = if input.just_pressed(Action::MoveLeft) {
+        -1
+    } else if input.just_pressed(Action::MoveRight) || input.just_pressed(Action::Confirm) {
+        1
+    } else {
+        return;
+    };
+
+    match entries[menu.selected] {
+        SettingsEntry::WindowMode => {
+            settings.window_mode = next_in(&WindowModeSetting::ALL, settings.window_mode, step);
+        }
+        SettingsEntry::Resolution => {
+            settings.resolution = next_in(&ZOOM_LEVELS, settings.resolution, step);
+        }
+        SettingsEntry::ScalingMode => {
+            settings.scaling_mode = next_in(&ScalingMode::ALL, settings.scaling_mode, step);
+        }
+        SettingsEntry::CrtEffect => settings.crt_effect = !settings.crt_effect,
+        SettingsEntry::Palette => {
+            settings.palette = next_in(&Palette::ALL, settings.palette, step);
+        }
+        SettingsEntry::Crosshair => {
+            settings.crosshair = next_in(&CrosshairStyle::ALL, settings.crosshair, step);
+        }
+        SettingsEntry::CrosshairColor => {
+            settings.crosshair_color =
+                next_in(&CrosshairColor::ALL, settings.crosshair_color, step);
+        }
+        SettingsEntry::CooldownArcs => {
+            settings.cooldown_arcs = next_in(&CooldownArcs::ALL, settings.cooldown_arcs, step);
+        }
+        SettingsEntry::AimLine => settings.aim_line = !settings.aim_line,
+        SettingsEntry::BlastWarning => settings.blast_warning = !settings.blast_warning,
+        SettingsEntry::Outlines => settings.outlines = !settings.outlines,
+        SettingsEntry::AttractMode => settings.attract_mode = !settings.attract_mode,
+        SettingsEntry::SpeedrunTimer => settings.speedrun_timer = !settings.speedrun_timer,
+        SettingsEntry::DayLength => step_day_length(&mut settings.day_length_secs, step),
+        SettingsEntry::MasterVolume => step_volume(&mut settings.master_volume, step),
+        SettingsEntry::MusicVolume => step_volume(&mut settings.music_volume, step),
+        SettingsEntry::SfxVolume => step_volume(&mut settings.sfx_volume, step),
+        SettingsEntry::Binding(action) => {
+            if input.just_pressed(Action::Confirm) {
+                menu.listening = Some(action);
+            }
+        }
+        SettingsEntry::Back => {
+            if input.just_pressed(Action::Confirm) {
+                next_state.set(GameState::MainMenu);
+            }
+        }
+    }
+}
+
+/// Binds the next pressed key, mouse button or gamepad button to the action being
+/// listened for. It replaces the action's bindings on that device only, so rebinding
+/// a key keeps the gamepad binding. Escape cancels.
+fn capture_binding(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepad_q: Query<&Gamepad>,
+    mut menu: ResMut<SettingsMenu>,
+    mut bindings: ResMut<InputBindings>,
+) {
+    let Some(action) = menu.listening else {
+        return;
+    };
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        menu.listening = None;
+        return;
+    }
+
+    let pressed = keyboard_input
+        .get_just_pressed()
+        .next()
+        .map(|key| InputBinding::Key(*key))
+        .or_else(|| {
+            mouse_input
+                .get_just_pressed()
+                .next()
+                .map(|button| InputBinding::Mouse(*button))
+        })
+        .or_else(|| {
+            gamepad_q
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next())
+                .map(|button| InputBinding::Gamepad(*button))
+        });
+    let Some(binding) = pressed.filter(|binding| binding.is_bindable()) else {
+        return;
+    };
+
+    let action_bindings = bindings.0.entry(action).or_default();
+    action_bindings
+        .retain(|existing| std::mem::discriminant(existing) != std::mem::discriminant(&binding));
+    action_bindings.push(binding);
+    menu.listening = None;
+}
+
+fn entry_text(entry: SettingsEntry, settings: &GameSettings, bindings: &InputBindings) -> String {
+    match entry {
+        SettingsEntry::WindowMode => format!("Window: {}", settings.window_mode.label()),
+        SettingsEntry::Resolution => {
+            format!("Res: {}x{}", settings.resolution.x, settings.resolution.y)
+        }
+        SettingsEntry::ScalingMode => format!("Scaling: {}", settings.scaling_mode.label()),
+        SettingsEntry::CrtEffect => {
+            format!("CRT: {}", if settings.crt_effect { "On" } else { "Off" })
+        }
+        SettingsEntry::Palette => format!("Palette: {}", settings.palette.label()),
+        SettingsEntry::Crosshair => format!("Crosshair: {}", settings.crosshair.label()),
+        SettingsEntry::CrosshairColor => {
+            format!("Aim color: {}", settings.crosshair_color.label())
+        }
+        SettingsEntry::CooldownArcs => format!("Cooldowns: {}", settings.cooldown_arcs.label()),
+        SettingsEntry::AimLine => {
+            format!("Aim line: {}", if settings.aim_line { "On" } else { "Off" })
+        }
+        SettingsEntry::BlastWarning => format!(
+            "Blast warn: {}",
+            if settings.blast_warning { "On" } else { "Off" }
+        ),
+        SettingsEntry::Outlines => {
+            format!("Outlines: {}", if settings.outlines { "On" } else { "Off" })
+        }
+        SettingsEntry::AttractMode => format!(
+            "Attract: {}",
+            if settings.attract_mode { "On" } else { "Off" }
+        ),
+        SettingsEntry::SpeedrunTimer => format!(
+            "Timer: {}",
+            if settings.speedrun_timer { "On" } else { "Off" }
+        ),
+        SettingsEntry::DayLength => format!("Day: {:.0} min", settings.day_length_secs / 60.),
+        SettingsEntry::MasterVolume => format!("Master: {:.0}%", settings.master_volume * 100.),
+        SettingsEntry::MusicVolume => format!("Music: {:.0}%", settings.music_volume * 100.),
+        SettingsEntry::SfxVolume => format!("Effects: {:.0}%", settings.sfx_volume * 100.),
+        SettingsEntry::Binding(action) => {
+            // Gamepad bindings are left out to keep rows within the canvas width.
+            let names: Vec<String> = bindings
+                .bindings(action)
+                .iter()
+                .filter(|binding| !matches!(binding, InputBinding::Gamepad(_)))
+                .map(|binding| String::from(*binding))
+                .collect();
+            format!("{action:?}: {}", names.join(" / "))
+        }
+        SettingsEntry::Back => "Back".to_string(),
+    }
+}
+
+fn update_settings_entries(
+    menu: Res<SettingsMenu>,
+    settings: Res<GameSettings>,
+    bindings: Res<InputBindings>,
+    mut row_q: Query<(&SettingsRow, &mut Text2d, &mut TextColor)>,
+) {
+    let entries = SettingsEntry::all();
+    for (row, mut text, mut color) in row_q.iter_mut() {
+        let index = menu.scroll + row.0;
+        let Some(entry) = entries.get(index) else {
+            text.0.clear();
+            continue;
+        };
+
+        let selected = index == menu.selected;
+        let label = match menu.listening {
+            Some(action) if selected => format!("{action:?}: press a key"),
+            _ => entry_text(*entry, &settings, &bindings),
+        };
+        if text.0 != label {
+            text.0 = label;
+        }
+        color.0 = match menu.listening {
+            Some(_) if selected => LISTENING_ENTRY_COLOR,
+            _ if selected => SELECTED_ENTRY_COLOR,
+            _ => ENTRY_COLOR,
+        };
+    }
+}
@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint::CheckpointReached,
+    config::{load_ron, save_ron},
+    level::LevelSource,
+    results::RunStats,
+    state::{GameState, GameplaySet, NewGame},
+    transition::RoomEntered,
+};
+
+const PERSONAL_BEST_FILE: &str = "personal_best.ron";
+/// How long the comparison with the personal best stays up after a split.
+const SPLIT_SHOWN_SECS: f32 = 3.;
+
+/// Splits the run timer in `RunStats` at each room entered and each new checkpoint
+/// reached, and keeps the fastest winning run to compare them with.
+pub struct SpeedrunPlugin;
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Splits>();
+        app.insert_resource(PersonalBest::load());
+        app.add_systems(NewGame, reset_splits);
+        app.add_systems(
+            Update,
+            (
+                split.run_if(on_event::<RoomEntered>.or(on_event::<CheckpointReached>)),
+                expire_split_delta,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+        app.add_systems(OnEnter(GameState::Victory), record_personal_best);
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Splits {
+    /// Every split so far, in order.
+    pub splits: Vec<Split>,
+    /// The last split against the one with the same key in the personal best, negative
+    /// when ahead. Cleared again after a few seconds.
+    pub delta: Option<f32>,
+    shown: Timer,
+}
+
+/// The fastest run that ended in victory, with its splits. Kept in the config
+/// directory.
+#[derive(Resource, Default, Debug, Serialize, Deserialize)]
+pub struct PersonalBest(pub Option<Run>);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Run {
+    /// In seconds.
+    pub time: f32,
+    pub splits: Vec<Split>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Split {
+    pub key: SplitKey,
+    /// Run time when it was reached, in seconds.
+    pub time: f32,
+}
+
+/// What a split was reached at. Rooms can be gone back into and checkpoints reached in
+/// any order, so splits are compared by this rather than by how many came before.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitKey {
+    pub point: SplitPoint,
+    /// How many times the run got there before, from 0.
+    pub visit: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitPoint {
+    /// Entering the room, named by its map file or seed.
+    Room(String),
+    /// A checkpoint in a room, by its position in whole pixels.
+    Checkpoint { room: String, position: [i32; 2] },
+}
+
+impl PersonalBest {
+    fn load() -> Self {
+        match load_ron(PERSONAL_BEST_FILE) {
+            Ok(Some(personal_best)) => personal_best,
+            Ok(None) => Self::default(),
+            Err(error) => {
+                warn!("Failed to load {PERSONAL_BEST_FILE}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        if let Err(error) = save_ron(PERSONAL_BEST_FILE, self) {
+            warn!("Failed to save {PERSONAL_BEST_FILE}: {error}");
+        }
+    }
+}
+
+/// As `m:ss.cc`.
+pub fn format_run_time(secs: f32) -> String {
+    let centis = (secs.max(0.) * 100.) as u32;
+    format!(
+        "{}:{:02}.{:02}",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+/// As seconds with a sign, like `-1.25`.
+pub fn format_split_delta(secs: f32) -> String {
+    format!("{secs:+.2}")
+}
+
+fn reset_splits(mut splits: ResMut<Splits>) {
+    *splits = Splits::default();
+}
+
+fn room_name(level_source: &LevelSource) -> String {
+    match level_source {
+        LevelSource::Map(path) => path.clone(),
+        LevelSource::Generated { seed } => format!("generated {seed}"),
+    }
+}
+
+fn split(
+    stats: Res<RunStats>,
+    level_source: Res<LevelSource>,
+    personal_best: Res<PersonalBest>,
+    mut splits: ResMut<Splits>,
+    mut room_events: EventReader<RoomEntered>,
+    mut checkpoint_events: EventReader<CheckpointReached>,
+) {
+    let room = room_name(&level_source);
+    let points: Vec<SplitPoint> = room_events
+        .read()
+        .map(|_| SplitPoint::Room(room.clone()))
+        .chain(
+            checkpoint_events
+                .read()
+                .map(|event| SplitPoint::Checkpoint {
+                    room: room.clone(),
+                    position: event.position.round().as_ivec2().to_array(),
+                }),
+        )
+        .collect();
+
+    for point in points {
+        let visit = splits
+            .splits
+            .iter()
+            .filter(|split| split.key.point == point)
+            .count() as u32;
+        let key = SplitKey { point, visit };
+        splits.delta = personal_best
+            .0
+            .as_ref()
+            .and_then(|run| run.splits.iter().find(|best| best.key == key))
+            .map(|best| stats.time - best.time);
+        splits.shown = Timer::from_seconds(SPLIT_SHOWN_SECS, TimerMode::Once);
+        splits.splits.push(Split {
+            key,
+            time: stats.time,
+        });
+    }
+}
+
+fn expire_split_delta(time: Res<Time>, mut splits: ResMut<Splits>) {
+    if splits.delta.is_some() && splits.shown.tick(time.delta()).finished() {
+        splits.delta = None;
+    }
+}
+
+pub fn record_personal_best(
+    stats: Res<RunStats>,
+    splits: Res<Splits>,
+    mut personal_best: ResMut<PersonalBest>,
+) {
+    if personal_best
+        .0
+        .as_ref()
+        .is_some_and(|run| run.time <= stats.time)
+    {
+        return;
+    }
+
+    personal_best.0 = Some(Run {
+        time: stats.time,
+        splits: splits.splits.clone(),
+    });
+    personal_best.save();
+    info!("new personal best: {}", format_run_time(stats.time));
+}
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+
+use crate::{
+    input::{Action, PlayerInput},
+    player::Player,
+    state::PlayerControlSet,
+};
+
+/// Once stamina runs out it has to recover to this fraction before sprinting works again.
+const EXHAUSTION_RECOVERY: f32 = 0.25;
+
+pub struct StaminaPlugin;
+
+impl Plugin for StaminaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_stamina.in_set(PlayerControlSet));
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    /// Per second while sprinting.
+    pub drain_rate: f32,
+    /// Per second while not sprinting.
+    pub regen_rate: f32,
+    pub sprinting: bool,
+    pub exhausted: bool,
+}
+
+impl Stamina {
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            drain_rate: 35.,
+            regen_rate: 20.,
+            sprinting: false,
+            exhausted: false,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current / self.max
+    }
+}
+
+fn update_stamina(
+    time: Res<Time>,
+    input: Res<PlayerInput>,
+    mut stamina: Single<&mut Stamina, With<Player>>,
+) {
+    let moving = input.movement() != Vec2::ZERO;
+    stamina.sprinting = input.pressed(Action::Sprint) && moving && !stamina.exhausted;
+
+    if stamina.sprinting {
+        stamina.current = (stamina.current - stamina.drain_rate * time.delta_secs()).max(0.);
+        if stamina.current == 0. {
+            stamina.exhausted = true;
+            stamina.sprinting = false;
+        }
+    } else {
+        stamina.current =
+            (stamina.current + stamina.regen_rate * time.delta_secs()).min(stamina.max);
+        if stamina.fraction() >= EXHAUSTION_RECOVERY {
+            stamina.exhausted = false;
+        }
+    }
+}
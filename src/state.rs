@@ -0,0 +1,197 @@
+use avian2d::prelude::*;
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::{
+    attract::MenuMode,
+    health::{DeathEvent, Health},
+    input::{Action, PlayerInput},
+    level::level_built,
+    pixel_perfect::{Canvas, CanvasText, HIGH_RES_LAYER, PixelCanvasConfig},
+    player::Player,
+};
+
+const OVERLAY_COLOR: Color = Color::srgba(0., 0., 0., 0.6);
+const OVERLAY_FONT_SIZE: f32 = 12.;
+
+pub struct StatePlugin;
+
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>();
+        // Held back until the level is in place, behind its loading screen. The attract
+        // mode plays a game behind the main menu.
+        app.configure_sets(
+            Update,
+            GameplaySet.run_if(
+                in_state(GameState::Playing)
+                    .or(in_state(MenuMode::Attract))
+                    .and(level_built),
+            ),
+        );
+        app.configure_sets(Update, PlayerControlSet.in_set(GameplaySet));
+        app.configure_sets(
+            FixedUpdate,
+            GameplaySet.run_if(
+                in_state(GameState::Playing)
+                    .or(in_state(MenuMode::Attract))
+                    .and(level_built),
+            ),
+        );
+        app.configure_sets(FixedUpdate, PlayerControlSet.in_set(GameplaySet));
+        app.add_systems(OnEnter(GameState::Playing), unpause_physics);
+        app.add_systems(OnExit(GameState::Playing), pause_physics);
+        app.add_systems(OnEnter(GameState::Paused), spawn_pause_overlay);
+        for ended in [GameState::GameOver, GameState::Victory] {
+            app.add_systems(OnExit(ended), despawn_game_world);
+        }
+        for exited in [GameState::MainMenu, GameState::GameOver, GameState::Victory] {
+            app.add_systems(
+                OnTransition {
+                    exited,
+                    entered: GameState::Playing,
+                },
+                run_new_game,
+            );
+        }
+        app.add_systems(
+            Update,
+            (
+                toggle_pause.run_if(in_state(GameState::Playing).or(in_state(GameState::Paused))),
+                game_over_on_player_death.in_set(GameplaySet),
+                clear_input_buffer
+                    .run_if(state_changed::<GameState>)
+                    .before(GameplaySet),
+                fit_screen_overlays,
+            ),
+        );
+    }
+}
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[states(scoped_entities)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    Settings,
+    Playing,
+    Paused,
+    /// The inventory screen is open; the world is frozen as when paused.
+    Inventory,
+    /// A conversation is on screen; the world is frozen as when paused.
+    Dialogue,
+    GameOver,
+    Victory,
+}
+
+/// Schedule for setting up a fresh game world when leaving the main menu or retrying
+/// after the game ended. Returning to `Playing` from `Paused` doesn't run it.
+#[derive(ScheduleLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NewGame;
+
+/// Entities that belong to the game in progress, like the player and the HUD. They're
+/// despawned when it ends, before a new game sets up its own.
+#[derive(Component, Default)]
+pub struct GameScoped;
+
+/// Systems that advance the game world. They only run while `GameState::Playing`, so
+/// pausing or dying freezes everything without each plugin checking the state itself.
+///
+/// Systems that drive velocities run in `FixedUpdate`, in step with physics, so movement
+/// doesn't depend on the frame rate. Physics interpolation keeps it smooth on screen.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GameplaySet;
+
+/// The gameplay systems that act on the player's input. Cutscenes take control away
+/// from the player by holding this set back.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PlayerControlSet;
+
+/// A full-canvas backdrop sprite, kept fitted to the letterboxed canvas.
+#[derive(Component)]
+pub struct ScreenOverlay;
+
+fn run_new_game(world: &mut World) {
+    world.run_schedule(NewGame);
+}
+
+fn despawn_game_world(mut commands: Commands, game_entity_q: Query<Entity, With<GameScoped>>) {
+    for entity in game_entity_q.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn pause_physics(mut physics_time: ResMut<Time<Physics>>) {
+    physics_time.pause();
+}
+
+fn unpause_physics(mut physics_time: ResMut<Time<Physics>>) {
+    physics_time.unpause();
+}
+
+fn toggle_pause(
+    input: Res<PlayerInput>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(Action::Pause) {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+fn clear_input_buffer(mut player_input: ResMut<PlayerInput>) {
+    player_input.clear_buffer();
+}
+
+/// Systems that bring the player back, like respawning at a checkpoint, run before
+/// this and restore their health.
+pub fn game_over_on_player_death(
+    mut death_events: EventReader<DeathEvent>,
+    player_q: Query<&Health, With<Player>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if death_events.read().any(|event| {
+        player_q
+            .get(event.entity)
+            .is_ok_and(|health| health.is_dead())
+    }) {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+fn spawn_screen_overlay(commands: &mut Commands, state: GameState, title: &str) {
+    commands
+        .spawn((
+            ScreenOverlay,
+            Name::new(format!("{state:?} overlay")),
+            Sprite::from_color(OVERLAY_COLOR, Vec2::ONE),
+            Transform::from_xyz(0., 0., 20.),
+            HIGH_RES_LAYER,
+            StateScoped(state),
+        ))
+        .with_child((
+            Text2d::new(title),
+            CanvasText::new(Vec2::ZERO, OVERLAY_FONT_SIZE),
+            Transform::from_xyz(0., 0., 0.1),
+        ));
+}
+
+fn spawn_pause_overlay(mut commands: Commands) {
+    spawn_screen_overlay(&mut commands, GameState::Paused, "Paused");
+}
+
+fn fit_screen_overlays(
+    config: Res<PixelCanvasConfig>,
+    canvas_transform: Single<&Transform, With<Canvas>>,
+    mut overlay_q: Query<&mut Sprite, With<ScreenOverlay>>,
+) {
+    let scale = canvas_transform.scale.truncate();
+    for mut sprite in overlay_q.iter_mut() {
+        sprite.custom_size = Some(config.size_f32() * scale);
+    }
+}
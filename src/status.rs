@@ -0,0 +1,226 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{health::DamageEvent, player::Player, state::GameplaySet};
+
+const MAX_BURN_STACKS: u32 = 3;
+/// Damage per stack, dealt every `BURN_TICK_SECS`.
+const BURN_DAMAGE: f32 = 2.;
+const BURN_TICK_SECS: f32 = 0.5;
+/// How long a burn lasts after the last time it was applied.
+const BURN_SECS: f32 = 3.;
+/// How often a flare sets what stands on it alight again, adding a stack.
+const IGNITE_INTERVAL: f32 = 1.;
+
+/// Burns, slows and stuns. Burns stack up in strength, while slows and stuns don't:
+/// the strongest slow wins, and a stun only ever gets longer.
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyStatus>();
+        app.add_systems(
+            Update,
+            (ignite_near_flares, apply_statuses, tick_status_effects)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatusEffect {
+    /// Damage over time, a stack more each time it's applied, up to `MAX_BURN_STACKS`.
+    Burn,
+    /// Caps `MaxLinearSpeed` at this many px/s.
+    Slow { max_speed: f32 },
+    /// Stops AI movement.
+    Stun,
+}
+
+/// Status effects only stick to entities with this component.
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects {
+    burn: Option<Burning>,
+    slow: Option<Slowed>,
+    stun: Option<Timer>,
+}
+
+impl StatusEffects {
+    pub fn is_stunned(&self) -> bool {
+        self.stun.is_some()
+    }
+}
+
+#[derive(Debug)]
+struct Burning {
+    stacks: u32,
+    duration: Timer,
+    tick: Timer,
+}
+
+#[derive(Debug)]
+struct Slowed {
+    max_speed: f32,
+    duration: Timer,
+    /// `MaxLinearSpeed` from before the slow, put back once it wears off.
+    base_max_speed: f32,
+}
+
+#[derive(Event, Debug)]
+pub struct ApplyStatus {
+    pub target: Entity,
+    pub effect: StatusEffect,
+    pub secs: f32,
+}
+
+/// Applies a status effect to whatever this entity, like a projectile, hits.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Inflicts {
+    pub effect: StatusEffect,
+    pub secs: f32,
+}
+
+/// Sets anything with `StatusEffects` within `radius` alight, other than the player.
+#[derive(Component, Debug)]
+pub struct Ignites {
+    pub radius: f32,
+    timer: Timer,
+}
+
+impl Ignites {
+    pub fn new(radius: f32) -> Self {
+        let mut timer = Timer::from_seconds(IGNITE_INTERVAL, TimerMode::Repeating);
+        // Whatever it lands on catches right away.
+        timer.tick(timer.duration());
+
+        Self { radius, timer }
+    }
+}
+
+/// Runs for at least `secs` from now, keeping any longer time left.
+fn extend(timer: &mut Timer, secs: f32) {
+    if timer.remaining_secs() < secs {
+        *timer = Timer::from_seconds(secs, TimerMode::Once);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn ignite_near_flares(
+    time: Res<Time>,
+    mut status_events: EventWriter<ApplyStatus>,
+    mut igniter_q: Query<(&mut Ignites, &Transform)>,
+    target_q: Query<(Entity, &Transform), (With<StatusEffects>, Without<Player>)>,
+) {
+    for (mut ignites, igniter_transform) in igniter_q.iter_mut() {
+        if !ignites.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let position = igniter_transform.translation.truncate();
+        for (target, transform) in target_q.iter() {
+            if transform.translation.truncate().distance(position) <= ignites.radius {
+                status_events.write(ApplyStatus {
+                    target,
+                    effect: StatusEffect::Burn,
+                    secs: BURN_SECS,
+                });
+            }
+        }
+    }
+}
+
+fn apply_statuses(
+    mut status_events: EventReader<ApplyStatus>,
+    mut target_q: Query<(&mut StatusEffects, Option<&mut MaxLinearSpeed>)>,
+) {
+    for event in status_events.read() {
+        let Ok((mut statuses, max_speed)) = target_q.get_mut(event.target) else {
+            continue;
+        };
+
+        match event.effect {
+            StatusEffect::Burn => match &mut statuses.burn {
+                Some(burning) => {
+                    burning.stacks = (burning.stacks + 1).min(MAX_BURN_STACKS);
+                    extend(&mut burning.duration, event.secs);
+                }
+                None => {
+                    statuses.burn = Some(Burning {
+                        stacks: 1,
+                        duration: Timer::from_seconds(event.secs, TimerMode::Once),
+                        tick: Timer::from_seconds(BURN_TICK_SECS, TimerMode::Repeating),
+                    });
+                }
+            },
+            StatusEffect::Slow {
+                max_speed: slow_speed,
+            } => {
+                let Some(mut max_speed) = max_speed else {
+                    continue;
+                };
+                match &mut statuses.slow {
+                    Some(slowed) if slow_speed < slowed.max_speed => {
+                        slowed.max_speed = slow_speed;
+                        slowed.duration = Timer::from_seconds(event.secs, TimerMode::Once);
+                    }
+                    Some(slowed) if slow_speed == slowed.max_speed => {
+                        extend(&mut slowed.duration, event.secs);
+                    }
+                    // A weaker slow doesn't change anything.
+                    Some(_) => {}
+                    None => {
+                        statuses.slow = Some(Slowed {
+                            max_speed: slow_speed,
+                            duration: Timer::from_seconds(event.secs, TimerMode::Once),
+                            base_max_speed: max_speed.0,
+                        });
+                    }
+                }
+                if let Some(slowed) = &statuses.slow {
+                    max_speed.0 = slowed.base_max_speed.min(slowed.max_speed);
+                }
+            }
+            StatusEffect::Stun => match &mut statuses.stun {
+                Some(stun) => extend(stun, event.secs),
+                None => statuses.stun = Some(Timer::from_seconds(event.secs, TimerMode::Once)),
+            },
+        }
+    }
+}
+
+fn tick_status_effects(
+    time: Res<Time>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut status_q: Query<(Entity, &mut StatusEffects, Option<&mut MaxLinearSpeed>)>,
+) {
+    for (entity, mut statuses, max_speed) in status_q.iter_mut() {
+        if let Some(burning) = &mut statuses.burn {
+            if burning.tick.tick(time.delta()).just_finished() {
+                damage_events.write(DamageEvent {
+                    target: entity,
+                    amount: BURN_DAMAGE * burning.stacks as f32,
+                    knockback: Vec2::ZERO,
+                });
+            }
+            if burning.duration.tick(time.delta()).finished() {
+                statuses.burn = None;
+            }
+        }
+
+        if let Some(slowed) = &mut statuses.slow
+            && slowed.duration.tick(time.delta()).finished()
+        {
+            if let Some(mut max_speed) = max_speed {
+                max_speed.0 = slowed.base_max_speed;
+            }
+            statuses.slow = None;
+        }
+
+        if let Some(stun) = &mut statuses.stun
+            && stun.tick(time.delta()).finished()
+        {
+            statuses.stun = None;
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    camera::CameraFollow,
+    collider::GameLayer,
+    level::{MarkerKind, MarkerSpawnSet, SpawnMarker},
+    particle::{ParticleEffect, SpawnParticles},
+    pixel_perfect::PIXEL_PERFECT_LAYER,
+    player::Player,
+    state::GameplaySet,
+    transition::RoomScoped,
+};
+
+/// Bodies with their center this close to a pad's are on it.
+const TELEPORTER_RADIUS: f32 = 6.;
+/// Minimum time before a body can teleport again. It also has to step off the pad it
+/// arrived on first.
+const TELEPORT_LOCKOUT_SECS: f32 = 0.5;
+const TELEPORTER_Z: f32 = -4.;
+const TELEPORT_FLASH: ParticleEffect = ParticleEffect {
+    burst: 24,
+    rate: 0.,
+    min_speed: 20.,
+    max_speed: 70.,
+    spread: std::f32::consts::PI,
+    min_lifetime: 0.2,
+    max_lifetime: 0.4,
+    gravity: Vec2::ZERO,
+    drag: 3.,
+    start_color: Color::srgb(0.7, 0.9, 1.),
+    end_color: Color::srgba(0.3, 0.4, 1., 0.),
+};
+
+/// Pads that come in pairs sharing a link: whatever steps onto one comes out of the
+/// other.
+pub struct TeleporterPlugin;
+
+impl Plugin for TeleporterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_level_teleporters.in_set(MarkerSpawnSet),
+                teleport.in_set(GameplaySet),
+            ),
+        );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Teleporter {
+    pub link: u32,
+    /// Only takes the player, leaving props and flares behind.
+    pub player_only: bool,
+}
+
+/// Stops a body that just teleported from going straight back.
+#[derive(Component, Debug)]
+pub struct TeleportLockout(pub Timer);
+
+/// A pad without a partner does nothing.
+fn spawn_level_teleporters(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut marker_events: EventReader<SpawnMarker>,
+) {
+    for SpawnMarker(marker) in marker_events.read() {
+        let MarkerKind::Teleporter { link, player_only } = marker.kind else {
+            continue;
+        };
+
+        commands.spawn((
+            Teleporter { link, player_only },
+            Name::new("Teleporter"),
+            RoomScoped,
+            Transform::from_translation(marker.position.extend(TELEPORTER_Z)),
+            Sprite::from_image(asset_server.load("teleporter.png")),
+            PIXEL_PERFECT_LAYER,
+        ));
+    }
+}
+
+/// Moves dynamic bodies on a pad over to its partner. Lockouts run out once their time
+/// is up and the body is off every pad.
+#[allow(clippy::type_complexity)]
+fn teleport(
+    mut commands: Commands,
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut camera_follow: ResMut<CameraFollow>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    teleporter_q: Query<(Entity, &Teleporter, &GlobalTransform)>,
+    mut body_q: Query<(
+        Entity,
+        &RigidBody,
+        &mut Transform,
+        Option<&mut TeleportLockout>,
+        Has<Player>,
+    )>,
+) {
+    let filter =
+        SpatialQueryFilter::from_mask([GameLayer::Default, GameLayer::Player, GameLayer::Flare]);
+    let pad = Collider::circle(TELEPORTER_RADIUS);
+
+    let mut on_pads = Vec::new();
+    for (entity, teleporter, transform) in teleporter_q.iter() {
+        let Some(partner) = teleporter_q
+            .iter()
+            .find(|(other, other_teleporter, _)| {
+                *other != entity && other_teleporter.link == teleporter.link
+            })
+            .map(|(_, _, partner_transform)| partner_transform.translation().truncate())
+        else {
+            continue;
+        };
+
+        let position = transform.translation().truncate();
+        for body in spatial_query.shape_intersections(&pad, position, 0., &filter) {
+            let Ok((_, rigid_body, mut body_transform, lockout, is_player)) = body_q.get_mut(body)
+            else {
+                continue;
+            };
+            on_pads.push(body);
+            if *rigid_body != RigidBody::Dynamic
+                || lockout.is_some()
+                || (teleporter.player_only && !is_player)
+            {
+                continue;
+            }
+
+            let offset = body_transform.translation.truncate() - position;
+            let arrival = partner + offset;
+            body_transform.translation = arrival.extend(body_transform.translation.z);
+            commands
+                .entity(body)
+                .insert(TeleportLockout(Timer::from_seconds(
+                    TELEPORT_LOCKOUT_SECS,
+                    TimerMode::Once,
+                )));
+            if is_player {
+                camera_follow.position = arrival;
+            }
+            for flash in [position, partner] {
+                particle_events.write(SpawnParticles {
+                    effect: TELEPORT_FLASH,
+                    position: flash,
+                    direction: Vec2::Y,
+                });
+            }
+        }
+    }
+
+    for (body, _, _, lockout, _) in body_q.iter_mut() {
+        let Some(mut lockout) = lockout else {
+            continue;
+        };
+        if lockout.0.tick(time.delta()).finished() && !on_pads.contains(&body) {
+            commands.entity(body).remove::<TeleportLockout>();
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use std::{collections::HashMap, fmt, io, str::FromStr};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use roxmltree::{Document, Node};
+
+use crate::{
+    force_zone::ForceZoneKind,
+    hazard::HazardKind,
+    level::{Level, LevelExit, LevelMarker, MarkerKind, TileKind},
+    portal::DEFAULT_PORTAL_RADIUS,
+};
+
+/// The top three bits of a tile GID are flip flags.
+const GID_FLAGS_MASK: u32 = 0xE000_0000;
+
+pub struct TiledPlugin;
+
+impl Plugin for TiledPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TiledMap>();
+        app.init_asset_loader::<TiledMapLoader>();
+    }
+}
+
+/// A level imported from a Tiled `.tmx` map.
+///
+/// Only the parts the game uses are read: CSV-encoded tile layers, embedded tilesets
+/// whose tiles have a `Floor`, `Wall` or `Cracked` (breakable wall) class, and point or
+/// rectangle objects whose class (or name) is a `MarkerKind` such as `PlayerStart`.
+///
+/// `Door` objects can be rectangles, sized to the doorway, or points, one tile across.
+/// A numeric `link` property locks a door to the `Switch`, `PressurePlate` and `Key`
+/// objects with the same link, which need one. `Npc` objects need a `dialogue` property
+/// with the asset path of what they say, and an `Intro` object needs a `cutscene`
+/// property with the asset path of the cutscene to open a new game with. `Boss` objects
+/// can have one to play as the fight starts. `Crate` and `Barrel` objects place props,
+/// and `Spikes`, `Fire` and `Pit` rectangles hazards. `Wind` rectangles need a `push`
+/// property and `Conveyor` ones a `velocity`, both `x,y` in pixels, and `Water` ones
+/// nothing.
+///
+/// `Platform` rectangles slide along a `path` property of `x,y` pixel offsets from
+/// where they start, separated by spaces, and back. A `link` makes one wait for the
+/// `Switch` or `PressurePlate` with the same link.
+///
+/// `Teleporter` objects come in pairs sharing a `link`. A `player_only` bool property
+/// keeps props and flares from going through.
+///
+/// `Portal` objects need a `flares` property with how many flares have to burn around
+/// them at once to open them, and can have a `radius` in pixels for how close.
+///
+/// `CombatRoom` rectangles cover a room whose doors shut while the player fights the
+/// enemies of the `EnemySpawn` points inside it.
+///
+/// Maps link up through `Exit` rectangles, with a `map` property naming the map file
+/// to go to and an `entry` property naming where to arrive in it. Arrival points are
+/// `Entry` objects, looked up by name.
+#[derive(Asset, TypePath, Debug)]
+pub struct TiledMap {
+    pub level: Level,
+    pub markers: Vec<LevelMarker>,
+    pub exits: Vec<LevelExit>,
+    pub entries: HashMap<String, Vec2>,
+}
+
+#[derive(Default)]
+pub struct TiledMapLoader;
+
+#[derive(Debug)]
+pub enum TiledMapError {
+    Io(io::Error),
+    Xml(roxmltree::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for TiledMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Xml(error) => write!(f, "{error}"),
+            Self::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for TiledMapError {}
+
+impl AssetLoader for TiledMapLoader {
+    type Asset = TiledMap;
+    type Settings = ();
+    type Error = TiledMapError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<TiledMap, TiledMapError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(TiledMapError::Io)?;
+        let text = String::from_utf8(bytes)
+            .map_err(|_| TiledMapError::Invalid("map is not valid UTF-8".to_string()))?;
+
+        parse_tmx(&text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx"]
+    }
+}
+
+fn attribute<T: FromStr>(node: Node, name: &str) -> Result<T, TiledMapError> {
+    let value = node.attribute(name).ok_or_else(|| {
+        TiledMapError::Invalid(format!("<{}> is missing `{name}`", node.tag_name().name()))
+    })?;
+    value.parse().map_err(|_| {
+        TiledMapError::Invalid(format!(
+            "<{}> has an invalid `{name}`: {value}",
+            node.tag_name().name()
+        ))
+    })
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    tag: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(move |child| child.has_tag_name(tag))
+}
+
+/// Tiled calls this `class` since 1.9 and `type` before and after it.
+fn class<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.attribute("class").or_else(|| node.attribute("type"))
+}
+
+fn property<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    children(node, "properties")
+        .flat_map(|properties| children(properties, "property"))
+        .find(|property| property.attribute("name") == Some(name))
+        .and_then(|property| property.attribute("value"))
+}
+
+fn link(object: Node, name: &str) -> Result<Option<u32>, TiledMapError> {
+    property(object, "link")
+        .map(|link| {
+            link.parse().map_err(|_| {
+                TiledMapError::Invalid(format!("`{name}` has a link `{link}` that isn't a number"))
+            })
+        })
+        .transpose()
+}
+
+fn required_link(object: Node, name: &str) -> Result<u32, TiledMapError> {
+    link(object, name)?
+        .ok_or_else(|| TiledMapError::Invalid(format!("`{name}` needs a `link` property")))
+}
+
+/// An `x,y` pair in Tiled's axes, flipped to point y upwards like the world.
+fn vector(text: &str) -> Option<IVec2> {
+    let (x, y) = text.split_once(',')?;
+    Some(IVec2::new(
+        x.trim().parse().ok()?,
+        -y.trim().parse::<i32>().ok()?,
+    ))
+}
+
+/// A required vector property, like a conveyor's `velocity`.
+fn vector_property(object: Node, name: &str, property_name: &str) -> Result<IVec2, TiledMapError> {
+    let value = property(object, property_name).ok_or_else(|| {
+        TiledMapError::Invalid(format!("`{name}` needs a `{property_name}` property"))
+    })?;
+    vector(value)
+        .ok_or_else(|| TiledMapError::Invalid(format!("`{name}` has an invalid `{property_name}`")))
+}
+
+/// Whitespace-separated `x,y` pixel offsets, like the points of a Tiled polyline.
+fn path(object: Node, name: &str) -> Result<Vec<IVec2>, TiledMapError> {
+    property(object, "path")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|point| {
+            vector(point)
+                .ok_or_else(|| TiledMapError::Invalid(format!("`{name}` has an invalid `path`")))
+        })
+        .collect()
+}
+
+fn tile_kinds(map: Node) -> Result<HashMap<u32, TileKind>, TiledMapError> {
+    let mut kinds = HashMap::new();
+    for tileset in children(map, "tileset") {
+        if tileset.has_attribute("source") {
+            return Err(TiledMapError::Invalid(
+                "external tilesets aren't supported, embed the tileset in the map".to_string(),
+            ));
+        }
+
+        let first_gid: u32 = attribute(tileset, "firstgid")?;
+        for tile in children(tileset, "tile") {
+            let kind = match class(tile) {
+                Some("Floor") => TileKind::Floor,
+                Some("Wall") => TileKind::Wall,
+                Some("Cracked") => TileKind::Cracked,
+                _ => continue,
+            };
+            kinds.insert(first_gid + attribute::<u32>(tile, "id")?, kind);
+        }
+    }
+    Ok(kinds)
+}
+
+fn parse_tmx(text: &str) -> Result<TiledMap, TiledMapError> {
+    let document = Document::parse(text).map_err(TiledMapError::Xml)?;
+    let map = document.root_element();
+    if !map.has_tag_name("map") {
+        return Err(TiledMapError::Invalid(
+            "root element is not <map>".to_string(),
+        ));
+    }
+
+    let width: u32 = attribute(map, "width")?;
+    let height: u32 = attribute(map, "height")?;
+    let tile_size: u32 = attribute(map, "tilewidth")?;
+    if attribute::<u32>(map, "tileheight")? != tile_size {
+        return Err(TiledMapError::Invalid("tiles must be square".to_string()));
+    }
+
+    let kinds = tile_kinds(map)?;
+    let mut level = Level::new(width, height);
+    level.tile_size = tile_size;
+
+    // Later layers draw over earlier ones wherever they have a tile.
+    for layer in children(map, "layer") {
+        let Some(data) = children(layer, "data").next() else {
+            continue;
+        };
+        if data.attribute("encoding") != Some("csv") {
+            return Err(TiledMapError::Invalid(
+                "only CSV tile layer encoding is supported".to_string(),
+            ));
+        }
+
+        let gids = data
+            .text()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty());
+        for (index, gid) in gids.enumerate() {
+            let gid: u32 = gid
+                .parse()
+                .map_err(|_| TiledMapError::Invalid(format!("invalid tile `{gid}`")))?;
+            let Some(kind) = kinds.get(&(gid & !GID_FLAGS_MASK)) else {
+                continue;
+            };
+
+            let index = index as u32;
+            if index >= width * height {
+                return Err(TiledMapError::Invalid(
+                    "tile layer is larger than the map".to_string(),
+                ));
+            }
+
+            // Tiled rows run top to bottom, level rows bottom to top.
+            let tile = UVec2::new(index % width, height - 1 - index / width);
+            level.set(tile, *kind);
+        }
+    }
+
+    let origin = level.bounds().min;
+    let map_height = level.size().y;
+    let mut markers = Vec::new();
+    let mut exits = Vec::new();
+    let mut entries = HashMap::new();
+    for object in children(map, "objectgroup").flat_map(|group| children(group, "object")) {
+        // Points have no size. Tiled measures y downwards from the top of the map.
+        let size = Vec2::new(
+            attribute(object, "width").unwrap_or(0.),
+            attribute(object, "height").unwrap_or(0.),
+        );
+        let corner = Vec2::new(attribute(object, "x")?, attribute(object, "y")?);
+        let min = origin + Vec2::new(corner.x, map_height - corner.y - size.y);
+        let area = Rect::from_corners(min, min + size);
+        let name = object.attribute("name").unwrap_or_default();
+
+        match class(object) {
+            Some("Exit") => {
+                let (Some(map), Some(entry)) = (property(object, "map"), property(object, "entry"))
+                else {
+                    return Err(TiledMapError::Invalid(format!(
+                        "exit `{name}` needs `map` and `entry` properties"
+                    )));
+                };
+                exits.push(LevelExit {
+                    area,
+                    map: map.to_string(),
+                    entry: entry.to_string(),
+                });
+            }
+            Some("Entry") => {
+                entries.insert(name.to_string(), area.center());
+            }
+            Some("Door") => {
+                let size = if size == Vec2::ZERO {
+                    UVec2::splat(level.tile_size)
+                } else {
+                    size.as_uvec2()
+                };
+                markers.push(LevelMarker {
+                    kind: MarkerKind::Door {
+                        link: link(object, name)?,
+                        size,
+                    },
+                    position: area.center(),
+                });
+            }
+            Some("Platform") => {
+                let size = if size == Vec2::ZERO {
+                    UVec2::splat(level.tile_size * 2)
+                } else {
+                    size.as_uvec2()
+                };
+                markers.push(LevelMarker {
+                    kind: MarkerKind::Platform {
+                        link: link(object, name)?,
+                        size,
+                        path: path(object, name)?,
+                    },
+                    position: area.center(),
+                });
+            }
+            Some("CombatRoom") => {
+                if size == Vec2::ZERO {
+                    return Err(TiledMapError::Invalid(format!(
+                        "combat room `{name}` needs to be a rectangle"
+                    )));
+                }
+                markers.push(LevelMarker {
+                    kind: MarkerKind::CombatRoom {
+                        size: size.as_uvec2(),
+                    },
+                    position: area.center(),
+                });
+            }
+            Some(class @ ("Spikes" | "Fire" | "Pit")) => {
+                let kind = match class {
+                    "Spikes" => HazardKind::Spikes,
+                    "Fire" => HazardKind::Fire,
+                    _ => HazardKind::Pit,
+                };
+                let size = if size == Vec2::ZERO {
+                    UVec2::splat(level.tile_size)
+                } else {
+                    size.as_uvec2()
+                };
+                markers.push(LevelMarker {
+                    kind: MarkerKind::Hazard { kind, size },
+                    position: area.center(),
+                });
+            }
+            Some(class @ ("Wind" | "Water" | "Conveyor")) => {
+                let kind = match class {
+                    "Wind" => ForceZoneKind::Wind {
+                        push: vector_property(object, name, "push")?,
+                    },
+                    "Water" => ForceZoneKind::Water,
+                    _ => ForceZoneKind::Conveyor {
+                        velocity: vector_property(object, name, "velocity")?,
+                    },
+                };
+                markers.push(LevelMarker {
+                    kind: MarkerKind::ForceZone {
+                        kind,
+                        size: size.as_uvec2(),
+                    },
+                    position: area.center(),
+                });
+            }
+            Some("Switch") => markers.push(LevelMarker {
+                kind: MarkerKind::Switch {
+                    link: required_link(object, name)?,
+                },
+                position: area.center(),
+            }),
+            Some("PressurePlate") => markers.push(LevelMarker {
+                kind: MarkerKind::PressurePlate {
+                    link: required_link(object, name)?,
+                },
+                position: area.center(),
+            }),
+            Some("Teleporter") => markers.push(LevelMarker {
+                kind: MarkerKind::Teleporter {
+                    link: required_link(object, name)?,
+                    player_only: property(object, "player_only") == Some("true"),
+                },
+                position: area.center(),
+            }),
+            Some("Key") => markers.push(LevelMarker {
+                kind: MarkerKind::Key {
+                    link: required_link(object, name)?,
+                },
+                position: area.center(),
+            }),
+            Some("Npc") => {
+                let Some(dialogue) = property(object, "dialogue") else {
+                    return Err(TiledMapError::Invalid(format!(
+                        "`{name}` needs a `dialogue` property"
+                    )));
+                };
+                markers.push(LevelMarker {
+                    kind: MarkerKind::Npc {
+                        dialogue: dialogue.to_string(),
+                    },
+                    position: area.center(),
+                });
+            }
+            Some("Boss") => markers.push(LevelMarker {
+                kind: MarkerKind::Boss {
+                    cutscene: property(object, "cutscene").map(str::to_string),
+                },
+                position: area.center(),
+            }),
+            Some("Portal") => {
+                let flares = property(object, "flares")
+                    .and_then(|flares| flares.parse().ok())
+                    .ok_or_else(|| {
+                        TiledMapError::Invalid(format!("`{name}` needs a `flares` count"))
+                    })?;
+                let radius = match property(object, "radius") {
+                    Some(radius) => radius.parse().map_err(|_| {
+                        TiledMapError::Invalid(format!(
+                            "`{name}` has a radius `{radius}` that isn't a number"
+                        ))
+                    })?,
+                    None => DEFAULT_PORTAL_RADIUS,
+                };
+                markers.push(LevelMarker {
+                    kind: MarkerKind::Portal { flares, radius },
+                    position: area.center(),
+                });
+            }
+            Some("Intro") => {
+                let Some(cutscene) = property(object, "cutscene") else {
+                    return Err(TiledMapError::Invalid(format!(
+                        "`{name}` needs a `cutscene` property"
+                    )));
+                };
+                markers.push(LevelMarker {
+                    kind: MarkerKind::Intro {
+                        cutscene: cutscene.to_string(),
+                    },
+                    position: area.center(),
+                });
+            }
+            class => {
+                // Rectangles mark their center.
+                if let Some(kind) = class.or(Some(name)).and_then(MarkerKind::from_name) {
+                    markers.push(LevelMarker {
+                        kind,
+                        position: area.center(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(TiledMap {
+        level,
+        markers,
+        exits,
+        entries,
+    })
+}
@@ -0,0 +1,245 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    camera::CameraFollow,
+    collider::GameLayer,
+    level::{
+        LevelExit, LevelExits, LevelHandle, LevelMarkers, LevelSource, apply_map, level_built,
+    },
+    pixel_perfect::HIGH_RES_LAYER,
+    player::Player,
+    state::{GameScoped, GameplaySet, ScreenOverlay},
+    tiled::TiledMap,
+};
+
+const FADE_SECS: f32 = 0.25;
+/// Above the HUD, below the pause and game over overlays.
+const FADE_Z: f32 = 15.;
+
+pub struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RoomEntered>();
+        app.init_resource::<RoomTransition>();
+        app.add_systems(Startup, spawn_fade_overlay);
+        app.add_systems(
+            Update,
+            (
+                spawn_exit_triggers.run_if(resource_changed::<LevelExits>),
+                enter_exits.in_set(GameplaySet),
+                fade_out,
+                arrive_in_room,
+                finish_building_room.run_if(level_built),
+                fade_in,
+                update_fade_overlay,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Entities that belong to the current room, despawned when the player leaves it.
+#[derive(Component, Default)]
+#[require(GameScoped)]
+pub struct RoomScoped;
+
+/// Sent once the player is in a new room and its level is in place, so the room can
+/// be populated.
+#[derive(Event, Debug)]
+pub struct RoomEntered;
+
+/// Physics is paused from the moment an exit is touched until the player is in the
+/// next room and it is built.
+#[derive(Resource, Default)]
+enum RoomTransition {
+    #[default]
+    Idle,
+    FadingOut {
+        timer: Timer,
+        exit: LevelExit,
+    },
+    Loading {
+        path: String,
+        map: Handle<TiledMap>,
+        entry: String,
+    },
+    /// Behind the loading screen until the room's level is built.
+    Building,
+    FadingIn {
+        timer: Timer,
+    },
+}
+
+#[derive(Component)]
+struct ExitTrigger(LevelExit);
+
+#[derive(Component)]
+struct FadeOverlay;
+
+fn spawn_fade_overlay(mut commands: Commands) {
+    commands.spawn((
+        FadeOverlay,
+        ScreenOverlay,
+        Name::new("Fade overlay"),
+        Sprite::from_color(Color::BLACK.with_alpha(0.), Vec2::ONE),
+        Transform::from_xyz(0., 0., FADE_Z),
+        HIGH_RES_LAYER,
+    ));
+}
+
+fn spawn_exit_triggers(
+    mut commands: Commands,
+    exits: Res<LevelExits>,
+    trigger_q: Query<Entity, With<ExitTrigger>>,
+) {
+    for trigger in trigger_q.iter() {
+        commands.entity(trigger).despawn();
+    }
+
+    for exit in &exits.0 {
+        commands.spawn((
+            ExitTrigger(exit.clone()),
+            Name::new(format!("Exit to {}", exit.map)),
+            Transform::from_translation(exit.area.center().extend(0.)),
+            RigidBody::Static,
+            Collider::rectangle(exit.area.width(), exit.area.height()),
+            GameLayer::Trigger.collision_layers(),
+            Sensor,
+            CollisionEventsEnabled,
+        ));
+    }
+}
+
+fn enter_exits(
+    mut collision_events: EventReader<CollisionStarted>,
+    mut transition: ResMut<RoomTransition>,
+    mut physics_time: ResMut<Time<Physics>>,
+    trigger_q: Query<&ExitTrigger>,
+    player_q: Query<(), With<Player>>,
+) {
+    for CollisionStarted(a, b) in collision_events.read() {
+        let (trigger_entity, player_entity) = if trigger_q.contains(*a) {
+            (*a, *b)
+        } else {
+            (*b, *a)
+        };
+        let Ok(trigger) = trigger_q.get(trigger_entity) else {
+            continue;
+        };
+        if !player_q.contains(player_entity) || !matches!(*transition, RoomTransition::Idle) {
+            continue;
+        }
+
+        physics_time.pause();
+        *transition = RoomTransition::FadingOut {
+            timer: Timer::from_seconds(FADE_SECS, TimerMode::Once),
+            exit: trigger.0.clone(),
+        };
+    }
+}
+
+fn fade_out(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut transition: ResMut<RoomTransition>,
+    room_entity_q: Query<Entity, With<RoomScoped>>,
+) {
+    let RoomTransition::FadingOut { timer, exit } = &mut *transition else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    for entity in room_entity_q.iter() {
+        commands.entity(entity).despawn();
+    }
+    *transition = RoomTransition::Loading {
+        path: exit.map.clone(),
+        map: asset_server.load(&exit.map),
+        entry: exit.entry.clone(),
+    };
+}
+
+fn arrive_in_room(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    maps: Res<Assets<TiledMap>>,
+    mut transition: ResMut<RoomTransition>,
+    mut camera_follow: ResMut<CameraFollow>,
+    player: Single<(&mut Transform, &mut LinearVelocity), With<Player>>,
+) {
+    let RoomTransition::Loading {
+        path,
+        map: handle,
+        entry,
+    } = &*transition
+    else {
+        return;
+    };
+
+    if let Some(map) = maps.get(handle) {
+        let position = map.entries.get(entry).copied().unwrap_or_else(|| {
+            warn!("map has no entry named `{entry}`, arriving at its player start");
+            LevelMarkers(map.markers.clone()).player_start()
+        });
+
+        apply_map(&mut commands, map);
+        commands.insert_resource(LevelHandle(handle.clone()));
+        commands.insert_resource(LevelSource::Map(path.clone()));
+        // Sent through commands so it arrives after the new level's resources.
+        commands.send_event(RoomEntered);
+
+        let (mut transform, mut velocity) = player.into_inner();
+        transform.translation = position.extend(transform.translation.z);
+        velocity.0 = Vec2::ZERO;
+        // Cut straight to the player rather than panning across the old room.
+        camera_follow.position = position;
+    } else if asset_server.load_state(handle).is_failed() {
+        // The old room's entities are gone, but the player can carry on.
+        error!("couldn't load the next room, staying in the current one");
+    } else {
+        return;
+    }
+
+    *transition = RoomTransition::Building;
+}
+
+fn finish_building_room(
+    mut transition: ResMut<RoomTransition>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    if !matches!(*transition, RoomTransition::Building) {
+        return;
+    }
+
+    physics_time.unpause();
+    *transition = RoomTransition::FadingIn {
+        timer: Timer::from_seconds(FADE_SECS, TimerMode::Once),
+    };
+}
+
+fn fade_in(time: Res<Time>, mut transition: ResMut<RoomTransition>) {
+    let RoomTransition::FadingIn { timer } = &mut *transition else {
+        return;
+    };
+    if timer.tick(time.delta()).finished() {
+        *transition = RoomTransition::Idle;
+    }
+}
+
+fn update_fade_overlay(
+    transition: Res<RoomTransition>,
+    mut overlay_sprite: Single<&mut Sprite, With<FadeOverlay>>,
+) {
+    let alpha = match &*transition {
+        RoomTransition::Idle => 0.,
+        RoomTransition::FadingOut { timer, .. } => timer.fraction(),
+        RoomTransition::Loading { .. } | RoomTransition::Building => 1.,
+        RoomTransition::FadingIn { timer } => timer.fraction_remaining(),
+    };
+    overlay_sprite.color.set_alpha(alpha);
+}
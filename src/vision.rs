@@ -0,0 +1,149 @@
+use avian2d::prelude::*;
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{
+    lighting::{LightOccluder, SHADOW_RAY_COUNT, cast_shadow_rays, ray_index},
+    player::Aim,
+};
+
+pub struct VisionPlugin;
+
+impl Plugin for VisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VisionField>();
+        app.add_systems(
+            PostUpdate,
+            (update_vision_field, hide_unseen_entities)
+                .chain()
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// What the player can see from this entity: a cone along its `Aim`, plus a small
+/// circle all around it, both cut off by walls.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct VisionCone {
+    pub range: f32,
+    /// Angle either side of the aim, in radians.
+    pub half_angle: f32,
+    /// Seen in every direction, so what's right behind the player isn't invisible.
+    pub near_radius: f32,
+}
+
+/// Lets the player see everything within `radius` of this entity that isn't behind a
+/// wall, the way a thrown flare lights up its surroundings.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct RevealsArea {
+    pub radius: f32,
+}
+
+/// Hidden while outside everything the player can see.
+#[derive(Component, Default)]
+pub struct HiddenWhenUnseen;
+
+/// Everything that currently lets the player see, rebuilt every frame. The lightmap
+/// blacks out the pixels none of them see.
+#[derive(Resource, Default)]
+pub struct VisionField {
+    viewers: Vec<Viewer>,
+}
+
+struct Viewer {
+    position: Vec2,
+    range: f32,
+    cone: Option<Cone>,
+    shadow_distances: [f32; SHADOW_RAY_COUNT],
+}
+
+struct Cone {
+    direction: Vec2,
+    cos_half_angle: f32,
+    near_radius: f32,
+}
+
+impl VisionField {
+    /// With nothing to see from, as in the menus, nothing is hidden.
+    pub fn sees(&self, point: Vec2) -> bool {
+        self.viewers.is_empty() || self.viewers.iter().any(|viewer| viewer.sees(point))
+    }
+}
+
+impl Viewer {
+    fn sees(&self, point: Vec2) -> bool {
+        let offset = point - self.position;
+        let distance = offset.length();
+        if distance > self.range || distance > self.shadow_distances[ray_index(offset)] {
+            return false;
+        }
+
+        match &self.cone {
+            Some(cone) if distance > cone.near_radius => {
+                offset.dot(cone.direction) >= distance * cone.cos_half_angle
+            }
+            _ => true,
+        }
+    }
+}
+
+pub fn update_vision_field(
+    mut field: ResMut<VisionField>,
+    spatial_query: SpatialQuery,
+    cone_q: Query<(Entity, &VisionCone, &Aim, &GlobalTransform)>,
+    reveal_q: Query<(Entity, &RevealsArea, &GlobalTransform)>,
+    occluder_q: Query<(), With<LightOccluder>>,
+) {
+    let is_occluder = |entity| occluder_q.contains(entity);
+    field.viewers.clear();
+
+    for (entity, vision, aim, transform) in cone_q.iter() {
+        let position = transform.translation().truncate();
+        field.viewers.push(Viewer {
+            position,
+            range: vision.range,
+            cone: Some(Cone {
+                direction: aim.0,
+                cos_half_angle: vision.half_angle.cos(),
+                near_radius: vision.near_radius,
+            }),
+            shadow_distances: cast_shadow_rays(
+                &spatial_query,
+                entity,
+                position,
+                vision.range,
+                &is_occluder,
+            ),
+        });
+    }
+    for (entity, reveal, transform) in reveal_q.iter() {
+        let position = transform.translation().truncate();
+        field.viewers.push(Viewer {
+            position,
+            range: reveal.radius,
+            cone: None,
+            shadow_distances: cast_shadow_rays(
+                &spatial_query,
+                entity,
+                position,
+                reveal.radius,
+                &is_occluder,
+            ),
+        });
+    }
+}
+
+fn hide_unseen_entities(
+    field: Res<VisionField>,
+    mut hidden_q: Query<(&GlobalTransform, &mut Visibility), With<HiddenWhenUnseen>>,
+) {
+    for (transform, mut visibility) in hidden_q.iter_mut() {
+        let wanted = if field.sees(transform.translation().truncate()) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
@@ -0,0 +1,705 @@
+use std::{collections::BTreeMap, fmt, fs, io};
+
+use bevy::{
+    asset::{
+        AssetLoader, LoadContext,
+        io::{AssetSourceBuilder, Reader, file::FileAssetReader},
+    },
+    prelude::*,
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    ai::AiMovement,
+    boss::spawn_boss,
+    camera::CameraFollow,
+    config::one,
+    enemy::{ENEMY_ARCHETYPES, Emerging, EnemyArchetype, enemy_archetype, spawn_enemy},
+    health::Health,
+    level::{Level, TileKind},
+    pixel_perfect::PixelCanvasConfig,
+    player::Player,
+    rng::GameRng,
+    state::{GameplaySet, NewGame},
+};
+
+const INTERMISSION_SECS: f32 = 4.;
+/// How far outside the visible playfield enemies appear.
+const SPAWN_MARGIN: f32 = 10.;
+/// Enemies never come out closer than this to a player.
+const MIN_SPAWN_DISTANCE: f32 = 48.;
+/// Spots tried for each enemy before settling for the best of them.
+const SPAWN_ATTEMPTS: usize = 10;
+/// Half the side of the square an enemy needs clear of walls to come out in.
+const SPAWN_CLEARANCE: f32 = 4.;
+const DEFAULT_WAVE_SET: &str = "waves/default.waves.ron";
+/// Next to the `assets` directory. Every `.waves.ron` file in it can be picked from the
+/// main menu.
+const MODS_DIR: &str = "mods";
+const MODS_SOURCE: &str = "mods";
+const WAVE_SET_EXTENSION: &str = ".waves.ron";
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WaveSet>();
+        app.init_asset_loader::<WaveSetLoader>();
+        app.insert_resource(WaveManager::default());
+        app.add_event::<WaveStarted>();
+        app.add_event::<WaveCleared>();
+        app.add_systems(Startup, load_wave_sets);
+        app.add_systems(NewGame, reset_waves);
+        app.add_systems(
+            Update,
+            (
+                track_wave_enemies,
+                start_next_wave,
+                spawn_wave_enemies,
+                log_wave_events,
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Lets wave sets load from the mods directory as `mods://`. Has to be called before
+/// `DefaultPlugins` are added.
+pub fn register_mods_source(app: &mut App) {
+    app.register_asset_source(
+        MODS_SOURCE,
+        AssetSourceBuilder::platform_default(MODS_DIR, None),
+    );
+}
+
+/// The waves of a survival game, in order. Clearing the last one wins it.
+///
+/// The built-in waves are in `assets/waves/default.waves.ron`, which shows the format.
+/// Editing a wave set while it's being played, with asset hot reloading on, takes
+/// effect from the next wave.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct WaveSet {
+    pub waves: Vec<WaveDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WaveDefinition {
+    /// How many of each enemy archetype, by name, see `ENEMY_ARCHETYPES`.
+    #[serde(default)]
+    pub enemies: BTreeMap<String, u32>,
+    /// Seconds between enemies coming out. All of them come out at once without it.
+    #[serde(default)]
+    pub spawn_interval: f32,
+    /// Multiplies the enemies' speeds.
+    #[serde(default = "one")]
+    pub speed: f32,
+    #[serde(default)]
+    pub modifiers: Vec<WaveModifier>,
+    /// A boss comes out after the other enemies.
+    #[serde(default)]
+    pub boss: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum WaveModifier {
+    DoubleSpeed,
+    /// Twice the health.
+    Armored,
+}
+
+impl WaveDefinition {
+    fn speed_multiplier(&self) -> f32 {
+        if self.modifiers.contains(&WaveModifier::DoubleSpeed) {
+            self.speed * 2.
+        } else {
+            self.speed
+        }
+    }
+
+    fn health_multiplier(&self) -> f32 {
+        if self.modifiers.contains(&WaveModifier::Armored) {
+            2.
+        } else {
+            1.
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WaveSetLoader;
+
+#[derive(Debug)]
+pub enum WaveSetError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for WaveSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for WaveSetError {}
+
+impl AssetLoader for WaveSetLoader {
+    type Asset = WaveSet;
+    type Settings = ();
+    type Error = WaveSetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<WaveSet, WaveSetError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(WaveSetError::Io)?;
+        parse_wave_set(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+fn parse_wave_set(bytes: &[u8]) -> Result<WaveSet, WaveSetError> {
+    let wave_set: WaveSet = ron::de::from_bytes(bytes).map_err(WaveSetError::Parse)?;
+    validate(&wave_set)?;
+    Ok(wave_set)
+}
+
+/// Waves are numbered from 1 in the messages, as the HUD shows them.
+fn validate(wave_set: &WaveSet) -> Result<(), WaveSetError> {
+    if wave_set.waves.is_empty() {
+        return Err(WaveSetError::Invalid("there are no waves".to_string()));
+    }
+
+    for (index, wave) in wave_set.waves.iter().enumerate() {
+        let number = index + 1;
+        if wave.enemies.values().all(|count| *count == 0) && !wave.boss {
+            return Err(WaveSetError::Invalid(format!(
+                "wave {number} has no enemies"
+            )));
+        }
+        if let Some(name) = wave
+            .enemies
+            .keys()
+            .find(|name| enemy_archetype(name).is_none())
+        {
+            let known: Vec<&str> = ENEMY_ARCHETYPES
+                .iter()
+                .map(|archetype| archetype.name)
+                .collect();
+            return Err(WaveSetError::Invalid(format!(
+                "wave {number} has an unknown enemy `{name}`, expected one of {}",
+                known.join(", ")
+            )));
+        }
+        if wave.spawn_interval.is_nan() || wave.spawn_interval < 0. {
+            return Err(WaveSetError::Invalid(format!(
+                "wave {number} has a negative spawn interval"
+            )));
+        }
+        if !wave.speed.is_finite() || wave.speed <= 0. {
+            return Err(WaveSetError::Invalid(format!(
+                "wave {number} has a speed of {}",
+                wave.speed
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The wave sets to pick from: the built-in one first, then any in the mods directory.
+#[derive(Resource, Debug)]
+pub struct WaveSets {
+    pub sets: Vec<WaveSetEntry>,
+    /// Index into `sets` of the one the next game plays.
+    pub selected: usize,
+}
+
+#[derive(Debug)]
+pub struct WaveSetEntry {
+    pub name: String,
+    pub handle: Handle<WaveSet>,
+}
+
+impl WaveSets {
+    pub fn selected(&self) -> &WaveSetEntry {
+        &self.sets[self.selected]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.sets.len();
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct WaveManager {
+    pub wave: u32,
+    /// Enemies of the wave that are alive or still to come out.
+    pub remaining: u32,
+    pub intermission: Timer,
+    pub in_progress: bool,
+    /// The wave set being played. Chosen as the game starts.
+    pub wave_set: Handle<WaveSet>,
+    /// The wave's enemies that haven't come out yet, next last.
+    queue: Vec<WaveEnemy>,
+    spawn_timer: Timer,
+}
+
+impl Default for WaveManager {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            remaining: 0,
+            intermission: Timer::from_seconds(INTERMISSION_SECS, TimerMode::Once),
+            in_progress: false,
+            wave_set: Handle::default(),
+            queue: Vec::new(),
+            spawn_timer: Timer::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum WaveEnemy {
+    Enemy {
+        archetype: &'static EnemyArchetype,
+        speed: f32,
+        health: f32,
+    },
+    Boss,
+}
+
+impl WaveManager {
+    /// The wave's enemies that haven't come out yet, next first.
+    pub fn queued(&self) -> impl Iterator<Item = WaveEnemy> + '_ {
+        self.queue.iter().rev().copied()
+    }
+
+    /// Seconds between the queued enemies coming out.
+    pub fn spawn_interval(&self) -> f32 {
+        self.spawn_timer.duration().as_secs_f32()
+    }
+
+    /// Picks a wave back up where a save left it, with `queued` still to come out,
+    /// next first.
+    pub fn resume(&mut self, queued: Vec<WaveEnemy>, spawn_interval: f32) {
+        self.queue = queued.into_iter().rev().collect();
+        self.spawn_timer = Timer::from_seconds(spawn_interval, TimerMode::Once);
+    }
+}
+
+#[derive(Component)]
+pub struct WaveMember;
+
+#[derive(Event, Debug)]
+pub struct WaveStarted {
+    pub wave: u32,
+    pub enemy_count: u32,
+}
+
+#[derive(Event, Debug)]
+pub struct WaveCleared {
+    pub wave: u32,
+    /// The last wave of the set, which wins the game.
+    pub last: bool,
+}
+
+/// An archetype's movement, sped up by a wave.
+pub fn wave_movement(archetype: &EnemyArchetype, speed: f32) -> AiMovement {
+    AiMovement {
+        patrol_speed: archetype.movement.patrol_speed * speed,
+        chase_speed: archetype.movement.chase_speed * speed,
+        lunge_speed: archetype.movement.lunge_speed,
+    }
+}
+
+fn load_wave_sets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut sets = vec![WaveSetEntry {
+        name: "Default".to_string(),
+        handle: asset_server.load(DEFAULT_WAVE_SET),
+    }];
+
+    let mods_dir = FileAssetReader::get_base_path().join(MODS_DIR);
+    let mut file_names: Vec<String> = match fs::read_dir(&mods_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|file_name| file_name.ends_with(WAVE_SET_EXTENSION))
+            .collect(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(error) => {
+            warn!("Failed to read {}: {error}", mods_dir.display());
+            Vec::new()
+        }
+    };
+    file_names.sort();
+    for file_name in file_names {
+        info!("found wave set {file_name}");
+        sets.push(WaveSetEntry {
+            name: file_name.trim_end_matches(WAVE_SET_EXTENSION).to_string(),
+            handle: asset_server.load(format!("{MODS_SOURCE}://{file_name}")),
+        });
+    }
+
+    commands.insert_resource(WaveSets { sets, selected: 0 });
+}
+
+/// A wave set that failed to load has already had its error logged, naming the file.
+/// The game goes on with the built-in waves instead.
+fn reset_waves(
+    asset_server: Res<AssetServer>,
+    wave_sets: Res<WaveSets>,
+    mut wave_manager: ResMut<WaveManager>,
+) {
+    let selected = wave_sets.selected();
+    let wave_set = if asset_server.load_state(&selected.handle).is_failed() {
+        warn!(
+            "wave set {} couldn't be loaded, playing the default waves",
+            selected.name
+        );
+        wave_sets.sets[0].handle.clone()
+    } else {
+        selected.handle.clone()
+    };
+
+    *wave_manager = WaveManager {
+        wave_set,
+        ..Default::default()
+    };
+}
+
+fn random_perimeter_point(rng: &mut impl Rng, playfield_size: Vec2) -> Vec2 {
+    let half_extents = playfield_size / 2. + SPAWN_MARGIN;
+    let perimeter = 4. * (half_extents.x + half_extents.y);
+    let mut distance = rng.gen_range(0. ..perimeter);
+
+    for (start, edge) in [
+        (Vec2::new(-1., 1.), Vec2::new(2. * half_extents.x, 0.)),
+        (Vec2::new(1., 1.), Vec2::new(0., -2. * half_extents.y)),
+        (Vec2::new(1., -1.), Vec2::new(-2. * half_extents.x, 0.)),
+        (Vec2::new(-1., -1.), Vec2::new(0., 2. * half_extents.y)),
+    ] {
+        let length = edge.length();
+        if distance <= length {
+            return start * half_extents + edge * (distance / length);
+        }
+        distance -= length;
+    }
+
+    -half_extents
+}
+
+/// Why a spot was turned down for an enemy to come out at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SpawnRejection {
+    /// Overlaps a wall, or lies outside the level.
+    InWall,
+    /// The player would see it pop in.
+    OnScreen,
+    NearPlayer,
+}
+
+fn spawn_rejection(
+    level: &Level,
+    view: Rect,
+    players: &[Vec2],
+    candidate: Vec2,
+) -> Option<SpawnRejection> {
+    let footprint = Rect::from_center_half_size(candidate, Vec2::splat(SPAWN_CLEARANCE));
+    if overlaps_wall(level, footprint) {
+        Some(SpawnRejection::InWall)
+    } else if !view.intersect(footprint).is_empty() {
+        Some(SpawnRejection::OnScreen)
+    } else if players
+        .iter()
+        .any(|player| player.distance(candidate) < MIN_SPAWN_DISTANCE)
+    {
+        Some(SpawnRejection::NearPlayer)
+    } else {
+        None
+    }
+}
+
+/// Whether `area` overlaps a solid tile or reaches outside the level's tiles.
+fn overlaps_wall(level: &Level, area: Rect) -> bool {
+    let bounds = level.bounds();
+    if !bounds.contains(area.min) || !bounds.contains(area.max) {
+        return true;
+    }
+
+    let tile_size = level.tile_size as f32;
+    let min = ((area.min - bounds.min) / tile_size).floor().as_uvec2();
+    // Exclusive, so an edge right on a tile boundary doesn't reach into the next tile.
+    let max = ((area.max - bounds.min) / tile_size).ceil().as_uvec2();
+    (min.y..max.y).any(|y| {
+        (min.x..max.x).any(|x| {
+            let kind = level.get(UVec2::new(x, y));
+            kind.is_solid() || kind == TileKind::Empty
+        })
+    })
+}
+
+/// A spot just outside `view` for an enemy to come out at, clear of walls and players.
+/// When none of the tries is, it's the one farthest from the players out of those clear
+/// of walls, or out of all of them if every one was in a wall.
+fn pick_spawn_point(rng: &mut impl Rng, level: &Level, view: Rect, players: &[Vec2]) -> Vec2 {
+    let distance_to_players = |candidate: Vec2| {
+        players
+            .iter()
+            .map(|player| player.distance(candidate))
+            .fold(f32::INFINITY, f32::min)
+    };
+
+    let mut best: Option<((bool, f32), Vec2)> = None;
+    for _ in 0..SPAWN_ATTEMPTS {
+        let candidate = view.center() + random_perimeter_point(rng, view.size());
+        let Some(rejection) = spawn_rejection(level, view, players, candidate) else {
+            return candidate;
+        };
+
+        let ranking = (
+            rejection != SpawnRejection::InWall,
+            distance_to_players(candidate),
+        );
+        if best.is_none_or(|(best_ranking, _)| best_ranking < ranking) {
+            best = Some((ranking, candidate));
+        }
+    }
+
+    best.map_or(view.center(), |(_, candidate)| candidate)
+}
+
+fn track_wave_enemies(
+    wave_sets: Res<Assets<WaveSet>>,
+    mut wave_manager: ResMut<WaveManager>,
+    mut wave_cleared: EventWriter<WaveCleared>,
+    member_q: Query<(), With<WaveMember>>,
+) {
+    // Until it's loaded there's no telling whether a cleared wave was the last.
+    let Some(wave_set) = wave_sets.get(&wave_manager.wave_set) else {
+        return;
+    };
+    let wave_count = wave_set.waves.len();
+    wave_manager.remaining = (member_q.iter().count() + wave_manager.queue.len()) as u32;
+
+    if wave_manager.in_progress && wave_manager.remaining == 0 {
+        wave_manager.in_progress = false;
+        wave_manager.intermission.reset();
+        wave_cleared.write(WaveCleared {
+            wave: wave_manager.wave,
+            last: wave_manager.wave as usize >= wave_count,
+        });
+    }
+}
+
+/// Waits for the wave set to load, the first time.
+fn start_next_wave(
+    time: Res<Time>,
+    wave_sets: Res<Assets<WaveSet>>,
+    mut wave_manager: ResMut<WaveManager>,
+    mut wave_started: EventWriter<WaveStarted>,
+) {
+    if wave_manager.in_progress || !wave_manager.intermission.tick(time.delta()).finished() {
+        return;
+    }
+    let Some(wave_set) = wave_sets.get(&wave_manager.wave_set) else {
+        return;
+    };
+    let Some(definition) = wave_set.waves.get(wave_manager.wave as usize) else {
+        return;
+    };
+
+    let mut queue = Vec::new();
+    for (name, count) in &definition.enemies {
+        let Some(archetype) = enemy_archetype(name) else {
+            continue;
+        };
+        queue.extend((0..*count).map(|_| WaveEnemy::Enemy {
+            archetype,
+            speed: definition.speed_multiplier(),
+            health: archetype.health * definition.health_multiplier(),
+        }));
+    }
+    if definition.boss {
+        queue.push(WaveEnemy::Boss);
+    }
+    // Taken from the back, so the first to come out goes last.
+    queue.reverse();
+
+    wave_manager.wave += 1;
+    wave_manager.in_progress = true;
+    wave_manager.remaining = queue.len() as u32;
+    wave_manager.spawn_timer = Timer::from_seconds(definition.spawn_interval, TimerMode::Once);
+    // The first enemy comes out straight away.
+    let spawn_timer_duration = wave_manager.spawn_timer.duration();
+    wave_manager.spawn_timer.tick(spawn_timer_duration);
+    wave_manager.queue = queue;
+    wave_started.write(WaveStarted {
+        wave: wave_manager.wave,
+        enemy_count: wave_manager.remaining,
+    });
+}
+
+/// Enemies come out just off-screen, away from the player and walls, and can't attack
+/// until they're all there.
+#[allow(clippy::too_many_arguments)]
+fn spawn_wave_enemies(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    config: Res<PixelCanvasConfig>,
+    level: Res<Level>,
+    camera_follow: Res<CameraFollow>,
+    mut rng: ResMut<GameRng>,
+    mut wave_manager: ResMut<WaveManager>,
+    player_q: Query<&GlobalTransform, With<Player>>,
+) {
+    let view = Rect::from_center_size(camera_follow.position, config.size_f32());
+    let players: Vec<Vec2> = player_q
+        .iter()
+        .map(|transform| transform.translation().truncate())
+        .collect();
+    wave_manager.spawn_timer.tick(time.delta());
+    while wave_manager.spawn_timer.finished() {
+        let Some(wave_enemy) = wave_manager.queue.pop() else {
+            return;
+        };
+
+        let position = pick_spawn_point(&mut *rng, &level, view, &players);
+        let entity = match wave_enemy {
+            WaveEnemy::Enemy {
+                archetype,
+                speed,
+                health,
+            } => {
+                let enemy = spawn_enemy(&mut commands, &asset_server, archetype, position);
+                commands
+                    .entity(enemy)
+                    .insert((wave_movement(archetype, speed), Health::new(health)));
+                enemy
+            }
+            WaveEnemy::Boss => spawn_boss(&mut commands, &asset_server, position, None, true),
+        };
+        commands
+            .entity(entity)
+            .insert((WaveMember, Emerging::default()));
+
+        // Anything but a zero interval lets one enemy out per interval.
+        if wave_manager.spawn_timer.duration().is_zero() {
+            continue;
+        }
+        wave_manager.spawn_timer.reset();
+    }
+}
+
+fn log_wave_events(
+    mut wave_started: EventReader<WaveStarted>,
+    mut wave_cleared: EventReader<WaveCleared>,
+) {
+    for event in wave_started.read() {
+        info!(
+            "wave {} started with {} enemies",
+            event.wave, event.enemy_count
+        );
+    }
+    for event in wave_cleared.read() {
+        info!("wave {} cleared", event.wave);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_wave_sets_are_valid() {
+        let default = parse_wave_set(include_bytes!("../assets/waves/default.waves.ron")).unwrap();
+        assert_eq!(default.waves.len(), 10);
+        parse_wave_set(include_bytes!("../mods/example.waves.ron")).unwrap();
+    }
+
+    #[test]
+    fn unknown_archetype_is_named() {
+        let error =
+            parse_wave_set(br#"(waves: [(enemies: {"grunt": 2}), (enemies: {"charger": 1})])"#)
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "wave 2 has an unknown enemy `charger`, expected one of grunt, brute"
+        );
+    }
+
+    /// 10x10 tiles of floor inside a wall, with a pillar right of the middle. Tile
+    /// centers fall on odd multiples of 4.
+    fn walled_level() -> Level {
+        let mut level = Level::new(10, 10);
+        for y in 0..10 {
+            for x in 0..10 {
+                let border = x == 0 || y == 0 || x == 9 || y == 9;
+                let kind = if border {
+                    TileKind::Wall
+                } else {
+                    TileKind::Floor
+                };
+                level.set(UVec2::new(x, y), kind);
+            }
+        }
+        level.set(UVec2::new(7, 5), TileKind::Wall);
+        level
+    }
+
+    #[test]
+    fn spawn_points_are_rejected_by_rule() {
+        let level = walled_level();
+        let view = Rect::from_center_size(Vec2::new(-20., -20.), Vec2::splat(16.));
+        let players = [Vec2::new(-20., -20.)];
+        let rejection = |candidate| spawn_rejection(&level, view, &players, candidate);
+
+        // Right next to the pillar, in the border wall and outside the level.
+        assert_eq!(rejection(Vec2::new(20., 4.)), Some(SpawnRejection::InWall));
+        assert_eq!(
+            rejection(Vec2::new(-36., 20.)),
+            Some(SpawnRejection::InWall)
+        );
+        assert_eq!(rejection(Vec2::new(60., 0.)), Some(SpawnRejection::InWall));
+        // Reaching into the view by a pixel.
+        assert_eq!(
+            rejection(Vec2::new(-9., -20.)),
+            Some(SpawnRejection::OnScreen)
+        );
+        assert_eq!(
+            rejection(Vec2::new(4., -20.)),
+            Some(SpawnRejection::NearPlayer)
+        );
+        assert_eq!(rejection(Vec2::new(20., 20.)), None);
+        // Touching the pillar's edge isn't overlapping it.
+        assert_eq!(rejection(Vec2::new(20., 12.)), None);
+    }
+
+    #[test]
+    fn spawn_point_falls_back_to_the_farthest_clear_candidate() {
+        let level = walled_level();
+        // Everything in the level is too close to a player in the middle.
+        let view = Rect::from_center_size(Vec2::ZERO, Vec2::splat(20.));
+        let players = [Vec2::ZERO];
+        let mut rng = GameRng::new(7);
+        for _ in 0..20 {
+            let position = pick_spawn_point(&mut rng, &level, view, &players);
+            let footprint = Rect::from_center_half_size(position, Vec2::splat(SPAWN_CLEARANCE));
+            assert!(!overlaps_wall(&level, footprint), "{position} is in a wall");
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use std::time::Duration;
+
+use bevy::{input::mouse::MouseWheel, prelude::*};
+use rand::Rng;
+
+use crate::{
+    ai::NoiseEvent,
+    camera::MouseWorldPos,
+    input::{Action, PlayerInput},
+    player::Player,
+    projectile::Projectiles,
+    rng::GameRng,
+    state::{GameplaySet, PlayerControlSet},
+};
+
+/// Distance from the shooter's center where projectiles appear.
+const MUZZLE_OFFSET: f32 = 11.;
+
+const WEAPON_SLOT_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+pub const PISTOL: WeaponDefinition = WeaponDefinition {
+    name: "Pistol",
+    fire_rate: 4.,
+    projectile_speed: 250.,
+    spread: 0.02,
+    pellets: 1,
+    damage: 10.,
+    trigger: TriggerMode::SemiAuto,
+    max_ammo: None,
+    loudness: 200.,
+};
+
+pub const SMG: WeaponDefinition = WeaponDefinition {
+    name: "SMG",
+    fire_rate: 12.,
+    projectile_speed: 280.,
+    spread: 0.15,
+    pellets: 1,
+    damage: 4.,
+    trigger: TriggerMode::FullAuto,
+    max_ammo: Some(180),
+    loudness: 160.,
+};
+
+pub const SHOTGUN: WeaponDefinition = WeaponDefinition {
+    name: "Shotgun",
+    fire_rate: 1.2,
+    projectile_speed: 220.,
+    spread: 0.5,
+    pellets: 6,
+    damage: 6.,
+    trigger: TriggerMode::SemiAuto,
+    max_ammo: Some(144),
+    loudness: 260.,
+};
+
+const STARTING_LOADOUT: [WeaponDefinition; 3] = [PISTOL, SMG, SHOTGUN];
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                give_starting_loadout,
+                (switch_weapons, fire_weapons).in_set(PlayerControlSet),
+            )
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WeaponDefinition {
+    pub name: &'static str,
+    /// Shots per second. Bursts are timed by their `TriggerMode` instead.
+    pub fire_rate: f32,
+    pub projectile_speed: f32,
+    /// Total width of the firing cone in radians.
+    pub spread: f32,
+    /// Projectiles fired per shot.
+    pub pellets: u32,
+    pub damage: f32,
+    pub trigger: TriggerMode,
+    /// Rounds, each pellet taking one, so a shot takes `pellets` of them. `None` for
+    /// unlimited ammo.
+    pub max_ammo: Option<u32>,
+    /// How far away enemies hear a shot, in pixels.
+    pub loudness: f32,
+}
+
+/// What pulling the trigger does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TriggerMode {
+    /// One shot per press.
+    SemiAuto,
+    /// Keeps firing at the fire rate while the trigger is held.
+    FullAuto,
+    /// `count` shots `interval` seconds apart per press, then `cooldown` seconds before
+    /// the next burst can start. Letting go mid-burst still fires the rest of it.
+    #[allow(dead_code)] // Nothing in the loadout fires in bursts yet.
+    Burst {
+        count: u32,
+        interval: f32,
+        cooldown: f32,
+    },
+}
+
+#[derive(Component, Debug)]
+pub struct Weapon {
+    pub definition: WeaponDefinition,
+    pub ammo: Option<u32>,
+    /// Until the next shot can go off, whether within a burst or not.
+    pub cooldown: Timer,
+    /// Shots still to come in the burst being fired.
+    pub burst_left: u32,
+}
+
+impl Weapon {
+    pub fn new(definition: WeaponDefinition) -> Self {
+        let mut cooldown = Timer::from_seconds(0., TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+
+        Self {
+            definition,
+            ammo: definition.max_ammo,
+            cooldown,
+            burst_left: 0,
+        }
+    }
+
+    pub fn can_fire(&self) -> bool {
+        self.cooldown.finished() && self.ammo != Some(0)
+    }
+
+    /// Works out whether the trigger fires a shot now, and if it does, takes a round per
+    /// pellet and starts the cooldown to the next one. Returns how many pellets go off,
+    /// none when it doesn't fire, and fewer than a full shot when the rounds run out.
+    /// The buffered press of fire is only used up when it starts a shot or burst, so one
+    /// made during a cooldown fires as it ends.
+    fn pull_trigger(&mut self, input: &mut PlayerInput) -> u32 {
+        if !self.can_fire() {
+            return 0;
+        }
+
+        let next_shot_secs = match self.definition.trigger {
+            TriggerMode::SemiAuto => {
+                if !input.take_buffered(Action::Fire) {
+                    return 0;
+                }
+                1. / self.definition.fire_rate
+            }
+            // Still takes the buffered press, so it isn't left over to fire a
+            // semi-automatic weapon switched to straight after.
+            TriggerMode::FullAuto => {
+                if !input.take_buffered(Action::Fire) && !input.pressed(Action::Fire) {
+                    return 0;
+                }
+                1. / self.definition.fire_rate
+            }
+            TriggerMode::Burst {
+                count,
+                interval,
+                cooldown,
+            } => {
+                if self.burst_left == 0 {
+                    if !input.take_buffered(Action::Fire) {
+                        return 0;
+                    }
+                    self.burst_left = count;
+                }
+                self.burst_left -= 1;
+                if self.burst_left > 0 {
+                    interval
+                } else {
+                    cooldown
+                }
+            }
+        };
+
+        self.cooldown
+            .set_duration(Duration::from_secs_f32(next_shot_secs));
+        self.cooldown.reset();
+        let Some(ammo) = self.ammo.as_mut() else {
+            return self.definition.pellets;
+        };
+        let pellets = self.definition.pellets.min(*ammo);
+        *ammo -= pellets;
+        // A burst that runs dry ends there, rather than going on after a reload.
+        if *ammo == 0 {
+            self.burst_left = 0;
+        }
+        pellets
+    }
+}
+
+/// Marks the weapon its owner currently fires.
+#[derive(Component)]
+pub struct Equipped;
+
+fn give_starting_loadout(mut commands: Commands, player_q: Query<Entity, Added<Player>>) {
+    for player in player_q.iter() {
+        commands.entity(player).with_children(|parent| {
+            for (i, definition) in STARTING_LOADOUT.into_iter().enumerate() {
+                let mut weapon = parent.spawn((
+                    Weapon::new(definition),
+                    Name::new(definition.name),
+                    Transform::default(),
+                ));
+                if i == 0 {
+                    weapon.insert(Equipped);
+                }
+            }
+        });
+    }
+}
+
+fn switch_weapons(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    player_children: Single<&Children, With<Player>>,
+    weapon_q: Query<Has<Equipped>, With<Weapon>>,
+) {
+    let weapons: Vec<Entity> = player_children
+        .iter()
+        .filter(|child| weapon_q.contains(*child))
+        .collect();
+    let Some(current) = weapons
+        .iter()
+        .position(|weapon| weapon_q.get(*weapon).is_ok_and(|equipped| equipped))
+    else {
+        return;
+    };
+
+    let scroll: f32 = mouse_wheel.read().map(|event| event.y).sum();
+    let selected = if let Some(slot) = WEAPON_SLOT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+    {
+        slot
+    } else if scroll > 0. {
+        (current + 1) % weapons.len()
+    } else if scroll < 0. {
+        (current + weapons.len() - 1) % weapons.len()
+    } else {
+        return;
+    };
+
+    if selected < weapons.len() && selected != current {
+        commands.entity(weapons[current]).remove::<Equipped>();
+        commands.entity(weapons[selected]).insert(Equipped);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fire_weapons(
+    mut commands: Commands,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut projectiles: Projectiles,
+    time: Res<Time>,
+    mut input: ResMut<PlayerInput>,
+    mouse_world_pos: Res<MouseWorldPos>,
+    mut rng: ResMut<GameRng>,
+    player: Single<(Entity, &Transform), With<Player>>,
+    mut weapon_q: Query<(&mut Weapon, Has<Equipped>, &ChildOf)>,
+) {
+    let (player_entity, player_transform) = *player;
+    let player_pos = player_transform.translation.truncate();
+
+    for (mut weapon, equipped, child_of) in weapon_q.iter_mut() {
+        weapon.cooldown.tick(time.delta());
+
+        if !equipped || child_of.parent() != player_entity {
+            // Switching away cuts a burst short.
+            weapon.burst_left = 0;
+            continue;
+        }
+
+        let Some(aim) = (mouse_world_pos.0 - player_pos).try_normalize() else {
+            continue;
+        };
+        let pellets = weapon.pull_trigger(&mut input);
+        if pellets == 0 {
+            continue;
+        }
+
+        let definition = weapon.definition;
+        for _ in 0..pellets {
+            let half_spread = definition.spread / 2.;
+            let angle = rng.gen_range(-half_spread..=half_spread);
+            let direction = Vec2::from_angle(angle).rotate(aim);
+
+            projectiles.spawn(
+                &mut commands,
+                player_entity,
+                player_pos + aim * MUZZLE_OFFSET,
+                direction * definition.projectile_speed,
+                definition.damage,
+            );
+        }
+
+        noise_events.write(NoiseEvent {
+            position: player_pos,
+            loudness: definition.loudness,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_micros(16_667);
+    const BUFFER_WINDOW: Duration = Duration::from_millis(120);
+
+    /// None of the loadout fires in bursts, so this one is for testing them.
+    const RIFLE: WeaponDefinition = WeaponDefinition {
+        name: "Rifle",
+        fire_rate: 0.,
+        projectile_speed: 320.,
+        spread: 0.04,
+        pellets: 1,
+        damage: 8.,
+        trigger: TriggerMode::Burst {
+            count: 3,
+            interval: 0.07,
+            cooldown: 0.45,
+        },
+        max_ammo: Some(90),
+        loudness: 220.,
+    };
+
+    /// Runs `weapon` for `frames` frames at 60 fps with fire held on the frames
+    /// `held` says, counting the shots.
+    fn count_shots(weapon: &mut Weapon, frames: u32, held: impl Fn(u32) -> bool) -> u32 {
+        let mut input = PlayerInput::default();
+        let mut shots = 0;
+        for frame in 0..frames {
+            let pressed = if held(frame) {
+                [Action::Fire].into()
+            } else {
+                Default::default()
+            };
+            input.set_pressed(pressed);
+            input.buffer_presses(FRAME * frame, BUFFER_WINDOW);
+            weapon.cooldown.tick(FRAME);
+            if weapon.pull_trigger(&mut input) > 0 {
+                shots += 1;
+            }
+        }
+        shots
+    }
+
+    #[test]
+    fn semi_auto_fires_once_per_press() {
+        // Held for a whole second, then pressed once more.
+        let shots = count_shots(&mut Weapon::new(PISTOL), 120, |frame| {
+            frame < 60 || frame == 90
+        });
+        assert_eq!(shots, 2);
+    }
+
+    #[test]
+    fn full_auto_fires_at_its_rate_while_held() {
+        // 12 shots a second, from the first frame of holding.
+        let shots = count_shots(&mut Weapon::new(SMG), 60, |_| true);
+        assert_eq!(shots, 12);
+        let shots = count_shots(&mut Weapon::new(SMG), 60, |frame| frame == 0);
+        assert_eq!(shots, 1);
+    }
+
+    #[test]
+    fn burst_completes_after_release() {
+        // A tap fires all three.
+        let shots = count_shots(&mut Weapon::new(RIFLE), 60, |frame| frame == 0);
+        assert_eq!(shots, 3);
+        // Holding doesn't start another burst.
+        let shots = count_shots(&mut Weapon::new(RIFLE), 60, |_| true);
+        assert_eq!(shots, 3);
+    }
+
+    #[test]
+    fn press_during_burst_cooldown_is_buffered() {
+        // The burst ends around frame 9 and its cooldown 27 frames later, and a press
+        // 3 frames before that starts the next burst as soon as it does.
+        let shots = count_shots(&mut Weapon::new(RIFLE), 90, |frame| {
+            frame == 0 || frame == 33
+        });
+        assert_eq!(shots, 6);
+        // Too early to be buffered.
+        let shots = count_shots(&mut Weapon::new(RIFLE), 90, |frame| {
+            frame == 0 || frame == 20
+        });
+        assert_eq!(shots, 3);
+    }
+
+    #[test]
+    fn every_shot_takes_a_round() {
+        let mut weapon = Weapon::new(RIFLE);
+        weapon.ammo = Some(4);
+        // Two bursts' worth of presses, but only 4 rounds; the second burst stops short.
+        let shots = count_shots(&mut weapon, 120, |frame| frame == 0 || frame == 60);
+        assert_eq!(shots, 4);
+        assert_eq!(weapon.ammo, Some(0));
+        assert_eq!(weapon.burst_left, 0);
+    }
+
+    #[test]
+    fn every_pellet_takes_a_round() {
+        let mut weapon = Weapon::new(SHOTGUN);
+        weapon.ammo = Some(8);
+        let mut input = PlayerInput::default();
+        input.set_pressed([Action::Fire].into());
+        input.buffer_presses(Duration::ZERO, BUFFER_WINDOW);
+        assert_eq!(weapon.pull_trigger(&mut input), 6);
+        assert_eq!(weapon.ammo, Some(2));
+
+        // The last two rounds make a short shot.
+        weapon.cooldown.tick(weapon.cooldown.duration());
+        input.set_pressed(Default::default());
+        input.set_pressed([Action::Fire].into());
+        input.buffer_presses(FRAME * 60, BUFFER_WINDOW);
+        assert_eq!(weapon.pull_trigger(&mut input), 2);
+        assert_eq!(weapon.ammo, Some(0));
+    }
+}
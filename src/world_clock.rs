@@ -0,0 +1,79 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    lighting::AmbientLight2d,
+    settings::GameSettings,
+    state::{GameplaySet, NewGame},
+};
+
+/// Skips ahead to the next dawn, noon, dusk or midnight.
+const SKIP_TIME_KEY: KeyCode = KeyCode::F8;
+/// Mid-morning, so the first dusk comes a good while into a run.
+const NEW_GAME_TIME: f32 = 0.35;
+const NOON_AMBIENT: f32 = 0.45;
+/// Dark enough that the player's own light and flares are all there is to see by.
+const MIDNIGHT_AMBIENT: f32 = 0.02;
+
+pub struct WorldClockPlugin;
+
+impl Plugin for WorldClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WorldClock {
+            time_of_day: NEW_GAME_TIME,
+        });
+        app.add_systems(NewGame, reset_world_clock);
+        app.add_systems(
+            Update,
+            (advance_world_clock, skip_time, apply_ambient_light)
+                .chain()
+                .in_set(GameplaySet),
+        );
+    }
+}
+
+/// Time of day, driving the ambient light. A full day lasts
+/// `GameSettings::day_length_secs`.
+#[derive(Resource, Debug)]
+pub struct WorldClock {
+    /// Fraction of the day from 0 to 1: midnight at 0, dawn at 0.25, noon at 0.5 and
+    /// dusk at 0.75.
+    pub time_of_day: f32,
+}
+
+impl WorldClock {
+    /// Brightest at noon and darkest at midnight, easing in between.
+    pub fn ambient_light(&self) -> f32 {
+        let daylight = 0.5 - 0.5 * (self.time_of_day * TAU).cos();
+        MIDNIGHT_AMBIENT + (NOON_AMBIENT - MIDNIGHT_AMBIENT) * daylight
+    }
+}
+
+fn reset_world_clock(mut clock: ResMut<WorldClock>) {
+    clock.time_of_day = NEW_GAME_TIME;
+}
+
+fn advance_world_clock(
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    mut clock: ResMut<WorldClock>,
+) {
+    // A hand-edited settings file could hold a zero length.
+    let day_length_secs = settings.day_length_secs.max(1.);
+    clock.time_of_day = (clock.time_of_day + time.delta_secs() / day_length_secs).fract();
+}
+
+fn skip_time(keyboard_input: Res<ButtonInput<KeyCode>>, mut clock: ResMut<WorldClock>) {
+    if !keyboard_input.just_pressed(SKIP_TIME_KEY) {
+        return;
+    }
+
+    let quarter = (clock.time_of_day * 4.).floor() + 1.;
+    clock.time_of_day = (quarter / 4.).fract();
+    info!("time of day {:.2}", clock.time_of_day);
+}
+
+fn apply_ambient_light(clock: Res<WorldClock>, mut ambient: ResMut<AmbientLight2d>) {
+    ambient.0 = clock.ambient_light();
+}